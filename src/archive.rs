@@ -0,0 +1,40 @@
+#![forbid(unsafe_code)]
+
+//! Helpers for yt-dlp's `--download-archive` text format.
+//!
+//! The downloader writes one `youtube <id>` line per finished video and the
+//! backend exposes the same format to other nodes, so both sides share the
+//! exact same encoding/decoding rules.
+
+/// Extractor prefix yt-dlp writes in front of every archived YouTube id.
+pub const ARCHIVE_EXTRACTOR: &str = "youtube";
+
+/// Formats a single archive line (without the trailing newline).
+pub fn archive_line(video_id: &str) -> String {
+    format!("{ARCHIVE_EXTRACTOR} {video_id}")
+}
+
+/// Extracts the video id from an archive line. yt-dlp places the id in the
+/// last whitespace-separated column, so blank lines yield `None`.
+pub fn parse_archive_line(line: &str) -> Option<&str> {
+    line.split_whitespace().last().filter(|id| !id.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_line_roundtrips() {
+        let line = archive_line("abc123");
+        assert_eq!(line, "youtube abc123");
+        assert_eq!(parse_archive_line(&line), Some("abc123"));
+    }
+
+    #[test]
+    fn parse_archive_line_skips_blank_lines() {
+        assert_eq!(parse_archive_line(""), None);
+        assert_eq!(parse_archive_line("   "), None);
+        assert_eq!(parse_archive_line("legacy-id"), Some("legacy-id"));
+    }
+}
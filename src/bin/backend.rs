@@ -3,41 +3,73 @@
 //! Minimal Axum backend that serves already-downloaded NewTube assets.
 //!
 //! Incoming requests never touch YouTube. We only expose the SQLite metadata
-//! plus the media files stored locally on disk. The number of comments in here
+//! plus the media files stored locally on disk, or, for files moved to cold
+//! storage, in the optional `OBJECT_STORE_URL` bucket. The number of comments in here
 //! is intentionally high, per project request, to make future maintenance easy.
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    io::{Read, SeekFrom},
     net::{IpAddr, SocketAddr},
     path::{Component, Path, PathBuf},
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
 };
 
 use anyhow::{Context, Result, anyhow};
 use axum::{
     Json, Router,
-    body::Body,
-    extract::{Path as AxumPath, State},
-    http::{HeaderMap, StatusCode, header},
+    body::{Body, Bytes, to_bytes},
+    extract::{Path as AxumPath, Query, Request, State},
+    handler::Handler,
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    middleware::{self, Next},
     response::{IntoResponse, Response},
-    routing::get,
+    routing::{any, get, post, put},
 };
+use chrono::{DateTime, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use futures_util::{Stream, StreamExt};
 use mime_guess::{MimeGuess, mime::Mime};
-use newtube_tools::config::{DEFAULT_CONFIG_PATH, load_runtime_paths_from};
-use newtube_tools::metadata::{
-    CommentRecord, MetadataReader, SubtitleCollection, VideoRecord, VideoSource,
+use newtube_tools::archive::archive_line;
+use newtube_tools::config::{
+    DEFAULT_CONFIG_PATH, absolute_root, load_runtime_paths_from, load_runtime_paths_or_defaults,
 };
+use newtube_tools::layout;
+use newtube_tools::logging::{self, Verbosity};
 #[cfg(test)]
-use newtube_tools::metadata::{MetadataStore, SubtitleTrack};
+use newtube_tools::metadata::SubtitleTrack;
+use newtube_tools::metadata::{
+    ChannelSummary, Chapter, CommentRecord, CommentThread, ContinueWatchingEntry, ListFilter,
+    MetadataReader, PageRange, SortKey, SortOrder, SponsorSegment, SubtitleCollection, VideoRecord,
+    VideoSource,
+};
+use newtube_tools::metadata::{
+    MetadataStore, SqliteTuning, build_comment_threads, canonical_channel_url,
+};
 use newtube_tools::security::ensure_not_root;
 use parking_lot::RwLock;
 #[cfg(test)]
 use rusqlite::Connection;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 #[cfg(test)]
 use serde_json::json;
-use tokio::{fs::File, signal, task};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt},
+    signal, task,
+};
 use tokio_util::io::ReaderStream;
+use tower_http::compression::{
+    CompressionLayer, Predicate,
+    predicate::{DefaultPredicate, NotForContentType},
+};
+use tower_http::services::{ServeDir, ServeFile};
+use tracing::{error, info, warn};
+use uuid::Uuid;
 
 // Directory layout defaults. Keeping them centralized means the same values
 // can be used when serving both long-form and short-form videos.
@@ -49,11 +81,51 @@ const SUBTITLES_SUBDIR: &str = "subtitles";
 // SQLite database file relative to the media root.
 const METADATA_DB_FILE: &str = "metadata.db";
 
+// Progress window (fraction of the duration) for the "continue watching" row:
+// below the minimum the video was barely started, above the maximum it is
+// considered finished.
+const CONTINUE_WATCHING_MIN_RATIO: f64 = 0.02;
+const CONTINUE_WATCHING_MAX_RATIO: f64 = 0.95;
+
+// Progress past this fraction marks the video as watched automatically. Kept
+// in line with the point where it drops out of "continue watching".
+const WATCHED_RATIO: f64 = CONTINUE_WATCHING_MAX_RATIO;
+
+// How long aggregated statistics (disk usage) stay cached. They require a
+// `stat` per media file, so recomputing them on every request is wasteful.
+const STATS_CACHE_TTL: Duration = Duration::from_secs(300);
+/// Comments inlined in `/api/videos/{id}/page`; the rest come from
+/// `/api/videos/{id}/comments`.
+const PAGE_COMMENT_LIMIT: usize = 50;
+/// Same-channel videos suggested by `/api/videos/{id}/page`.
+const PAGE_RELATED_LIMIT: usize = 12;
+/// Object store requests give up after this long connecting or this long
+/// without receiving a byte.
+const OBJECT_STORE_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const OBJECT_STORE_READ_TIMEOUT: Duration = Duration::from_secs(60);
+/// Size of the chunks relayed from the object store to the client.
+const OBJECT_STORE_CHUNK_BYTES: usize = 64 * 1024;
+/// `Cache-Control` for downloaded media streams and thumbnails. Stream URLs
+/// are keyed by video id and format, not by content, so a re-download or a
+/// deleted video is served under the same URL: caches keep a copy for a day
+/// and then revalidate it against the `ETag` (usually a cheap `304`).
+const MEDIA_CACHE_CONTROL: &str = "public, max-age=86400, must-revalidate";
+
 #[derive(Debug, Clone)]
 struct BackendArgs {
     media_root: PathBuf,
+    www_root: PathBuf,
     newtube_port: u16,
     listen_host: IpAddr,
+    admin_token: Option<String>,
+    sqlite: SqliteTuning,
+    display_timezone: Option<Tz>,
+    stream_rate_limit: Option<u64>,
+    list_description_chars: usize,
+    object_store_url: Option<String>,
+    app_version: Option<String>,
+    /// `--quiet`/`--verbose`, see [`logging`].
+    verbosity: Verbosity,
 }
 
 impl BackendArgs {
@@ -66,15 +138,22 @@ impl BackendArgs {
         I: IntoIterator<Item = String>,
     {
         let mut media_root_override: Option<PathBuf> = None;
+        let mut www_root_override: Option<PathBuf> = None;
         let mut port_override: Option<u16> = None;
         let mut host_override: Option<IpAddr> = None;
-        let mut config_path = PathBuf::from(DEFAULT_CONFIG_PATH);
+        let mut config_path: Option<PathBuf> = None;
+        let mut quiet = false;
+        let mut verbose = false;
         let mut args = iter.into_iter();
         while let Some(arg) = args.next() {
             if let Some(value) = arg.strip_prefix("--media-root=") {
                 media_root_override = Some(PathBuf::from(value));
                 continue;
             }
+            if let Some(value) = arg.strip_prefix("--www-root=") {
+                www_root_override = Some(PathBuf::from(value));
+                continue;
+            }
             if let Some(value) = arg.strip_prefix("--port=") {
                 port_override = Some(parse_port_arg(value)?);
                 continue;
@@ -84,7 +163,7 @@ impl BackendArgs {
                 continue;
             }
             if let Some(value) = arg.strip_prefix("--config=") {
-                config_path = PathBuf::from(value);
+                config_path = Some(PathBuf::from(value));
                 continue;
             }
 
@@ -95,6 +174,12 @@ impl BackendArgs {
                         .ok_or_else(|| anyhow!("--media-root requires a value"))?;
                     media_root_override = Some(PathBuf::from(value));
                 }
+                "--www-root" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--www-root requires a value"))?;
+                    www_root_override = Some(PathBuf::from(value));
+                }
                 "--port" => {
                     let value = args
                         .next()
@@ -111,22 +196,45 @@ impl BackendArgs {
                     let value = args
                         .next()
                         .ok_or_else(|| anyhow!("--config requires a value"))?;
-                    config_path = PathBuf::from(value);
+                    config_path = Some(PathBuf::from(value));
                 }
+                "--quiet" | "-q" => quiet = true,
+                "--verbose" | "-v" => verbose = true,
                 _ => return Err(anyhow!("unknown argument: {arg}")),
             }
         }
 
-        let runtime_paths = load_runtime_paths_from(&config_path)?;
+        // An explicit `--config` (as the systemd unit passes) must exist;
+        // only the implicit default may be absent, e.g. for a local run.
+        let runtime_paths = match &config_path {
+            Some(path) => load_runtime_paths_from(path)?,
+            None => load_runtime_paths_or_defaults(DEFAULT_CONFIG_PATH)?,
+        };
         let runtime_host = parse_host_arg(&runtime_paths.newtube_host)?;
-        let media_root = media_root_override.unwrap_or(runtime_paths.media_root);
+        let media_root = absolute_root(
+            "media root",
+            media_root_override.unwrap_or(runtime_paths.media_root),
+        )?;
+        let www_root = absolute_root(
+            "www root",
+            www_root_override.unwrap_or(runtime_paths.www_root),
+        )?;
         let newtube_port = port_override.unwrap_or(runtime_paths.newtube_port);
         let listen_host = host_override.unwrap_or(runtime_host);
 
         Ok(Self {
             media_root,
+            www_root,
             newtube_port,
             listen_host,
+            admin_token: runtime_paths.admin_token,
+            sqlite: runtime_paths.sqlite,
+            display_timezone: runtime_paths.display_timezone,
+            stream_rate_limit: runtime_paths.stream_rate_limit,
+            list_description_chars: runtime_paths.list_description_chars,
+            object_store_url: runtime_paths.object_store_url,
+            app_version: runtime_paths.app_version,
+            verbosity: Verbosity::from_flags(quiet, verbose)?,
         })
     }
 }
@@ -155,11 +263,98 @@ enum MediaCategory {
 /// * `cache` prevents repeated deserialization for hot endpoints such as the
 ///   homepage feed.
 /// * `files` knows where audio/video/subtitle payloads live on disk.
+/// * `admin_token` guards operator-only endpoints; `None` disables them.
+/// * `egress` counts streamed bytes and applies `STREAM_RATE_LIMIT`.
 #[derive(Clone)]
 struct AppState {
     reader: Arc<MetadataReader>,
     cache: Arc<ApiCache>,
     files: Arc<FilePaths>,
+    admin_token: Option<Arc<str>>,
+    sqlite: SqliteTuning,
+    egress: Arc<Egress>,
+    /// Description length in list responses (`LIST_DESCRIPTION_CHARS`).
+    list_description_chars: usize,
+    /// Where files missing from the media root are fetched from, if anywhere.
+    object_store: Option<Arc<ObjectStore>>,
+    /// `APP_VERSION` from the config, reported by `/api/version`.
+    app_version: Option<Arc<str>>,
+}
+
+/// Bytes served by `stream_file` since startup, plus the optional
+/// per-connection rate limit applied to those responses.
+#[derive(Debug, Default)]
+struct Egress {
+    rate_limit: Option<u64>,
+    bytes_served: AtomicU64,
+}
+
+impl Egress {
+    fn new(rate_limit: Option<u64>) -> Self {
+        Self {
+            rate_limit,
+            bytes_served: AtomicU64::new(0),
+        }
+    }
+
+    fn bytes_served(&self) -> u64 {
+        self.bytes_served.load(Ordering::Relaxed)
+    }
+
+    /// Turns a file stream into a response body that counts every chunk and,
+    /// when a rate limit is set, paces them through a [`TokenBucket`].
+    fn body<S>(self: &Arc<Self>, stream: S) -> Body
+    where
+        S: Stream<Item = std::io::Result<Bytes>> + Send + Unpin + 'static,
+    {
+        let bucket = self.rate_limit.map(TokenBucket::new);
+        let state = (stream, bucket, Arc::clone(self));
+        Body::from_stream(futures_util::stream::unfold(
+            state,
+            |(mut stream, mut bucket, egress)| async move {
+                let chunk = stream.next().await?;
+                if let Ok(bytes) = &chunk {
+                    let len = bytes.len() as u64;
+                    if let Some(bucket) = &mut bucket {
+                        bucket.take(len).await;
+                    }
+                    egress.bytes_served.fetch_add(len, Ordering::Relaxed);
+                }
+                Some((chunk, (stream, bucket, egress)))
+            },
+        ))
+    }
+}
+
+/// Token bucket refilled at `rate` bytes per second that holds at most one
+/// second's worth. A chunk larger than the balance is sent once the debt it
+/// leaves has been slept off, so the long-run rate never exceeds `rate`.
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u64) -> Self {
+        Self {
+            rate: rate as f64,
+            tokens: rate as f64,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    async fn take(&mut self, bytes: u64) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.refilled_at = now;
+
+        self.tokens -= bytes as f64;
+        if self.tokens < 0.0 {
+            tokio::time::sleep(Duration::from_secs_f64(-self.tokens / self.rate)).await;
+        }
+    }
 }
 
 /// Very small in-memory cache to avoid re-querying SQLite on every request.
@@ -174,6 +369,11 @@ struct ApiCache {
     comments: RwLock<HashMap<String, Vec<CommentRecord>>>,
     subtitles: RwLock<HashMap<String, SubtitleCollection>>,
     bootstrap: RwLock<Option<Arc<BootstrapPayload>>>,
+    /// `GET /api/channels`, derived from both lists.
+    channels: RwLock<Option<Vec<ChannelSummary>>>,
+    /// Disk usage keyed by channel id (`None` = whole library), stamped with
+    /// the time it was computed so entries expire after `STATS_CACHE_TTL`.
+    usage: RwLock<HashMap<Option<String>, (Instant, UsageReport)>>,
 }
 
 impl ApiCache {
@@ -188,6 +388,8 @@ impl ApiCache {
             comments: RwLock::new(HashMap::new()),
             subtitles: RwLock::new(HashMap::new()),
             bootstrap: RwLock::new(None),
+            channels: RwLock::new(None),
+            usage: RwLock::new(HashMap::new()),
         }
     }
 
@@ -204,27 +406,107 @@ impl ApiCache {
             MediaCategory::Short => &self.short_details,
         }
     }
+
+    /// Drops every cached entry derived from `videoid` after a write so the
+    /// next read goes back to SQLite.
+    fn invalidate_media(&self, category: MediaCategory, videoid: &str) {
+        self.media_list(category).write().take();
+        self.media_details(category).write().remove(videoid);
+        self.bootstrap.write().take();
+        self.channels.write().take();
+    }
+
+    /// Like [`Self::invalidate_media`], but also forgets the comments and
+    /// subtitles of a video that no longer exists.
+    fn forget_media(&self, category: MediaCategory, videoid: &str) {
+        self.invalidate_media(category, videoid);
+        self.comments.write().remove(videoid);
+        self.subtitles.write().remove(videoid);
+    }
+
+    /// Empties one part of the cache along with the bootstrap payload, which
+    /// embeds every part but usage. Lists also drop the channel summaries
+    /// built from them.
+    fn clear(&self, category: CacheCategory) {
+        match category {
+            CacheCategory::Videos => {
+                self.videos.write().take();
+                self.video_details.write().clear();
+                self.bootstrap.write().take();
+                self.channels.write().take();
+            }
+            CacheCategory::Shorts => {
+                self.shorts.write().take();
+                self.short_details.write().clear();
+                self.bootstrap.write().take();
+                self.channels.write().take();
+            }
+            CacheCategory::Comments => {
+                self.comments.write().clear();
+                self.bootstrap.write().take();
+            }
+            CacheCategory::Subtitles => {
+                self.subtitles.write().clear();
+                self.bootstrap.write().take();
+            }
+            CacheCategory::Usage => self.usage.write().clear(),
+        }
+    }
+}
+
+/// Parts of [`ApiCache`] that `POST /api/cache/refresh` can clear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CacheCategory {
+    Videos,
+    Shorts,
+    Comments,
+    Subtitles,
+    Usage,
+}
+
+impl CacheCategory {
+    const ALL: [Self; 5] = [
+        Self::Videos,
+        Self::Shorts,
+        Self::Comments,
+        Self::Subtitles,
+        Self::Usage,
+    ];
 }
 
 /// Materialized file-system locations used at runtime.
 struct FilePaths {
+    /// Object store keys are paths relative to this directory.
+    media_root: PathBuf,
     videos: PathBuf,
     shorts: PathBuf,
     thumbnails: PathBuf,
     subtitles: PathBuf,
+    metadata_db: PathBuf,
+    /// Mirrors the downloader's case-suffixed directory naming.
+    case_insensitive: bool,
 }
 
 impl FilePaths {
     /// Builds the folder structure based on the provided media root.
     fn new(media_root: &Path) -> Self {
         Self {
+            media_root: media_root.to_path_buf(),
             videos: media_root.join(VIDEOS_SUBDIR),
             shorts: media_root.join(SHORTS_SUBDIR),
             thumbnails: media_root.join(THUMBNAILS_SUBDIR),
             subtitles: media_root.join(SUBTITLES_SUBDIR),
+            metadata_db: media_root.join(METADATA_DB_FILE),
+            case_insensitive: false,
         }
     }
 
+    /// Per-video directory for `videoid` under `parent`.
+    fn video_dir(&self, parent: &Path, videoid: &str) -> PathBuf {
+        layout::video_dir(parent, videoid, self.case_insensitive)
+    }
+
     /// Chooses either the `videos` or `shorts` directory.
     fn media_dir(&self, category: MediaCategory) -> &Path {
         match category {
@@ -246,7 +528,7 @@ impl FilePaths {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct ApiError {
     status: StatusCode,
     message: String,
@@ -261,6 +543,46 @@ impl ApiError {
         }
     }
 
+    /// Creates a 400 error with the provided message.
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: message.into(),
+        }
+    }
+
+    /// Creates a 401 error with the provided message.
+    fn unauthorized(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::UNAUTHORIZED,
+            message: message.into(),
+        }
+    }
+
+    /// Creates a 403 error with the provided message.
+    fn forbidden(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::FORBIDDEN,
+            message: message.into(),
+        }
+    }
+
+    /// Creates a 503 error, e.g. when the metadata DB cannot be opened.
+    fn service_unavailable(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            message: message.into(),
+        }
+    }
+
+    /// Creates a 502 error for a failed upstream (object store) request.
+    fn bad_gateway(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::BAD_GATEWAY,
+            message: message.into(),
+        }
+    }
+
     /// Creates a 500 error with the provided message.
     fn internal(message: impl Into<String>) -> Self {
         Self {
@@ -270,26 +592,205 @@ impl ApiError {
     }
 }
 
-impl IntoResponse for ApiError {
-    fn into_response(self) -> Response {
+impl ApiError {
+    fn to_response(&self, request_id: Option<&str>) -> Response {
         let mut headers = HeaderMap::new();
         headers.insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
-        let body = serde_json::json!({
+        let mut body = serde_json::json!({
             "error": self.message,
         });
+        if let Some(request_id) = request_id {
+            body["request_id"] = request_id.into();
+        }
         (self.status, headers, Json(body)).into_response()
     }
 }
 
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        // Handlers do not know the request id; `request_context` finds the
+        // error in the extensions and rebuilds the body with it.
+        let mut response = self.to_response(None);
+        response.extensions_mut().insert(self);
+        response
+    }
+}
+
+// Correlates client-reported errors with server logs. Clients may pass their
+// own id; otherwise a UUID is generated.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Outermost middleware: assigns the request id, echoes it in the
+/// `X-Request-Id` header and error bodies, and writes one access log line.
+async fn request_context(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid_request_id(value))
+        .map(str::to_owned)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let method = request.method().clone();
+    let path = request.uri().path().to_owned();
+    let started = Instant::now();
+
+    let mut response = next.run(request).await;
+    if let Some(error) = response.extensions_mut().remove::<ApiError>() {
+        response = error.to_response(Some(&request_id));
+    }
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    info!(
+        request_id = %request_id,
+        "{} {} -> {} ({} ms)",
+        method,
+        path,
+        response.status().as_u16(),
+        started.elapsed().as_millis()
+    );
+    response
+}
+
+/// Client-supplied ids end up in logs and headers, so only short printable
+/// ASCII tokens are echoed back.
+fn is_valid_request_id(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LEN
+        && value.bytes().all(|byte| byte.is_ascii_graphic())
+}
+
+/// Keys a [`VideoRecord`] serializes to; the names `?fields=` accepts.
+const VIDEO_RECORD_FIELDS: &[&str] = &[
+    "videoid",
+    "title",
+    "description",
+    "likes",
+    "dislikes",
+    "views",
+    "upload_date",
+    "upload_epoch",
+    "upload_date_display",
+    "author",
+    "subscriber_count",
+    "duration",
+    "duration_text",
+    "channel_url",
+    "thumbnail_url",
+    "poster_override",
+    "tags",
+    "thumbnails",
+    "extras",
+    "sources",
+    "audio_source",
+    "chapters",
+    "sponsor_segments",
+    "has_subtitles",
+    "subtitle_languages",
+    "watched",
+    "refreshed_at",
+    "webpage_url",
+    "original_url",
+    "description_truncated",
+    "local_views",
+];
+
+#[derive(Debug, Default, Deserialize)]
+struct FieldsQuery {
+    /// Comma-separated [`VIDEO_RECORD_FIELDS`] to keep.
+    fields: Option<String>,
+}
+
+/// Route middleware for `?fields=title,duration`: trims every video record
+/// in the JSON response to the requested keys. `videoid` is always kept so
+/// trimmed records can still be linked; envelopes such as pages keep their
+/// own keys.
+async fn select_fields(Query(query): Query<FieldsQuery>, request: Request, next: Next) -> Response {
+    let Some(fields) = query.fields.as_deref() else {
+        return next.run(request).await;
+    };
+    let fields = match parse_fields(fields) {
+        Ok(fields) => fields,
+        Err(err) => return err.into_response(),
+    };
+
+    let response = next.run(request).await;
+    if !response.status().is_success() {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            return ApiError::internal(format!("reading response body: {err}")).into_response();
+        }
+    };
+    let Ok(mut value) = serde_json::from_slice::<serde_json::Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    project_records(&mut value, &fields);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(value.to_string()))
+}
+
+fn parse_fields(value: &str) -> ApiResult<HashSet<&str>> {
+    let mut fields = HashSet::new();
+    for field in value
+        .split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+    {
+        if !VIDEO_RECORD_FIELDS.contains(&field) {
+            return Err(ApiError::bad_request(format!("unknown field: {field}")));
+        }
+        fields.insert(field);
+    }
+    fields.insert("videoid");
+    Ok(fields)
+}
+
+/// Applies the projection to every object carrying a `videoid`, however
+/// deeply the response nests it.
+fn project_records(value: &mut serde_json::Value, fields: &HashSet<&str>) {
+    match value {
+        serde_json::Value::Array(items) => {
+            for item in items {
+                project_records(item, fields);
+            }
+        }
+        serde_json::Value::Object(map) if map.contains_key("videoid") => {
+            map.retain(|key, _| fields.contains(key.as_str()));
+        }
+        serde_json::Value::Object(map) => {
+            for item in map.values_mut() {
+                project_records(item, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
 type ApiResult<T> = Result<T, ApiError>;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let BackendArgs {
         media_root,
+        www_root,
         newtube_port,
         listen_host,
+        admin_token,
+        sqlite,
+        display_timezone,
+        stream_rate_limit,
+        list_description_chars,
+        object_store_url,
+        app_version,
+        verbosity,
     } = BackendArgs::parse()?;
+    logging::init("backend", verbosity);
 
     ensure_not_root("backend")?;
 
@@ -305,66 +806,288 @@ async fn main() -> Result<()> {
         _ => listen_host,
     };
 
-    let metadata_path = media_root.join(METADATA_DB_FILE);
-    let reader = MetadataReader::new(&metadata_path).context("initializing metadata reader")?;
+    let mut files = FilePaths::new(&media_root);
+    files.case_insensitive = layout::is_case_insensitive(&media_root).unwrap_or_else(|err| {
+        warn!("Could not probe filesystem case sensitivity: {err:#}");
+        false
+    });
+    let reader = MetadataReader::new(&files.metadata_db)
+        .context("initializing metadata reader")?
+        .with_tuning(sqlite)
+        .with_display_timezone(display_timezone);
 
     let state = AppState {
         reader: Arc::new(reader),
         cache: Arc::new(ApiCache::new()),
-        files: Arc::new(FilePaths::new(&media_root)),
+        files: Arc::new(files),
+        admin_token: admin_token.map(Arc::from),
+        sqlite,
+        egress: Arc::new(Egress::new(stream_rate_limit)),
+        list_description_chars,
+        object_store: object_store_url
+            .as_deref()
+            .map(|url| Arc::new(ObjectStore::new(url))),
+        app_version: app_version.map(Arc::from),
+    };
+
+    let addr = SocketAddr::new(host, port);
+    let metadata_db = state.files.metadata_db.clone();
+    let report = StartupReport {
+        media_root: &media_root,
+        www_root: &www_root,
+        metadata_db: &metadata_db,
+        addr,
+        admin_enabled: state.admin_token.is_some(),
+        stream_rate_limit,
+        display_timezone,
+        list_description_chars,
+        case_insensitive: state.files.case_insensitive,
+        object_store_url: object_store_url.as_deref(),
     };
 
+    let app = build_router(state, &www_root);
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("binding to {}", addr))?;
+    report.log();
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .context("running API server")?;
+
+    Ok(())
+}
+
+/// Effective configuration, logged once the backend is listening so a wrong
+/// root or a missing admin token shows up in the journal at boot rather than
+/// on the first failing request.
+struct StartupReport<'a> {
+    media_root: &'a Path,
+    www_root: &'a Path,
+    metadata_db: &'a Path,
+    addr: SocketAddr,
+    admin_enabled: bool,
+    stream_rate_limit: Option<u64>,
+    display_timezone: Option<Tz>,
+    list_description_chars: usize,
+    case_insensitive: bool,
+    object_store_url: Option<&'a str>,
+}
+
+impl StartupReport<'_> {
+    /// One event with a field per setting. Unset optional settings (no rate
+    /// limit, UTC, no object store) are left out.
+    fn log(&self) {
+        info!(
+            host = %self.addr.ip(),
+            port = self.addr.port(),
+            media_root = %self.media_root.display(),
+            www_root = %self.www_root.display(),
+            metadata_db = %self.metadata_db.display(),
+            case_insensitive = self.case_insensitive,
+            admin_endpoints = self.admin_enabled,
+            stream_rate_limit = self.stream_rate_limit,
+            display_timezone = self.display_timezone.map(|tz| tz.name()),
+            // `0` returns descriptions unclipped.
+            list_description_chars = self.list_description_chars,
+            object_store_url = self.object_store_url,
+            usage_cache_ttl_secs = STATS_CACHE_TTL.as_secs(),
+            "API server listening on http://{}",
+            self.addr
+        );
+    }
+}
+
+/// Wires every `/api` route plus the static frontend under `www_root`.
+fn build_router(state: AppState, www_root: &Path) -> Router {
     // Each route is extremely small; helpers supplement anything that is shared
     // between videos and shorts.
-    let app = Router::new()
+    Router::new()
+        .route("/api/health", get(health))
+        .route("/api/version", get(version))
         .route("/api/bootstrap", get(bootstrap))
-        .route("/api/videos", get(list_videos))
-        .route("/api/videos/{id}", get(get_video))
+        .route("/api/archive", get(list_archive))
+        .route("/api/continue-watching", get(continue_watching))
+        .route("/api/favorites", get(list_favorites))
+        .route("/api/usage", get(library_usage))
+        .route("/api/admin/issues", get(list_issues))
+        .route("/api/metrics", get(metrics))
+        .route("/api/cache/refresh", post(refresh_cache))
+        .route("/api/channels", get(list_channels))
+        .route("/api/channels/{id}/usage", get(channel_usage))
+        .route(
+            "/api/videos",
+            get(list_videos).layer(middleware::from_fn(select_fields)),
+        )
+        .route(
+            "/api/videos/{id}",
+            get(get_video)
+                .layer(middleware::from_fn(select_fields))
+                .delete(delete_video),
+        )
+        .route("/api/videos/by-date", get(list_videos_by_date))
+        .route("/api/videos/{id}/page", get(get_video_page))
+        .route("/api/videos/{id}/next", get(get_next_video))
+        .route("/api/videos/{id}/provenance", get(get_video_provenance))
         .route("/api/videos/{id}/comments", get(get_video_comments))
+        .route(
+            "/api/videos/{id}/comments/{comment_id}",
+            get(get_comment_thread),
+        )
         .route("/api/videos/{id}/subtitles", get(list_video_subtitles))
         .route(
             "/api/videos/{id}/subtitles/{code}",
             get(download_video_subtitle),
         )
+        .route("/api/videos/{id}/transcript", get(get_transcript))
+        .route("/api/videos/{id}/chapters.vtt", get(get_chapters_vtt))
+        .route("/api/videos/{id}/sponsorblock", get(get_sponsor_segments))
         .route(
             "/api/videos/{id}/thumbnails/{file}",
             get(download_video_thumbnail),
         )
         .route("/api/videos/{id}/streams/{format}", get(stream_video_file))
-        .route("/api/shorts", get(list_shorts))
-        .route("/api/shorts/{id}", get(get_short))
+        .route("/api/videos/{id}/audio", get(stream_video_audio))
+        .route("/api/videos/{id}/poster", put(set_video_poster))
+        .route("/api/videos/{id}/progress", post(record_video_progress))
+        .route("/api/videos/{id}/watched", post(set_video_watched))
+        .route("/api/videos/{id}/view", post(record_video_view))
+        .route(
+            "/api/videos/{id}/favorite",
+            post(add_video_favorite).delete(remove_video_favorite),
+        )
+        .route(
+            "/api/shorts",
+            get(list_shorts).layer(middleware::from_fn(select_fields)),
+        )
+        .route(
+            "/api/search",
+            get(search).layer(middleware::from_fn(select_fields)),
+        )
+        .route(
+            "/api/shorts/{id}",
+            get(get_short).layer(middleware::from_fn(select_fields)),
+        )
         .route("/api/shorts/{id}/comments", get(get_video_comments))
+        .route(
+            "/api/shorts/{id}/comments/{comment_id}",
+            get(get_comment_thread),
+        )
+        .route("/api/shorts/{id}/provenance", get(get_short_provenance))
         .route("/api/shorts/{id}/subtitles", get(list_short_subtitles))
         .route(
             "/api/shorts/{id}/subtitles/{code}",
             get(download_short_subtitle),
         )
+        .route("/api/shorts/{id}/transcript", get(get_transcript))
         .route(
             "/api/shorts/{id}/thumbnails/{file}",
             get(download_short_thumbnail),
         )
         .route("/api/shorts/{id}/streams/{format}", get(stream_short_file))
-        .with_state(state);
+        .route("/api/shorts/{id}/progress", post(record_short_progress))
+        // Unknown API paths answer with a JSON 404 instead of reaching the
+        // SPA fallback below.
+        .route("/api", any(unknown_api_route))
+        .route("/api/", any(unknown_api_route))
+        .route("/api/{*rest}", any(unknown_api_route))
+        .fallback_service(static_assets(www_root))
+        .layer(middleware::from_fn(request_context))
+        .layer(
+            CompressionLayer::new()
+                .gzip(true)
+                .deflate(true)
+                .compress_when(compressible_response()),
+        )
+        .with_state(state)
+}
 
-    let addr = SocketAddr::new(host, port);
-    let listener = tokio::net::TcpListener::bind(addr)
-        .await
-        .with_context(|| format!("binding to {}", addr))?;
-    println!("API server listening on http://{}", addr);
+/// JSON and other text bodies are compressed when the client asks for it.
+/// Media is already compressed and served with byte ranges, so video, audio
+/// and partial responses go out untouched (as do images, via the default).
+fn compressible_response() -> impl Predicate {
+    DefaultPredicate::new()
+        .and(NotForContentType::const_new("video/"))
+        .and(NotForContentType::const_new("audio/"))
+        .and(
+            |status: StatusCode,
+             _: axum::http::Version,
+             headers: &HeaderMap,
+             _: &axum::http::Extensions| {
+                status != StatusCode::PARTIAL_CONTENT
+                    && !headers.contains_key(header::CONTENT_RANGE)
+            },
+        )
+}
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .context("running API server")?;
+async fn unknown_api_route() -> ApiError {
+    ApiError::not_found("unknown API route")
+}
 
-    Ok(())
+/// Serves the SPA straight from `www_root` so the backend works without
+/// nginx in front. `.br`/`.gz` siblings produced by the frontend build are
+/// preferred when the client's `Accept-Encoding` allows it, and unknown paths
+/// fall back to `index.html` for client-side routing.
+fn static_assets(www_root: &Path) -> Router {
+    let index = ServeFile::new(www_root.join("index.html"))
+        .precompressed_br()
+        .precompressed_gzip();
+    let assets = ServeDir::new(www_root)
+        .precompressed_br()
+        .precompressed_gzip()
+        .fallback(spa_shell.with_state(index));
+    Router::new().fallback_service(assets)
+}
+
+/// Answers paths `ServeDir` could not find: `index.html` for app routes, a
+/// plain 404 for anything that looks like a file (`/missing.js`) so a broken
+/// asset reference does not come back as HTML.
+async fn spa_shell(State(mut index): State<ServeFile>, request: Request) -> Response {
+    if Path::new(request.uri().path()).extension().is_some() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    match index.try_call(request).await {
+        Ok(response) => response.map(Body::new),
+        Err(err) => ApiError::internal(format!("serving index.html: {err}")).into_response(),
+    }
 }
 
+/// Resolves once the server should stop: on Ctrl+C (SIGINT) everywhere and,
+/// on unix, on SIGTERM, which is what `systemctl stop` sends. axum then stops
+/// accepting connections and drains in-flight requests and streams before
+/// `main` returns; systemd's `TimeoutStopSec` still bounds how long that may
+/// take. Other targets only watch Ctrl+C.
 async fn shutdown_signal() {
-    // We do not propagate this error up because it only affects graceful
-    // shutdown; the process still terminates when Ctrl+C fires.
-    if let Err(err) = signal::ctrl_c().await {
-        eprintln!("Failed to install Ctrl+C handler: {}", err);
+    // Install failures are not propagated because they only affect graceful
+    // shutdown: the signal then keeps its default action and still ends the
+    // process. The branch waits forever instead of stopping the server.
+    let ctrl_c = async {
+        if let Err(err) = signal::ctrl_c().await {
+            error!("Failed to install Ctrl+C handler: {err}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut stream) => {
+                stream.recv().await;
+            }
+            Err(err) => {
+                error!("Failed to install SIGTERM handler: {err}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received Ctrl+C, draining connections"),
+        _ = terminate => info!("Received SIGTERM, draining connections"),
     }
 }
 
@@ -373,817 +1096,5125 @@ async fn bootstrap(State(state): State<AppState>) -> ApiResult<Json<BootstrapPay
     Ok(Json((*payload).clone()))
 }
 
-async fn list_videos(State(state): State<AppState>) -> ApiResult<Json<Vec<VideoRecord>>> {
-    let videos = state.get_media_list(MediaCategory::Video).await?;
-    Ok(Json(sanitize_video_records(&videos)))
+/// Body of `GET /api/health`.
+#[derive(Debug, PartialEq, Serialize)]
+struct Health {
+    status: &'static str,
+}
+
+/// Liveness probe for load balancers: `200 {"status":"ok"}` when the
+/// metadata DB can be opened and queried, `503` otherwise. Never cached and
+/// needs no token, so the 503 body stays generic; the cause is only logged.
+async fn health(State(state): State<AppState>) -> ApiResult<Json<Health>> {
+    let reader = state.reader.clone();
+    task::spawn_blocking(move || reader.check())
+        .await
+        .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
+        .map_err(|err| {
+            error!("Health check failed: {err:#}");
+            ApiError::service_unavailable("metadata database unavailable")
+        })?;
+    Ok(Json(Health { status: "ok" }))
 }
 
-async fn list_shorts(State(state): State<AppState>) -> ApiResult<Json<Vec<VideoRecord>>> {
-    let shorts = state.get_media_list(MediaCategory::Short).await?;
-    Ok(Json(sanitize_video_records(&shorts)))
+/// Body of `GET /api/version`.
+#[derive(Debug, PartialEq, Serialize)]
+struct VersionInfo {
+    /// `APP_VERSION` from the config; `null` when it is not set.
+    app_version: Option<String>,
+    /// Version of the backend binary itself.
+    crate_version: &'static str,
 }
 
-async fn get_video(
-    State(state): State<AppState>,
-    AxumPath(id): AxumPath<String>,
-) -> ApiResult<Json<VideoRecord>> {
-    let record = state.get_media(MediaCategory::Video, &id).await?;
-    Ok(Json(sanitize_video_record(&record)))
+async fn version(State(state): State<AppState>) -> Json<VersionInfo> {
+    Json(VersionInfo {
+        app_version: state.app_version.as_deref().map(str::to_owned),
+        crate_version: env!("CARGO_PKG_VERSION"),
+    })
 }
 
-async fn get_short(
-    State(state): State<AppState>,
-    AxumPath(id): AxumPath<String>,
-) -> ApiResult<Json<VideoRecord>> {
-    let record = state.get_media(MediaCategory::Short, &id).await?;
-    Ok(Json(sanitize_video_record(&record)))
+/// Operator counters returned by `GET /api/metrics`.
+#[derive(Debug, PartialEq, Serialize)]
+struct Metrics {
+    /// Bytes streamed since startup.
+    bytes_served: u64,
+    /// Per-connection limit in bytes/s; `null` when unlimited.
+    stream_rate_limit: Option<u64>,
 }
 
-async fn get_video_comments(
-    State(state): State<AppState>,
-    AxumPath(id): AxumPath<String>,
-) -> ApiResult<Json<Vec<CommentRecord>>> {
-    let comments = state.get_comments(&id).await?;
-    Ok(Json(comments))
+async fn metrics(State(state): State<AppState>, headers: HeaderMap) -> ApiResult<Json<Metrics>> {
+    require_admin(&state, &headers)?;
+    Ok(Json(Metrics {
+        bytes_served: state.egress.bytes_served(),
+        stream_rate_limit: state.egress.rate_limit,
+    }))
 }
 
-async fn list_video_subtitles(
-    State(state): State<AppState>,
-    AxumPath(id): AxumPath<String>,
-) -> ApiResult<Json<Vec<SubtitleInfo>>> {
-    list_subtitles(state, id, "videos").await
+/// Optional body of `POST /api/cache/refresh`; without one everything is
+/// cleared.
+#[derive(Debug, Deserialize)]
+struct CacheRefreshRequest {
+    categories: Vec<CacheCategory>,
 }
 
-async fn list_short_subtitles(
-    State(state): State<AppState>,
-    AxumPath(id): AxumPath<String>,
-) -> ApiResult<Json<Vec<SubtitleInfo>>> {
-    list_subtitles(state, id, "shorts").await
+#[derive(Debug, Serialize)]
+struct CacheRefreshResponse {
+    cleared: Vec<CacheCategory>,
 }
 
-async fn list_subtitles(
-    state: AppState,
-    id: String,
-    slug: &'static str,
-) -> ApiResult<Json<Vec<SubtitleInfo>>> {
-    // Build lightweight DTOs that point the frontend to the download
-    // endpoints; the actual subtitle JSON remains cached server side.
-    let mut response = Vec::new();
-    if let Some(collection) = state.get_subtitles(&id).await? {
-        for track in collection.languages {
-            let url = format!("/api/{slug}/{}/subtitles/{}", id, track.code);
-            response.push(SubtitleInfo {
-                code: track.code,
-                name: track.name,
-                url,
-            });
-        }
+/// Drops cached API data so rows written by the downloader since startup
+/// are served without restarting the backend.
+async fn refresh_cache(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    request: Option<Json<CacheRefreshRequest>>,
+) -> ApiResult<Json<CacheRefreshResponse>> {
+    require_admin(&state, &headers)?;
+
+    let cleared = match request {
+        Some(Json(request)) => request.categories,
+        None => CacheCategory::ALL.to_vec(),
+    };
+    for category in &cleared {
+        state.cache.clear(*category);
     }
+    Ok(Json(CacheRefreshResponse { cleared }))
+}
 
-    Ok(Json(response))
+/// Query string accepted by `/api/archive`.
+#[derive(Debug, Default, Deserialize)]
+struct ArchiveQuery {
+    format: Option<String>,
 }
 
-async fn download_video_subtitle(
+/// Lists every archived video id so another node can diff its own library
+/// before downloading. `?format=text` mirrors yt-dlp's archive file so the
+/// output can be dropped straight into `download-archive.txt`.
+async fn list_archive(
     State(state): State<AppState>,
-    AxumPath((id, code)): AxumPath<(String, String)>,
+    headers: HeaderMap,
+    Query(query): Query<ArchiveQuery>,
 ) -> ApiResult<Response> {
-    download_subtitle(state, id, code).await
+    require_admin(&state, &headers)?;
+
+    let reader = state.reader.clone();
+    let ids = task::spawn_blocking(move || reader.list_archived_ids())
+        .await
+        .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
+        .map_err(|err| ApiError::internal(err.to_string()))?;
+
+    match query.format.as_deref() {
+        None | Some("json") => Ok(Json(ids).into_response()),
+        Some("text") => {
+            let mut body = String::new();
+            for id in &ids {
+                body.push_str(&archive_line(id));
+                body.push('\n');
+            }
+            Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], body).into_response())
+        }
+        Some(other) => Err(ApiError::bad_request(format!(
+            "unknown archive format: {other}"
+        ))),
+    }
 }
 
-async fn download_short_subtitle(
-    State(state): State<AppState>,
-    AxumPath((id, code)): AxumPath<(String, String)>,
-) -> ApiResult<Response> {
-    download_subtitle(state, id, code).await
+/// Query string accepted by `/api/videos`.
+#[derive(Debug, Default, Deserialize)]
+struct ListQuery {
+    /// `true` keeps only watched videos, `false` hides them.
+    watched: Option<bool>,
+    /// RFC 3339 timestamp or an earlier `next_since`; switches the response
+    /// to a [`VideoListing::Delta`] of videos refreshed since then.
+    since: Option<String>,
+    /// 1-based page; with `per_page`, switches the response to a [`Page`].
+    page: Option<usize>,
+    per_page: Option<usize>,
+    /// Exact stored `channel_url`; keeps only that channel's videos.
+    channel: Option<String>,
+    /// Any of these switches to a listing read from SQLite in that order.
+    sort: Option<SortKey>,
+    order: Option<SortOrder>,
+    /// Keeps only videos carrying this tag.
+    tag: Option<String>,
 }
 
-async fn download_subtitle(state: AppState, id: String, code: String) -> ApiResult<Response> {
-    ensure_safe_path_segment(&id)?;
-    ensure_safe_path_segment(&code)?;
+/// Page size used when the client passes `page` without `per_page`.
+const DEFAULT_PER_PAGE: usize = 50;
+/// Upper bound on `per_page` for `/api/videos` and `/api/shorts`.
+const MAX_PER_PAGE: usize = 200;
+
+/// One page of `/api/videos` or `/api/shorts`, newest first unless the
+/// request asked for another order.
+#[derive(Debug, Serialize)]
+struct Page {
+    items: Vec<VideoRecord>,
+    /// Rows across every page, after filters.
+    total: usize,
+    page: usize,
+    per_page: usize,
+}
 
-    let subtitles = state
-        .get_subtitles(&id)
-        .await?
-        .ok_or_else(|| ApiError::not_found("subtitles not available"))?;
+/// Validated `page`/`per_page` pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PageRequest {
+    page: usize,
+    per_page: usize,
+}
 
-    let track = subtitles
-        .languages
-        .into_iter()
-        .find(|track| track.code == code)
-        .ok_or_else(|| ApiError::not_found("subtitle track not found"))?;
+impl PageRequest {
+    /// `None` when the client passed neither parameter and wants the whole
+    /// list, which is what the bundled frontend does.
+    fn from_query(page: Option<usize>, per_page: Option<usize>) -> ApiResult<Option<Self>> {
+        if page.is_none() && per_page.is_none() {
+            return Ok(None);
+        }
+        let page = page.unwrap_or(1);
+        let per_page = per_page.unwrap_or(DEFAULT_PER_PAGE);
+        if page == 0 {
+            return Err(ApiError::bad_request("page starts at 1"));
+        }
+        if !(1..=MAX_PER_PAGE).contains(&per_page) {
+            return Err(ApiError::bad_request(format!(
+                "per_page must be between 1 and {MAX_PER_PAGE}"
+            )));
+        }
+        Ok(Some(Self { page, per_page }))
+    }
 
-    // Prefer the explicit filesystem path recorded during download, but fall
-    // back to the standard `videoid/lang` layout when missing.
-    let path = track.path.map(PathBuf::from).unwrap_or_else(|| {
-        state
-            .files
-            .subtitles
-            .join(&id)
-            .join(format!("{}.{}.vtt", id, code))
-    });
+    fn range(self) -> PageRange {
+        PageRange {
+            limit: self.per_page,
+            offset: (self.page - 1).saturating_mul(self.per_page),
+        }
+    }
+
+    fn into_page(self, items: &[VideoRecord], total: usize, description_chars: usize) -> Page {
+        Page {
+            items: list_video_records(items, description_chars),
+            total,
+            page: self.page,
+            per_page: self.per_page,
+        }
+    }
 
-    stream_file(path, Some("text/vtt".parse().unwrap())).await
+    /// Cuts this page out of a list that is already loaded.
+    fn slice_page(self, items: &[VideoRecord], description_chars: usize) -> Page {
+        let range = self.range();
+        let start = range.offset.min(items.len());
+        let end = start.saturating_add(range.limit).min(items.len());
+        self.into_page(&items[start..end], items.len(), description_chars)
+    }
 }
 
-async fn download_video_thumbnail(
-    State(state): State<AppState>,
-    AxumPath((id, file)): AxumPath<(String, String)>,
-) -> ApiResult<Response> {
-    download_thumbnail(state, id, file).await
+/// Body of `/api/videos`: the whole list, or only what changed when the
+/// client passes `?since=`.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum VideoListing {
+    Full(Vec<VideoRecord>),
+    Delta {
+        /// Oldest-to-newest by `refreshed_at`.
+        videos: Vec<VideoRecord>,
+        /// Cursor for the next poll: the position of the last video
+        /// returned, or the requested `since` when nothing changed.
+        next_since: String,
+    },
+    Page(Page),
 }
 
-async fn download_short_thumbnail(
+async fn list_videos(
     State(state): State<AppState>,
-    AxumPath((id, file)): AxumPath<(String, String)>,
-) -> ApiResult<Response> {
-    download_thumbnail(state, id, file).await
+    Query(query): Query<ListQuery>,
+) -> ApiResult<Json<VideoListing>> {
+    let since = query.since.as_deref().map(SinceCursor::parse).transpose()?;
+    let paging = PageRequest::from_query(query.page, query.per_page)?;
+    let sorted = query.sort.is_some() || query.order.is_some() || query.tag.is_some();
+    if since.is_some() && (paging.is_some() || sorted) {
+        return Err(ApiError::bad_request(
+            "since cannot be combined with page, per_page, sort, order or tag",
+        ));
+    }
+    if paging.is_some() || sorted {
+        let filter = ListFilter {
+            watched: query.watched,
+            channel_url: query.channel,
+            tag: query.tag,
+            sort: query.sort.unwrap_or_default(),
+            order: query.order.unwrap_or_default(),
+        };
+        let (videos, total) = state
+            .get_filtered_media(MediaCategory::Video, filter, paging.map(PageRequest::range))
+            .await?;
+        return Ok(Json(match paging {
+            Some(request) => {
+                VideoListing::Page(request.into_page(&videos, total, state.list_description_chars))
+            }
+            None => VideoListing::Full(list_video_records(&videos, state.list_description_chars)),
+        }));
+    }
+    if let Some(since) = since {
+        // Read from SQLite rather than the cached list, so rows the
+        // downloader wrote since the cache was filled are not missed.
+        let filter = ListFilter {
+            watched: query.watched,
+            channel_url: query.channel,
+            ..ListFilter::default()
+        };
+        let videos = state.get_refreshed_videos(filter, since.clone()).await?;
+        let next_since = match videos.last() {
+            Some(video) => SinceCursor {
+                refreshed_at: video.refreshed_at.clone().unwrap_or_default(),
+                videoid: video.videoid.clone(),
+            },
+            None => since,
+        };
+        return Ok(Json(VideoListing::Delta {
+            videos: list_video_records(&videos, state.list_description_chars),
+            next_since: next_since.encode(),
+        }));
+    }
+
+    let videos = match query.channel {
+        Some(channel) => {
+            state
+                .get_channel_media(MediaCategory::Video, channel)
+                .await?
+        }
+        None => state.get_media_list(MediaCategory::Video).await?,
+    };
+    let videos: Vec<VideoRecord> = match query.watched {
+        Some(watched) => videos
+            .into_iter()
+            .filter(|video| video.watched == watched)
+            .collect(),
+        None => videos,
+    };
+    Ok(Json(VideoListing::Full(list_video_records(
+        &videos,
+        state.list_description_chars,
+    ))))
 }
 
-async fn download_thumbnail(state: AppState, id: String, file: String) -> ApiResult<Response> {
-    ensure_safe_path_segment(&id)?;
-    ensure_safe_path_segment(&file)?;
-    let path = state.files.thumbnails.join(&id).join(&file);
-    stream_file(path, None).await
+/// Position in the `?since=` feed, which runs in `(refreshed_at, videoid)`
+/// order. Clients start from a plain RFC 3339 timestamp and then pass back
+/// each `next_since`, which is `<refreshed_at>~<videoid>` so that videos
+/// written in the same millisecond are neither skipped nor repeated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SinceCursor {
+    /// In the store's `refreshed_at` format (UTC, millisecond precision) so
+    /// the two compare as plain strings.
+    refreshed_at: String,
+    /// Last video already returned at `refreshed_at`; empty for a plain
+    /// timestamp, which includes videos written at exactly that instant.
+    videoid: String,
 }
 
-async fn stream_video_file(
-    State(state): State<AppState>,
-    AxumPath((id, format)): AxumPath<(String, String)>,
-) -> ApiResult<Response> {
-    stream_media(state, MediaCategory::Video, id, format).await
+impl SinceCursor {
+    fn parse(value: &str) -> ApiResult<Self> {
+        let (timestamp, videoid) = value.split_once('~').unwrap_or((value, ""));
+        let parsed = DateTime::parse_from_rfc3339(timestamp).map_err(|_| {
+            ApiError::bad_request("since must be an RFC 3339 timestamp or a next_since value")
+        })?;
+        Ok(Self {
+            refreshed_at: parsed
+                .with_timezone(&Utc)
+                .to_rfc3339_opts(SecondsFormat::Millis, true),
+            videoid: videoid.to_owned(),
+        })
+    }
+
+    fn encode(&self) -> String {
+        if self.videoid.is_empty() {
+            self.refreshed_at.clone()
+        } else {
+            format!("{}~{}", self.refreshed_at, self.videoid)
+        }
+    }
 }
 
-async fn stream_short_file(
-    State(state): State<AppState>,
-    AxumPath((id, format)): AxumPath<(String, String)>,
-) -> ApiResult<Response> {
-    stream_media(state, MediaCategory::Short, id, format).await
+/// Query string accepted by `/api/shorts`.
+#[derive(Debug, Default, Deserialize)]
+struct ShortsQuery {
+    order: Option<ShortsOrder>,
+    /// Column for `order=asc|desc`; the older `order=date|views` pick the
+    /// column themselves.
+    sort: Option<SortKey>,
+    /// Seed for `order=shuffle`; reusing it keeps the order (and therefore
+    /// paging) stable for a session.
+    seed: Option<u64>,
+    page: Option<usize>,
+    per_page: Option<usize>,
+    /// Exact stored `channel_url`; keeps only that channel's Shorts.
+    channel: Option<String>,
+    /// Keeps only Shorts carrying this tag.
+    tag: Option<String>,
 }
 
-async fn stream_media(
-    state: AppState,
-    category: MediaCategory,
-    id: String,
-    format: String,
-) -> ApiResult<Response> {
-    ensure_safe_path_segment(&id)?;
-    ensure_safe_path_segment(&format)?;
+impl ShortsQuery {
+    /// Sort key and direction of the feed, or `None` when it is shuffled.
+    fn sorting(&self) -> ApiResult<Option<(SortKey, SortOrder)>> {
+        Ok(Some(match (self.order, self.sort) {
+            (Some(ShortsOrder::Shuffle), None) => return Ok(None),
+            (Some(ShortsOrder::Date), None) => (SortKey::Date, SortOrder::Desc),
+            (Some(ShortsOrder::Views), None) => (SortKey::Views, SortOrder::Desc),
+            (Some(ShortsOrder::Asc), sort) => (sort.unwrap_or_default(), SortOrder::Asc),
+            (Some(ShortsOrder::Desc) | None, sort) => (sort.unwrap_or_default(), SortOrder::Desc),
+            (Some(_), Some(_)) => {
+                return Err(ApiError::bad_request(
+                    "sort only combines with order=asc or order=desc",
+                ));
+            }
+        }))
+    }
+}
 
-    // We load metadata first so we can map the requested format slug to a file
-    // path and mime type before hitting the disk.
-    let record = state.get_media(category, &id).await?;
+/// Body of `/api/shorts`: the whole feed, or one [`Page`] of it.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum ShortsListing {
+    Full(Vec<VideoRecord>),
+    Page(Page),
+}
 
-    let source = record
-        .sources
-        .iter()
-        .find(|source| source_key(source).as_deref() == Some(format.as_str()))
-        .ok_or_else(|| ApiError::not_found("requested format not found"))?;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ShortsOrder {
+    /// Newest first, like `/api/videos`.
+    Date,
+    /// Most viewed first; ties stay newest first.
+    Views,
+    Shuffle,
+    /// Direction of `sort`, as on `/api/videos`.
+    Asc,
+    Desc,
+}
 
-    let path = match &source.path {
-        Some(path) => PathBuf::from(path),
-        None => {
-            let ext = source.ext.as_deref().unwrap_or("mp4");
-            state
-                .files
-                .media_dir(category)
-                .join(&id)
-                .join(format!("{}_{}.{}", id, format, ext))
+/// Seed actually used for a shuffled feed, so clients that did not pass one
+/// can pin it for the rest of the session.
+const SHUFFLE_SEED_HEADER: &str = "x-shuffle-seed";
+
+async fn list_shorts(
+    State(state): State<AppState>,
+    Query(query): Query<ShortsQuery>,
+) -> ApiResult<(HeaderMap, Json<ShortsListing>)> {
+    let paging = PageRequest::from_query(query.page, query.per_page)?;
+    let Some((sort, order)) = query.sorting()? else {
+        let mut shorts = match (query.tag, query.channel) {
+            (Some(tag), channel_url) => {
+                let filter = ListFilter {
+                    channel_url,
+                    tag: Some(tag),
+                    ..ListFilter::default()
+                };
+                state
+                    .get_filtered_media(MediaCategory::Short, filter, None)
+                    .await?
+                    .0
+            }
+            (None, Some(channel)) => {
+                state
+                    .get_channel_media(MediaCategory::Short, channel)
+                    .await?
+            }
+            (None, None) => state.get_media_list(MediaCategory::Short).await?,
+        };
+        // The shuffled order only exists in memory, so pages are sliced out
+        // of the whole feed rather than queried.
+        let seed = query.seed.unwrap_or_else(|| Uuid::new_v4().as_u64_pair().0);
+        seeded_shuffle(&mut shorts, seed);
+        let mut headers = HeaderMap::new();
+        headers.insert(SHUFFLE_SEED_HEADER, HeaderValue::from(seed));
+        let listing = match paging {
+            Some(request) => {
+                ShortsListing::Page(request.slice_page(&shorts, state.list_description_chars))
+            }
+            None => ShortsListing::Full(list_video_records(&shorts, state.list_description_chars)),
+        };
+        return Ok((headers, Json(listing)));
+    };
+
+    let filter = ListFilter {
+        watched: None,
+        channel_url: query.channel,
+        tag: query.tag,
+        sort,
+        order,
+    };
+    // The plain feed the bundled frontend asks for comes from the cache.
+    if paging.is_none() && filter.tag.is_none() && (sort, order) == Default::default() {
+        let shorts = match filter.channel_url {
+            Some(channel) => {
+                state
+                    .get_channel_media(MediaCategory::Short, channel)
+                    .await?
+            }
+            None => state.get_media_list(MediaCategory::Short).await?,
+        };
+        let listing =
+            ShortsListing::Full(list_video_records(&shorts, state.list_description_chars));
+        return Ok((HeaderMap::new(), Json(listing)));
+    }
+    let (shorts, total) = state
+        .get_filtered_media(MediaCategory::Short, filter, paging.map(PageRequest::range))
+        .await?;
+    let listing = match paging {
+        Some(request) => {
+            ShortsListing::Page(request.into_page(&shorts, total, state.list_description_chars))
         }
+        None => ShortsListing::Full(list_video_records(&shorts, state.list_description_chars)),
     };
+    Ok((HeaderMap::new(), Json(listing)))
+}
 
-    stream_file(
-        path,
-        source.mime_type.as_ref().and_then(|mime| mime.parse().ok()),
-    )
-    .await
+/// Fisher-Yates driven by SplitMix64, so a seed maps to the same order on
+/// every platform and release.
+fn seeded_shuffle<T>(items: &mut [T], seed: u64) {
+    let mut state = seed;
+    for index in (1..items.len()).rev() {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut mixed = state;
+        mixed = (mixed ^ (mixed >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        mixed ^= mixed >> 31;
+        let other = (mixed % (index as u64 + 1)) as usize;
+        items.swap(index, other);
+    }
 }
 
-/// Lightweight response that exposes a download URL for each subtitle track.
-#[derive(serde::Serialize)]
-struct SubtitleInfo {
-    code: String,
-    name: String,
-    url: String,
+/// Results returned by `/api/search` when the client does not pass `limit`.
+const SEARCH_DEFAULT_LIMIT: usize = 50;
+/// Upper bound on `limit` for `/api/search`.
+const SEARCH_MAX_LIMIT: usize = 200;
+
+/// Query string accepted by `/api/search`.
+#[derive(Debug, Default, Deserialize)]
+struct SearchQuery {
+    #[serde(default)]
+    q: String,
+    #[serde(default)]
+    kind: SearchKind,
+    limit: Option<usize>,
 }
 
-/// Payload returned by `/api/bootstrap` so the client can hydrate offline.
-#[derive(Clone, Serialize)]
-struct BootstrapPayload {
-    videos: Vec<VideoRecord>,
-    shorts: Vec<VideoRecord>,
-    subtitles: Vec<SubtitleCollection>,
-    comments: Vec<CommentRecord>,
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SearchKind {
+    #[default]
+    Videos,
+    Shorts,
 }
 
-impl AppState {
-    /// Returns a cached snapshot containing everything the SPA needs to boot
-    /// without hitting follow-up endpoints (videos, shorts, subtitles,
-    /// comments). The heavy lifting runs in a blocking task because SQLite is a
-    /// synchronous API.
-    async fn get_bootstrap(&self) -> ApiResult<Arc<BootstrapPayload>> {
-        if let Some(cached) = self.cache.bootstrap.read().clone() {
-            return Ok(cached);
-        }
+/// Full-text search over titles, descriptions and tags, best match first.
+async fn search(
+    State(state): State<AppState>,
+    Query(query): Query<SearchQuery>,
+) -> ApiResult<Json<Vec<VideoRecord>>> {
+    if query.q.trim().is_empty() {
+        return Err(ApiError::bad_request("q must not be empty"));
+    }
+    let limit = query
+        .limit
+        .unwrap_or(SEARCH_DEFAULT_LIMIT)
+        .clamp(1, SEARCH_MAX_LIMIT);
+
+    let reader = state.reader.clone();
+    let results = task::spawn_blocking(move || match query.kind {
+        SearchKind::Videos => reader.search_videos(&query.q, limit),
+        SearchKind::Shorts => reader.search_shorts(&query.q, limit),
+    })
+    .await
+    .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
+    .map_err(|err| ApiError::internal(err.to_string()))?;
 
-        let reader = self.reader.clone();
-        let payload = task::spawn_blocking(move || -> Result<BootstrapPayload> {
-            let videos = reader.list_videos()?;
-            let shorts = reader.list_shorts()?;
-            let subtitles = reader.list_subtitles()?;
-            let comments = reader.list_all_comments()?;
-            Ok(BootstrapPayload {
-                videos: sanitize_video_records(&videos),
-                shorts: sanitize_video_records(&shorts),
-                subtitles,
-                comments,
-            })
-        })
-        .await
-        .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
-        .map_err(|err| ApiError::internal(err.to_string()))?;
+    Ok(Json(list_video_records(
+        &results,
+        state.list_description_chars,
+    )))
+}
 
-        let payload = Arc::new(payload);
-        self.cache.bootstrap.write().replace(payload.clone());
-        Ok(payload)
+#[derive(Debug, Deserialize)]
+struct ByDateQuery {
+    year: Option<i32>,
+    month: Option<u32>,
+    day: Option<u32>,
+}
+
+/// `GET /api/videos/by-date`: `?month=&day=` lists videos uploaded on that
+/// calendar date in any year ("on this day"), `?year=&month=` one archive
+/// month. Newest first.
+async fn list_videos_by_date(
+    State(state): State<AppState>,
+    Query(query): Query<ByDateQuery>,
+) -> ApiResult<Json<Vec<VideoRecord>>> {
+    let Some(month) = query.month else {
+        return Err(ApiError::bad_request("month is required"));
+    };
+    if !(1..=12).contains(&month) {
+        return Err(ApiError::bad_request("month must be between 1 and 12"));
+    }
+    if query.day.is_some_and(|day| !(1..=31).contains(&day)) {
+        return Err(ApiError::bad_request("day must be between 1 and 31"));
+    }
+    if query.year.is_some_and(|year| !(1..=9999).contains(&year)) {
+        return Err(ApiError::bad_request("year must be between 1 and 9999"));
     }
 
-    /// Retrieves every video/short record, memoizing both the list and the
-    /// individual details map for quick follow-up lookups.
-    async fn get_media_list(&self, category: MediaCategory) -> ApiResult<Vec<VideoRecord>> {
-        if let Some(cached) = self.cache.media_list(category).read().clone() {
-            return Ok(cached);
-        }
+    let reader = state.reader.clone();
+    let results =
+        task::spawn_blocking(move || reader.list_videos_by_date(query.year, month, query.day))
+            .await
+            .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
+            .map_err(|err| ApiError::internal(err.to_string()))?;
 
-        let reader = self.reader.clone();
-        let records = task::spawn_blocking(move || match category {
-            MediaCategory::Video => reader.list_videos(),
-            MediaCategory::Short => reader.list_shorts(),
-        })
-        .await
-        .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
-        .map_err(|err| ApiError::internal(err.to_string()))?;
+    Ok(Json(list_video_records(
+        &results,
+        state.list_description_chars,
+    )))
+}
 
-        self.cache
-            .media_list(category)
-            .write()
-            .replace(records.clone());
+async fn get_video(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Json<VideoRecord>> {
+    let record = state.get_media(MediaCategory::Video, &id).await?;
+    Ok(Json(sanitize_video_record(&record)))
+}
 
-        let mut details = self.cache.media_details(category).write();
-        for record in &records {
-            details.insert(record.videoid.clone(), record.clone());
-        }
+/// Everything a watch page needs, returned by `GET /api/videos/{id}/page`.
+#[derive(Serialize)]
+struct VideoPage {
+    video: VideoRecord,
+    /// First [`PAGE_COMMENT_LIMIT`] comments; `comment_total` counts them all.
+    comments: Vec<CommentRecord>,
+    comment_total: usize,
+    subtitles: Vec<SubtitleInfo>,
+    chapters: Vec<Chapter>,
+    /// Newest videos from the same channel, at most [`PAGE_RELATED_LIMIT`].
+    related: Vec<VideoRecord>,
+}
 
-        Ok(records)
-    }
+/// Assembles a watch page in one round trip from the same cached helpers the
+/// individual endpoints use.
+async fn get_video_page(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Json<VideoPage>> {
+    let record = state.get_media(MediaCategory::Video, &id).await?;
+    let mut comments = state.get_comments(&id).await?;
+    let comment_total = comments.len();
+    comments.truncate(PAGE_COMMENT_LIMIT);
+    let subtitles = subtitle_infos(&state, &id, "videos").await?;
+    let chapters = record.chapters.clone();
+
+    let channel = record
+        .channel_url
+        .as_deref()
+        .map(canonical_channel_url)
+        .or_else(|| channel_id(&record).map(str::to_owned));
+    let related = match channel {
+        Some(channel) => state
+            .get_media_list(MediaCategory::Video)
+            .await?
+            .iter()
+            .filter(|other| other.videoid != record.videoid)
+            .filter(|other| {
+                other
+                    .channel_url
+                    .as_deref()
+                    .map(canonical_channel_url)
+                    .as_ref()
+                    == Some(&channel)
+                    || channel_id(other) == Some(channel.as_str())
+            })
+            .take(PAGE_RELATED_LIMIT)
+            .map(|other| list_video_record(other, state.list_description_chars))
+            .collect(),
+        None => Vec::new(),
+    };
 
-    /// Loads metadata for a single video or short, preferring the cache before
-    /// falling back to SQLite.
-    async fn get_media(&self, category: MediaCategory, videoid: &str) -> ApiResult<VideoRecord> {
-        if let Some(record) = self
-            .cache
-            .media_details(category)
-            .read()
-            .get(videoid)
-            .cloned()
-        {
-            return Ok(record);
-        }
+    Ok(Json(VideoPage {
+        video: sanitize_video_record(&record),
+        comments,
+        comment_total,
+        subtitles,
+        chapters,
+        related,
+    }))
+}
 
-        let reader = self.reader.clone();
-        let result = task::spawn_blocking({
-            let videoid = videoid.to_owned();
-            move || match category {
-                MediaCategory::Video => reader.get_video(&videoid),
-                MediaCategory::Short => reader.get_short(&videoid),
-            }
-        })
+/// Autoplay target: the channel's next upload after `id`, or `204 No
+/// Content` once the newest one has played.
+async fn get_next_video(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Response> {
+    state.get_media(MediaCategory::Video, &id).await?;
+
+    let reader = state.reader.clone();
+    let next = task::spawn_blocking(move || reader.next_in_channel(&id))
         .await
         .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
         .map_err(|err| ApiError::internal(err.to_string()))?;
 
-        let record = result.ok_or_else(|| ApiError::not_found("video not found"))?;
+    Ok(match next {
+        Some(record) => {
+            Json(list_video_record(&record, state.list_description_chars)).into_response()
+        }
+        None => StatusCode::NO_CONTENT.into_response(),
+    })
+}
 
-        self.cache
-            .media_details(category)
-            .write()
-            .insert(videoid.to_owned(), record.clone());
+/// Which yt-dlp build and extractor wrote a record, from its
+/// `extras.provenance`. Fields are `null` for records downloaded before this
+/// was captured.
+#[derive(Debug, Serialize)]
+struct Provenance {
+    videoid: String,
+    extractor: Option<String>,
+    extractor_key: Option<String>,
+    yt_dlp_version: Option<String>,
+    /// When the metadata was last written by that build.
+    refreshed_at: Option<String>,
+}
 
-        Ok(record)
+impl Provenance {
+    fn of(record: &VideoRecord) -> Self {
+        let field = |key: &str| {
+            record
+                .extras
+                .pointer(&format!("/provenance/{key}"))
+                .and_then(|value| value.as_str())
+                .map(str::to_owned)
+        };
+        Self {
+            videoid: record.videoid.clone(),
+            extractor: field("extractor"),
+            extractor_key: field("extractorKey"),
+            yt_dlp_version: field("ytDlpVersion"),
+            refreshed_at: record.refreshed_at.clone(),
+        }
     }
+}
 
-    /// Lazy-loads comment threads; we store them keyed by id because comment
-    /// payloads are far smaller than video blobs.
-    async fn get_comments(&self, videoid: &str) -> ApiResult<Vec<CommentRecord>> {
-        if let Some(cached) = self.cache.comments.read().get(videoid).cloned() {
-            return Ok(cached);
-        }
+async fn get_video_provenance(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Json<Provenance>> {
+    let record = state.get_media(MediaCategory::Video, &id).await?;
+    Ok(Json(Provenance::of(&record)))
+}
 
-        let reader = self.reader.clone();
-        let comments = task::spawn_blocking({
-            let videoid = videoid.to_owned();
-            move || reader.get_comments(&videoid)
-        })
-        .await
-        .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
-        .map_err(|err| ApiError::internal(err.to_string()))?;
+async fn get_short_provenance(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Json<Provenance>> {
+    let record = state.get_media(MediaCategory::Short, &id).await?;
+    Ok(Json(Provenance::of(&record)))
+}
 
-        self.cache
-            .comments
-            .write()
-            .insert(videoid.to_owned(), comments.clone());
+fn channel_id(record: &VideoRecord) -> Option<&str> {
+    record
+        .extras
+        .get("channelId")
+        .and_then(|value| value.as_str())
+}
 
-        Ok(comments)
-    }
+async fn get_short(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Json<VideoRecord>> {
+    let record = state.get_media(MediaCategory::Short, &id).await?;
+    Ok(Json(sanitize_video_record(&record)))
+}
 
-    /// Provides subtitle metadata if available. Not every video has subtitles
-    /// so the API returns an Option.
-    async fn get_subtitles(&self, videoid: &str) -> ApiResult<Option<SubtitleCollection>> {
-        if let Some(cached) = self.cache.subtitles.read().get(videoid).cloned() {
-            return Ok(Some(cached));
-        }
+/// Query string accepted by the `/comments` endpoints.
+#[derive(Debug, Default, Deserialize)]
+struct CommentsQuery {
+    /// Nest replies under their parents instead of returning a flat list.
+    #[serde(default)]
+    threaded: bool,
+}
 
-        let reader = self.reader.clone();
-        let result = task::spawn_blocking({
-            let videoid = videoid.to_owned();
-            move || reader.get_subtitles(&videoid)
-        })
+async fn get_video_comments(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+    Query(query): Query<CommentsQuery>,
+) -> ApiResult<Response> {
+    let comments = state.get_comments(&id).await?;
+    if query.threaded {
+        return Ok(Json(build_comment_threads(comments)).into_response());
+    }
+    Ok(Json(comments).into_response())
+}
+
+/// One comment and its replies, for deep links that should not load the
+/// whole comment section.
+async fn get_comment_thread(
+    State(state): State<AppState>,
+    AxumPath((id, comment_id)): AxumPath<(String, String)>,
+) -> ApiResult<Json<CommentThread>> {
+    let reader = state.reader.clone();
+    let thread = task::spawn_blocking(move || reader.get_comment_thread(&id, &comment_id))
         .await
         .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
         .map_err(|err| ApiError::internal(err.to_string()))?;
-
-        if let Some(collection) = &result {
-            self.cache
-                .subtitles
-                .write()
-                .insert(videoid.to_owned(), collection.clone());
-        }
-
-        Ok(result)
-    }
+    thread
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found("comment not found"))
 }
 
-/// Normalizes a VideoSource URL by keeping only the trailing segment. During
-/// download we store files named `{videoid}_{format}` and the format parameter
-/// is the only piece users need to specify.
-fn source_key(source: &VideoSource) -> Option<String> {
-    source.url.rsplit('/').next().map(|value| value.to_owned())
+async fn list_video_subtitles(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Json<Vec<SubtitleInfo>>> {
+    list_subtitles(state, id, "videos").await
 }
 
-/// Validates that a single dynamic path segment never escapes its base folder.
-fn ensure_safe_path_segment(value: &str) -> ApiResult<()> {
-    if value.is_empty()
-        || Path::new(value)
-            .components()
-            .any(|component| !matches!(component, Component::Normal(_)))
-    {
-        return Err(ApiError::not_found("file not found"));
-    }
-
-    Ok(())
+async fn list_short_subtitles(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Json<Vec<SubtitleInfo>>> {
+    list_subtitles(state, id, "shorts").await
 }
 
-async fn stream_file(path: PathBuf, mime: Option<Mime>) -> ApiResult<Response> {
-    let file = File::open(&path)
-        .await
-        .map_err(|_| ApiError::not_found("file not found"))?;
+async fn list_subtitles(
+    state: AppState,
+    id: String,
+    slug: &'static str,
+) -> ApiResult<Json<Vec<SubtitleInfo>>> {
+    Ok(Json(subtitle_infos(&state, &id, slug).await?))
+}
 
-    // Either use the explicit mime provided by the VideoSource or infer it from
-    // the file extension. Setting CONTENT_TYPE hints allows browsers to stream
-    // video without sniffing.
-    let guessed = mime.or_else(|| MimeGuess::from_path(&path).first());
-    let stream = ReaderStream::new(file);
-    let body = Body::from_stream(stream);
-    let mut response = body.into_response();
-    if let Some(mime) = guessed
-        && let Ok(value) = mime.to_string().parse()
-    {
-        response.headers_mut().insert(header::CONTENT_TYPE, value);
+/// Builds lightweight DTOs that point the frontend to the download
+/// endpoints; the actual subtitle JSON remains cached server side.
+async fn subtitle_infos(
+    state: &AppState,
+    id: &str,
+    slug: &'static str,
+) -> ApiResult<Vec<SubtitleInfo>> {
+    let mut response = Vec::new();
+    if let Some(collection) = state.get_subtitles(id).await? {
+        for track in collection.languages {
+            let url = format!("/api/{slug}/{}/subtitles/{}", id, track.code);
+            response.push(SubtitleInfo {
+                code: track.code,
+                name: track.name,
+                url,
+            });
+        }
     }
-
     Ok(response)
 }
 
-fn sanitize_video_records(records: &[VideoRecord]) -> Vec<VideoRecord> {
-    records.iter().map(sanitize_video_record).collect()
+async fn download_video_subtitle(
+    State(state): State<AppState>,
+    AxumPath((id, code)): AxumPath<(String, String)>,
+) -> ApiResult<Response> {
+    download_subtitle(state, id, code).await
 }
 
-fn sanitize_video_record(record: &VideoRecord) -> VideoRecord {
-    let mut clone = record.clone();
-    for source in &mut clone.sources {
-        source.path = None;
-    }
-    clone
+async fn download_short_subtitle(
+    State(state): State<AppState>,
+    AxumPath((id, code)): AxumPath<(String, String)>,
+) -> ApiResult<Response> {
+    download_subtitle(state, id, code).await
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use axum::{body::to_bytes, extract::State as AxumState};
-    use serde_json::Value;
-    use std::{io::Write, path::PathBuf, sync::Arc};
-    use tempfile::{NamedTempFile, tempdir};
+async fn download_subtitle(state: AppState, id: String, code: String) -> ApiResult<Response> {
+    let path = subtitle_track_path(&state, &id, Some(&code)).await?;
+    serve_file(
+        &state,
+        path,
+        Some("text/vtt".parse().unwrap()),
+        &HeaderMap::new(),
+    )
+    .await
+}
 
-    struct BackendTestContext {
-        _temp: tempfile::TempDir,
-        db_path: PathBuf,
-        store: MetadataStore,
-        state: AppState,
+/// Finds the file for the `code` track of `id`, or the first stored track
+/// when no code is given.
+async fn subtitle_track_path(state: &AppState, id: &str, code: Option<&str>) -> ApiResult<PathBuf> {
+    ensure_safe_path_segment(id)?;
+    if let Some(code) = code {
+        ensure_safe_path_segment(code)?;
     }
 
-    impl BackendTestContext {
-        fn new() -> Self {
-            let temp = tempdir().unwrap();
-            let db_path = temp.path().join("metadata.db");
-            let store = MetadataStore::open(&db_path).unwrap();
-            let reader = MetadataReader::new(&db_path).unwrap();
-            let files = FilePaths::for_base(temp.path());
+    let subtitles = state
+        .get_subtitles(id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("subtitles not available"))?;
 
-            Self {
-                state: AppState {
-                    reader: Arc::new(reader),
-                    cache: Arc::new(ApiCache::new()),
-                    files: Arc::new(files),
-                },
-                db_path,
-                store,
-                _temp: temp,
-            }
-        }
+    let track = subtitles
+        .languages
+        .into_iter()
+        .find(|track| code.is_none_or(|code| track.code == code))
+        .ok_or_else(|| ApiError::not_found("subtitle track not found"))?;
 
-        fn insert_video(&mut self, id: &str) {
-            self.store.upsert_video(&sample_video(id)).unwrap();
-        }
+    // Prefer the explicit filesystem path recorded during download, but fall
+    // back to the standard `videoid/lang` layout when missing.
+    Ok(track.path.map(PathBuf::from).unwrap_or_else(|| {
+        state
+            .files
+            .video_dir(&state.files.subtitles, id)
+            .join(format!("{}.{}.vtt", id, track.code))
+    }))
+}
 
-        fn insert_short(&mut self, id: &str) {
-            self.store.upsert_short(&sample_video(id)).unwrap();
-        }
+/// Query string accepted by `/api/{videos,shorts}/{id}/transcript`.
+#[derive(Debug, Default, Deserialize)]
+struct TranscriptQuery {
+    /// Subtitle track code; defaults to the first stored track.
+    lang: Option<String>,
+}
 
-        fn insert_subtitles(&mut self, id: &str, tracks: Vec<SubtitleTrack>) {
-            self.store
-                .upsert_subtitles(&SubtitleCollection {
-                    videoid: id.into(),
-                    languages: tracks,
-                })
-                .unwrap();
-        }
+/// Returns a subtitle track as plain text, for copy-paste and search.
+async fn get_transcript(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+    Query(query): Query<TranscriptQuery>,
+) -> ApiResult<Response> {
+    let path = subtitle_track_path(&state, &id, query.lang.as_deref()).await?;
+    let raw = tokio::fs::read(&path)
+        .await
+        .map_err(|_| ApiError::not_found("subtitle file missing"))?;
+    let transcript = subtitle_to_transcript(&String::from_utf8_lossy(&raw));
 
-        fn insert_comments(&mut self, id: &str, comments: Vec<CommentRecord>) {
-            self.store
-                .replace_comments(id, &comments)
-                .expect("comments persisted");
-        }
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        transcript,
+    )
+        .into_response())
+}
 
-        fn delete_by_videoid(&self, table: &str, value: &str) {
-            let conn = Connection::open(&self.db_path).unwrap();
-            conn.execute(&format!("DELETE FROM {table} WHERE videoid = ?1"), [value])
-                .unwrap();
+/// Strips timings, cue settings and inline markup from a WebVTT or SRT file.
+/// Auto-generated captions repeat the previous line at the top of each cue,
+/// so consecutive duplicate lines are collapsed.
+fn subtitle_to_transcript(raw: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let normalized = raw.replace("\r\n", "\n");
+
+    for block in normalized.split("\n\n") {
+        // Header, NOTE, STYLE and REGION blocks carry no timing line.
+        let mut block_lines = block.lines();
+        if !block_lines.any(|line| line.contains("-->")) {
+            continue;
         }
-    }
 
-    fn sample_video(id: &str) -> VideoRecord {
-        VideoRecord {
-            videoid: id.into(),
-            title: format!("Video {id}"),
-            description: "desc".into(),
-            likes: Some(1),
-            dislikes: Some(0),
-            views: Some(10),
-            upload_date: Some("2024-01-01T00:00:00Z".into()),
-            author: Some("Channel".into()),
-            subscriber_count: Some(100),
-            duration: Some(60),
-            duration_text: Some("1:00".into()),
-            channel_url: Some("https://example.test/channel".into()),
-            thumbnail_url: Some("/thumb.jpg".into()),
-            tags: vec![],
-            thumbnails: vec![],
-            extras: json!(null),
-            sources: vec![VideoSource {
-                format_id: "1080p".into(),
-                quality_label: Some("1080p".into()),
-                width: Some(1920),
-                height: Some(1080),
-                fps: Some(30.0),
-                mime_type: Some("video/mp4".into()),
-                ext: Some("mp4".into()),
-                file_size: Some(1024),
-                url: format!("/api/videos/{id}/streams/1080p"),
-                path: None,
-            }],
+        for line in block_lines {
+            let text = strip_cue_markup(line);
+            if text.is_empty() || lines.last() == Some(&text) {
+                continue;
+            }
+            lines.push(text);
         }
     }
 
-    fn sample_comment(id: &str, videoid: &str) -> CommentRecord {
-        CommentRecord {
-            id: id.into(),
-            videoid: videoid.into(),
-            author: "tester".into(),
-            text: "hello world".into(),
-            likes: Some(1),
-            time_posted: Some("2024-01-01T00:00:00Z".into()),
-            parent_comment_id: None,
-            status_likedbycreator: false,
-            reply_count: Some(0),
-        }
+    let mut transcript = lines.join("\n");
+    if !transcript.is_empty() {
+        transcript.push('\n');
     }
+    transcript
+}
 
-    fn write_runtime_config(media: &str, www: &str, port: u16, host: &str) -> NamedTempFile {
-        let mut file = NamedTempFile::new().unwrap();
-        writeln!(
-            file,
-            "MEDIA_ROOT=\"{media}\"\nWWW_ROOT=\"{www}\"\nNEWTUBE_PORT=\"{port}\"\nNEWTUBE_HOST=\"{host}\"\nAPP_VERSION=\"1.0\"\nDOMAIN_NAME=\"example.com\"\n"
-        )
-        .unwrap();
-        file
-    }
+/// Serves the stored chapters as a WebVTT file for `<track kind="chapters">`.
+async fn get_chapters_vtt(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Response> {
+    let record = state.get_media(MediaCategory::Video, &id).await?;
+    let Some(vtt) = chapters_to_vtt(&record.chapters, record.duration) else {
+        return Err(ApiError::not_found("video has no chapters"));
+    };
 
-    fn parse_backend_args(config: &NamedTempFile, extra: &[&str]) -> BackendArgs {
-        let mut argv = vec![
-            "--config".to_string(),
-            config.path().to_string_lossy().into_owned(),
-        ];
-        argv.extend(extra.iter().map(|value| value.to_string()));
-        BackendArgs::from_iter(argv).expect("parsed args")
-    }
+    Ok(([(header::CONTENT_TYPE, "text/vtt; charset=utf-8")], vtt).into_response())
+}
 
-    #[test]
-    fn backend_args_default_media_root() {
-        let config = write_runtime_config("/yt/test", "/www/test", 4242, "127.0.0.1");
-        let args = parse_backend_args(&config, &[]);
-        assert_eq!(args.media_root, PathBuf::from("/yt/test"));
-        assert_eq!(args.newtube_port, 4242);
+/// SponsorBlock segments stored for the video, `[]` when it was never
+/// downloaded with `--sponsorblock`.
+async fn get_sponsor_segments(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Json<Vec<SponsorSegment>>> {
+    let record = state.get_media(MediaCategory::Video, &id).await?;
+    Ok(Json(record.sponsor_segments.clone().unwrap_or_default()))
+}
+
+/// Formats chapters as WebVTT cues. A missing end time falls back to the next
+/// chapter's start, then to the video duration. Returns `None` when no cue
+/// has a positive length.
+fn chapters_to_vtt(chapters: &[Chapter], duration: Option<i64>) -> Option<String> {
+    let mut vtt = String::from("WEBVTT\n");
+    let mut cues = 0;
+    for (index, chapter) in chapters.iter().enumerate() {
+        let end = chapter
+            .end_time
+            .or_else(|| chapters.get(index + 1).map(|next| next.start_time))
+            .or_else(|| duration.map(|seconds| seconds as f64));
+        let Some(end) = end.filter(|end| *end > chapter.start_time) else {
+            continue;
+        };
+        cues += 1;
+        let title = chapter.title.trim();
+        let title = if title.is_empty() {
+            format!("Chapter {cues}")
+        } else {
+            title.replace("-->", "->")
+        };
+        vtt.push_str(&format!(
+            "\n{cues}\n{} --> {}\n{title}\n",
+            vtt_timestamp(chapter.start_time),
+            vtt_timestamp(end)
+        ));
     }
+    (cues > 0).then_some(vtt)
+}
 
-    #[test]
+/// `HH:MM:SS.mmm`, as WebVTT requires.
+fn vtt_timestamp(seconds: f64) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Removes `<...>` tags (voice spans, inline timestamps, styling) and
+/// decodes the handful of entities WebVTT allows.
+fn strip_cue_markup(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut in_tag = false;
+    for ch in line.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(ch),
+            _ => {}
+        }
+    }
+
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+async fn download_video_thumbnail(
+    State(state): State<AppState>,
+    AxumPath((id, file)): AxumPath<(String, String)>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    download_thumbnail(state, id, file, &headers).await
+}
+
+async fn download_short_thumbnail(
+    State(state): State<AppState>,
+    AxumPath((id, file)): AxumPath<(String, String)>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    download_thumbnail(state, id, file, &headers).await
+}
+
+/// Thumbnails get the same validators and `Cache-Control` as media streams.
+async fn download_thumbnail(
+    state: AppState,
+    id: String,
+    file: String,
+    headers: &HeaderMap,
+) -> ApiResult<Response> {
+    ensure_safe_path_segment(&id)?;
+    ensure_safe_path_segment(&file)?;
+    let path = state
+        .files
+        .video_dir(&state.files.thumbnails, &id)
+        .join(&file);
+    let response = serve_file(&state, path, None, headers).await?;
+    Ok(with_media_cache_control(response))
+}
+
+async fn stream_video_file(
+    State(state): State<AppState>,
+    AxumPath((id, format)): AxumPath<(String, String)>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    stream_media(state, MediaCategory::Video, id, format, &headers).await
+}
+
+async fn stream_short_file(
+    State(state): State<AppState>,
+    AxumPath((id, format)): AxumPath<(String, String)>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    stream_media(state, MediaCategory::Short, id, format, &headers).await
+}
+
+/// Raw `Range` header value, if present and valid UTF-8.
+fn range_header(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+}
+
+async fn stream_media(
+    state: AppState,
+    category: MediaCategory,
+    id: String,
+    format: String,
+    headers: &HeaderMap,
+) -> ApiResult<Response> {
+    ensure_safe_path_segment(&id)?;
+    ensure_safe_path_segment(&format)?;
+
+    // We load metadata first so we can map the requested format slug to a file
+    // path and mime type before hitting the disk.
+    let record = state.get_media(category, &id).await?;
+
+    let source = record
+        .sources
+        .iter()
+        .find(|source| source_matches(source, &format))
+        .ok_or_else(|| ApiError::not_found("requested format not found"))?;
+
+    serve_source(state, category, &id, source, headers).await
+}
+
+/// Audio-only track kept by `download_channel --keep-audio`, for background
+/// listening. `404` when the video was downloaded without it.
+async fn stream_video_audio(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    ensure_safe_path_segment(&id)?;
+    let record = state.get_media(MediaCategory::Video, &id).await?;
+    let source = record
+        .audio_source
+        .as_ref()
+        .ok_or_else(|| ApiError::not_found("video has no audio-only track"))?;
+    serve_source(state, MediaCategory::Video, &id, source, &headers).await
+}
+
+/// Streams the file behind `source` with its mime type and long-lived
+/// caching headers.
+async fn serve_source(
+    state: AppState,
+    category: MediaCategory,
+    id: &str,
+    source: &VideoSource,
+    headers: &HeaderMap,
+) -> ApiResult<Response> {
+    let format = &source.format_id;
+    let path = resolve_source_path(&state.files, category, id, source);
+
+    // An empty file is what an interrupted or failed download leaves behind;
+    // serving it as an empty 200 just shows a broken player.
+    if tokio::fs::metadata(&path)
+        .await
+        .is_ok_and(|meta| meta.len() == 0)
+    {
+        warn!(
+            video_id = %id,
+            format = %format,
+            "{} is empty (likely a failed download); answering 404",
+            path.display()
+        );
+        return Err(ApiError::not_found("media file is empty"));
+    }
+
+    let response = serve_file(
+        &state,
+        path,
+        source.mime_type.as_ref().and_then(|mime| mime.parse().ok()),
+        headers,
+    )
+    .await?;
+    Ok(with_media_cache_control(response))
+}
+
+/// Adds [`MEDIA_CACHE_CONTROL`] to successful and `304` responses.
+fn with_media_cache_control(mut response: Response) -> Response {
+    if response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED {
+        response.headers_mut().insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(MEDIA_CACHE_CONTROL),
+        );
+    }
+    response
+}
+
+/// Body accepted by `PUT /api/videos/{id}/poster`.
+#[derive(Debug, Deserialize)]
+struct PosterRequest {
+    /// File name inside `thumbnails/<id>/`, e.g. `maxresdefault.jpg`.
+    file: String,
+}
+
+/// Lets operators pick which downloaded thumbnail is used as the poster
+/// instead of the downloader's alphabetical first pick.
+async fn set_video_poster(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(id): AxumPath<String>,
+    Json(request): Json<PosterRequest>,
+) -> ApiResult<Json<VideoRecord>> {
+    require_admin(&state, &headers)?;
+    ensure_safe_path_segment(&id)?;
+    ensure_safe_path_segment(&request.file)
+        .map_err(|_| ApiError::bad_request("invalid thumbnail file name"))?;
+
+    // Only accept files the downloader actually stored for this video.
+    let candidate = state
+        .files
+        .video_dir(&state.files.thumbnails, &id)
+        .join(&request.file);
+    let exists = tokio::fs::metadata(&candidate)
+        .await
+        .map(|meta| meta.is_file())
+        .unwrap_or(false);
+    if !exists {
+        return Err(ApiError::bad_request(format!(
+            "thumbnail {} does not exist for video {}",
+            request.file, id
+        )));
+    }
+
+    let url = format!("/api/videos/{}/thumbnails/{}", id, request.file);
+    let updated = state
+        .with_store({
+            let id = id.clone();
+            move |store| store.set_thumbnail_url(&id, &url)
+        })
+        .await?;
+    if !updated {
+        return Err(ApiError::not_found("video not found"));
+    }
+
+    state.cache.invalidate_media(MediaCategory::Video, &id);
+    let record = state.get_media(MediaCategory::Video, &id).await?;
+    Ok(Json(sanitize_video_record(&record)))
+}
+
+/// Removes a video's metadata so operators can prune takedowns. Media files
+/// on disk are left for the operator to clean up; the store's tombstone keeps
+/// the next channel refresh from indexing the video again.
+async fn delete_video(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<StatusCode> {
+    require_admin(&state, &headers)?;
+    ensure_safe_path_segment(&id)?;
+
+    let deleted = state
+        .with_store({
+            let id = id.clone();
+            move |store| store.delete_video(&id)
+        })
+        .await?;
+    if !deleted {
+        return Err(ApiError::not_found("video not found"));
+    }
+
+    state.cache.forget_media(MediaCategory::Video, &id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Body accepted by the `/progress` endpoints.
+#[derive(Debug, Deserialize)]
+struct ProgressRequest {
+    /// Current playback position in seconds.
+    position: f64,
+}
+
+async fn record_video_progress(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+    Json(request): Json<ProgressRequest>,
+) -> ApiResult<StatusCode> {
+    record_progress(state, MediaCategory::Video, id, request.position).await
+}
+
+async fn record_short_progress(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+    Json(request): Json<ProgressRequest>,
+) -> ApiResult<StatusCode> {
+    record_progress(state, MediaCategory::Short, id, request.position).await
+}
+
+/// Stores the player's position so the home page can offer to resume it.
+async fn record_progress(
+    state: AppState,
+    category: MediaCategory,
+    id: String,
+    position: f64,
+) -> ApiResult<StatusCode> {
+    if !position.is_finite() || position < 0.0 {
+        return Err(ApiError::bad_request(
+            "position must be a non-negative number",
+        ));
+    }
+
+    // Reject unknown ids so the table only tracks media we actually serve.
+    let record = state.get_media(category, &id).await?;
+    let finished = reached_watched_threshold(record.duration, position);
+
+    let videoid = id.clone();
+    state
+        .with_store(move |store| {
+            store.record_watch_progress(&videoid, position)?;
+            if finished {
+                store.set_watched(&videoid, true)?;
+            }
+            Ok(())
+        })
+        .await?;
+    if finished && !record.watched {
+        state.cache.invalidate_media(category, &id);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Whether `position` is far enough into a video of `duration` seconds to
+/// count as watched. Unknown durations never auto-mark.
+fn reached_watched_threshold(duration: Option<i64>, position: f64) -> bool {
+    match duration {
+        Some(duration) if duration > 0 => position >= duration as f64 * WATCHED_RATIO,
+        _ => false,
+    }
+}
+
+/// Body accepted by `POST /api/videos/{id}/watched`.
+#[derive(Debug, Deserialize)]
+struct WatchedRequest {
+    watched: bool,
+}
+
+/// Explicitly marks a video as watched or unwatched.
+async fn set_video_watched(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+    Json(request): Json<WatchedRequest>,
+) -> ApiResult<StatusCode> {
+    ensure_safe_path_segment(&id)?;
+    state.get_media(MediaCategory::Video, &id).await?;
+
+    let videoid = id.clone();
+    state
+        .with_store(move |store| store.set_watched(&videoid, request.watched))
+        .await?;
+    state.cache.invalidate_media(MediaCategory::Video, &id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Response of `POST /api/videos/{id}/view`.
+#[derive(Debug, Serialize)]
+struct ViewCount {
+    local_views: i64,
+}
+
+/// Counts one play of a video in this archive. Only the video's own cache
+/// entry is dropped; lists do not carry `local_views`.
+async fn record_video_view(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Json<ViewCount>> {
+    ensure_safe_path_segment(&id)?;
+    state.get_media(MediaCategory::Video, &id).await?;
+
+    let videoid = id.clone();
+    let local_views = state
+        .with_store(move |store| store.increment_view(&videoid))
+        .await?;
+    state
+        .cache
+        .media_details(MediaCategory::Video)
+        .write()
+        .remove(&id);
+    Ok(Json(ViewCount { local_views }))
+}
+
+/// Bookmarks a video for the single operator. Unknown videos answer 404.
+async fn add_video_favorite(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<StatusCode> {
+    require_admin(&state, &headers)?;
+    ensure_safe_path_segment(&id)?;
+    state.get_media(MediaCategory::Video, &id).await?;
+
+    state
+        .with_store(move |store| store.add_favorite(&id))
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Removes a bookmark. Idempotent: un-favoriting twice is not an error.
+async fn remove_video_favorite(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<StatusCode> {
+    require_admin(&state, &headers)?;
+    ensure_safe_path_segment(&id)?;
+
+    state
+        .with_store(move |store| store.remove_favorite(&id))
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Lists favorited videos, most recently added first.
+async fn list_favorites(State(state): State<AppState>) -> ApiResult<Json<Vec<VideoRecord>>> {
+    let reader = state.reader.clone();
+    let videos = task::spawn_blocking(move || {
+        let ids = reader.list_favorite_ids()?;
+        reader.get_videos_by_ids(&ids)
+    })
+    .await
+    .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
+    .map_err(|err| ApiError::internal(err.to_string()))?;
+
+    Ok(Json(list_video_records(
+        &videos,
+        state.list_description_chars,
+    )))
+}
+
+/// Lists partially watched videos/shorts, most recently watched first.
+async fn continue_watching(
+    State(state): State<AppState>,
+) -> ApiResult<Json<Vec<ContinueWatchingEntry>>> {
+    let reader = state.reader.clone();
+    let mut entries = task::spawn_blocking(move || {
+        reader.list_continue_watching(CONTINUE_WATCHING_MIN_RATIO, CONTINUE_WATCHING_MAX_RATIO)
+    })
+    .await
+    .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
+    .map_err(|err| ApiError::internal(err.to_string()))?;
+
+    for entry in &mut entries {
+        entry.video = list_video_record(&entry.video, state.list_description_chars);
+    }
+    Ok(Json(entries))
+}
+
+/// Disk usage summary returned by `/api/usage` and `/api/channels/{id}/usage`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+struct UsageReport {
+    total_bytes: u64,
+    file_count: u64,
+    video_count: u64,
+}
+
+/// Reports how much disk space the whole library occupies.
+async fn library_usage(State(state): State<AppState>) -> ApiResult<Json<UsageReport>> {
+    Ok(Json(state.get_usage(None).await?))
+}
+
+/// Lists every author in the archive with their upload counts, busiest
+/// channel first.
+async fn list_channels(State(state): State<AppState>) -> ApiResult<Json<Vec<ChannelSummary>>> {
+    Ok(Json(state.get_channels().await?))
+}
+
+/// Reports how much disk space a single channel occupies. `{id}` is the
+/// YouTube channel id (`UC...`) or the last segment of the channel URL
+/// (e.g. `@handle`).
+async fn channel_usage(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResult<Json<UsageReport>> {
+    Ok(Json(state.get_usage(Some(id)).await?))
+}
+
+/// yt-dlp `availability` values that mean the video can no longer be fetched
+/// anonymously.
+const RESTRICTED_AVAILABILITY: &[&str] =
+    &["private", "premium_only", "subscriber_only", "needs_auth"];
+
+/// One entry of `GET /api/admin/issues`.
+#[derive(Debug, PartialEq, Serialize)]
+struct MediaIssue {
+    videoid: String,
+    title: String,
+    /// `videos` or `shorts`.
+    category: &'static str,
+    problems: Vec<MediaProblem>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum MediaProblem {
+    /// The row lists no formats at all.
+    NoMedia,
+    /// A listed format has no file on disk.
+    MissingFile { format: String },
+    /// A listed format's file is empty.
+    EmptyFile { format: String },
+    /// An interrupted download left files under `.staging`.
+    PartialDownload,
+    /// YouTube reported the video as private or behind a login.
+    Unavailable { availability: String },
+}
+
+/// Lists every video or short with something wrong in the archive, so
+/// operators have one place to see what needs a re-download.
+async fn list_issues(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<Vec<MediaIssue>>> {
+    require_admin(&state, &headers)?;
+
+    let mut media = Vec::new();
+    for category in [MediaCategory::Video, MediaCategory::Short] {
+        for record in state.get_media_list(category).await? {
+            media.push((category, record));
+        }
+    }
+    let files = state.files.clone();
+    let object_store = state.object_store.is_some();
+    let issues = task::spawn_blocking(move || find_issues(&files, object_store, &media))
+        .await
+        .map_err(|err| ApiError::internal(format!("task join error: {err}")))?;
+    Ok(Json(issues))
+}
+
+/// Checks each record's stored status and its files on disk. With an object
+/// store configured, a file missing locally is served from the bucket and
+/// is not reported.
+fn find_issues(
+    files: &FilePaths,
+    object_store: bool,
+    media: &[(MediaCategory, VideoRecord)],
+) -> Vec<MediaIssue> {
+    let mut issues = Vec::new();
+    for (category, record) in media {
+        let mut problems = Vec::new();
+        if record.sources.is_empty() {
+            problems.push(MediaProblem::NoMedia);
+        }
+        for source in &record.sources {
+            let format = source
+                .stream_key
+                .clone()
+                .unwrap_or_else(|| source.format_id.clone());
+            let path = resolve_source_path(files, *category, &record.videoid, source);
+            match std::fs::metadata(&path) {
+                Ok(metadata) if metadata.is_file() && metadata.len() == 0 => {
+                    problems.push(MediaProblem::EmptyFile { format })
+                }
+                Ok(metadata) if metadata.is_file() => {}
+                _ if object_store => {}
+                _ => problems.push(MediaProblem::MissingFile { format }),
+            }
+        }
+        let staging =
+            files
+                .media_dir(*category)
+                .join(layout::STAGING_DIR)
+                .join(layout::video_dir_name(
+                    &record.videoid,
+                    files.case_insensitive,
+                ));
+        if staging.is_dir() {
+            problems.push(MediaProblem::PartialDownload);
+        }
+        if let Some(availability) = record
+            .extras
+            .get("availability")
+            .and_then(|value| value.as_str())
+            .filter(|value| RESTRICTED_AVAILABILITY.contains(value))
+        {
+            problems.push(MediaProblem::Unavailable {
+                availability: availability.to_owned(),
+            });
+        }
+
+        if !problems.is_empty() {
+            issues.push(MediaIssue {
+                videoid: record.videoid.clone(),
+                title: record.title.clone(),
+                category: match category {
+                    MediaCategory::Video => "videos",
+                    MediaCategory::Short => "shorts",
+                },
+                problems,
+            });
+        }
+    }
+    issues
+}
+
+/// Lightweight response that exposes a download URL for each subtitle track.
+#[derive(serde::Serialize)]
+struct SubtitleInfo {
+    code: String,
+    name: String,
+    url: String,
+}
+
+/// Payload returned by `/api/bootstrap` so the client can hydrate offline.
+#[derive(Clone, Serialize)]
+struct BootstrapPayload {
+    videos: Vec<VideoRecord>,
+    shorts: Vec<VideoRecord>,
+    subtitles: Vec<SubtitleCollection>,
+    comments: Vec<CommentRecord>,
+}
+
+impl AppState {
+    /// Runs a write against a short-lived `MetadataStore` on the blocking
+    /// pool. Writes are rare (admin endpoints) so we do not keep a connection
+    /// open between requests.
+    async fn with_store<F, T>(&self, f: F) -> ApiResult<T>
+    where
+        F: FnOnce(&mut MetadataStore) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let db_path = self.files.metadata_db.clone();
+        let sqlite = self.sqlite;
+        task::spawn_blocking(move || -> Result<T> {
+            let mut store = MetadataStore::open_tuned(&db_path, sqlite)?;
+            f(&mut store)
+        })
+        .await
+        .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
+        .map_err(|err| ApiError::internal(err.to_string()))
+    }
+
+    /// Returns a cached snapshot containing everything the SPA needs to boot
+    /// without hitting follow-up endpoints (videos, shorts, subtitles,
+    /// comments). The heavy lifting runs in a blocking task because SQLite is a
+    /// synchronous API.
+    async fn get_bootstrap(&self) -> ApiResult<Arc<BootstrapPayload>> {
+        if let Some(cached) = self.cache.bootstrap.read().clone() {
+            return Ok(cached);
+        }
+
+        let reader = self.reader.clone();
+        let description_chars = self.list_description_chars;
+        let payload = task::spawn_blocking(move || -> Result<BootstrapPayload> {
+            let videos = reader.list_videos()?;
+            let shorts = reader.list_shorts()?;
+            let subtitles = reader.list_subtitles()?;
+            let comments = reader.list_all_comments()?;
+            Ok(BootstrapPayload {
+                videos: list_video_records(&videos, description_chars),
+                shorts: list_video_records(&shorts, description_chars),
+                subtitles,
+                comments,
+            })
+        })
+        .await
+        .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
+        .map_err(|err| ApiError::internal(err.to_string()))?;
+
+        let payload = Arc::new(payload);
+        self.cache.bootstrap.write().replace(payload.clone());
+        Ok(payload)
+    }
+
+    /// Per-author counts for `GET /api/channels`, cached until a video or
+    /// short changes.
+    async fn get_channels(&self) -> ApiResult<Vec<ChannelSummary>> {
+        if let Some(cached) = self.cache.channels.read().clone() {
+            return Ok(cached);
+        }
+
+        let reader = self.reader.clone();
+        let channels = task::spawn_blocking(move || reader.list_channels())
+            .await
+            .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
+            .map_err(|err| ApiError::internal(err.to_string()))?;
+
+        self.cache.channels.write().replace(channels.clone());
+        Ok(channels)
+    }
+
+    /// Videos written after `since`, oldest first. Always reads SQLite: the
+    /// cached list would hide rows other processes wrote since it was filled.
+    async fn get_refreshed_videos(
+        &self,
+        filter: ListFilter,
+        since: SinceCursor,
+    ) -> ApiResult<Vec<VideoRecord>> {
+        let reader = self.reader.clone();
+        task::spawn_blocking(move || {
+            reader.list_videos_refreshed_after(&filter, &since.refreshed_at, &since.videoid)
+        })
+        .await
+        .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
+        .map_err(|err| ApiError::internal(err.to_string()))
+    }
+
+    /// One channel's videos or shorts (exact `channel_url` match). Only the
+    /// full lists are cached, so this always reads SQLite.
+    async fn get_channel_media(
+        &self,
+        category: MediaCategory,
+        channel_url: String,
+    ) -> ApiResult<Vec<VideoRecord>> {
+        let reader = self.reader.clone();
+        task::spawn_blocking(move || match category {
+            MediaCategory::Video => reader.list_videos_for_channel(&channel_url),
+            MediaCategory::Short => reader.list_shorts_for_channel(&channel_url),
+        })
+        .await
+        .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
+        .map_err(|err| ApiError::internal(err.to_string()))
+    }
+
+    /// Rows matching `filter` in its order, read from SQLite rather than the
+    /// cached list, with the number of matches across every page when only
+    /// `range` is returned.
+    async fn get_filtered_media(
+        &self,
+        category: MediaCategory,
+        filter: ListFilter,
+        range: Option<PageRange>,
+    ) -> ApiResult<(Vec<VideoRecord>, usize)> {
+        let reader = self.reader.clone();
+        task::spawn_blocking(move || {
+            let items = match category {
+                MediaCategory::Video => reader.list_videos_matching(&filter, range)?,
+                MediaCategory::Short => reader.list_shorts_matching(&filter, range)?,
+            };
+            let total = match (range, category) {
+                (None, _) => items.len(),
+                (Some(_), MediaCategory::Video) => reader.count_videos_matching(&filter)?,
+                (Some(_), MediaCategory::Short) => reader.count_shorts_matching(&filter)?,
+            };
+            anyhow::Ok((items, total))
+        })
+        .await
+        .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
+        .map_err(|err| ApiError::internal(err.to_string()))
+    }
+
+    /// Retrieves every video/short record, memoizing both the list and the
+    /// individual details map for quick follow-up lookups.
+    async fn get_media_list(&self, category: MediaCategory) -> ApiResult<Vec<VideoRecord>> {
+        if let Some(cached) = self.cache.media_list(category).read().clone() {
+            return Ok(cached);
+        }
+
+        let reader = self.reader.clone();
+        let records = task::spawn_blocking(move || match category {
+            MediaCategory::Video => reader.list_videos(),
+            MediaCategory::Short => reader.list_shorts(),
+        })
+        .await
+        .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
+        .map_err(|err| ApiError::internal(err.to_string()))?;
+
+        self.cache
+            .media_list(category)
+            .write()
+            .replace(records.clone());
+
+        let mut details = self.cache.media_details(category).write();
+        for record in &records {
+            details.insert(record.videoid.clone(), record.clone());
+        }
+
+        Ok(records)
+    }
+
+    /// Loads metadata for a single video or short, preferring the cache before
+    /// falling back to SQLite.
+    async fn get_media(&self, category: MediaCategory, videoid: &str) -> ApiResult<VideoRecord> {
+        if let Some(record) = self
+            .cache
+            .media_details(category)
+            .read()
+            .get(videoid)
+            .cloned()
+        {
+            return Ok(record);
+        }
+
+        let reader = self.reader.clone();
+        let result = task::spawn_blocking({
+            let videoid = videoid.to_owned();
+            move || match category {
+                MediaCategory::Video => reader.get_video(&videoid),
+                MediaCategory::Short => reader.get_short(&videoid),
+            }
+        })
+        .await
+        .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
+        .map_err(|err| ApiError::internal(err.to_string()))?;
+
+        let record = result.ok_or_else(|| ApiError::not_found("video not found"))?;
+
+        self.cache
+            .media_details(category)
+            .write()
+            .insert(videoid.to_owned(), record.clone());
+
+        Ok(record)
+    }
+
+    /// Sums the on-disk size of every source belonging to `channel` (or the
+    /// whole library). Results are cached for `STATS_CACHE_TTL`.
+    async fn get_usage(&self, channel: Option<String>) -> ApiResult<UsageReport> {
+        if let Some((computed_at, report)) = self.cache.usage.read().get(&channel)
+            && computed_at.elapsed() < STATS_CACHE_TTL
+        {
+            return Ok(report.clone());
+        }
+
+        let mut media = Vec::new();
+        for category in [MediaCategory::Video, MediaCategory::Short] {
+            for record in self.get_media_list(category).await? {
+                if channel
+                    .as_deref()
+                    .is_none_or(|channel| record_in_channel(&record, channel))
+                {
+                    media.push((category, record));
+                }
+            }
+        }
+
+        if channel.is_some() && media.is_empty() {
+            return Err(ApiError::not_found("channel not found"));
+        }
+
+        let files = self.files.clone();
+        let report = task::spawn_blocking(move || compute_usage(&files, &media))
+            .await
+            .map_err(|err| ApiError::internal(format!("task join error: {err}")))?;
+
+        self.cache
+            .usage
+            .write()
+            .insert(channel, (Instant::now(), report.clone()));
+
+        Ok(report)
+    }
+
+    /// Lazy-loads comment threads; we store them keyed by id because comment
+    /// payloads are far smaller than video blobs.
+    async fn get_comments(&self, videoid: &str) -> ApiResult<Vec<CommentRecord>> {
+        if let Some(cached) = self.cache.comments.read().get(videoid).cloned() {
+            return Ok(cached);
+        }
+
+        let reader = self.reader.clone();
+        let comments = task::spawn_blocking({
+            let videoid = videoid.to_owned();
+            move || reader.get_comments(&videoid)
+        })
+        .await
+        .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
+        .map_err(|err| ApiError::internal(err.to_string()))?;
+
+        self.cache
+            .comments
+            .write()
+            .insert(videoid.to_owned(), comments.clone());
+
+        Ok(comments)
+    }
+
+    /// Provides subtitle metadata if available. Not every video has subtitles
+    /// so the API returns an Option.
+    async fn get_subtitles(&self, videoid: &str) -> ApiResult<Option<SubtitleCollection>> {
+        if let Some(cached) = self.cache.subtitles.read().get(videoid).cloned() {
+            return Ok(Some(cached));
+        }
+
+        let reader = self.reader.clone();
+        let result = task::spawn_blocking({
+            let videoid = videoid.to_owned();
+            move || reader.get_subtitles(&videoid)
+        })
+        .await
+        .map_err(|err| ApiError::internal(format!("task join error: {err}")))?
+        .map_err(|err| ApiError::internal(err.to_string()))?;
+
+        if let Some(collection) = &result {
+            self.cache
+                .subtitles
+                .write()
+                .insert(videoid.to_owned(), collection.clone());
+        }
+
+        Ok(result)
+    }
+}
+
+/// See [`layout::source_path`].
+fn resolve_source_path(
+    files: &FilePaths,
+    category: MediaCategory,
+    videoid: &str,
+    source: &VideoSource,
+) -> PathBuf {
+    layout::source_path(
+        files.media_dir(category),
+        videoid,
+        source,
+        files.case_insensitive,
+    )
+}
+
+/// Whether `record` belongs to the channel addressed by `channel`, matching
+/// either the stored YouTube channel id or the trailing channel URL segment.
+fn record_in_channel(record: &VideoRecord, channel: &str) -> bool {
+    if record
+        .extras
+        .get("channelId")
+        .and_then(|value| value.as_str())
+        == Some(channel)
+    {
+        return true;
+    }
+
+    record.channel_url.as_deref().is_some_and(|url| {
+        canonical_channel_url(url).rsplit('/').next() == Some(channel.to_lowercase().as_str())
+    })
+}
+
+/// Adds up source sizes, preferring the real on-disk size and falling back to
+/// the `file_size` yt-dlp reported when the file cannot be stat-ed.
+fn compute_usage(files: &FilePaths, media: &[(MediaCategory, VideoRecord)]) -> UsageReport {
+    let mut report = UsageReport::default();
+    for (category, record) in media {
+        report.video_count += 1;
+        for source in &record.sources {
+            let path = resolve_source_path(files, *category, &record.videoid, source);
+            let size = match std::fs::metadata(&path) {
+                Ok(metadata) if metadata.is_file() => Some(metadata.len()),
+                _ => source.file_size.and_then(|size| u64::try_from(size).ok()),
+            };
+            if let Some(size) = size {
+                report.total_bytes += size;
+                report.file_count += 1;
+            }
+        }
+    }
+    report
+}
+
+/// Normalizes a VideoSource URL by keeping only the trailing segment. During
+/// download we store files named `{videoid}_{format}` and the format parameter
+/// is the only piece users need to specify. Only used for legacy rows that
+/// predate the explicit `stream_key` field.
+fn source_key(source: &VideoSource) -> Option<String> {
+    source.url.rsplit('/').next().map(|value| value.to_owned())
+}
+
+/// Whether `source` is addressed by the `{format}` stream slug. Prefers the
+/// stored `stream_key` so routing no longer depends on URL formatting.
+fn source_matches(source: &VideoSource, format: &str) -> bool {
+    match &source.stream_key {
+        Some(key) => key == format,
+        None => source_key(source).as_deref() == Some(format),
+    }
+}
+
+/// Checks the `Authorization: Bearer <token>` header against the configured
+/// admin token. Admin endpoints stay disabled (403) until `ADMIN_TOKEN` is set
+/// in the runtime config.
+fn require_admin(state: &AppState, headers: &HeaderMap) -> ApiResult<()> {
+    let expected = state
+        .admin_token
+        .as_deref()
+        .ok_or_else(|| ApiError::forbidden("admin endpoints are disabled"))?;
+
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .ok_or_else(|| ApiError::unauthorized("missing bearer token"))?;
+
+    if !constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+        return Err(ApiError::unauthorized("invalid bearer token"));
+    }
+
+    Ok(())
+}
+
+/// Compares two byte slices without short-circuiting on the first mismatch so
+/// token checks do not leak timing information.
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+    left.iter()
+        .zip(right)
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+        == 0
+}
+
+/// Validates that a single dynamic path segment never escapes its base folder
+/// and never names a hidden entry such as the downloader's `.staging`.
+fn ensure_safe_path_segment(value: &str) -> ApiResult<()> {
+    if value.is_empty()
+        || value.starts_with('.')
+        || Path::new(value)
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return Err(ApiError::not_found("file not found"));
+    }
+
+    Ok(())
+}
+
+/// What a `Range` request header asks for, resolved against the file size.
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRange {
+    /// No usable range: serve the whole file with 200.
+    Full,
+    /// Inclusive `start..=end` slice served with 206.
+    Partial { start: u64, end: u64 },
+    /// Syntactically valid but outside the file: answer 416.
+    Unsatisfiable,
+}
+
+/// Parses a single `bytes=` range (RFC 9110 §14.1.2). Malformed headers and
+/// multi-range requests are ignored, which the spec allows, so callers fall
+/// back to a plain 200.
+fn parse_byte_range(value: &str, size: u64) -> ByteRange {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((first, last)) = spec.split_once('-') else {
+        return ByteRange::Full;
+    };
+    let (first, last) = (first.trim(), last.trim());
+
+    // `bytes=-N`: the last N bytes.
+    if first.is_empty() {
+        return match last.parse::<u64>() {
+            Ok(0) => ByteRange::Unsatisfiable,
+            Ok(_) if size == 0 => ByteRange::Unsatisfiable,
+            Ok(suffix) => ByteRange::Partial {
+                start: size.saturating_sub(suffix),
+                end: size - 1,
+            },
+            Err(_) => ByteRange::Full,
+        };
+    }
+
+    let Ok(start) = first.parse::<u64>() else {
+        return ByteRange::Full;
+    };
+    let end = if last.is_empty() {
+        u64::MAX
+    } else {
+        match last.parse::<u64>() {
+            Ok(end) if end >= start => end,
+            _ => return ByteRange::Full,
+        }
+    };
+    if start >= size {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial {
+        start,
+        end: end.min(size - 1),
+    }
+}
+
+/// Serves `path` from disk, or from the object store when the file is not
+/// on local disk and `OBJECT_STORE_URL` is set. Local files always win, so
+/// re-downloading a file moves it back to local serving.
+async fn serve_file(
+    state: &AppState,
+    path: PathBuf,
+    mime: Option<Mime>,
+    headers: &HeaderMap,
+) -> ApiResult<Response> {
+    if let Some(store) = &state.object_store
+        && !tokio::fs::try_exists(&path).await.unwrap_or(true)
+        && let Some(url) = path
+            .strip_prefix(&state.files.media_root)
+            .ok()
+            .and_then(|key| store.object_url(key))
+    {
+        let mime = mime.or_else(|| MimeGuess::from_path(&path).first());
+        let range = range_header(headers);
+        return stream_remote(&state.egress, Arc::clone(store), url, mime, range).await;
+    }
+    stream_file(&state.egress, path, mime, headers).await
+}
+
+/// Read-only HTTP view of the bucket named by `OBJECT_STORE_URL`. Objects
+/// are keyed by their path below the media root (`videos/<id>/<file>`), so
+/// cold media can be moved with a plain `aws s3 sync` or `mc mirror`.
+/// Requests are unsigned: the bucket must allow anonymous reads (typically
+/// only from the backend's network) or sit behind a signing proxy.
+#[derive(Debug)]
+struct ObjectStore {
+    base_url: String,
+    agent: ureq::Agent,
+}
+
+impl ObjectStore {
+    fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            agent: ureq::AgentBuilder::new()
+                .timeout_connect(OBJECT_STORE_CONNECT_TIMEOUT)
+                .timeout_read(OBJECT_STORE_READ_TIMEOUT)
+                .build(),
+        }
+    }
+
+    /// URL of the object for `key`, percent-encoding each path segment.
+    /// `None` for keys that are not plain UTF-8 path segments.
+    fn object_url(&self, key: &Path) -> Option<String> {
+        let mut url = self.base_url.clone();
+        for component in key.components() {
+            let Component::Normal(segment) = component else {
+                return None;
+            };
+            url.push('/');
+            for byte in segment.to_str()?.bytes() {
+                if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+                    url.push(byte as char);
+                } else {
+                    url.push_str(&format!("%{byte:02X}"));
+                }
+            }
+        }
+        Some(url)
+    }
+
+    /// Issues the GET, forwarding `range`. Missing objects (404, or 403 from
+    /// buckets that hide which keys exist) map to 404; a 416 is returned as
+    /// a response so its `Content-Range` reaches the client.
+    fn get(&self, url: &str, range: Option<&str>) -> ApiResult<ureq::Response> {
+        let mut request = self.agent.get(url);
+        if let Some(range) = range {
+            request = request.set("Range", range);
+        }
+        match request.call() {
+            Ok(response) | Err(ureq::Error::Status(416, response)) => Ok(response),
+            Err(ureq::Error::Status(403 | 404, _)) => Err(ApiError::not_found("file not found")),
+            Err(ureq::Error::Status(code, _)) => {
+                warn!("Object store answered {code} for {url}");
+                Err(ApiError::bad_gateway(format!(
+                    "object store answered {code}"
+                )))
+            }
+            Err(err) => {
+                warn!("Object store request for {url} failed: {err}");
+                Err(ApiError::bad_gateway("object store unreachable"))
+            }
+        }
+    }
+}
+
+/// Proxies an object store response, passing its status (200/206/416) and
+/// range headers through. Nothing is cached; the body is relayed chunk by
+/// chunk through the same egress accounting and rate limit as local files.
+async fn stream_remote(
+    egress: &Arc<Egress>,
+    store: Arc<ObjectStore>,
+    url: String,
+    mime: Option<Mime>,
+    range: Option<&str>,
+) -> ApiResult<Response> {
+    let range = range.map(str::to_owned);
+    let remote = task::spawn_blocking(move || store.get(&url, range.as_deref()))
+        .await
+        .map_err(|err| ApiError::internal(format!("task join error: {err}")))??;
+
+    let status = StatusCode::from_u16(remote.status())
+        .map_err(|_| ApiError::bad_gateway("object store sent an invalid status"))?;
+    let mut headers = HeaderMap::new();
+    for name in [
+        header::CONTENT_LENGTH,
+        header::CONTENT_RANGE,
+        header::ETAG,
+        header::LAST_MODIFIED,
+    ] {
+        if let Some(value) = remote
+            .header(name.as_str())
+            .and_then(|value| HeaderValue::from_str(value).ok())
+        {
+            headers.insert(name, value);
+        }
+    }
+    let content_type = mime
+        .and_then(|mime| HeaderValue::from_str(mime.as_ref()).ok())
+        .or_else(|| {
+            remote
+                .header("Content-Type")
+                .and_then(|value| HeaderValue::from_str(value).ok())
+        });
+    if let Some(content_type) = content_type {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+    if status == StatusCode::RANGE_NOT_SATISFIABLE {
+        headers.remove(header::CONTENT_LENGTH);
+        return Ok((status, headers).into_response());
+    }
+
+    // ureq only offers a blocking reader, so a blocking task pumps it into a
+    // small channel. A closed channel means the client went away.
+    let (sender, receiver) = tokio::sync::mpsc::channel::<std::io::Result<Bytes>>(4);
+    let mut reader = remote.into_reader();
+    task::spawn_blocking(move || {
+        let mut buffer = vec![0; OBJECT_STORE_CHUNK_BYTES];
+        loop {
+            let chunk = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => Ok(Bytes::copy_from_slice(&buffer[..read])),
+                Err(err) => Err(err),
+            };
+            let failed = chunk.is_err();
+            if sender.blocking_send(chunk).is_err() || failed {
+                break;
+            }
+        }
+    });
+    let chunks = futures_util::stream::unfold(receiver, |mut receiver| async move {
+        let chunk = receiver.recv().await?;
+        Some((chunk, receiver))
+    });
+
+    Ok((status, headers, egress.body(Box::pin(chunks))).into_response())
+}
+
+/// Streams `path` from disk, honoring a single-range `Range` header so
+/// browsers can seek without downloading the whole file first. Responses
+/// carry an `ETag` and `Last-Modified` built from the file's size and mtime,
+/// and a matching `If-None-Match` (or, without one, `If-Modified-Since`)
+/// answers `304 Not Modified` before any range is applied.
+async fn stream_file(
+    egress: &Arc<Egress>,
+    path: PathBuf,
+    mime: Option<Mime>,
+    headers: &HeaderMap,
+) -> ApiResult<Response> {
+    let mut file = File::open(&path)
+        .await
+        .map_err(|_| ApiError::not_found("file not found"))?;
+    let metadata = file
+        .metadata()
+        .await
+        .map_err(|err| ApiError::internal(format!("reading file metadata: {err}")))?;
+    // Opening a directory succeeds on Unix; never stream or list one.
+    if !metadata.is_file() {
+        return Err(ApiError::not_found("file not found"));
+    }
+    let size = metadata.len();
+    let validators = FileValidators::new(size, metadata.modified().ok());
+    if validators.not_modified(headers) {
+        let mut response = StatusCode::NOT_MODIFIED.into_response();
+        validators.insert_into(response.headers_mut());
+        return Ok(response);
+    }
+
+    let range =
+        range_header(headers).map_or(ByteRange::Full, |value| parse_byte_range(value, size));
+    let mut response = match range {
+        ByteRange::Full => egress.body(ReaderStream::new(file)).into_response(),
+        ByteRange::Partial { start, end } => {
+            file.seek(SeekFrom::Start(start))
+                .await
+                .map_err(|err| ApiError::internal(format!("seeking in file: {err}")))?;
+            let length = end - start + 1;
+            let body = egress.body(ReaderStream::new(file.take(length)));
+            (
+                StatusCode::PARTIAL_CONTENT,
+                [
+                    (header::CONTENT_RANGE, format!("bytes {start}-{end}/{size}")),
+                    (header::CONTENT_LENGTH, length.to_string()),
+                ],
+                body,
+            )
+                .into_response()
+        }
+        ByteRange::Unsatisfiable => {
+            return Ok((
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                [(header::CONTENT_RANGE, format!("bytes */{size}"))],
+            )
+                .into_response());
+        }
+    };
+
+    // Either use the explicit mime provided by the VideoSource or infer it from
+    // the file extension. Setting CONTENT_TYPE hints allows browsers to stream
+    // video without sniffing.
+    let guessed = mime.or_else(|| MimeGuess::from_path(&path).first());
+    if let Some(mime) = guessed
+        && let Ok(value) = mime.to_string().parse()
+    {
+        response.headers_mut().insert(header::CONTENT_TYPE, value);
+    }
+    response
+        .headers_mut()
+        .insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    validators.insert_into(response.headers_mut());
+
+    Ok(response)
+}
+
+/// Cache validators of a file on disk.
+struct FileValidators {
+    etag: String,
+    /// Whole seconds since the epoch, the precision of HTTP dates.
+    modified: Option<i64>,
+}
+
+impl FileValidators {
+    fn new(size: u64, modified: Option<std::time::SystemTime>) -> Self {
+        let since_epoch = modified.and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok());
+        let nanos = since_epoch.map_or(0, |elapsed| elapsed.as_nanos());
+        Self {
+            etag: format!("\"{size:x}-{nanos:x}\""),
+            modified: since_epoch.and_then(|elapsed| i64::try_from(elapsed.as_secs()).ok()),
+        }
+    }
+
+    fn last_modified(&self) -> Option<String> {
+        let time = DateTime::<Utc>::from_timestamp(self.modified?, 0)?;
+        Some(time.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+    }
+
+    /// Whether the client's cached copy is current. `If-None-Match` wins
+    /// over `If-Modified-Since` when both are sent.
+    fn not_modified(&self, headers: &HeaderMap) -> bool {
+        if let Some(value) = headers.get(header::IF_NONE_MATCH) {
+            let Ok(value) = value.to_str() else {
+                return false;
+            };
+            return value
+                .split(',')
+                .map(str::trim)
+                .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == self.etag);
+        }
+        let since = headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| DateTime::parse_from_rfc2822(value).ok());
+        match (since, self.modified) {
+            (Some(since), Some(modified)) => modified <= since.timestamp(),
+            _ => false,
+        }
+    }
+
+    fn insert_into(&self, headers: &mut HeaderMap) {
+        if let Ok(value) = HeaderValue::from_str(&self.etag) {
+            headers.insert(header::ETAG, value);
+        }
+        if let Some(value) = self
+            .last_modified()
+            .and_then(|date| HeaderValue::from_str(&date).ok())
+        {
+            headers.insert(header::LAST_MODIFIED, value);
+        }
+    }
+}
+
+fn list_video_records(records: &[VideoRecord], description_chars: usize) -> Vec<VideoRecord> {
+    records
+        .iter()
+        .map(|record| list_video_record(record, description_chars))
+        .collect()
+}
+
+/// Sanitized copy for list responses, with the description clipped to
+/// `description_chars` characters (`0` keeps it whole). The cut lands on a
+/// char boundary, so multibyte text stays valid UTF-8.
+fn list_video_record(record: &VideoRecord, description_chars: usize) -> VideoRecord {
+    let mut clone = sanitize_video_record(record);
+    if description_chars > 0
+        && let Some((cut, _)) = clone.description.char_indices().nth(description_chars)
+    {
+        clone.description.truncate(cut);
+        clone.description_truncated = true;
+    }
+    clone
+}
+
+fn sanitize_video_record(record: &VideoRecord) -> VideoRecord {
+    let mut clone = record.clone();
+    for source in clone.sources.iter_mut().chain(&mut clone.audio_source) {
+        source.path = None;
+    }
+    clone
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::to_bytes, extract::State as AxumState};
+    use newtube_tools::archive::parse_archive_line;
+    use newtube_tools::config::DEFAULT_LIST_DESCRIPTION_CHARS;
+    use serde_json::Value;
+    use std::collections::HashSet;
+    use std::{io::Write, path::PathBuf, sync::Arc};
+    use tempfile::{NamedTempFile, tempdir};
+
+    const TEST_ADMIN_TOKEN: &str = "test-token";
+
+    fn admin_headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            format!("Bearer {TEST_ADMIN_TOKEN}").parse().unwrap(),
+        );
+        headers
+    }
+
+    struct BackendTestContext {
+        _temp: tempfile::TempDir,
+        db_path: PathBuf,
+        store: MetadataStore,
+        state: AppState,
+    }
+
+    impl BackendTestContext {
+        fn new() -> Self {
+            let temp = tempdir().unwrap();
+            let db_path = temp.path().join("metadata.db");
+            let store = MetadataStore::open(&db_path).unwrap();
+            let reader = MetadataReader::new(&db_path).unwrap();
+            let files = FilePaths::for_base(temp.path());
+
+            Self {
+                state: AppState {
+                    reader: Arc::new(reader),
+                    cache: Arc::new(ApiCache::new()),
+                    files: Arc::new(files),
+                    admin_token: Some(Arc::from(TEST_ADMIN_TOKEN)),
+                    sqlite: SqliteTuning::default(),
+                    egress: Arc::new(Egress::default()),
+                    list_description_chars: DEFAULT_LIST_DESCRIPTION_CHARS,
+                    object_store: None,
+                    app_version: Some(Arc::from("1.0")),
+                },
+                db_path,
+                store,
+                _temp: temp,
+            }
+        }
+
+        fn insert_video(&mut self, id: &str) {
+            self.store.upsert_video(&sample_video(id)).unwrap();
+        }
+
+        fn insert_short(&mut self, id: &str) {
+            self.store.upsert_short(&sample_video(id)).unwrap();
+        }
+
+        fn insert_subtitles(&mut self, id: &str, tracks: Vec<SubtitleTrack>) {
+            self.store
+                .upsert_subtitles(&SubtitleCollection {
+                    videoid: id.into(),
+                    languages: tracks,
+                })
+                .unwrap();
+        }
+
+        fn insert_comments(&mut self, id: &str, comments: Vec<CommentRecord>) {
+            self.store
+                .replace_comments(id, &comments)
+                .expect("comments persisted");
+        }
+
+        fn delete_by_videoid(&self, table: &str, value: &str) {
+            let conn = Connection::open(&self.db_path).unwrap();
+            conn.execute(&format!("DELETE FROM {table} WHERE videoid = ?1"), [value])
+                .unwrap();
+        }
+    }
+
+    fn sample_video(id: &str) -> VideoRecord {
+        VideoRecord {
+            videoid: id.into(),
+            title: format!("Video {id}"),
+            description: "desc".into(),
+            likes: Some(1),
+            dislikes: Some(0),
+            views: Some(10),
+            upload_date: Some("2024-01-01T00:00:00Z".into()),
+            upload_epoch: Some(1_704_067_200),
+            upload_date_display: None,
+            author: Some("Channel".into()),
+            subscriber_count: Some(100),
+            duration: Some(60),
+            duration_text: Some("1:00".into()),
+            channel_url: Some("https://example.test/channel".into()),
+            thumbnail_url: Some("/thumb.jpg".into()),
+            poster_override: false,
+            tags: vec![],
+            thumbnails: vec![],
+            extras: json!(null),
+            sources: vec![VideoSource {
+                format_id: "1080p".into(),
+                quality_label: Some("1080p".into()),
+                width: Some(1920),
+                height: Some(1080),
+                fps: Some(30.0),
+                mime_type: Some("video/mp4".into()),
+                ext: Some("mp4".into()),
+                file_size: Some(1024),
+                url: format!("/api/videos/{id}/streams/1080p"),
+                path: None,
+                stream_key: Some("1080p".into()),
+            }],
+            audio_source: None,
+            chapters: Vec::new(),
+            sponsor_segments: None,
+            has_subtitles: false,
+            subtitle_languages: Vec::new(),
+            watched: false,
+            refreshed_at: None,
+            webpage_url: None,
+            original_url: None,
+            description_truncated: false,
+            local_views: None,
+        }
+    }
+
+    fn sample_comment(id: &str, videoid: &str) -> CommentRecord {
+        CommentRecord {
+            id: id.into(),
+            videoid: videoid.into(),
+            author: "tester".into(),
+            text: "hello world".into(),
+            likes: Some(1),
+            time_posted: Some("2024-01-01T00:00:00Z".into()),
+            time_posted_epoch: Some(1_704_067_200),
+            time_posted_display: None,
+            parent_comment_id: None,
+            status_likedbycreator: false,
+            reply_count: Some(0),
+        }
+    }
+
+    fn write_runtime_config(media: &str, www: &str, port: u16, host: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "MEDIA_ROOT=\"{media}\"\nWWW_ROOT=\"{www}\"\nNEWTUBE_PORT=\"{port}\"\nNEWTUBE_HOST=\"{host}\"\nAPP_VERSION=\"1.0\"\nDOMAIN_NAME=\"example.com\"\n"
+        )
+        .unwrap();
+        file
+    }
+
+    fn parse_backend_args(config: &NamedTempFile, extra: &[&str]) -> BackendArgs {
+        let mut argv = vec![
+            "--config".to_string(),
+            config.path().to_string_lossy().into_owned(),
+        ];
+        argv.extend(extra.iter().map(|value| value.to_string()));
+        BackendArgs::from_iter(argv).expect("parsed args")
+    }
+
+    /// `MakeWriter` that collects formatted events for assertions.
+    #[derive(Clone, Default)]
+    struct CapturedLog(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Write for CapturedLog {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn startup_report_logs_effective_settings_as_fields() {
+        let report = StartupReport {
+            media_root: Path::new("/yt"),
+            www_root: Path::new("/www/newtube.com"),
+            metadata_db: Path::new("/yt/metadata.db"),
+            addr: "127.0.0.1:8080".parse().unwrap(),
+            admin_enabled: false,
+            stream_rate_limit: Some(1_000_000),
+            display_timezone: Some(chrono_tz::Europe::Paris),
+            list_description_chars: 0,
+            case_insensitive: false,
+            object_store_url: None,
+        };
+
+        let captured = CapturedLog::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        tracing::subscriber::with_default(subscriber, || report.log());
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(output.lines().count(), 1, "{output}");
+        for expected in [
+            "API server listening on http://127.0.0.1:8080",
+            "host=127.0.0.1",
+            "port=8080",
+            "media_root=/yt",
+            "metadata_db=/yt/metadata.db",
+            "admin_endpoints=false",
+            "stream_rate_limit=1000000",
+            "display_timezone=\"Europe/Paris\"",
+            "list_description_chars=0",
+            "usage_cache_ttl_secs=300",
+        ] {
+            assert!(
+                output.contains(expected),
+                "{expected} missing from {output}"
+            );
+        }
+        assert!(!output.contains("object_store_url"), "{output}");
+    }
+
+    #[test]
+    fn backend_args_default_media_root() {
+        let config = write_runtime_config("/yt/test", "/www/test", 4242, "127.0.0.1");
+        let args = parse_backend_args(&config, &[]);
+        assert_eq!(args.media_root, PathBuf::from("/yt/test"));
+        assert_eq!(args.newtube_port, 4242);
+    }
+
+    #[test]
     fn backend_args_override_media_root() {
         let config = write_runtime_config("/yt/test", "/www/test", 4242, "127.0.0.1");
         let args = parse_backend_args(&config, &["--media-root", "/custom/media"]);
         assert_eq!(args.media_root, PathBuf::from("/custom/media"));
     }
 
-    #[test]
-    fn backend_args_override_port() {
-        let config = write_runtime_config("/yt/test", "/www/test", 4242, "127.0.0.1");
-        let args = parse_backend_args(&config, &["--port", "9000"]);
-        assert_eq!(args.newtube_port, 9000);
+    #[test]
+    fn backend_args_override_www_root() {
+        let config = write_runtime_config("/yt/test", "/www/test", 4242, "127.0.0.1");
+        assert_eq!(
+            parse_backend_args(&config, &[]).www_root,
+            PathBuf::from("/www/test")
+        );
+        let args = parse_backend_args(&config, &["--www-root=/srv/site"]);
+        assert_eq!(args.www_root, PathBuf::from("/srv/site"));
+    }
+
+    #[test]
+    fn backend_args_require_an_explicit_config_to_exist() {
+        let dir = tempdir().unwrap();
+        let missing = dir.path().join("newtube-env");
+        let Err(err) =
+            BackendArgs::from_iter(["--config".to_string(), missing.display().to_string()])
+        else {
+            panic!("a missing --config is an error");
+        };
+        assert!(err.to_string().contains("Missing config file"), "{err}");
+    }
+
+    #[test]
+    fn backend_args_override_port() {
+        let config = write_runtime_config("/yt/test", "/www/test", 4242, "127.0.0.1");
+        let args = parse_backend_args(&config, &["--port", "9000"]);
+        assert_eq!(args.newtube_port, 9000);
+    }
+
+    #[test]
+    fn backend_args_override_host() {
+        let config = write_runtime_config("/yt/test", "/www/test", 4242, "127.0.0.1");
+        let args = parse_backend_args(&config, &["--host", "0.0.0.0"]);
+        assert_eq!(args.listen_host, "0.0.0.0".parse::<IpAddr>().unwrap());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn shutdown_signal_resolves_on_sigterm() {
+        let shutdown = shutdown_signal();
+        tokio::pin!(shutdown);
+        // The first poll installs both handlers, so the signal sent below is
+        // caught instead of killing the test process.
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), &mut shutdown)
+                .await
+                .is_err()
+        );
+
+        let status = std::process::Command::new("kill")
+            .args(["-TERM", &std::process::id().to_string()])
+            .status()
+            .expect("running kill");
+        assert!(status.success());
+        tokio::time::timeout(Duration::from_secs(5), shutdown)
+            .await
+            .expect("SIGTERM triggers graceful shutdown");
+    }
+
+    #[tokio::test]
+    async fn bootstrap_caches_payload() {
+        let mut ctx = BackendTestContext::new();
+        ctx.insert_video("alpha");
+        ctx.insert_short("beta");
+        ctx.insert_subtitles(
+            "alpha",
+            vec![SubtitleTrack {
+                code: "en".into(),
+                name: "English".into(),
+                url: "/api/videos/alpha/subtitles/en".into(),
+                path: None,
+            }],
+        );
+        ctx.insert_comments("alpha", vec![sample_comment("1", "alpha")]);
+
+        let first = ctx.state.get_bootstrap().await.unwrap();
+        assert_eq!(first.videos.len(), 1);
+
+        ctx.insert_video("gamma");
+        let second = ctx.state.get_bootstrap().await.unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // Clearing any part the payload embeds rebuilds it.
+        for category in [CacheCategory::Comments, CacheCategory::Subtitles] {
+            let before = ctx.state.get_bootstrap().await.unwrap();
+            ctx.state.cache.clear(category);
+            let after = ctx.state.get_bootstrap().await.unwrap();
+            assert!(!Arc::ptr_eq(&before, &after), "{category:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn media_list_populates_cache() {
+        let mut ctx = BackendTestContext::new();
+        ctx.insert_video("alpha");
+
+        let list = ctx
+            .state
+            .get_media_list(MediaCategory::Video)
+            .await
+            .unwrap();
+        assert_eq!(list.len(), 1);
+        ctx.delete_by_videoid("videos", "alpha");
+
+        let cached = ctx
+            .state
+            .get_media_list(MediaCategory::Video)
+            .await
+            .unwrap();
+        assert_eq!(cached.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn delta_feed_cursor_advances() {
+        let mut ctx = BackendTestContext::new();
+        for id in ["alpha", "beta", "gamma"] {
+            ctx.insert_video(id);
+        }
+        // Fill the cache first: writes from another process (the raw
+        // connection below) never invalidate it, yet must show up.
+        ctx.state
+            .get_media_list(MediaCategory::Video)
+            .await
+            .unwrap();
+        let conn = Connection::open(&ctx.db_path).unwrap();
+        let set_refreshed = |id: &str, at: &str| {
+            conn.execute(
+                "UPDATE videos SET refreshed_at = ?2 WHERE videoid = ?1",
+                [id, at],
+            )
+            .unwrap();
+        };
+        set_refreshed("alpha", "2024-05-01T10:00:00.000Z");
+        set_refreshed("beta", "2024-05-01T12:00:00.000Z");
+        set_refreshed("gamma", "2024-05-01T11:00:00.000Z");
+
+        let poll = |since: &str| {
+            let state = ctx.state.clone();
+            let since = since.to_owned();
+            async move {
+                let Json(listing) = list_videos(
+                    AxumState(state),
+                    Query(ListQuery {
+                        since: Some(since),
+                        ..ListQuery::default()
+                    }),
+                )
+                .await
+                .unwrap();
+                let VideoListing::Delta { videos, next_since } = listing else {
+                    panic!("expected a delta listing");
+                };
+                let ids: Vec<String> = videos.into_iter().map(|v| v.videoid).collect();
+                (ids, next_since)
+            }
+        };
+
+        // Offsets are normalized to UTC; results come oldest first, and a
+        // plain timestamp includes videos written at exactly that instant.
+        let (ids, cursor) = poll("2024-05-01T13:00:00+02:00").await;
+        assert_eq!(ids, ["gamma", "beta"]);
+        assert_eq!(cursor, "2024-05-01T12:00:00.000Z~beta");
+
+        // Nothing new: same cursor back.
+        let (ids, next) = poll(&cursor).await;
+        assert!(ids.is_empty());
+        assert_eq!(next, cursor);
+
+        // A refresh moves the video past the cursor.
+        set_refreshed("alpha", "2024-05-01T13:00:00.000Z");
+        let (ids, next) = poll(&cursor).await;
+        assert_eq!(ids, ["alpha"]);
+        assert_eq!(next, "2024-05-01T13:00:00.000Z~alpha");
+
+        // A later write in the same millisecond is not skipped.
+        set_refreshed("gamma", "2024-05-01T13:00:00.000Z");
+        let (ids, next) = poll(&next).await;
+        assert_eq!(ids, ["gamma"]);
+        assert_eq!(next, "2024-05-01T13:00:00.000Z~gamma");
+
+        let err = list_videos(
+            AxumState(ctx.state.clone()),
+            Query(ListQuery {
+                since: Some("yesterday".into()),
+                ..ListQuery::default()
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn listings_page_with_totals() {
+        let ctx = BackendTestContext::new();
+        for day in 1..=5 {
+            let mut video = sample_video(&format!("day{day}"));
+            video.upload_date = Some(format!("2024-01-0{day}T00:00:00Z"));
+            ctx.store.upsert_video(&video).unwrap();
+            ctx.store.upsert_short(&video).unwrap();
+        }
+
+        let query = |page, per_page| ListQuery {
+            page,
+            per_page,
+            ..ListQuery::default()
+        };
+        let Json(VideoListing::Page(page)) =
+            list_videos(AxumState(ctx.state.clone()), Query(query(Some(2), Some(2))))
+                .await
+                .unwrap()
+        else {
+            panic!("expected a page");
+        };
+        let ids: Vec<&str> = page.items.iter().map(|v| v.videoid.as_str()).collect();
+        assert_eq!(ids, ["day3", "day2"]);
+        assert_eq!((page.total, page.page, page.per_page), (5, 2, 2));
+
+        // `page` alone uses the default size.
+        let Json(VideoListing::Page(page)) =
+            list_videos(AxumState(ctx.state.clone()), Query(query(Some(1), None)))
+                .await
+                .unwrap()
+        else {
+            panic!("expected a page");
+        };
+        assert_eq!((page.items.len(), page.per_page), (5, DEFAULT_PER_PAGE));
+
+        for (page, per_page) in [(Some(0), None), (None, Some(0)), (None, Some(1_000))] {
+            let err = list_videos(AxumState(ctx.state.clone()), Query(query(page, per_page)))
+                .await
+                .unwrap_err();
+            assert_eq!(err.status, StatusCode::BAD_REQUEST);
+        }
+
+        let shorts = ShortsQuery {
+            order: Some(ShortsOrder::Views),
+            page: Some(3),
+            per_page: Some(2),
+            ..ShortsQuery::default()
+        };
+        let (_, Json(ShortsListing::Page(page))) =
+            list_shorts(AxumState(ctx.state.clone()), Query(shorts))
+                .await
+                .unwrap()
+        else {
+            panic!("expected a page");
+        };
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.total, 5);
+    }
+
+    #[tokio::test]
+    async fn fields_query_projects_records() {
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let ctx = BackendTestContext::new();
+        ctx.store.upsert_video(&sample_video("alpha")).unwrap();
+        let www = tempdir().unwrap();
+        let app = build_router(ctx.state.clone(), www.path());
+        let get_json = |uri: &str| {
+            let app = app.clone();
+            let request = Request::get(uri).body(Body::empty()).unwrap();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+                )
+            }
+        };
+
+        let (status, list) = get_json("/api/videos?fields=title,duration").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            list,
+            json!([{"videoid": "alpha", "title": "Video alpha", "duration": 60}])
+        );
+
+        let (_, page) = get_json("/api/videos?page=1&fields=title").await;
+        assert_eq!(page["total"], 1);
+        assert_eq!(
+            page["items"],
+            json!([{"videoid": "alpha", "title": "Video alpha"}])
+        );
+
+        let (_, single) = get_json("/api/videos/alpha?fields=views").await;
+        assert_eq!(single, json!({"videoid": "alpha", "views": 10}));
+
+        let (status, error) = get_json("/api/videos?fields=title,secret").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["error"], "unknown field: secret");
+
+        // Every key a record can serialize to is selectable, including the
+        // optional ones the fixture leaves empty.
+        let mut record = sample_video("alpha");
+        record.local_views = Some(3);
+        let record = serde_json::to_value(record).unwrap();
+        for key in record.as_object().unwrap().keys() {
+            assert!(VIDEO_RECORD_FIELDS.contains(&key.as_str()), "{key}");
+        }
+    }
+
+    #[tokio::test]
+    async fn api_responses_strip_file_paths() {
+        let ctx = BackendTestContext::new();
+        let mut video = sample_video("alpha");
+        video.sources[0].path = Some("/yt/videos/alpha/secret.mp4".into());
+        ctx.store.upsert_video(&video).unwrap();
+
+        let Json(VideoListing::Full(videos)) =
+            super::list_videos(AxumState(ctx.state.clone()), Query(ListQuery::default()))
+                .await
+                .unwrap()
+        else {
+            panic!("expected the full listing");
+        };
+        assert!(videos[0].sources[0].path.is_none());
+
+        let Json(single) = super::get_video(AxumState(ctx.state.clone()), AxumPath("alpha".into()))
+            .await
+            .unwrap();
+        assert!(single.sources[0].path.is_none());
+
+        let bootstrap = ctx.state.get_bootstrap().await.unwrap();
+        assert!(bootstrap.videos[0].sources[0].path.is_none());
+    }
+
+    #[tokio::test]
+    async fn shorts_feed_orders_by_views_or_seeded_shuffle() {
+        let ctx = BackendTestContext::new();
+        for index in 0..20 {
+            let mut short = sample_video(&format!("short{index:02}"));
+            short.views = Some((index * 7 % 20) as i64);
+            ctx.store.upsert_short(&short).unwrap();
+        }
+        let feed = |order: ShortsOrder, seed: Option<u64>| {
+            let state = ctx.state.clone();
+            async move {
+                let query = ShortsQuery {
+                    order: Some(order),
+                    seed,
+                    ..ShortsQuery::default()
+                };
+                let (headers, Json(listing)) =
+                    list_shorts(AxumState(state), Query(query)).await.unwrap();
+                let ShortsListing::Full(shorts) = listing else {
+                    panic!("expected the whole feed");
+                };
+                let ids: Vec<String> = shorts.into_iter().map(|short| short.videoid).collect();
+                (headers, ids)
+            }
+        };
+
+        let (_, by_date) = feed(ShortsOrder::Date, None).await;
+        let (_, by_views) = feed(ShortsOrder::Views, None).await;
+        assert_ne!(by_date, by_views);
+        let views: Vec<i64> = by_views
+            .iter()
+            .map(|id| {
+                let index: i64 = id["short".len()..].parse().unwrap();
+                index * 7 % 20
+            })
+            .collect();
+        assert!(views.windows(2).all(|pair| pair[0] >= pair[1]));
+
+        let (headers, first) = feed(ShortsOrder::Shuffle, Some(42)).await;
+        let (_, again) = feed(ShortsOrder::Shuffle, Some(42)).await;
+        let (_, other) = feed(ShortsOrder::Shuffle, Some(43)).await;
+        assert_eq!(headers[SHUFFLE_SEED_HEADER], "42");
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert_eq!(sorted.len(), 20);
+        sorted.dedup();
+        assert_eq!(sorted.len(), 20);
+
+        // Without a seed one is picked and reported so it can be reused.
+        let (headers, unseeded) = feed(ShortsOrder::Shuffle, None).await;
+        let seed: u64 = headers[SHUFFLE_SEED_HEADER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(feed(ShortsOrder::Shuffle, Some(seed)).await.1, unseeded);
+    }
+
+    #[tokio::test]
+    async fn next_video_follows_channel_upload_order() {
+        let ctx = BackendTestContext::new();
+        for (id, epoch, channel) in [
+            ("first", 100, "UCone"),
+            ("other", 150, "UCtwo"),
+            ("second", 200, "UCone"),
+            ("third", 300, "UCone"),
+        ] {
+            let mut video = sample_video(id);
+            video.upload_epoch = Some(epoch);
+            video.extras = json!({ "channelId": channel });
+            ctx.store.upsert_video(&video).unwrap();
+        }
+        let next = |id: &str| {
+            let state = ctx.state.clone();
+            let id = id.to_owned();
+            async move { get_next_video(AxumState(state), AxumPath(id)).await }
+        };
+
+        let response = next("first").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let record: VideoRecord = serde_json::from_slice(&body).unwrap();
+        assert_eq!(record.videoid, "second");
+
+        // The newest upload has nothing to advance to.
+        let response = next("third").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = next("other").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let err = next("missing").await.unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn provenance_reports_the_yt_dlp_build() {
+        let ctx = BackendTestContext::new();
+        let mut video = sample_video("alpha");
+        video.extras = json!({
+            "provenance": {
+                "extractor": "youtube",
+                "extractorKey": "Youtube",
+                "ytDlpVersion": "2025.09.26",
+            }
+        });
+        ctx.store.upsert_video(&video).unwrap();
+        ctx.store.upsert_short(&sample_video("legacy")).unwrap();
+
+        let Json(provenance) =
+            get_video_provenance(AxumState(ctx.state.clone()), AxumPath("alpha".into()))
+                .await
+                .unwrap();
+        assert_eq!(provenance.extractor.as_deref(), Some("youtube"));
+        assert_eq!(provenance.extractor_key.as_deref(), Some("Youtube"));
+        assert_eq!(provenance.yt_dlp_version.as_deref(), Some("2025.09.26"));
+        assert!(provenance.refreshed_at.is_some());
+
+        // Records from before provenance was captured report nulls.
+        let Json(provenance) =
+            get_short_provenance(AxumState(ctx.state.clone()), AxumPath("legacy".into()))
+                .await
+                .unwrap();
+        assert_eq!(provenance.yt_dlp_version, None);
+
+        let err = get_video_provenance(AxumState(ctx.state.clone()), AxumPath("legacy".into()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn search_filters_by_kind_and_rejects_empty_queries() {
+        let ctx = BackendTestContext::new();
+        let mut video = sample_video("vid");
+        video.title = "Building a birdhouse".into();
+        ctx.store.upsert_video(&video).unwrap();
+        let mut short = sample_video("short");
+        short.title = "Birdhouse in 60 seconds".into();
+        ctx.store.upsert_short(&short).unwrap();
+
+        let run = |q: &str, kind: SearchKind| {
+            let state = ctx.state.clone();
+            let query = SearchQuery {
+                q: q.into(),
+                kind,
+                limit: None,
+            };
+            async move { search(AxumState(state), Query(query)).await }
+        };
+
+        let Json(videos) = run("BIRDHOUSE", SearchKind::Videos).await.unwrap();
+        let ids: Vec<&str> = videos.iter().map(|v| v.videoid.as_str()).collect();
+        assert_eq!(ids, ["vid"]);
+        let Json(shorts) = run("birdhouse", SearchKind::Shorts).await.unwrap();
+        assert_eq!(shorts.len(), 1);
+        assert_eq!(shorts[0].videoid, "short");
+        let Json(none) = run("kayak", SearchKind::Videos).await.unwrap();
+        assert!(none.is_empty());
+
+        let err = run("   ", SearchKind::Videos).await.unwrap_err();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn list_endpoints_filter_by_channel() {
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let ctx = BackendTestContext::new();
+        for (id, channel) in [
+            ("a1", "https://youtube.com/@Alpha"),
+            ("b1", "https://youtube.com/@Beta"),
+            ("a2", "https://youtube.com/@Alpha"),
+            ("a3", "https://youtube.com/@Alpha"),
+        ] {
+            let mut video = sample_video(id);
+            video.channel_url = Some(channel.into());
+            ctx.store.upsert_video(&video).unwrap();
+            ctx.store.upsert_short(&video).unwrap();
+        }
+        let www = tempdir().unwrap();
+        let app = build_router(ctx.state.clone(), www.path());
+        let get = |uri: String| {
+            let app = app.clone();
+            async move {
+                let request = Request::get(uri).body(Body::empty()).unwrap();
+                let response = app.oneshot(request).await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<Value>(&body).unwrap()
+            }
+        };
+        let ids = |items: &Value| -> Vec<String> {
+            items
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|item| item["videoid"].as_str().unwrap().to_owned())
+                .collect()
+        };
+        let beta = "https%3A%2F%2Fyoutube.com%2F%40Beta";
+        let alpha = "https%3A%2F%2Fyoutube.com%2F%40Alpha";
+
+        let listed = get(format!("/api/videos?channel={beta}")).await;
+        assert_eq!(ids(&listed), ["b1"]);
+        let listed = get(format!("/api/shorts?channel={beta}")).await;
+        assert_eq!(ids(&listed), ["b1"]);
+
+        // Pages and totals only count the requested channel.
+        let page = get(format!("/api/videos?channel={alpha}&page=2&per_page=2")).await;
+        assert_eq!(page["total"], 3);
+        assert_eq!(ids(&page["items"]), ["a1"]);
+        let page = get(format!("/api/shorts?channel={alpha}&page=1&per_page=2")).await;
+        assert_eq!(page["total"], 3);
+        assert_eq!(ids(&page["items"]), ["a3", "a2"]);
+
+        let listed = get("/api/videos?channel=https%3A%2F%2Fyoutube.com%2F%40beta".into()).await;
+        assert!(ids(&listed).is_empty());
+    }
+
+    #[tokio::test]
+    async fn list_endpoints_sort_and_filter_by_tag() {
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let ctx = BackendTestContext::new();
+        for (id, date, views, likes, duration, tag) in [
+            ("a", "2024-01-01", 30, 1, 300, "rust"),
+            ("b", "2024-01-02", 10, 3, 100, "Rust"),
+            ("c", "2024-01-03", 20, 2, 200, "music"),
+        ] {
+            let mut video = sample_video(id);
+            video.upload_date = Some(date.into());
+            video.views = Some(views);
+            video.likes = Some(likes);
+            video.duration = Some(duration);
+            video.tags = vec![tag.into()];
+            ctx.store.upsert_video(&video).unwrap();
+            ctx.store.upsert_short(&video).unwrap();
+        }
+        let www = tempdir().unwrap();
+        let app = build_router(ctx.state.clone(), www.path());
+        let get = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let request = Request::get(uri).body(Body::empty()).unwrap();
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null),
+                )
+            }
+        };
+        let ids = |items: &Value| -> Vec<String> {
+            items
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|item| item["videoid"].as_str().unwrap().to_owned())
+                .collect()
+        };
+
+        for (uri, expected) in [
+            ("/api/videos?sort=date", ["c", "b", "a"]),
+            ("/api/videos?sort=date&order=asc", ["a", "b", "c"]),
+            ("/api/videos?sort=views", ["a", "c", "b"]),
+            ("/api/videos?sort=views&order=asc", ["b", "c", "a"]),
+            ("/api/videos?sort=likes", ["b", "c", "a"]),
+            ("/api/videos?sort=likes&order=asc", ["a", "c", "b"]),
+            ("/api/videos?sort=duration", ["a", "c", "b"]),
+            ("/api/videos?sort=duration&order=asc", ["b", "c", "a"]),
+            ("/api/videos?order=asc", ["a", "b", "c"]),
+            ("/api/shorts?sort=likes&order=asc", ["a", "c", "b"]),
+            ("/api/shorts?order=views", ["a", "c", "b"]),
+        ] {
+            let (status, listed) = get(uri).await;
+            assert_eq!(status, StatusCode::OK, "{uri}");
+            assert_eq!(ids(&listed), expected, "{uri}");
+        }
+
+        let (_, page) = get("/api/videos?tag=RUST&sort=views&page=1&per_page=1").await;
+        assert_eq!(page["total"], 2);
+        assert_eq!(ids(&page["items"]), ["a"]);
+        let (_, listed) = get("/api/shorts?tag=music").await;
+        assert_eq!(ids(&listed), ["c"]);
+        let (_, listed) = get("/api/shorts?tag=rust&order=shuffle&seed=1").await;
+        assert_eq!(ids(&listed).len(), 2);
+
+        for uri in [
+            "/api/videos?sort=title",
+            "/api/videos?sort=views;DROP%20TABLE%20videos",
+            "/api/videos?order=sideways",
+            "/api/videos?sort=views&since=2024-01-01T00:00:00Z",
+            "/api/shorts?sort=views&order=views",
+            "/api/shorts?sort=views&order=shuffle",
+        ] {
+            assert_eq!(get(uri).await.0, StatusCode::BAD_REQUEST, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn videos_by_date_routes_and_validates() {
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let ctx = BackendTestContext::new();
+        for (id, epoch) in [("old", 1_552_550_400), ("new", 1_710_403_200)] {
+            // 2019-03-14 and 2024-03-14, 08:00 UTC.
+            let mut video = sample_video(id);
+            video.upload_epoch = Some(epoch);
+            ctx.store.upsert_video(&video).unwrap();
+        }
+        let www = tempdir().unwrap();
+        let app = build_router(ctx.state.clone(), www.path());
+        let get = |uri: &str| {
+            let app = app.clone();
+            let request = Request::get(uri).body(Body::empty()).unwrap();
+            async move { app.oneshot(request).await.unwrap() }
+        };
+        let ids = |body: Bytes| {
+            let videos: Vec<VideoRecord> = serde_json::from_slice(&body).unwrap();
+            videos
+                .into_iter()
+                .map(|video| video.videoid)
+                .collect::<Vec<_>>()
+        };
+
+        let response = get("/api/videos/by-date?month=3&day=14").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(ids(body), ["new", "old"]);
+
+        let response = get("/api/videos/by-date?year=2019&month=03").await;
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(ids(body), ["old"]);
+
+        for uri in [
+            "/api/videos/by-date?day=14",
+            "/api/videos/by-date?month=13",
+            "/api/videos/by-date?month=3&day=32",
+        ] {
+            assert_eq!(get(uri).await.status(), StatusCode::BAD_REQUEST, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn list_responses_clip_descriptions_on_char_boundaries() {
+        let mut ctx = BackendTestContext::new();
+        ctx.state.list_description_chars = 4;
+        // `é` and `🎉` are multibyte; byte 4 falls inside `é`.
+        let mut long = sample_video("long");
+        long.description = "cafés 🎉 and more".into();
+        ctx.store.upsert_video(&long).unwrap();
+        let mut short = sample_video("short");
+        short.description = "née!".into();
+        ctx.store.upsert_video(&short).unwrap();
+
+        let Json(VideoListing::Full(videos)) =
+            super::list_videos(AxumState(ctx.state.clone()), Query(ListQuery::default()))
+                .await
+                .unwrap()
+        else {
+            panic!("expected the full listing");
+        };
+        let listed = |id: &str| videos.iter().find(|video| video.videoid == id).unwrap();
+        assert_eq!(listed("long").description, "café");
+        assert!(listed("long").description_truncated);
+        assert_eq!(listed("short").description, "née!");
+        assert!(!listed("short").description_truncated);
+
+        let bootstrap = ctx.state.get_bootstrap().await.unwrap();
+        assert!(
+            bootstrap
+                .videos
+                .iter()
+                .all(|video| video.description.chars().count() <= 4)
+        );
+
+        let Json(single) = super::get_video(AxumState(ctx.state.clone()), AxumPath("long".into()))
+            .await
+            .unwrap();
+        assert_eq!(single.description, "cafés 🎉 and more");
+        assert!(!single.description_truncated);
+
+        ctx.state.list_description_chars = 0;
+        let records = list_video_records(&[long], ctx.state.list_description_chars);
+        assert_eq!(records[0].description, "cafés 🎉 and more");
+    }
+
+    #[tokio::test]
+    async fn media_lookup_prefers_cache() {
+        let mut ctx = BackendTestContext::new();
+        ctx.insert_video("alpha");
+        let record = ctx
+            .state
+            .get_media(MediaCategory::Video, "alpha")
+            .await
+            .unwrap();
+        assert_eq!(record.videoid, "alpha");
+
+        ctx.delete_by_videoid("videos", "alpha");
+        let cached = ctx
+            .state
+            .get_media(MediaCategory::Video, "alpha")
+            .await
+            .unwrap();
+        assert_eq!(cached.videoid, "alpha");
+
+        let err = ctx
+            .state
+            .get_media(MediaCategory::Video, "ghost")
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn comments_and_subtitles_cache() {
+        let mut ctx = BackendTestContext::new();
+        ctx.insert_video("alpha");
+        ctx.insert_comments("alpha", vec![sample_comment("1", "alpha")]);
+        ctx.insert_subtitles(
+            "alpha",
+            vec![SubtitleTrack {
+                code: "en".into(),
+                name: "English".into(),
+                url: "/sub".into(),
+                path: None,
+            }],
+        );
+
+        let first_comments = ctx.state.get_comments("alpha").await.unwrap();
+        assert_eq!(first_comments.len(), 1);
+        ctx.delete_by_videoid("comments", "alpha");
+        let cached_comments = ctx.state.get_comments("alpha").await.unwrap();
+        assert_eq!(cached_comments.len(), 1);
+
+        let first_subtitles = ctx.state.get_subtitles("alpha").await.unwrap();
+        assert!(first_subtitles.is_some());
+        ctx.delete_by_videoid("subtitles", "alpha");
+        let cached_subtitles = ctx.state.get_subtitles("alpha").await.unwrap();
+        assert!(cached_subtitles.is_some());
+    }
+
+    #[tokio::test]
+    async fn list_subtitles_includes_download_urls() {
+        let mut ctx = BackendTestContext::new();
+        ctx.insert_video("alpha");
+        ctx.insert_subtitles(
+            "alpha",
+            vec![SubtitleTrack {
+                code: "en".into(),
+                name: "English".into(),
+                url: "/api/videos/alpha/subtitles/en".into(),
+                path: None,
+            }],
+        );
+
+        let Json(payload) = super::list_subtitles(ctx.state.clone(), "alpha".into(), "videos")
+            .await
+            .unwrap();
+        assert_eq!(payload.len(), 1);
+        assert!(payload[0].url.contains("/videos/alpha/subtitles/en"));
+    }
+
+    #[tokio::test]
+    async fn download_subtitle_uses_fallback_path() {
+        let mut ctx = BackendTestContext::new();
+        ctx.insert_video("alpha");
+        ctx.insert_subtitles(
+            "alpha",
+            vec![SubtitleTrack {
+                code: "en".into(),
+                name: "English".into(),
+                url: "/api/videos/alpha/subtitles/en".into(),
+                path: None,
+            }],
+        );
+
+        let subtitle_dir = ctx.state.files.subtitles.join("alpha");
+        std::fs::create_dir_all(&subtitle_dir).unwrap();
+        std::fs::write(subtitle_dir.join("alpha.en.vtt"), "WEBVTT").unwrap();
+
+        let response = download_subtitle(ctx.state.clone(), "alpha".into(), "en".into())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn transcript_collapses_rolling_auto_caption_lines() {
+        let mut ctx = BackendTestContext::new();
+        ctx.insert_video("alpha");
+        ctx.insert_subtitles(
+            "alpha",
+            vec![SubtitleTrack {
+                code: "en".into(),
+                name: "English".into(),
+                url: "/api/videos/alpha/subtitles/en".into(),
+                path: None,
+            }],
+        );
+
+        let vtt = "WEBVTT\nKind: captions\nLanguage: en\n\n\
+            00:00:00.000 --> 00:00:02.000 align:start position:0%\n\
+            hello<00:00:00.500><c> world</c>\n\n\
+            00:00:02.000 --> 00:00:02.010 align:start position:0%\n\
+            hello world\n \n\n\
+            00:00:02.010 --> 00:00:04.000 align:start position:0%\n\
+            hello world\n\
+            this &amp; that<00:00:02.500><c> again</c>\n\n\
+            00:00:04.000 --> 00:00:06.000 align:start position:0%\n\
+            this &amp; that again\n\
+            goodbye\n";
+        let subtitle_dir = ctx.state.files.subtitles.join("alpha");
+        std::fs::create_dir_all(&subtitle_dir).unwrap();
+        std::fs::write(subtitle_dir.join("alpha.en.vtt"), vtt).unwrap();
+
+        let response = get_transcript(
+            AxumState(ctx.state.clone()),
+            AxumPath("alpha".into()),
+            Query(TranscriptQuery {
+                lang: Some("en".into()),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&body),
+            "hello world\nthis & that again\ngoodbye\n"
+        );
+
+        let missing = get_transcript(
+            AxumState(ctx.state.clone()),
+            AxumPath("alpha".into()),
+            Query(TranscriptQuery {
+                lang: Some("fr".into()),
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(missing.status, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn transcript_handles_srt_cues() {
+        let srt = "1\r\n00:00:01,000 --> 00:00:02,000\r\n<i>First</i> line\r\n\r\n\
+            2\r\n00:00:02,000 --> 00:00:03,000\r\nSecond line\r\n";
+        assert_eq!(subtitle_to_transcript(srt), "First line\nSecond line\n");
+    }
+
+    #[tokio::test]
+    async fn chapters_vtt_formats_stored_chapters() {
+        let mut ctx = BackendTestContext::new();
+        ctx.insert_video("plain");
+        let mut video = sample_video("alpha");
+        video.duration = Some(3725);
+        video.chapters = serde_json::from_value(json!([
+            {"startTime": 0.0, "endTime": 65.5, "title": "Intro"},
+            {"startTime": 65.5, "endTime": null, "title": "Main part"},
+            {"startTime": 3600.0, "endTime": null, "title": ""},
+        ]))
+        .unwrap();
+        ctx.store.upsert_video(&video).unwrap();
+
+        let response = get_chapters_vtt(AxumState(ctx.state.clone()), AxumPath("alpha".into()))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/vtt; charset=utf-8"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&body),
+            "WEBVTT\n\n\
+             1\n00:00:00.000 --> 00:01:05.500\nIntro\n\n\
+             2\n00:01:05.500 --> 01:00:00.000\nMain part\n\n\
+             3\n01:00:00.000 --> 01:02:05.000\nChapter 3\n"
+        );
+
+        let missing = get_chapters_vtt(AxumState(ctx.state.clone()), AxumPath("plain".into()))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn sponsorblock_route_lists_stored_segments() {
+        let mut ctx = BackendTestContext::new();
+        ctx.insert_video("plain");
+        let mut video = sample_video("alpha");
+        video.sponsor_segments = Some(vec![SponsorSegment {
+            start_time: 12.5,
+            end_time: 40.0,
+            category: "sponsor".into(),
+        }]);
+        ctx.store.upsert_video(&video).unwrap();
+
+        let Json(segments) =
+            get_sponsor_segments(AxumState(ctx.state.clone()), AxumPath("alpha".into()))
+                .await
+                .unwrap();
+        assert_eq!(
+            serde_json::to_value(&segments).unwrap(),
+            json!([{"startTime": 12.5, "endTime": 40.0, "category": "sponsor"}])
+        );
+
+        let Json(none) =
+            get_sponsor_segments(AxumState(ctx.state.clone()), AxumPath("plain".into()))
+                .await
+                .unwrap();
+        assert!(none.is_empty());
+
+        let missing = get_sponsor_segments(AxumState(ctx.state.clone()), AxumPath("ghost".into()))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn video_page_bundles_every_section() {
+        let mut ctx = BackendTestContext::new();
+        let mut alpha = sample_video("alpha");
+        alpha.chapters = vec![Chapter {
+            start_time: 0.0,
+            end_time: Some(5.0),
+            title: "Intro".into(),
+        }];
+        ctx.store.upsert_video(&alpha).unwrap();
+        ctx.insert_video("beta");
+        let mut other_channel = sample_video("gamma");
+        other_channel.channel_url = Some("https://example.test/elsewhere".into());
+        ctx.store.upsert_video(&other_channel).unwrap();
+        let comments = (0..PAGE_COMMENT_LIMIT + 5)
+            .map(|index| sample_comment(&format!("c{index}"), "alpha"))
+            .collect();
+        ctx.insert_comments("alpha", comments);
+        ctx.insert_subtitles(
+            "alpha",
+            vec![SubtitleTrack {
+                code: "en".into(),
+                name: "English".into(),
+                url: "/api/videos/alpha/subtitles/en".into(),
+                path: None,
+            }],
+        );
+
+        let Json(page) = get_video_page(AxumState(ctx.state.clone()), AxumPath("alpha".into()))
+            .await
+            .unwrap();
+        let page = serde_json::to_value(page).unwrap();
+        assert_eq!(page["video"]["videoid"], "alpha");
+        assert_eq!(
+            page["comments"].as_array().unwrap().len(),
+            PAGE_COMMENT_LIMIT
+        );
+        assert_eq!(page["comment_total"], PAGE_COMMENT_LIMIT + 5);
+        assert_eq!(page["subtitles"][0]["code"], "en");
+        assert_eq!(page["chapters"][0]["title"], "Intro");
+        assert_eq!(page["video"]["chapters"], page["chapters"]);
+        assert_eq!(page["related"][0]["chapters"], json!([]));
+        let related: Vec<&str> = page["related"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|video| video["videoid"].as_str().unwrap())
+            .collect();
+        assert_eq!(related, ["beta"]);
+
+        let missing = get_video_page(AxumState(ctx.state.clone()), AxumPath("nope".into()))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(missing.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn download_thumbnail_serves_local_files() {
+        let ctx = BackendTestContext::new();
+        let thumb_dir = ctx.state.files.thumbnails.join("alpha");
+        std::fs::create_dir_all(&thumb_dir).unwrap();
+        std::fs::write(thumb_dir.join("poster.png"), b"PNG").unwrap();
+
+        let fetch = |headers: HeaderMap| {
+            let state = ctx.state.clone();
+            async move { download_thumbnail(state, "alpha".into(), "poster.png".into(), &headers).await }
+        };
+        let response = fetch(HeaderMap::new()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            MEDIA_CACHE_CONTROL
+        );
+        assert!(response.headers().contains_key(header::LAST_MODIFIED));
+        let etag = response.headers()[header::ETAG].clone();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), b"PNG");
+
+        // A revalidation with the ETag is answered without a body.
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, etag);
+        let response = fetch(headers).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            MEDIA_CACHE_CONTROL
+        );
+
+        // Directories inside the thumbnail folder are not served.
+        std::fs::create_dir(thumb_dir.join("nested")).unwrap();
+        let err = download_thumbnail(
+            ctx.state.clone(),
+            "alpha".into(),
+            "nested".into(),
+            &HeaderMap::new(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn resolve_source_path_keeps_case_colliding_ids_apart() {
+        let dir = tempdir().unwrap();
+        let mut files = FilePaths::for_base(dir.path());
+        files.case_insensitive = true;
+        let mut source = sample_video("abcD").sources[0].clone();
+        source.path = None;
+
+        let upper = resolve_source_path(&files, MediaCategory::Video, "abcD", &source);
+        let lower = resolve_source_path(&files, MediaCategory::Video, "abcd", &source);
+        assert_eq!(upper.parent().unwrap(), files.videos.join("abcD~8"));
+        assert_eq!(lower.parent().unwrap(), files.videos.join("abcd~0"));
+    }
+
+    #[tokio::test]
+    async fn download_thumbnail_rejects_path_traversal() {
+        let ctx = BackendTestContext::new();
+        let err = download_thumbnail(
+            ctx.state.clone(),
+            "alpha".into(),
+            "../secret.txt".into(),
+            &HeaderMap::new(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+
+        let staging = ctx.state.files.thumbnails.join(".staging");
+        std::fs::create_dir_all(&staging).unwrap();
+        std::fs::write(staging.join("partial.jpg"), b"JPG").unwrap();
+        let err = download_thumbnail(
+            ctx.state.clone(),
+            ".staging".into(),
+            "partial.jpg".into(),
+            &HeaderMap::new(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn stream_media_uses_custom_path() {
+        let ctx = BackendTestContext::new();
+        let mut video = sample_video("alpha");
+        let custom = ctx.state.files.videos.join("custom.mp4");
+        std::fs::create_dir_all(custom.parent().unwrap()).unwrap();
+        std::fs::write(&custom, "bytes").unwrap();
+        video.sources[0].path = Some(custom.to_string_lossy().into_owned());
+        ctx.store.upsert_video(&video).unwrap();
+
+        let response = stream_media(
+            ctx.state.clone(),
+            MediaCategory::Video,
+            "alpha".into(),
+            "1080p".into(),
+            &HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "video/mp4"
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_media_builds_default_path() {
+        let ctx = BackendTestContext::new();
+        let mut video = sample_video("alpha");
+        video.sources[0].path = None;
+        ctx.store.upsert_video(&video).unwrap();
+        let media_dir = ctx
+            .state
+            .files
+            .media_dir(MediaCategory::Video)
+            .join("alpha");
+        std::fs::create_dir_all(&media_dir).unwrap();
+        std::fs::write(media_dir.join("alpha_1080p.mp4"), "bytes").unwrap();
+
+        let response = stream_media(
+            ctx.state.clone(),
+            MediaCategory::Video,
+            "alpha".into(),
+            "1080p".into(),
+            &HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn zero_byte_media_is_not_found() {
+        let ctx = BackendTestContext::new();
+        let mut video = sample_video("alpha");
+        video.sources[0].path = None;
+        ctx.store.upsert_video(&video).unwrap();
+        let media_dir = ctx.state.files.videos.join("alpha");
+        std::fs::create_dir_all(&media_dir).unwrap();
+        std::fs::write(media_dir.join("alpha_1080p.mp4"), "").unwrap();
+
+        let err = stream_media(
+            ctx.state.clone(),
+            MediaCategory::Video,
+            "alpha".into(),
+            "1080p".into(),
+            &HeaderMap::new(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+        assert_eq!(err.message, "media file is empty");
+    }
+
+    type ObjectStoreLog = Arc<std::sync::Mutex<Vec<(String, Option<String>)>>>;
+
+    /// Minimal S3-style server for `objects` (keyed by request path) that
+    /// honors `bytes=a-b`/`bytes=a-` ranges and logs each request's path and
+    /// `Range` header. Returns its base URL.
+    fn spawn_object_store(objects: HashMap<String, Vec<u8>>) -> (String, ObjectStoreLog) {
+        use std::io::{BufRead, BufReader};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let log = ObjectStoreLog::default();
+        let requests = Arc::clone(&log);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let path = request_line.split_whitespace().nth(1).unwrap().to_owned();
+                let mut range = None;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':')
+                        && name.eq_ignore_ascii_case("range")
+                    {
+                        range = Some(value.trim().to_owned());
+                    }
+                }
+                requests.lock().unwrap().push((path.clone(), range.clone()));
+
+                let (head, body): (String, &[u8]) = match objects.get(&path) {
+                    None => ("404 Not Found".into(), b""),
+                    Some(data) => match range.as_deref().and_then(|r| r.strip_prefix("bytes=")) {
+                        None => ("200 OK".into(), data),
+                        Some(spec) => {
+                            let (first, last) = spec.split_once('-').unwrap();
+                            let start: usize = first.parse().unwrap();
+                            let end = last.parse().unwrap_or(data.len() - 1);
+                            if start >= data.len() {
+                                (
+                                    format!(
+                                        "416 Range Not Satisfiable\r\nContent-Range: bytes */{}",
+                                        data.len()
+                                    ),
+                                    b"",
+                                )
+                            } else {
+                                (
+                                    format!(
+                                        "206 Partial Content\r\nContent-Range: bytes {start}-{end}/{}",
+                                        data.len()
+                                    ),
+                                    &data[start..=end],
+                                )
+                            }
+                        }
+                    },
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {head}\r\nContent-Length: {}\r\nContent-Type: application/octet-stream\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(body).unwrap();
+            }
+        });
+        (base, log)
+    }
+
+    /// Context whose `alpha` 1080p source only exists in a mock bucket.
+    fn object_store_context() -> (BackendTestContext, ObjectStoreLog) {
+        let mut ctx = BackendTestContext::new();
+        let mut video = sample_video("alpha");
+        video.sources[0].path = None;
+        ctx.store.upsert_video(&video).unwrap();
+        let objects = HashMap::from([(
+            "/bucket/videos/alpha/alpha_1080p.mp4".to_owned(),
+            b"0123456789".to_vec(),
+        )]);
+        let (base, log) = spawn_object_store(objects);
+        ctx.state.object_store = Some(Arc::new(ObjectStore::new(&format!("{base}/bucket/"))));
+        (ctx, log)
+    }
+
+    async fn stream_alpha(ctx: &BackendTestContext, range: Option<&str>) -> ApiResult<Response> {
+        stream_media(
+            ctx.state.clone(),
+            MediaCategory::Video,
+            "alpha".into(),
+            "1080p".into(),
+            &range.map(range_headers).unwrap_or_default(),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn object_store_serves_missing_media_with_range_passthrough() {
+        let (ctx, log) = object_store_context();
+
+        let response = stream_alpha(&ctx, Some("bytes=2-5")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            response.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 2-5/10"
+        );
+        assert_eq!(response.headers().get(header::CONTENT_LENGTH).unwrap(), "4");
+        // The source's own mime wins over the bucket's generic one.
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "video/mp4"
+        );
+        assert_eq!(
+            response.headers().get(header::ACCEPT_RANGES).unwrap(),
+            "bytes"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), b"2345");
+
+        let response = stream_alpha(&ctx, None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), b"0123456789");
+
+        let response = stream_alpha(&ctx, Some("bytes=20-")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(
+            response.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes */10"
+        );
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                (
+                    "/bucket/videos/alpha/alpha_1080p.mp4".to_owned(),
+                    Some("bytes=2-5".to_owned())
+                ),
+                ("/bucket/videos/alpha/alpha_1080p.mp4".to_owned(), None),
+                (
+                    "/bucket/videos/alpha/alpha_1080p.mp4".to_owned(),
+                    Some("bytes=20-".to_owned())
+                ),
+            ]
+        );
+        assert_eq!(ctx.state.egress.bytes_served(), 14);
+    }
+
+    #[tokio::test]
+    async fn object_store_is_only_used_for_files_missing_locally() {
+        let (ctx, log) = object_store_context();
+        let media_dir = ctx.state.files.videos.join("alpha");
+        std::fs::create_dir_all(&media_dir).unwrap();
+        std::fs::write(media_dir.join("alpha_1080p.mp4"), "local").unwrap();
+
+        let response = stream_alpha(&ctx, None).await.unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), b"local");
+        assert!(log.lock().unwrap().is_empty());
+
+        // Missing in both places: still a 404, after asking the bucket.
+        let err = download_thumbnail(
+            ctx.state.clone(),
+            "alpha".into(),
+            "poster image.jpg".into(),
+            &HeaderMap::new(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+        assert_eq!(
+            log.lock().unwrap()[0].0,
+            "/bucket/thumbnails/alpha/poster%20image.jpg"
+        );
+    }
+
+    fn range_headers(range: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, HeaderValue::from_str(range).unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn audio_route_streams_the_audio_only_track() {
+        let mut ctx = BackendTestContext::new();
+        ctx.insert_video("plain");
+        let media_dir = ctx.state.files.videos.join("alpha");
+        std::fs::create_dir_all(&media_dir).unwrap();
+        let file = media_dir.join("alpha_audio.m4a");
+        std::fs::write(&file, "AUDIO").unwrap();
+        let mut video = sample_video("alpha");
+        let mut audio = video.sources[0].clone();
+        audio.format_id = "audio".into();
+        audio.mime_type = Some("audio/mp4".into());
+        audio.ext = Some("m4a".into());
+        audio.url = "/api/videos/alpha/audio".into();
+        audio.path = Some(file.to_string_lossy().into_owned());
+        audio.stream_key = Some("audio".into());
+        video.audio_source = Some(audio);
+        ctx.store.upsert_video(&video).unwrap();
+
+        let response = stream_video_audio(
+            AxumState(ctx.state.clone()),
+            AxumPath("alpha".into()),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "audio/mp4");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"AUDIO");
+
+        // The audio track is not a playable video source.
+        let missing = stream_media(
+            ctx.state.clone(),
+            MediaCategory::Video,
+            "alpha".into(),
+            "audio".into(),
+            &HeaderMap::new(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(missing.status, StatusCode::NOT_FOUND);
+        let record = sanitize_video_record(
+            &ctx.state
+                .get_media(MediaCategory::Video, "alpha")
+                .await
+                .unwrap(),
+        );
+        assert!(record.audio_source.unwrap().path.is_none());
+
+        let none = stream_video_audio(
+            AxumState(ctx.state.clone()),
+            AxumPath("plain".into()),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(none.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn media_streams_answer_conditional_requests() {
+        let ctx = BackendTestContext::new();
+        let mut video = sample_video("alpha");
+        video.sources[0].path = None;
+        ctx.store.upsert_video(&video).unwrap();
+        let media_dir = ctx.state.files.videos.join("alpha");
+        std::fs::create_dir_all(&media_dir).unwrap();
+        let file = media_dir.join("alpha_1080p.mp4");
+        std::fs::write(&file, "0123456789").unwrap();
+        let stream = |pairs: Vec<(header::HeaderName, String)>| {
+            let state = ctx.state.clone();
+            async move {
+                let mut headers = HeaderMap::new();
+                for (name, value) in pairs {
+                    headers.insert(name, HeaderValue::from_str(&value).unwrap());
+                }
+                stream_media(
+                    state,
+                    MediaCategory::Video,
+                    "alpha".into(),
+                    "1080p".into(),
+                    &headers,
+                )
+                .await
+                .unwrap()
+            }
+        };
+        let header_of = |response: &Response, name: header::HeaderName| {
+            response.headers()[name].to_str().unwrap().to_owned()
+        };
+
+        let first = stream(Vec::new()).await;
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(
+            header_of(&first, header::CACHE_CONTROL),
+            MEDIA_CACHE_CONTROL
+        );
+        let etag = header_of(&first, header::ETAG);
+        let last_modified = header_of(&first, header::LAST_MODIFIED);
+        assert!(last_modified.ends_with(" GMT"), "{last_modified}");
+
+        for pairs in [
+            vec![(header::IF_NONE_MATCH, etag.clone())],
+            vec![(header::IF_NONE_MATCH, format!("\"other\", W/{etag}"))],
+            vec![
+                (header::IF_NONE_MATCH, etag.clone()),
+                (header::RANGE, "bytes=0-3".to_owned()),
+            ],
+            vec![(header::IF_MODIFIED_SINCE, last_modified.clone())],
+        ] {
+            let response = stream(pairs.clone()).await;
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED, "{pairs:?}");
+            assert_eq!(header_of(&response, header::ETAG), etag);
+            assert_eq!(
+                header_of(&response, header::CACHE_CONTROL),
+                MEDIA_CACHE_CONTROL
+            );
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            assert!(body.is_empty());
+        }
+        let stale = stream(vec![(
+            header::IF_MODIFIED_SINCE,
+            "Mon, 01 Jan 1990 00:00:00 GMT".to_owned(),
+        )])
+        .await;
+        assert_eq!(stale.status(), StatusCode::OK);
+
+        // A re-downloaded file gets a new tag, so old copies are refetched.
+        std::fs::write(&file, "0123456789abcdef").unwrap();
+        let changed = stream(vec![(header::IF_NONE_MATCH, etag.clone())]).await;
+        assert_eq!(changed.status(), StatusCode::OK);
+        assert_ne!(header_of(&changed, header::ETAG), etag);
+        let body = to_bytes(changed.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), b"0123456789abcdef");
+    }
+
+    /// Streams the default `alpha` 1080p source (`0123456789`) with `range`.
+    async fn stream_alpha_range(range: &str) -> Response {
+        let ctx = BackendTestContext::new();
+        let mut video = sample_video("alpha");
+        video.sources[0].path = None;
+        ctx.store.upsert_video(&video).unwrap();
+        let media_dir = ctx.state.files.videos.join("alpha");
+        std::fs::create_dir_all(&media_dir).unwrap();
+        std::fs::write(media_dir.join("alpha_1080p.mp4"), "0123456789").unwrap();
+
+        stream_media(
+            ctx.state.clone(),
+            MediaCategory::Video,
+            "alpha".into(),
+            "1080p".into(),
+            &range_headers(range),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn stream_media_serves_partial_content() {
+        let response = stream_alpha_range("bytes=2-5").await;
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            response.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 2-5/10"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), b"2345");
+
+        let response = stream_alpha_range("bytes=-3").await;
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), b"789");
+    }
+
+    #[tokio::test]
+    async fn stream_media_serves_open_ended_ranges() {
+        let response = stream_alpha_range("bytes=7-").await;
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            response.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 7-9/10"
+        );
+        assert_eq!(response.headers().get(header::CONTENT_LENGTH).unwrap(), "3");
+        assert_eq!(
+            response.headers().get(header::ACCEPT_RANGES).unwrap(),
+            "bytes"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), b"789");
+
+        // An end past the file is clamped rather than rejected.
+        let response = stream_alpha_range("bytes=8-100").await;
+        assert_eq!(
+            response.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 8-9/10"
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_media_rejects_out_of_bounds_range() {
+        let response = stream_alpha_range("bytes=10-20").await;
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(
+            response.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes */10"
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_media_ignores_malformed_range() {
+        for range in ["bytes=abc", "items=0-1", "bytes=5-2", "bytes=0-1,4-5"] {
+            let response = stream_alpha_range(range).await;
+            assert_eq!(response.status(), StatusCode::OK, "{range}");
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            assert_eq!(body.as_ref(), b"0123456789");
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_limited_streams_are_paced_and_counted() {
+        let mut ctx = BackendTestContext::new();
+        ctx.state.egress = Arc::new(Egress::new(Some(40_000)));
+        let path = ctx.state.files.videos.join("big.mp4");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, vec![0u8; 60_000]).unwrap();
+
+        // The bucket starts with one second of credit, so the remaining
+        // 20 000 bytes need at least half a second at 40 000 B/s.
+        let started = Instant::now();
+        let response = stream_file(&ctx.state.egress, path.clone(), None, &HeaderMap::new())
+            .await
+            .unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.len(), 60_000);
+        assert!(started.elapsed() >= Duration::from_millis(490));
+
+        let response = stream_file(&ctx.state.egress, path, None, &range_headers("bytes=0-9"))
+            .await
+            .unwrap();
+        to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        let metrics = metrics(AxumState(ctx.state.clone()), admin_headers())
+            .await
+            .unwrap();
+        assert_eq!(
+            metrics.0,
+            Metrics {
+                bytes_served: 60_010,
+                stream_rate_limit: Some(40_000),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_media_missing_format_errors() {
+        let mut ctx = BackendTestContext::new();
+        ctx.insert_video("alpha");
+        let err = stream_media(
+            ctx.state.clone(),
+            MediaCategory::Video,
+            "alpha".into(),
+            "4k".into(),
+            &HeaderMap::new(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
     }
 
-    #[test]
-    fn backend_args_override_host() {
-        let config = write_runtime_config("/yt/test", "/www/test", 4242, "127.0.0.1");
-        let args = parse_backend_args(&config, &["--host", "0.0.0.0"]);
-        assert_eq!(args.listen_host, "0.0.0.0".parse::<IpAddr>().unwrap());
+    #[tokio::test]
+    async fn api_error_serializes_json() {
+        let response = ApiError::not_found("missing").into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let parsed: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(parsed["error"], "missing");
     }
 
     #[tokio::test]
-    async fn bootstrap_caches_payload() {
+    async fn archive_requires_admin_token() {
+        let ctx = BackendTestContext::new();
+        let err = list_archive(
+            AxumState(ctx.state.clone()),
+            HeaderMap::new(),
+            Query(ArchiveQuery::default()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+
+        let mut disabled = ctx.state.clone();
+        disabled.admin_token = None;
+        let err = list_archive(
+            AxumState(disabled),
+            admin_headers(),
+            Query(ArchiveQuery::default()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn archive_lists_video_and_short_ids() {
         let mut ctx = BackendTestContext::new();
         ctx.insert_video("alpha");
         ctx.insert_short("beta");
-        ctx.insert_subtitles(
-            "alpha",
-            vec![SubtitleTrack {
-                code: "en".into(),
-                name: "English".into(),
-                url: "/api/videos/alpha/subtitles/en".into(),
-                path: None,
-            }],
+
+        let response = list_archive(
+            AxumState(ctx.state.clone()),
+            admin_headers(),
+            Query(ArchiveQuery::default()),
+        )
+        .await
+        .unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let ids: Vec<String> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(ids, vec!["alpha", "beta"]);
+    }
+
+    #[tokio::test]
+    async fn archive_text_format_parses_like_download_archive() {
+        let mut ctx = BackendTestContext::new();
+        ctx.insert_video("alpha");
+        ctx.insert_short("beta");
+
+        let response = list_archive(
+            AxumState(ctx.state.clone()),
+            admin_headers(),
+            Query(ArchiveQuery {
+                format: Some("text".into()),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/plain; charset=utf-8"
         );
-        ctx.insert_comments("alpha", vec![sample_comment("1", "alpha")]);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.lines().all(|line| line.starts_with("youtube ")));
+
+        // Same parsing rules as the downloader's `load_archive`.
+        let parsed: HashSet<&str> = text.lines().filter_map(parse_archive_line).collect();
+        assert_eq!(parsed, HashSet::from(["alpha", "beta"]));
+
+        let err = list_archive(
+            AxumState(ctx.state.clone()),
+            admin_headers(),
+            Query(ArchiveQuery {
+                format: Some("csv".into()),
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+    }
 
-        let first = ctx.state.get_bootstrap().await.unwrap();
-        assert_eq!(first.videos.len(), 1);
+    #[tokio::test]
+    async fn stream_media_matches_stream_key_not_url() {
+        let ctx = BackendTestContext::new();
+        let mut video = sample_video("alpha");
+        // The URL no longer ends with the slug; routing must rely on stream_key.
+        video.sources[0].url = "/api/videos/alpha/streams/1080p?legacy=1".into();
+        ctx.store.upsert_video(&video).unwrap();
+        let media_dir = ctx.state.files.videos.join("alpha");
+        std::fs::create_dir_all(&media_dir).unwrap();
+        std::fs::write(media_dir.join("alpha_1080p.mp4"), "bytes").unwrap();
 
-        ctx.insert_video("gamma");
-        let second = ctx.state.get_bootstrap().await.unwrap();
-        assert!(Arc::ptr_eq(&first, &second));
+        let response = stream_media(
+            ctx.state.clone(),
+            MediaCategory::Video,
+            "alpha".into(),
+            "1080p".into(),
+            &HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn source_matches_falls_back_to_url_segment() {
+        let mut source = sample_video("alpha").sources.remove(0);
+        source.stream_key = None;
+        assert!(source_matches(&source, "1080p"));
+        assert!(!source_matches(&source, "720p"));
+
+        source.stream_key = Some("hd".into());
+        assert!(source_matches(&source, "hd"));
+        assert!(!source_matches(&source, "1080p"));
     }
 
     #[tokio::test]
-    async fn media_list_populates_cache() {
+    async fn cache_refresh_serves_rows_written_since_startup() {
+        let ctx = BackendTestContext::new();
+        ctx.store.upsert_video(&sample_video("old")).unwrap();
+        let listed = |state: AppState| async move {
+            state
+                .get_media_list(MediaCategory::Video)
+                .await
+                .unwrap()
+                .iter()
+                .map(|video| video.videoid.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(listed(ctx.state.clone()).await, ["old"]);
+
+        // Written behind the backend's back, like a downloader run.
+        ctx.store.upsert_video(&sample_video("new")).unwrap();
+        assert_eq!(listed(ctx.state.clone()).await, ["old"]);
+
+        let err = refresh_cache(AxumState(ctx.state.clone()), HeaderMap::new(), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+
+        // Clearing another category leaves the video list cached.
+        let Json(response) = refresh_cache(
+            AxumState(ctx.state.clone()),
+            admin_headers(),
+            Some(Json(CacheRefreshRequest {
+                categories: vec![CacheCategory::Shorts],
+            })),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.cleared, [CacheCategory::Shorts]);
+        assert_eq!(listed(ctx.state.clone()).await, ["old"]);
+
+        let Json(response) = refresh_cache(AxumState(ctx.state.clone()), admin_headers(), None)
+            .await
+            .unwrap();
+        assert_eq!(response.cleared, CacheCategory::ALL);
+        let mut ids = listed(ctx.state.clone()).await;
+        ids.sort();
+        assert_eq!(ids, ["new", "old"]);
+    }
+
+    #[tokio::test]
+    async fn set_video_poster_validates_file() {
         let mut ctx = BackendTestContext::new();
         ctx.insert_video("alpha");
+        let thumb_dir = ctx.state.files.thumbnails.join("alpha");
+        std::fs::create_dir_all(&thumb_dir).unwrap();
+        std::fs::write(thumb_dir.join("maxres.jpg"), b"JPG").unwrap();
+
+        let err = set_video_poster(
+            AxumState(ctx.state.clone()),
+            admin_headers(),
+            AxumPath("alpha".into()),
+            Json(PosterRequest {
+                file: "missing.jpg".into(),
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
 
-        let list = ctx
-            .state
-            .get_media_list(MediaCategory::Video)
+        // Warm the cache so we also verify the write invalidates it.
+        ctx.state
+            .get_media(MediaCategory::Video, "alpha")
             .await
             .unwrap();
-        assert_eq!(list.len(), 1);
-        ctx.delete_by_videoid("videos", "alpha");
-
+        let Json(updated) = set_video_poster(
+            AxumState(ctx.state.clone()),
+            admin_headers(),
+            AxumPath("alpha".into()),
+            Json(PosterRequest {
+                file: "maxres.jpg".into(),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            updated.thumbnail_url.as_deref(),
+            Some("/api/videos/alpha/thumbnails/maxres.jpg")
+        );
         let cached = ctx
             .state
+            .get_media(MediaCategory::Video, "alpha")
+            .await
+            .unwrap();
+        assert_eq!(cached.thumbnail_url, updated.thumbnail_url);
+    }
+
+    #[tokio::test]
+    async fn continue_watching_skips_unstarted_and_finished() {
+        let mut ctx = BackendTestContext::new();
+        // sample_video durations are 60 seconds.
+        ctx.insert_video("started");
+        ctx.insert_video("finished");
+        ctx.insert_video("untouched");
+        ctx.insert_short("short");
+
+        for (category, id, position) in [
+            (MediaCategory::Video, "started", 30.0),
+            (MediaCategory::Video, "finished", 59.5),
+            (MediaCategory::Video, "untouched", 0.5),
+            (MediaCategory::Short, "short", 20.0),
+        ] {
+            let status = record_progress(ctx.state.clone(), category, id.into(), position)
+                .await
+                .unwrap();
+            assert_eq!(status, StatusCode::NO_CONTENT);
+        }
+
+        let Json(entries) = continue_watching(AxumState(ctx.state.clone()))
+            .await
+            .unwrap();
+        let mut ids: Vec<&str> = entries.iter().map(|e| e.video.videoid.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["short", "started"]);
+        assert!(entries.iter().all(|e| e.video.sources[0].path.is_none()));
+    }
+
+    #[test]
+    fn watched_threshold_requires_known_duration() {
+        assert!(!reached_watched_threshold(Some(100), 94.9));
+        assert!(reached_watched_threshold(Some(100), 95.0));
+        assert!(reached_watched_threshold(Some(100), 120.0));
+        assert!(!reached_watched_threshold(Some(0), 10.0));
+        assert!(!reached_watched_threshold(None, 10.0));
+    }
+
+    #[tokio::test]
+    async fn watched_flag_filters_video_list() {
+        let mut ctx = BackendTestContext::new();
+        // sample_video durations are 60 seconds.
+        ctx.insert_video("auto");
+        ctx.insert_video("manual");
+        ctx.insert_video("fresh");
+
+        // Warm the list cache so the writes below must invalidate it.
+        ctx.state
             .get_media_list(MediaCategory::Video)
             .await
             .unwrap();
-        assert_eq!(cached.len(), 1);
+
+        record_progress(ctx.state.clone(), MediaCategory::Video, "auto".into(), 57.0)
+            .await
+            .unwrap();
+        record_progress(
+            ctx.state.clone(),
+            MediaCategory::Video,
+            "fresh".into(),
+            30.0,
+        )
+        .await
+        .unwrap();
+        set_video_watched(
+            AxumState(ctx.state.clone()),
+            AxumPath("manual".into()),
+            Json(WatchedRequest { watched: true }),
+        )
+        .await
+        .unwrap();
+
+        let ids = |listing: VideoListing| {
+            let VideoListing::Full(videos) = listing else {
+                panic!("expected the full listing");
+            };
+            let mut ids: Vec<String> = videos.into_iter().map(|v| v.videoid).collect();
+            ids.sort();
+            ids
+        };
+        let Json(watched) = list_videos(
+            AxumState(ctx.state.clone()),
+            Query(ListQuery {
+                watched: Some(true),
+                ..ListQuery::default()
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(ids(watched), vec!["auto", "manual"]);
+
+        set_video_watched(
+            AxumState(ctx.state.clone()),
+            AxumPath("manual".into()),
+            Json(WatchedRequest { watched: false }),
+        )
+        .await
+        .unwrap();
+        let Json(unwatched) = list_videos(
+            AxumState(ctx.state.clone()),
+            Query(ListQuery {
+                watched: Some(false),
+                ..ListQuery::default()
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(ids(unwatched), vec!["fresh", "manual"]);
+
+        let err = set_video_watched(
+            AxumState(ctx.state.clone()),
+            AxumPath("ghost".into()),
+            Json(WatchedRequest { watched: true }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn view_route_counts_local_plays() {
+        let mut ctx = BackendTestContext::new();
+        ctx.insert_video("alpha");
+
+        // Warm the details cache so the view must refresh it.
+        let Json(before) = get_video(AxumState(ctx.state.clone()), AxumPath("alpha".into()))
+            .await
+            .unwrap();
+        assert_eq!(before.local_views, Some(0));
+
+        for expected in [1, 2] {
+            let Json(count) =
+                record_video_view(AxumState(ctx.state.clone()), AxumPath("alpha".into()))
+                    .await
+                    .unwrap();
+            assert_eq!(count.local_views, expected);
+        }
+        let Json(after) = get_video(AxumState(ctx.state.clone()), AxumPath("alpha".into()))
+            .await
+            .unwrap();
+        assert_eq!(after.local_views, Some(2));
+        assert_eq!(after.views, before.views);
+
+        let err = record_video_view(AxumState(ctx.state.clone()), AxumPath("ghost".into()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn record_progress_rejects_unknown_or_invalid_input() {
+        let mut ctx = BackendTestContext::new();
+        ctx.insert_video("alpha");
+
+        let err = record_progress(ctx.state.clone(), MediaCategory::Video, "ghost".into(), 1.0)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+
+        let err = record_progress(
+            ctx.state.clone(),
+            MediaCategory::Video,
+            "alpha".into(),
+            -1.0,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn channels_list_is_cached_until_the_lists_change() {
+        let ctx = BackendTestContext::new();
+        let mut video = sample_video("alpha");
+        video.author = Some("Alpha".into());
+        ctx.store.upsert_video(&video).unwrap();
+        let mut anonymous = sample_video("beta");
+        anonymous.author = None;
+        ctx.store.upsert_short(&anonymous).unwrap();
+
+        let channels = || async { super::list_channels(AxumState(ctx.state.clone())).await };
+        let Json(listed) = channels().await.unwrap();
+        let authors: Vec<&str> = listed.iter().map(|c| c.author.as_str()).collect();
+        assert_eq!(authors, ["Alpha", "Unknown"]);
+
+        let mut second = sample_video("gamma");
+        second.author = Some("Unknown".into());
+        ctx.store.upsert_video(&second).unwrap();
+        let Json(cached) = channels().await.unwrap();
+        assert_eq!(cached, listed);
+
+        ctx.state.cache.clear(CacheCategory::Videos);
+        let Json(fresh) = channels().await.unwrap();
+        // A channel literally called "Unknown" stays apart from the bucket of
+        // uploads without an author.
+        assert_eq!(fresh.len(), 3);
+        assert!(fresh.iter().all(|channel| channel.video_count == 1));
+        assert_eq!(
+            fresh
+                .iter()
+                .filter(|channel| channel.channel_url.is_none())
+                .count(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn usage_sums_channel_sources() {
+        let ctx = BackendTestContext::new();
+
+        // On disk: the stat result wins over the recorded file_size.
+        let mut alpha = sample_video("alpha");
+        alpha.extras = json!({ "channelId": "UCchan" });
+        let alpha_dir = ctx.state.files.videos.join("alpha");
+        std::fs::create_dir_all(&alpha_dir).unwrap();
+        std::fs::write(alpha_dir.join("alpha_1080p.mp4"), vec![0u8; 300]).unwrap();
+        ctx.store.upsert_video(&alpha).unwrap();
+
+        // Missing on disk: fall back to the recorded file_size (1024).
+        let mut beta = sample_video("beta");
+        beta.extras = json!({ "channelId": "UCchan" });
+        ctx.store.upsert_short(&beta).unwrap();
+
+        let mut other = sample_video("gamma");
+        other.channel_url = Some("https://example.test/@Other".into());
+        ctx.store.upsert_video(&other).unwrap();
+
+        let Json(report) = channel_usage(AxumState(ctx.state.clone()), AxumPath("UCchan".into()))
+            .await
+            .unwrap();
+        assert_eq!(
+            report,
+            UsageReport {
+                total_bytes: 300 + 1024,
+                file_count: 2,
+                video_count: 2,
+            }
+        );
+
+        let Json(report) = channel_usage(AxumState(ctx.state.clone()), AxumPath("@other".into()))
+            .await
+            .unwrap();
+        assert_eq!(report.total_bytes, 1024);
+
+        let Json(report) = library_usage(AxumState(ctx.state.clone())).await.unwrap();
+        assert_eq!(report.total_bytes, 300 + 1024 + 1024);
+        assert_eq!(report.video_count, 3);
+        assert!(ctx.state.cache.usage.read().contains_key(&None));
+
+        let err = channel_usage(AxumState(ctx.state.clone()), AxumPath("missing".into()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn health_and_version_need_no_token() {
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let mut ctx = BackendTestContext::new();
+        let www = tempdir().unwrap();
+        let get = |state: AppState, uri: &'static str| {
+            let app = build_router(state, www.path());
+            async move {
+                let response = app
+                    .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap())
+            }
+        };
+
+        let (status, body) = get(ctx.state.clone(), "/api/health").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!({"status": "ok"}));
+
+        let (status, body) = get(ctx.state.clone(), "/api/version").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["app_version"], "1.0");
+        assert_eq!(body["crate_version"], env!("CARGO_PKG_VERSION"));
+
+        ctx.state.reader =
+            Arc::new(MetadataReader::new(ctx.db_path.with_file_name("gone.db")).unwrap());
+        let (status, body) = get(ctx.state.clone(), "/api/health").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["error"], "metadata database unavailable");
+    }
+
+    #[tokio::test]
+    async fn static_assets_prefer_precompressed_variants() {
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let ctx = BackendTestContext::new();
+        let www = tempdir().unwrap();
+        std::fs::write(www.path().join("index.html"), "<html></html>").unwrap();
+        std::fs::write(www.path().join("app.js"), "console.log(1)").unwrap();
+        std::fs::write(www.path().join("app.js.gz"), b"GZIP").unwrap();
+        let app = build_router(ctx.state.clone(), www.path());
+
+        let request = Request::get("/app.js")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"GZIP");
+
+        // Clients that do not accept gzip get the raw file.
+        let request = Request::get("/app.js").body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"console.log(1)");
+
+        // Client-side routes resolve to the SPA shell.
+        let request = Request::get("/watch/alpha").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"<html></html>");
     }
 
     #[tokio::test]
-    async fn api_responses_strip_file_paths() {
+    async fn json_responses_are_compressed_but_media_is_not() {
+        use axum::http::Request;
+        use tower::ServiceExt;
+
         let ctx = BackendTestContext::new();
         let mut video = sample_video("alpha");
-        video.sources[0].path = Some("/yt/videos/alpha/secret.mp4".into());
+        video.sources[0].path = None;
         ctx.store.upsert_video(&video).unwrap();
-
-        let Json(videos) = super::list_videos(AxumState(ctx.state.clone()))
-            .await
+        let media_dir = ctx
+            .state
+            .files
+            .media_dir(MediaCategory::Video)
+            .join("alpha");
+        std::fs::create_dir_all(&media_dir).unwrap();
+        std::fs::write(media_dir.join("alpha_1080p.mp4"), "x".repeat(4096)).unwrap();
+        let www = tempdir().unwrap();
+        std::fs::write(www.path().join("index.html"), "<html></html>").unwrap();
+        let app = build_router(ctx.state.clone(), www.path());
+
+        let request = Request::get("/api/videos")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
             .unwrap();
-        assert!(videos[0].sources[0].path.is_none());
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
 
-        let Json(single) = super::get_video(AxumState(ctx.state.clone()), AxumPath("alpha".into()))
-            .await
+        let request = Request::get("/api/videos")
+            .header(header::ACCEPT_ENCODING, "deflate")
+            .body(Body::empty())
             .unwrap();
-        assert!(single.sources[0].path.is_none());
-
-        let bootstrap = ctx.state.get_bootstrap().await.unwrap();
-        assert!(bootstrap.videos[0].sources[0].path.is_none());
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "deflate");
+
+        // Without Accept-Encoding the JSON goes out as-is.
+        let request = Request::get("/api/videos").body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+
+        for range in [None, Some("bytes=0-99")] {
+            let mut request = Request::get("/api/videos/alpha/streams/1080p")
+                .header(header::ACCEPT_ENCODING, "gzip");
+            if let Some(range) = range {
+                request = request.header(header::RANGE, range);
+            }
+            let response = app
+                .clone()
+                .oneshot(request.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert!(response.status().is_success());
+            assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        }
     }
 
     #[tokio::test]
-    async fn media_lookup_prefers_cache() {
+    async fn unknown_api_routes_never_reach_the_spa_fallback() {
+        use axum::http::Request;
+        use tower::ServiceExt;
+
         let mut ctx = BackendTestContext::new();
         ctx.insert_video("alpha");
-        let record = ctx
-            .state
-            .get_media(MediaCategory::Video, "alpha")
-            .await
-            .unwrap();
-        assert_eq!(record.videoid, "alpha");
+        let www = tempdir().unwrap();
+        std::fs::write(www.path().join("index.html"), "<html></html>").unwrap();
+        // Even a stray `api` folder in the web root must not be served.
+        std::fs::create_dir(www.path().join("api")).unwrap();
+        std::fs::write(www.path().join("api/index.html"), "stale").unwrap();
+        let app = build_router(ctx.state.clone(), www.path());
+
+        for path in [
+            "/api",
+            "/api/",
+            "/api/nope",
+            "/api/videos/alpha/nope",
+            "/api/index.html",
+        ] {
+            let request = Request::get(path).body(Body::empty()).unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "{path}");
+            assert_eq!(
+                response.headers()[header::CONTENT_TYPE],
+                "application/json",
+                "{path}"
+            );
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let parsed: Value = serde_json::from_slice(&body).unwrap();
+            assert!(parsed["error"].is_string(), "{path}");
+        }
 
-        ctx.delete_by_videoid("videos", "alpha");
-        let cached = ctx
-            .state
-            .get_media(MediaCategory::Video, "alpha")
-            .await
+        // Known routes still win over the catch-all.
+        let request = Request::get("/api/videos/alpha")
+            .body(Body::empty())
             .unwrap();
-        assert_eq!(cached.videoid, "alpha");
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
 
-        let err = ctx
-            .state
-            .get_media(MediaCategory::Video, "ghost")
-            .await
-            .unwrap_err();
-        assert_eq!(err.status, StatusCode::NOT_FOUND);
+        // App routes get the SPA shell; missing assets get a plain 404.
+        let request = Request::get("/channel/xyz").body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"<html></html>");
+
+        let request = Request::get("/assets/missing.js")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 
     #[tokio::test]
-    async fn comments_and_subtitles_cache() {
-        let mut ctx = BackendTestContext::new();
-        ctx.insert_video("alpha");
-        ctx.insert_comments("alpha", vec![sample_comment("1", "alpha")]);
-        ctx.insert_subtitles(
-            "alpha",
-            vec![SubtitleTrack {
-                code: "en".into(),
-                name: "English".into(),
-                url: "/sub".into(),
-                path: None,
-            }],
-        );
+    async fn errors_carry_request_id() {
+        use axum::http::Request;
+        use tower::ServiceExt;
 
-        let first_comments = ctx.state.get_comments("alpha").await.unwrap();
-        assert_eq!(first_comments.len(), 1);
-        ctx.delete_by_videoid("comments", "alpha");
-        let cached_comments = ctx.state.get_comments("alpha").await.unwrap();
-        assert_eq!(cached_comments.len(), 1);
+        let ctx = BackendTestContext::new();
+        let www = tempdir().unwrap();
+        let app = build_router(ctx.state.clone(), www.path());
 
-        let first_subtitles = ctx.state.get_subtitles("alpha").await.unwrap();
-        assert!(first_subtitles.is_some());
-        ctx.delete_by_videoid("subtitles", "alpha");
-        let cached_subtitles = ctx.state.get_subtitles("alpha").await.unwrap();
-        assert!(cached_subtitles.is_some());
+        let request = Request::get("/api/videos/ghost")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let header_id = response.headers()[REQUEST_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_owned();
+        assert!(Uuid::parse_str(&header_id).is_ok());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let parsed: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(parsed["request_id"], header_id.as_str());
+        assert!(parsed["error"].is_string());
+
+        // A client-supplied id is echoed back instead of generating one.
+        let request = Request::get("/api/videos/ghost")
+            .header(REQUEST_ID_HEADER, "support-ticket-42")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "support-ticket-42");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let parsed: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(parsed["request_id"], "support-ticket-42");
+
+        // Successful responses get the header too; garbage ids are replaced.
+        let request = Request::get("/api/videos")
+            .header(REQUEST_ID_HEADER, "has spaces")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let id = response.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+        assert!(Uuid::parse_str(id).is_ok());
     }
 
     #[tokio::test]
-    async fn list_subtitles_includes_download_urls() {
+    async fn favorites_toggle_and_list() {
         let mut ctx = BackendTestContext::new();
         ctx.insert_video("alpha");
-        ctx.insert_subtitles(
-            "alpha",
-            vec![SubtitleTrack {
-                code: "en".into(),
-                name: "English".into(),
-                url: "/api/videos/alpha/subtitles/en".into(),
-                path: None,
-            }],
-        );
+        ctx.insert_video("beta");
 
-        let Json(payload) = super::list_subtitles(ctx.state.clone(), "alpha".into(), "videos")
+        let err = add_video_favorite(
+            AxumState(ctx.state.clone()),
+            HeaderMap::new(),
+            AxumPath("alpha".into()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+
+        let err = add_video_favorite(
+            AxumState(ctx.state.clone()),
+            admin_headers(),
+            AxumPath("missing".into()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+
+        for id in ["alpha", "beta"] {
+            let status = add_video_favorite(
+                AxumState(ctx.state.clone()),
+                admin_headers(),
+                AxumPath(id.into()),
+            )
             .await
             .unwrap();
-        assert_eq!(payload.len(), 1);
-        assert!(payload[0].url.contains("/videos/alpha/subtitles/en"));
+            assert_eq!(status, StatusCode::NO_CONTENT);
+        }
+        let Json(favorites) = list_favorites(AxumState(ctx.state.clone())).await.unwrap();
+        let ids: HashSet<_> = favorites.iter().map(|v| v.videoid.as_str()).collect();
+        assert_eq!(ids, HashSet::from(["alpha", "beta"]));
+
+        remove_video_favorite(
+            AxumState(ctx.state.clone()),
+            admin_headers(),
+            AxumPath("alpha".into()),
+        )
+        .await
+        .unwrap();
+        let Json(favorites) = list_favorites(AxumState(ctx.state.clone())).await.unwrap();
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].videoid, "beta");
+        assert!(favorites[0].sources.iter().all(|s| s.path.is_none()));
     }
 
     #[tokio::test]
-    async fn download_subtitle_uses_fallback_path() {
+    async fn delete_video_prunes_metadata_and_cache() {
         let mut ctx = BackendTestContext::new();
         ctx.insert_video("alpha");
-        ctx.insert_subtitles(
-            "alpha",
-            vec![SubtitleTrack {
-                code: "en".into(),
-                name: "English".into(),
-                url: "/api/videos/alpha/subtitles/en".into(),
-                path: None,
-            }],
+        ctx.insert_video("beta");
+        // Warm the caches so the delete has something to invalidate.
+        assert_eq!(
+            ctx.state
+                .get_media_list(MediaCategory::Video)
+                .await
+                .unwrap()
+                .len(),
+            2
         );
-
-        let subtitle_dir = ctx.state.files.subtitles.join("alpha");
-        std::fs::create_dir_all(&subtitle_dir).unwrap();
-        std::fs::write(subtitle_dir.join("alpha.en.vtt"), "WEBVTT").unwrap();
-
-        let response = download_subtitle(ctx.state.clone(), "alpha".into(), "en".into())
+        ctx.state
+            .get_media(MediaCategory::Video, "alpha")
             .await
             .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-    }
 
-    #[tokio::test]
-    async fn download_thumbnail_serves_local_files() {
-        let ctx = BackendTestContext::new();
-        let thumb_dir = ctx.state.files.thumbnails.join("alpha");
-        std::fs::create_dir_all(&thumb_dir).unwrap();
-        std::fs::write(thumb_dir.join("poster.png"), b"PNG").unwrap();
+        let err = delete_video(
+            AxumState(ctx.state.clone()),
+            HeaderMap::new(),
+            AxumPath("alpha".into()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::UNAUTHORIZED);
 
-        let response = download_thumbnail(ctx.state.clone(), "alpha".into(), "poster.png".into())
-            .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
-        assert_eq!(body.as_ref(), b"PNG");
-    }
+        let status = delete_video(
+            AxumState(ctx.state.clone()),
+            admin_headers(),
+            AxumPath("alpha".into()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
 
-    #[tokio::test]
-    async fn download_thumbnail_rejects_path_traversal() {
-        let ctx = BackendTestContext::new();
-        let err = download_thumbnail(ctx.state.clone(), "alpha".into(), "../secret.txt".into())
+        let err = ctx
+            .state
+            .get_media(MediaCategory::Video, "alpha")
             .await
             .unwrap_err();
         assert_eq!(err.status, StatusCode::NOT_FOUND);
+        let ids: Vec<_> = ctx
+            .state
+            .get_media_list(MediaCategory::Video)
+            .await
+            .unwrap()
+            .iter()
+            .map(|video| video.videoid.clone())
+            .collect();
+        assert_eq!(ids, ["beta"]);
+
+        let err = delete_video(
+            AxumState(ctx.state.clone()),
+            admin_headers(),
+            AxumPath("alpha".into()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
     }
 
     #[tokio::test]
-    async fn stream_media_uses_custom_path() {
-        let ctx = BackendTestContext::new();
-        let mut video = sample_video("alpha");
-        let custom = ctx.state.files.videos.join("custom.mp4");
-        std::fs::create_dir_all(custom.parent().unwrap()).unwrap();
-        std::fs::write(&custom, "bytes").unwrap();
-        video.sources[0].path = Some(custom.to_string_lossy().into_owned());
-        ctx.store.upsert_video(&video).unwrap();
+    async fn comments_can_be_threaded() {
+        use axum::http::Request;
+        use tower::ServiceExt;
 
-        let response = stream_media(
-            ctx.state.clone(),
-            MediaCategory::Video,
-            "alpha".into(),
-            "1080p".into(),
-        )
-        .await
-        .unwrap();
+        let mut ctx = BackendTestContext::new();
+        ctx.insert_video("alpha");
+        let reply = CommentRecord {
+            parent_comment_id: Some("top".into()),
+            ..sample_comment("reply", "alpha")
+        };
+        ctx.insert_comments("alpha", vec![sample_comment("top", "alpha"), reply]);
+        let www = tempdir().unwrap();
+        let app = build_router(ctx.state.clone(), www.path());
+
+        let request = Request::get("/api/videos/alpha/comments")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let flat: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(flat.as_array().unwrap().len(), 2);
+
+        let request = Request::get("/api/videos/alpha/comments?threaded=true")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
         assert_eq!(response.status(), StatusCode::OK);
-        assert_eq!(
-            response.headers().get(header::CONTENT_TYPE).unwrap(),
-            "video/mp4"
-        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let threads: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(threads.as_array().unwrap().len(), 1);
+        assert_eq!(threads[0]["comment"]["id"], "top");
+        assert_eq!(threads[0]["replies"][0]["comment"]["id"], "reply");
+        assert_eq!(threads[0]["replies"][0]["replies"], json!([]));
     }
 
     #[tokio::test]
-    async fn stream_media_builds_default_path() {
-        let ctx = BackendTestContext::new();
-        let mut video = sample_video("alpha");
-        video.sources[0].path = None;
-        ctx.store.upsert_video(&video).unwrap();
-        let media_dir = ctx
-            .state
-            .files
-            .media_dir(MediaCategory::Video)
-            .join("alpha");
-        std::fs::create_dir_all(&media_dir).unwrap();
-        std::fs::write(media_dir.join("alpha_1080p.mp4"), "bytes").unwrap();
+    async fn comment_thread_endpoint_scopes_to_the_video() {
+        let mut ctx = BackendTestContext::new();
+        ctx.insert_video("alpha");
+        ctx.insert_video("beta");
+        let reply = CommentRecord {
+            parent_comment_id: Some("top".into()),
+            ..sample_comment("reply", "alpha")
+        };
+        ctx.insert_comments("alpha", vec![sample_comment("top", "alpha"), reply]);
+
+        let Json(thread) = get_comment_thread(
+            AxumState(ctx.state.clone()),
+            AxumPath(("alpha".into(), "top".into())),
+        )
+        .await
+        .unwrap();
+        assert_eq!(thread.comment.id, "top");
+        assert_eq!(thread.replies[0].comment.id, "reply");
 
-        let response = stream_media(
-            ctx.state.clone(),
-            MediaCategory::Video,
-            "alpha".into(),
-            "1080p".into(),
+        let Json(thread) = get_comment_thread(
+            AxumState(ctx.state.clone()),
+            AxumPath(("alpha".into(), "reply".into())),
         )
         .await
         .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-    }
+        assert!(thread.replies.is_empty());
 
-    #[tokio::test]
-    async fn stream_media_missing_format_errors() {
-        let mut ctx = BackendTestContext::new();
-        ctx.insert_video("alpha");
-        let err = stream_media(
-            ctx.state.clone(),
-            MediaCategory::Video,
-            "alpha".into(),
-            "4k".into(),
+        let err = get_comment_thread(
+            AxumState(ctx.state.clone()),
+            AxumPath(("beta".into(), "top".into())),
         )
         .await
         .unwrap_err();
@@ -1191,11 +6222,83 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn api_error_serializes_json() {
-        let response = ApiError::not_found("missing").into_response();
-        assert_eq!(response.status(), StatusCode::NOT_FOUND);
-        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
-        let parsed: Value = serde_json::from_slice(&body).unwrap();
-        assert_eq!(parsed["error"], "missing");
+    async fn issues_classify_broken_media() {
+        let mut ctx = BackendTestContext::new();
+        let files = ctx.state.files.clone();
+        let publish = |id: &str, bytes: &[u8]| {
+            let dir = files.video_dir(&files.videos, id);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join(format!("{id}_1080p.mp4")), bytes).unwrap();
+        };
+
+        ctx.insert_video("healthy");
+        publish("healthy", b"MP4");
+        ctx.insert_video("missing");
+        ctx.insert_video("empty");
+        publish("empty", b"");
+        ctx.insert_video("partial");
+        publish("partial", b"MP4");
+        std::fs::create_dir_all(files.videos.join(layout::STAGING_DIR).join("partial")).unwrap();
+        let mut private = sample_video("private");
+        private.extras = json!({ "availability": "private" });
+        ctx.store.upsert_video(&private).unwrap();
+        publish("private", b"MP4");
+        let mut bare = sample_video("bare");
+        bare.sources.clear();
+        ctx.store.upsert_short(&bare).unwrap();
+
+        let err = list_issues(AxumState(ctx.state.clone()), HeaderMap::new())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+
+        let Json(issues) = list_issues(AxumState(ctx.state.clone()), admin_headers())
+            .await
+            .unwrap();
+        let by_id: HashMap<_, _> = issues
+            .iter()
+            .map(|issue| (issue.videoid.as_str(), issue))
+            .collect();
+        assert!(!by_id.contains_key("healthy"));
+        assert_eq!(by_id.len(), 5);
+        let format = || "1080p".to_string();
+        assert_eq!(
+            by_id["missing"].problems,
+            [MediaProblem::MissingFile { format: format() }]
+        );
+        assert_eq!(
+            by_id["empty"].problems,
+            [MediaProblem::EmptyFile { format: format() }]
+        );
+        assert_eq!(by_id["partial"].problems, [MediaProblem::PartialDownload]);
+        assert_eq!(
+            by_id["private"].problems,
+            [MediaProblem::Unavailable {
+                availability: "private".into()
+            }]
+        );
+        assert_eq!(by_id["bare"].category, "shorts");
+        assert_eq!(by_id["bare"].problems, [MediaProblem::NoMedia]);
+
+        let json = serde_json::to_value(&by_id["missing"].problems).unwrap();
+        assert_eq!(json, json!([{ "kind": "missing_file", "format": "1080p" }]));
+    }
+
+    #[tokio::test]
+    async fn issues_skip_files_the_object_store_serves() {
+        let (mut ctx, _log) = object_store_context();
+        ctx.insert_video("offloaded");
+
+        let Json(issues) = list_issues(AxumState(ctx.state.clone()), admin_headers())
+            .await
+            .unwrap();
+        assert!(
+            !issues.iter().any(|issue| issue.videoid == "offloaded"),
+            "{:?}",
+            issues
+                .iter()
+                .map(|issue| &issue.videoid)
+                .collect::<Vec<_>>()
+        );
     }
 }
@@ -8,22 +8,41 @@
 //! tweak behaviour without re-reading the entire file.
 
 use anyhow::{Context, Result, bail};
-use chrono::{NaiveDate, Utc};
-use newtube_tools::config::{DEFAULT_CONFIG_PATH, load_runtime_paths_from};
+use chrono::{Days, NaiveDate, Utc};
+use flate2::Compression;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use newtube_tools::archive::{archive_line, parse_archive_line};
+use newtube_tools::config::{
+    DEFAULT_CONFIG_PATH, absolute_root, check_sleep_intervals, ensure_roots_disjoint,
+    load_runtime_paths_from, parse_max_height, parse_rate_limit, parse_since_days,
+    parse_sleep_secs, parse_sub_langs, validate_proxy,
+};
+use newtube_tools::layout;
+use newtube_tools::logging::{self, Verbosity};
 use newtube_tools::metadata::{
-    CommentRecord, MetadataStore, SubtitleCollection, SubtitleTrack, VideoRecord, VideoSource,
+    Chapter, CommentRecord, MetadataReader, MetadataStore, SponsorSegment, SqliteTuning,
+    SubtitleCollection, SubtitleTrack, VideoRecord, VideoSource, canonical_channel_url,
 };
-use newtube_tools::security::ensure_not_root;
-use serde::Deserialize;
+use newtube_tools::preflight::{DEFAULT_MIN_FREE_BYTES, PreflightInputs, run_preflight};
+use newtube_tools::rotation::RotationPolicy;
+use newtube_tools::security::{CookieStatus, check_cookies, ensure_not_root};
+use newtube_tools::timestamps::{epoch_to_iso, yt_dlp_date_to_epoch};
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::collections::{BTreeSet, HashMap, HashSet};
 use std::env;
+use std::fmt;
 use std::fs::{self, File, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
+use std::io::{self, BufRead, BufReader, Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
-#[cfg(test)]
-use std::sync::{Mutex, MutexGuard};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, info_span, warn};
 
 #[cfg(test)]
 const DEFAULT_MEDIA_ROOT: &str = "/yt";
@@ -34,9 +53,37 @@ const THUMBNAILS_SUBDIR: &str = "thumbnails";
 const COMMENTS_SUBDIR: &str = "comments";
 const ARCHIVE_FILE: &str = "download-archive.txt";
 const COOKIES_FILE: &str = "cookies.txt";
+const RETRY_QUEUE_FILE: &str = "retry-queue.jsonl";
+/// Summary of the latest run; earlier ones are kept as `run-summary.json.1`,
+/// `.2`, ... per the `LOG_*` rotation keys.
+const RUN_SUMMARY_FILE: &str = "run-summary.json";
 #[cfg(test)]
 const DEFAULT_WWW_ROOT: &str = "/www/newtube.com";
 const METADATA_DB_FILE: &str = "metadata.db";
+// `--incremental` re-lists this many days before the newest known upload so
+// late-published or re-dated videos are not missed.
+const INCREMENTAL_MARGIN_DAYS: u64 = 7;
+// yt-dlp attempts after the first when `--retries` is not given, and the
+// wait before the first retry (doubled for each further one).
+const DEFAULT_YTDLP_RETRIES: u32 = 0;
+const YTDLP_RETRY_BACKOFF: Duration = Duration::from_secs(5);
+// How often a `--timeout` watchdog checks whether yt-dlp has exited.
+const YTDLP_POLL_INTERVAL: Duration = Duration::from_millis(50);
+// Built-in `--match-filter`s that split a channel into regular uploads and
+// Shorts. A user `--match-filter` is ANDed onto these, never replaces them.
+const VIDEOS_MATCH_FILTER: &str = "!is_live & original_url!*=/shorts/";
+const SHORTS_MATCH_FILTER: &str = "original_url*=/shorts/";
+// `--keep-audio`: yt-dlp selector for the audio-only download (m4a plays in
+// every browser, anything else is the fallback) and the file/stream key it is
+// saved under, `<id>_audio.<ext>`.
+const AUDIO_FORMAT_SELECTOR: &str = "bestaudio[ext=m4a]/bestaudio";
+const AUDIO_STREAM_KEY: &str = "audio";
+// yt-dlp `--sub-langs` without `--sub-langs`/`SUB_LANGS`.
+const DEFAULT_SUB_LANGS: &str = "all";
+// YouTube's thumbnail names from best to worst. `default` (120x90) is the
+// smallest, so names outside this list sort just before it.
+const THUMBNAIL_QUALITY: [&str; 4] = ["maxresdefault", "sddefault", "hqdefault", "mqdefault"];
+const SMALLEST_THUMBNAIL: &str = "default";
 
 #[cfg(test)]
 static YT_DLP_STUB: Mutex<Option<PathBuf>> = Mutex::new(None);
@@ -76,6 +123,177 @@ impl Drop for YtDlpStubGuard {
     }
 }
 
+/// Retry, timeout, cookie and proxy settings applied to every yt-dlp call by
+/// [`run_ytdlp`].
+#[derive(Debug, Clone)]
+struct YtDlpSettings {
+    /// Passed as `--cookies` when the file exists.
+    cookies: PathBuf,
+    /// `--proxy <url>`: handed to yt-dlp's own `--proxy`.
+    proxy: Option<String>,
+    /// `--retries <n>`: extra attempts after a per-video failure or timeout.
+    retries: u32,
+    /// `--timeout <secs>`: kill an attempt that runs longer than this.
+    timeout: Option<Duration>,
+    /// Wait before the first retry; doubled for each further one.
+    backoff: Duration,
+    /// `--rate-limit <rate>`: yt-dlp's `--limit-rate`, e.g. `4M`.
+    rate_limit: Option<String>,
+    /// `--sleep-interval`/`--max-sleep-interval`: yt-dlp's pause before each
+    /// download, randomized between the two when both are set.
+    sleep_interval: Option<u32>,
+    max_sleep_interval: Option<u32>,
+}
+
+impl YtDlpSettings {
+    fn new(cookies: PathBuf) -> Self {
+        Self {
+            cookies,
+            proxy: None,
+            retries: DEFAULT_YTDLP_RETRIES,
+            timeout: None,
+            backoff: YTDLP_RETRY_BACKOFF,
+            rate_limit: None,
+            sleep_interval: None,
+            max_sleep_interval: None,
+        }
+    }
+}
+
+/// How a yt-dlp attempt ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum YtDlpStatus {
+    Exited(ExitStatus),
+    /// Killed after running longer than [`YtDlpSettings::timeout`].
+    TimedOut(Duration),
+}
+
+impl fmt::Display for YtDlpStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exited(status) => write!(f, "{status}"),
+            Self::TimedOut(limit) => write!(f, "timed out after {}s", limit.as_secs_f64()),
+        }
+    }
+}
+
+/// Result of the last yt-dlp attempt made by [`run_ytdlp`].
+#[derive(Debug)]
+struct YtDlpOutput {
+    status: YtDlpStatus,
+    /// Empty unless stdout was captured.
+    stdout: Vec<u8>,
+}
+
+impl YtDlpOutput {
+    fn success(&self) -> bool {
+        matches!(self.status, YtDlpStatus::Exited(status) if status.success())
+    }
+
+    /// A timeout counts as a per-video failure: retried, never fatal.
+    fn exit(&self) -> YtDlpExit {
+        match self.status {
+            YtDlpStatus::Exited(status) => YtDlpExit::classify(status),
+            YtDlpStatus::TimedOut(_) => YtDlpExit::VideoFailed,
+        }
+    }
+}
+
+/// Runs yt-dlp with `args`, adding `--cookies`, `--proxy` and the throttling
+/// options from `settings`.
+/// Per-video failures (exit code 1) and timeouts are retried up to
+/// `settings.retries` times with exponential backoff; usage errors and
+/// cancellations are returned at once. With `capture_stdout` the output is
+/// collected and stderr discarded, otherwise both are inherited. Only a
+/// failure to start yt-dlp is an `Err`.
+fn run_ytdlp(
+    settings: &YtDlpSettings,
+    label: &str,
+    args: &[String],
+    capture_stdout: bool,
+) -> io::Result<YtDlpOutput> {
+    let attempts = settings.retries.saturating_add(1);
+    let mut delay = settings.backoff;
+    for attempt in 1.. {
+        debug!("Running yt-dlp {label}, attempt {attempt}/{attempts}");
+        let output = run_ytdlp_once(settings, args, capture_stdout)?;
+        if attempt >= attempts || output.exit() != YtDlpExit::VideoFailed {
+            return Ok(output);
+        }
+        warn!(
+            "{label} attempt {attempt}/{attempts} failed ({}); retrying in {}s",
+            output.status,
+            delay.as_secs_f64()
+        );
+        thread::sleep(delay);
+        delay = delay.saturating_mul(2);
+    }
+    unreachable!("the attempt loop only ends by returning")
+}
+
+fn run_ytdlp_once(
+    settings: &YtDlpSettings,
+    args: &[String],
+    capture_stdout: bool,
+) -> io::Result<YtDlpOutput> {
+    let mut command = yt_dlp_command();
+    command.args(args);
+    if settings.cookies.exists() {
+        command.arg("--cookies").arg(&settings.cookies);
+    }
+    if let Some(proxy) = &settings.proxy {
+        command.arg("--proxy").arg(proxy);
+    }
+    if let Some(rate) = &settings.rate_limit {
+        command.arg("--limit-rate").arg(rate);
+    }
+    if let Some(secs) = settings.sleep_interval {
+        command.arg("--sleep-interval").arg(secs.to_string());
+    }
+    if let Some(secs) = settings.max_sleep_interval {
+        command.arg("--max-sleep-interval").arg(secs.to_string());
+    }
+    if capture_stdout {
+        command.stdout(Stdio::piped()).stderr(Stdio::null());
+    }
+
+    let mut child = command.spawn()?;
+    // Drained on a thread so a large listing never blocks yt-dlp on a full
+    // pipe while we wait for it.
+    let reader = child.stdout.take().map(|mut stdout| {
+        thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let _ = stdout.read_to_end(&mut buffer);
+            buffer
+        })
+    });
+    let status = match settings.timeout {
+        Some(limit) => wait_with_timeout(&mut child, limit)?,
+        None => YtDlpStatus::Exited(child.wait()?),
+    };
+    let stdout = reader
+        .map(|reader| reader.join().unwrap_or_default())
+        .unwrap_or_default();
+    Ok(YtDlpOutput { status, stdout })
+}
+
+fn wait_with_timeout(child: &mut Child, limit: Duration) -> io::Result<YtDlpStatus> {
+    let deadline = Instant::now() + limit;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(YtDlpStatus::Exited(status));
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            // Already exited between the checks if this fails.
+            let _ = child.kill();
+            child.wait()?;
+            return Ok(YtDlpStatus::TimedOut(limit));
+        }
+        thread::sleep((deadline - now).min(YTDLP_POLL_INTERVAL));
+    }
+}
+
 /// Convenience wrapper around every filesystem location this binary touches.
 struct Paths {
     base: PathBuf,
@@ -85,16 +303,113 @@ struct Paths {
     thumbnails: PathBuf,
     comments: PathBuf,
     archive: PathBuf,
-    cookies: PathBuf,
+    /// Formats that failed to download, drained by `--process-retries`.
+    retry_queue: PathBuf,
+    /// JSON summary of the latest channel run.
+    run_summary: PathBuf,
     www_root: PathBuf,
     metadata_db: PathBuf,
+    /// Set when the media root folds case, so per-video directories need
+    /// the suffix from [`layout::video_dir_name`].
+    case_insensitive: bool,
+    /// `--max-height`/`MAX_HEIGHT`: download only muxed formats up to this
+    /// height instead of every format.
+    max_height: Option<u32>,
+    /// `--formats`: download exactly these format ids instead of every
+    /// format. Empty keeps the default.
+    formats: Vec<String>,
+    /// `--sponsorblock`: ask yt-dlp to mark SponsorBlock segments in the
+    /// info JSON.
+    sponsorblock: bool,
+    /// `--keep-audio`: also download the best audio-only format.
+    keep_audio: bool,
+    /// `--sub-langs`/`SUB_LANGS`: subtitle languages handed to yt-dlp.
+    sub_langs: String,
+    /// Cleared by `--no-auto-subs` to skip YouTube's automatic captions.
+    auto_subs: bool,
+    /// Set when `OBJECT_STORE_URL` is configured: media missing locally was
+    /// offloaded to the bucket, so it is not re-downloaded.
+    object_store: bool,
+    ytdlp: YtDlpSettings,
 }
 
 #[derive(Debug, Clone)]
 struct DownloaderArgs {
+    /// Empty in the modes that do not need a channel (`--preflight`,
+    /// `--reindex`, `--process-retries`).
     channel_url: String,
     media_root: PathBuf,
     www_root: PathBuf,
+    incremental: bool,
+    /// `--since <days>`: only list uploads from the last `days` days. `0`
+    /// (or no flag) scans everything.
+    since: Option<u32>,
+    /// `--overwrite-metadata-only`: refresh the video rows without touching
+    /// media, subtitles or comments.
+    metadata_only: bool,
+    /// `--resume` (default) / `--no-resume`: skip entries an interrupted
+    /// previous run already handled.
+    resume: bool,
+    /// `--preflight`: only check the environment and exit.
+    preflight: bool,
+    /// `--reindex`: rebuild the DB from the files already on disk, offline.
+    reindex: bool,
+    /// `--process-retries`: re-attempt only the formats listed in the retry
+    /// queue.
+    process_retries: bool,
+    /// `--match-filter <expr>`: extra yt-dlp filter ANDed with the built-in
+    /// videos/Shorts split.
+    match_filter: Option<String>,
+    /// `--collection <spec>` (repeatable): tabs to walk, in order. Defaults
+    /// to regular videos followed by Shorts. A video or playlist URL gets a
+    /// single collection listing that URL instead.
+    collections: Vec<CollectionSpec>,
+    /// `--jobs <n>`: entries processed in parallel within a collection.
+    jobs: usize,
+    /// `--max-height <lines>`, falling back to `MAX_HEIGHT` from the config.
+    max_height: Option<u32>,
+    /// `--formats <id,...>`: exact yt-dlp format ids (`22`, `137+140`) to
+    /// download instead of every format. Overrides `MAX_HEIGHT`.
+    formats: Vec<String>,
+    /// `--sponsorblock`: store SponsorBlock segments with each video.
+    sponsorblock: bool,
+    /// `--keep-audio`: keep an audio-only file next to the muxed formats.
+    keep_audio: bool,
+    /// `--sub-langs <list>`, falling back to `SUB_LANGS` and then `all`.
+    sub_langs: String,
+    /// `--no-auto-subs`: only download uploaded subtitles.
+    auto_subs: bool,
+    /// `--webhook <url>`: receives the run summary once downloads finish.
+    webhook: Option<String>,
+    /// `--strict-cookies`: refuse a `cookies.txt` that other accounts
+    /// can read, with a warning instead of an error.
+    strict_cookies: bool,
+    /// `--archive <path>`: download archive to use instead of
+    /// `<media_root>/download-archive.txt`. A `.gz` path is read and written
+    /// gzip-compressed.
+    archive: Option<PathBuf>,
+    /// `WEBHOOK_SECRET` from the config, sent with the webhook POST.
+    webhook_secret: Option<String>,
+    /// Whether the config sets `OBJECT_STORE_URL`.
+    object_store: bool,
+    /// `LOG_*` limits from the config, applied to `run-summary.json`.
+    rotation: RotationPolicy,
+    /// `--retries <n>`: extra attempts for a failed or timed-out yt-dlp call.
+    retries: u32,
+    /// `--timeout <secs>`: upper bound for a single yt-dlp call.
+    timeout: Option<Duration>,
+    /// `--proxy <url>`, falling back to `PROXY` from the config; forwarded
+    /// to every yt-dlp call.
+    proxy: Option<String>,
+    /// `--rate-limit <rate>`, falling back to `RATE_LIMIT` from the config.
+    rate_limit: Option<String>,
+    /// `--sleep-interval <secs>`/`--max-sleep-interval <secs>`, falling back
+    /// to `SLEEP_INTERVAL`/`MAX_SLEEP_INTERVAL`.
+    sleep_interval: Option<u32>,
+    max_sleep_interval: Option<u32>,
+    sqlite: SqliteTuning,
+    /// `--quiet`/`--verbose`, see [`logging`].
+    verbosity: Verbosity,
 }
 
 impl DownloaderArgs {
@@ -115,6 +430,34 @@ impl DownloaderArgs {
         let mut www_root_override: Option<PathBuf> = None;
         let mut config_path = PathBuf::from(DEFAULT_CONFIG_PATH);
         let mut channel_url: Option<String> = None;
+        let mut incremental = false;
+        let mut metadata_only = false;
+        let mut resume = true;
+        let mut preflight = false;
+        let mut reindex = false;
+        let mut process_retries = false;
+        let mut match_filter: Option<String> = None;
+        let mut collections: Vec<CollectionSpec> = Vec::new();
+        let mut jobs = 1;
+        let mut max_height: Option<u32> = None;
+        let mut formats: Vec<String> = Vec::new();
+        let mut sponsorblock = false;
+        let mut keep_audio = false;
+        let mut strict_cookies = false;
+        let mut sub_langs: Option<String> = None;
+        let mut auto_subs = true;
+        let mut webhook: Option<String> = None;
+        let mut archive: Option<PathBuf> = None;
+        let mut kind: Option<MediaKind> = None;
+        let mut retries = DEFAULT_YTDLP_RETRIES;
+        let mut timeout: Option<Duration> = None;
+        let mut proxy: Option<String> = None;
+        let mut rate_limit: Option<String> = None;
+        let mut sleep_interval: Option<u32> = None;
+        let mut max_sleep_interval: Option<u32> = None;
+        let mut since = 0;
+        let mut quiet = false;
+        let mut verbose = false;
         let mut args = iter.into_iter();
 
         while let Some(arg) = args.next() {
@@ -137,6 +480,70 @@ impl DownloaderArgs {
                 config_path = PathBuf::from(value);
                 continue;
             }
+            if let Some(value) = arg.strip_prefix("--match-filter=") {
+                match_filter = Some(validate_match_filter(value)?);
+                continue;
+            }
+            if let Some(value) = arg.strip_prefix("--jobs=") {
+                jobs = parse_jobs(value)?;
+                continue;
+            }
+            if let Some(value) = arg.strip_prefix("--max-height=") {
+                max_height = Some(parse_max_height(value)?);
+                continue;
+            }
+            if let Some(value) = arg.strip_prefix("--sub-langs=") {
+                sub_langs = Some(parse_sub_langs(value)?);
+                continue;
+            }
+            if let Some(value) = arg.strip_prefix("--formats=") {
+                formats = parse_formats(value)?;
+                continue;
+            }
+            if let Some(value) = arg.strip_prefix("--since=") {
+                since = parse_since_days(value)?;
+                continue;
+            }
+            if let Some(value) = arg.strip_prefix("--retries=") {
+                retries = parse_retries(value)?;
+                continue;
+            }
+            if let Some(value) = arg.strip_prefix("--timeout=") {
+                timeout = Some(parse_timeout(value)?);
+                continue;
+            }
+            if let Some(value) = arg.strip_prefix("--proxy=") {
+                proxy = Some(validate_proxy(value)?);
+                continue;
+            }
+            if let Some(value) = arg.strip_prefix("--rate-limit=") {
+                rate_limit = Some(parse_rate_limit(value)?);
+                continue;
+            }
+            if let Some(value) = arg.strip_prefix("--sleep-interval=") {
+                sleep_interval = Some(parse_sleep_secs(value)?);
+                continue;
+            }
+            if let Some(value) = arg.strip_prefix("--max-sleep-interval=") {
+                max_sleep_interval = Some(parse_sleep_secs(value)?);
+                continue;
+            }
+            if let Some(value) = arg.strip_prefix("--kind=") {
+                kind = Some(parse_kind(value)?);
+                continue;
+            }
+            if let Some(value) = arg.strip_prefix("--archive=") {
+                archive = Some(PathBuf::from(value));
+                continue;
+            }
+            if let Some(value) = arg.strip_prefix("--webhook=") {
+                webhook = Some(validate_webhook_url(value)?);
+                continue;
+            }
+            if let Some(value) = arg.strip_prefix("--collection=") {
+                Self::add_collection(&mut collections, value)?;
+                continue;
+            }
 
             match arg.as_str() {
                 "--media-root" => {
@@ -157,6 +564,115 @@ impl DownloaderArgs {
                         .ok_or_else(|| anyhow::anyhow!("--config requires a value"))?;
                     config_path = PathBuf::from(value);
                 }
+                "--match-filter" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--match-filter requires a value"))?;
+                    match_filter = Some(validate_match_filter(&value)?);
+                }
+                "--jobs" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--jobs requires a value"))?;
+                    jobs = parse_jobs(&value)?;
+                }
+                "--max-height" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--max-height requires a value"))?;
+                    max_height = Some(parse_max_height(&value)?);
+                }
+                "--formats" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--formats requires a value"))?;
+                    formats = parse_formats(&value)?;
+                }
+                "--retries" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--retries requires a value"))?;
+                    retries = parse_retries(&value)?;
+                }
+                "--timeout" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--timeout requires a value"))?;
+                    timeout = Some(parse_timeout(&value)?);
+                }
+                "--proxy" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--proxy requires a value"))?;
+                    proxy = Some(validate_proxy(&value)?);
+                }
+                "--rate-limit" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--rate-limit requires a value"))?;
+                    rate_limit = Some(parse_rate_limit(&value)?);
+                }
+                "--sleep-interval" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--sleep-interval requires a value"))?;
+                    sleep_interval = Some(parse_sleep_secs(&value)?);
+                }
+                "--max-sleep-interval" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--max-sleep-interval requires a value"))?;
+                    max_sleep_interval = Some(parse_sleep_secs(&value)?);
+                }
+                "--since" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--since requires a value"))?;
+                    since = parse_since_days(&value)?;
+                }
+                "--kind" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--kind requires a value"))?;
+                    kind = Some(parse_kind(&value)?);
+                }
+                "--archive" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--archive requires a value"))?;
+                    archive = Some(PathBuf::from(value));
+                }
+                "--webhook" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--webhook requires a value"))?;
+                    webhook = Some(validate_webhook_url(&value)?);
+                }
+                "--collection" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--collection requires a value"))?;
+                    Self::add_collection(&mut collections, &value)?;
+                }
+                "--incremental" => incremental = true,
+                "--overwrite-metadata-only" => metadata_only = true,
+                "--resume" => resume = true,
+                "--no-resume" => resume = false,
+                "--preflight" => preflight = true,
+                "--reindex" => reindex = true,
+                "--sponsorblock" => sponsorblock = true,
+                "--keep-audio" => keep_audio = true,
+                "--strict-cookies" => strict_cookies = true,
+                "--sub-langs" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--sub-langs requires a value"))?;
+                    sub_langs = Some(parse_sub_langs(&value)?);
+                }
+                "--no-auto-subs" => auto_subs = false,
+                "--quiet" | "-q" => quiet = true,
+                "--verbose" | "-v" => verbose = true,
+                "--process-retries" => process_retries = true,
                 _ if arg.starts_with('-') => {
                     bail!("unknown argument: {arg}");
                 }
@@ -166,23 +682,105 @@ impl DownloaderArgs {
             }
         }
 
-        let channel_url = channel_url.ok_or_else(|| {
-            anyhow::anyhow!(
-                "Usage: download_channel [--config <path>] [--media-root <path>] [--www-root <path>] <channel_url>"
-            )
-        })?;
+        let channel_url = match channel_url {
+            Some(url) => url,
+            None if preflight || reindex || process_retries => String::new(),
+            None => bail!(
+                "Usage: download_channel [--config <path>] [--media-root <path>] [--www-root <path>] [--incremental] [--since <days>] [--overwrite-metadata-only] [--resume|--no-resume] [--match-filter <expr>] [--collection <spec>]... [--jobs <n>] [--max-height <lines>] [--formats <id,...>] [--sponsorblock] [--keep-audio] [--sub-langs <list>] [--no-auto-subs] [--quiet|--verbose] [--retries <n>] [--timeout <secs>] [--proxy <url>] [--rate-limit <rate>] [--sleep-interval <secs>] [--max-sleep-interval <secs>] [--strict-cookies] [--webhook <url>] [--archive <path>] [--kind video|short] <channel_url|video_url|playlist_url>\n       download_channel [--config <path>] [--media-root <path>] [--archive <path>] [--strict-cookies] --preflight|--reindex\n       download_channel [--config <path>] [--media-root <path>] [--archive <path>] [--retries <n>] [--timeout <secs>] [--proxy <url>] [--rate-limit <rate>] [--sleep-interval <secs>] [--max-sleep-interval <secs>] [--strict-cookies] --process-retries"
+            ),
+        };
+
+        if max_height.is_some() && !formats.is_empty() {
+            bail!("--formats and --max-height cannot be combined");
+        }
 
         let runtime_paths = load_runtime_paths_from(&config_path)?;
-        let media_root = media_root_override.unwrap_or_else(|| runtime_paths.media_root.clone());
-        let www_root = www_root_override.unwrap_or_else(|| runtime_paths.www_root.clone());
+        let sleep_interval = sleep_interval.or(runtime_paths.sleep_interval);
+        let max_sleep_interval = max_sleep_interval.or(runtime_paths.max_sleep_interval);
+        check_sleep_intervals(sleep_interval, max_sleep_interval)?;
+        let media_root = absolute_root(
+            "media root",
+            media_root_override.unwrap_or_else(|| runtime_paths.media_root.clone()),
+        )?;
+        let www_root = absolute_root(
+            "www root",
+            www_root_override.unwrap_or_else(|| runtime_paths.www_root.clone()),
+        )?;
+        ensure_roots_disjoint(&media_root, &www_root, &[&config_path])?;
+        let archive = archive
+            .map(|path| absolute_root("archive path", path))
+            .transpose()?;
+        match DownloadTarget::classify(&channel_url) {
+            DownloadTarget::Channel if kind.is_some() => {
+                bail!("--kind only applies to video and playlist URLs")
+            }
+            DownloadTarget::Channel => {
+                if collections.is_empty() {
+                    collections = CollectionSpec::defaults();
+                }
+            }
+            target => {
+                if !collections.is_empty() {
+                    bail!("--collection only applies to channel URLs");
+                }
+                if incremental {
+                    bail!("--incremental only applies to channel URLs");
+                }
+                if since > 0 {
+                    bail!("--since only applies to channel URLs");
+                }
+                collections = vec![CollectionSpec::for_target(&target, &channel_url, kind)];
+            }
+        }
 
         Ok(Self {
             channel_url,
             media_root,
             www_root,
+            incremental,
+            since: (since > 0).then_some(since),
+            metadata_only,
+            resume,
+            preflight,
+            reindex,
+            process_retries,
+            match_filter,
+            collections,
+            jobs,
+            max_height: max_height.or(runtime_paths.max_height),
+            formats,
+            sponsorblock,
+            keep_audio,
+            sub_langs: sub_langs
+                .or(runtime_paths.sub_langs)
+                .unwrap_or_else(|| DEFAULT_SUB_LANGS.to_owned()),
+            auto_subs,
+            webhook,
+            strict_cookies,
+            webhook_secret: runtime_paths.webhook_secret,
+            object_store: runtime_paths.object_store_url.is_some(),
+            rotation: runtime_paths.rotation,
+            archive,
+            retries,
+            timeout,
+            proxy: proxy.or(runtime_paths.proxy),
+            rate_limit: rate_limit.or(runtime_paths.rate_limit),
+            sleep_interval,
+            max_sleep_interval,
+            sqlite: runtime_paths.sqlite,
+            verbosity: Verbosity::from_flags(quiet, verbose)?,
         })
     }
 
+    fn add_collection(collections: &mut Vec<CollectionSpec>, value: &str) -> Result<()> {
+        let spec = CollectionSpec::parse(value)?;
+        if collections.iter().any(|existing| existing.tab == spec.tab) {
+            bail!("--collection lists the /{} tab more than once", spec.tab);
+        }
+        collections.push(spec);
+        Ok(())
+    }
+
     fn set_channel(target: &mut Option<String>, value: String) -> Result<()> {
         if target.is_some() {
             bail!("channel URL specified multiple times");
@@ -192,6 +790,116 @@ impl DownloaderArgs {
     }
 }
 
+/// What the positional URL points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DownloadTarget {
+    Channel,
+    /// A `watch?v=`, `youtu.be/` or `/shorts/<id>` link.
+    Video {
+        id: String,
+        kind: MediaKind,
+    },
+    /// A `playlist?list=` URL, listed without the channel tab suffixes.
+    Playlist,
+}
+
+impl DownloadTarget {
+    fn classify(url: &str) -> Self {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let param = |name: &str| {
+            query
+                .split(['&', '#'])
+                .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+                .filter(|value| !value.is_empty())
+        };
+        let segment_after = |marker: &str| {
+            path.split_once(marker)
+                .and_then(|(_, rest)| rest.split(['/', '#']).next())
+                .filter(|id| !id.is_empty())
+        };
+
+        if let Some(id) = segment_after("youtu.be/") {
+            return Self::Video {
+                id: id.to_owned(),
+                kind: MediaKind::Video,
+            };
+        }
+        // `/@handle/shorts` is the channel tab; only `/shorts/<id>` is a video.
+        if let Some(id) = segment_after("/shorts/") {
+            return Self::Video {
+                id: id.to_owned(),
+                kind: MediaKind::Short,
+            };
+        }
+        if path.ends_with("/watch")
+            && let Some(id) = param("v")
+        {
+            return Self::Video {
+                id: id.to_owned(),
+                kind: MediaKind::Video,
+            };
+        }
+        if path.ends_with("/playlist") && param("list").is_some() {
+            return Self::Playlist;
+        }
+        Self::Channel
+    }
+}
+
+fn parse_kind(value: &str) -> Result<MediaKind> {
+    match value {
+        "video" => Ok(MediaKind::Video),
+        "short" => Ok(MediaKind::Short),
+        _ => bail!("--kind must be video or short, got {value:?}"),
+    }
+}
+
+/// Upper bound for `--jobs`; more parallel yt-dlp processes mostly earn
+/// throttling from YouTube.
+const MAX_JOBS: usize = 16;
+
+/// Splits `--formats` on commas. Ids are passed to yt-dlp's `--format`
+/// as-is, so `137+140` still asks for a merged download.
+fn parse_formats(value: &str) -> Result<Vec<String>> {
+    let mut formats: Vec<String> = Vec::new();
+    for id in value.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+        if id.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            bail!("--formats ids cannot contain whitespace, got {id:?}");
+        }
+        if !formats.iter().any(|existing| existing == id) {
+            formats.push(id.to_owned());
+        }
+    }
+    if formats.is_empty() {
+        bail!("--formats needs at least one format id, got {value:?}");
+    }
+    Ok(formats)
+}
+
+fn parse_jobs(value: &str) -> Result<usize> {
+    match value.trim().parse::<usize>() {
+        Ok(jobs) if (1..=MAX_JOBS).contains(&jobs) => Ok(jobs),
+        _ => bail!("--jobs must be a number between 1 and {MAX_JOBS}, got {value:?}"),
+    }
+}
+
+/// Upper bound for `--retries`; past that a failure is not transient.
+const MAX_YTDLP_RETRIES: u32 = 10;
+
+fn parse_retries(value: &str) -> Result<u32> {
+    match value.trim().parse::<u32>() {
+        Ok(retries) if retries <= MAX_YTDLP_RETRIES => Ok(retries),
+        _ => bail!("--retries must be a number between 0 and {MAX_YTDLP_RETRIES}, got {value:?}"),
+    }
+}
+
+fn parse_timeout(value: &str) -> Result<Duration> {
+    match value.trim().parse::<u64>() {
+        Ok(secs) if secs > 0 => Ok(Duration::from_secs(secs)),
+        _ => bail!("--timeout must be a positive number of seconds, got {value:?}"),
+    }
+}
+
 /// Minimal version of yt-dlp's `info.json` just to extract available formats.
 #[derive(Deserialize)]
 struct InfoJson {
@@ -203,6 +911,35 @@ struct InfoJson {
 struct FormatEntry {
     #[serde(rename = "format_id")]
     format_id: Option<String>,
+    #[serde(default)]
+    height: Option<u32>,
+    #[serde(default)]
+    vcodec: Option<String>,
+    #[serde(default)]
+    acodec: Option<String>,
+}
+
+impl FormatEntry {
+    fn id(&self) -> Option<&str> {
+        self.format_id
+            .as_deref()
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+    }
+
+    fn has_video(&self) -> bool {
+        has_codec(self.vcodec.as_deref())
+    }
+
+    /// Carries both audio and video, so it plays without a separate track.
+    fn is_muxed(&self) -> bool {
+        self.has_video() && has_codec(self.acodec.as_deref())
+    }
+}
+
+/// yt-dlp writes `"none"` for a missing stream.
+fn has_codec(codec: Option<&str>) -> bool {
+    codec.is_some_and(|codec| !codec.is_empty() && codec != "none")
 }
 
 #[allow(dead_code)]
@@ -218,6 +955,9 @@ struct VideoInfo {
     dislike_count: Option<i64>,
     view_count: Option<i64>,
     upload_date: Option<String>,
+    /// Exact upload time (UTC epoch); missing on some older videos.
+    #[serde(default)]
+    timestamp: Option<i64>,
     #[serde(default)]
     release_timestamp: Option<i64>,
     uploader: Option<String>,
@@ -237,6 +977,45 @@ struct VideoInfo {
     #[serde(default, rename = "automatic_captions")]
     automatic_captions: Option<HashMap<String, Vec<SubtitleInfo>>>,
     formats: Option<Vec<FormatInfo>>,
+    #[serde(default)]
+    chapters: Option<Vec<ChapterInfo>>,
+    /// Only written when yt-dlp ran with `--sponsorblock-mark`.
+    #[serde(default)]
+    sponsorblock_chapters: Option<Vec<SponsorBlockInfo>>,
+    #[serde(default)]
+    webpage_url: Option<String>,
+    #[serde(default)]
+    original_url: Option<String>,
+    /// Extractor that produced the info JSON, e.g. `youtube`.
+    #[serde(default)]
+    extractor: Option<String>,
+    #[serde(default)]
+    extractor_key: Option<String>,
+    #[serde(default, rename = "_version")]
+    version: Option<VersionInfo>,
+    /// `public`, `unlisted`, `private`, `needs_auth`, ...
+    #[serde(default)]
+    availability: Option<String>,
+}
+
+/// yt-dlp's `_version` block.
+#[derive(Debug, Deserialize)]
+struct VersionInfo {
+    version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChapterInfo {
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+    title: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SponsorBlockInfo {
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+    category: Option<String>,
 }
 
 #[allow(dead_code)]
@@ -301,7 +1080,8 @@ struct RawComment {
 
 /// Distinguishes long-form uploads from Shorts so we can route files to the
 /// right directory and API slug.
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 enum MediaKind {
     Video,
     Short,
@@ -316,86 +1096,256 @@ fn main() -> Result<()> {
         channel_url,
         media_root,
         www_root,
+        incremental,
+        since,
+        metadata_only,
+        resume,
+        preflight,
+        reindex,
+        process_retries,
+        match_filter,
+        collections,
+        jobs,
+        max_height,
+        formats,
+        sponsorblock,
+        keep_audio,
+        sub_langs,
+        auto_subs,
+        webhook,
+        strict_cookies,
+        webhook_secret,
+        object_store,
+        rotation,
+        archive,
+        retries,
+        timeout,
+        proxy,
+        rate_limit,
+        sleep_interval,
+        max_sleep_interval,
+        sqlite,
+        verbosity,
     } = DownloaderArgs::parse()?;
+    logging::init("download_channel", verbosity);
+
+    if preflight {
+        let paths = Paths::with_roots(&media_root, &www_root);
+        info!("Preflight for {}:", paths.base.display());
+        return run_preflight(PreflightInputs {
+            yt_dlp: yt_dlp_command(),
+            media_root: paths.base,
+            metadata_db: paths.metadata_db,
+            cookies: paths.ytdlp.cookies,
+            strict_cookies,
+            sqlite,
+            min_free_bytes: DEFAULT_MIN_FREE_BYTES,
+        })
+        .print_and_finish();
+    }
 
-    ensure_program_available("yt-dlp")?;
-
-    let paths = Paths::with_roots(&media_root, &www_root);
+    let mut paths = Paths::with_roots(&media_root, &www_root);
+    if let Some(archive) = archive {
+        paths.archive = archive;
+    }
     paths.prepare()?;
-    let mut metadata =
-        MetadataStore::open(&paths.metadata_db).context("initializing metadata database")?;
+    paths.case_insensitive = layout::is_case_insensitive(&paths.base)?;
+    paths.max_height = max_height;
+    paths.formats = formats;
+    paths.sponsorblock = sponsorblock;
+    paths.keep_audio = keep_audio;
+    paths.sub_langs = sub_langs;
+    paths.auto_subs = auto_subs;
+    paths.object_store = object_store;
+    paths.ytdlp.retries = retries;
+    paths.ytdlp.timeout = timeout;
+    paths.ytdlp.proxy = proxy;
+    paths.ytdlp.rate_limit = rate_limit;
+    paths.ytdlp.sleep_interval = sleep_interval;
+    paths.ytdlp.max_sleep_interval = max_sleep_interval;
+    if paths.case_insensitive {
+        info!(
+            "{} is case-insensitive; video directories carry a case suffix",
+            paths.base.display()
+        );
+    }
+    let mut metadata = MetadataStore::open_tuned(&paths.metadata_db, sqlite)
+        .context("initializing metadata database")?;
 
-    println!("===================================");
-    println!("YouTube Channel Downloader");
-    println!("===================================");
-    println!("Channel: {}", channel_url);
-    println!("Base directory: {}", paths.base.display());
-    println!("WWW root: {}", paths.www_root.display());
-    println!();
+    if reindex {
+        info!(
+            "Reindexing {} from disk (no network access)",
+            paths.base.display()
+        );
+        let indexed = reindex_library(&paths, &mut metadata)?;
+        info!("Indexed {} entries", indexed);
+        return Ok(());
+    }
 
-    println!("Starting download process...");
-    println!();
+    ensure_program_available("yt-dlp")?;
+    match check_cookies(&paths.ytdlp.cookies, strict_cookies) {
+        CookieStatus::Refused(reason) => {
+            bail!("{reason} (refusing to use it because of --strict-cookies)")
+        }
+        CookieStatus::Exposed(reason) => warn!("{reason}"),
+        CookieStatus::Missing | CookieStatus::Private => {}
+    }
 
-    let mut archive = load_archive(&paths.archive)?;
+    if process_retries {
+        info!("Processing the retry queue {}", paths.retry_queue.display());
+        let mut archive = load_archive(&paths.archive)?;
+        let (completed, pending) = process_retry_queue(&paths, &mut archive, &mut metadata)?;
+        info!(
+            "Retry queue: {} video(s) completed, {} format(s) still failing",
+            completed, pending
+        );
+        return Ok(());
+    }
 
-    download_collection(
-        "regular videos",
-        format!("{}/videos", &channel_url),
-        Some("!is_live & original_url!*=/shorts/"),
-        &paths,
-        &mut archive,
-        MediaKind::Video,
-        &mut metadata,
-    )?;
-
-    download_collection(
-        "shorts",
-        format!("{}/shorts", &channel_url),
-        Some("original_url*=/shorts/"),
-        &paths,
-        &mut archive,
-        MediaKind::Short,
-        &mut metadata,
-    )?;
-
-    println!();
-    println!("===================================");
-    println!("Download complete!");
-    println!("===================================");
-    println!("Videos: {}", paths.videos.display());
-    println!("Shorts: {}", paths.shorts.display());
-    println!("Subtitles: {}", paths.subtitles.display());
-    println!("Thumbnails: {}", paths.thumbnails.display());
-    println!("Archive: {}", paths.archive.display());
-    println!();
-    println!("Metadata files:");
-    println!("  - <video_id>.info.json (video metadata)");
-    println!("  - <video_id>.description (video description)");
-    println!("  - <video_id>.jpg (thumbnail)");
-    println!();
-    println!("Next steps:");
-    println!("1. Download likes/dislikes data separately");
-    println!("2. Download comments data separately");
-    println!("3. Process .info.json files to populate IndexedDB");
+    info!(channel = %channel_url, "Starting download process");
+    info!("Base directory: {}", paths.base.display());
+    info!("WWW root: {}", paths.www_root.display());
 
-    Ok(())
-}
+    let mut archive = load_archive(&paths.archive)?;
 
-impl Paths {
-    /// Builds the struct using the provided media and www roots.
-    fn with_roots(media_root: &Path, www_root: &Path) -> Self {
-        let base = media_root.to_path_buf();
-        let videos = base.join(VIDEOS_SUBDIR);
-        let shorts = base.join(SHORTS_SUBDIR);
-        let subtitles = base.join(SUBTITLES_SUBDIR);
-        let thumbnails = base.join(THUMBNAILS_SUBDIR);
-        let comments = base.join(COMMENTS_SUBDIR);
-        let archive = base.join(ARCHIVE_FILE);
-        let cookies = base.join(COOKIES_FILE);
-        let www_root = www_root.to_path_buf();
-        let metadata_db = base.join(METADATA_DB_FILE);
+    // Video and playlist targets are one-off fetches: they neither resume
+    // nor count as a run of the channel.
+    let channel_key = matches!(
+        DownloadTarget::classify(&channel_url),
+        DownloadTarget::Channel
+    )
+    .then(|| canonical_channel_url(&channel_url));
+    let since_date = since.and_then(|days| since_date_after(Utc::now().date_naive(), days));
+    let date_after = if let (true, Some(key)) = (incremental, &channel_key) {
+        let last_upload = metadata.channel_last_upload_date(key)?;
+        let date_after = last_upload.as_deref().and_then(incremental_date_after);
+        match &date_after {
+            Some(date) => info!("Incremental mode: only listing uploads after {}", date),
+            None => info!("Incremental mode: no previous run recorded, doing a full scan"),
+        }
+        // With `--since` as well, the wider window wins so neither one hides
+        // uploads the other would list.
+        date_after.map(|date| match since_date {
+            Some(since) => since.min(date),
+            None => date,
+        })
+    } else {
+        if let (Some(days), Some(date)) = (since, &since_date) {
+            info!(
+                "Only listing uploads from the last {} days (after {})",
+                days, date
+            );
+        }
+        since_date
+    };
 
-        Self {
+    if metadata_only {
+        info!("Metadata-only mode: media, subtitles and comments are left untouched");
+        let mut refreshed = 0;
+        for collection in &collections {
+            refreshed += refresh_collection_stats(
+                &collection.label,
+                collection.listing(&channel_url, match_filter.as_deref(), date_after.as_deref()),
+                &paths,
+                collection.kind,
+                &mut metadata,
+            )?;
+        }
+        info!("Metadata refreshed for {} entries", refreshed);
+        return Ok(());
+    }
+
+    let resume_from = match &channel_key {
+        Some(key) if resume => metadata
+            .channel_resume_cursor(key)?
+            .as_deref()
+            .and_then(ResumeCursor::parse),
+        _ => None,
+    };
+    if let Some(cursor) = &resume_from {
+        info!(
+            "Resuming an interrupted run after {} (use --no-resume to start from the top)",
+            cursor.encode()
+        );
+    }
+    let mut run = ChannelRun {
+        summary: RunSummary::default(),
+        channel_key: channel_key.clone(),
+        resume_from,
+        tab_order: collections
+            .iter()
+            .map(|collection| collection.tab.clone())
+            .collect(),
+        jobs,
+    };
+
+    let outcome = (|| -> Result<()> {
+        let mut uploads = Vec::new();
+        for collection in &collections {
+            uploads.push(download_collection(
+                collection,
+                collection.listing(&channel_url, match_filter.as_deref(), date_after.as_deref()),
+                &paths,
+                &mut archive,
+                &mut metadata,
+                &mut run,
+            )?);
+        }
+        match &channel_key {
+            Some(key) => metadata
+                .record_channel_run(key, channel_cursor(&uploads).as_deref())
+                .context("recording channel run"),
+            None => Ok(()),
+        }
+    })();
+    let report = |summary: &RunSummary, error: Option<&str>| {
+        write_run_summary(&paths.run_summary, &rotation, &channel_url, summary, error);
+        if let Some(url) = &webhook {
+            notify_webhook(url, webhook_secret.as_deref(), &channel_url, summary, error);
+        }
+    };
+    if let Err(err) = outcome {
+        // The journal and the receiver still hear about a run that died
+        // part-way, with what it managed before the error.
+        warn!(
+            channel = %channel_url,
+            abort = abort_kind(&err),
+            "Run aborted: {err:#}"
+        );
+        run.summary.log();
+        report(&run.summary, Some(&format!("{err:#}")));
+        return Err(err);
+    }
+
+    info!(channel = %channel_url, "Download complete");
+    info!("Videos: {}", paths.videos.display());
+    info!("Shorts: {}", paths.shorts.display());
+    info!("Subtitles: {}", paths.subtitles.display());
+    info!("Thumbnails: {}", paths.thumbnails.display());
+    info!("Archive: {}", paths.archive.display());
+    run.summary.log();
+    report(&run.summary, None);
+
+    Ok(())
+}
+
+impl Paths {
+    /// Builds the struct using the provided media and www roots.
+    fn with_roots(media_root: &Path, www_root: &Path) -> Self {
+        let base = media_root.to_path_buf();
+        let videos = base.join(VIDEOS_SUBDIR);
+        let shorts = base.join(SHORTS_SUBDIR);
+        let subtitles = base.join(SUBTITLES_SUBDIR);
+        let thumbnails = base.join(THUMBNAILS_SUBDIR);
+        let comments = base.join(COMMENTS_SUBDIR);
+        let archive = base.join(ARCHIVE_FILE);
+        let cookies = base.join(COOKIES_FILE);
+        let retry_queue = base.join(RETRY_QUEUE_FILE);
+        let run_summary = base.join(RUN_SUMMARY_FILE);
+        let www_root = www_root.to_path_buf();
+        let metadata_db = base.join(METADATA_DB_FILE);
+
+        Self {
             base,
             videos,
             shorts,
@@ -403,9 +1353,19 @@ impl Paths {
             thumbnails,
             comments,
             archive,
-            cookies,
+            retry_queue,
+            run_summary,
             www_root,
             metadata_db,
+            case_insensitive: false,
+            max_height: None,
+            formats: Vec::new(),
+            sponsorblock: false,
+            keep_audio: false,
+            sub_langs: DEFAULT_SUB_LANGS.to_owned(),
+            auto_subs: true,
+            object_store: false,
+            ytdlp: YtDlpSettings::new(cookies),
         }
     }
 
@@ -424,6 +1384,10 @@ impl Paths {
             .with_context(|| format!("creating {}", self.comments.display()))?;
         fs::create_dir_all(&self.www_root)
             .with_context(|| format!("creating {}", self.www_root.display()))?;
+        // `--archive` may point outside the media root.
+        if let Some(parent) = self.archive.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+        }
         Ok(())
     }
 
@@ -434,13 +1398,22 @@ impl Paths {
             MediaKind::Short => &self.shorts,
         }
     }
+
+    /// Per-video directory for `video_id` under `parent` (a media, subtitle,
+    /// thumbnail or comment root).
+    fn video_dir(&self, parent: &Path, video_id: &str) -> PathBuf {
+        layout::video_dir(parent, video_id, self.case_insensitive)
+    }
 }
 
 #[cfg(test)]
 impl Paths {
     fn from_base(base: &Path) -> Self {
         let www_root = base.join("www");
-        Self::with_roots(base, &www_root)
+        let mut paths = Self::with_roots(base, &www_root);
+        // Stubs decide each call's outcome; retrying would only repeat it.
+        paths.ytdlp.retries = 0;
+        paths
     }
 }
 
@@ -460,6 +1433,11 @@ fn ensure_program_available(name: &str) -> Result<()> {
     }
 }
 
+/// Whether the archive at `path` is stored gzip-compressed.
+fn is_gzip_archive(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
+
 /// Parses yt-dlp's archive file to avoid duplicate downloads.
 fn load_archive(path: &Path) -> Result<HashSet<String>> {
     if !path.exists() {
@@ -467,14 +1445,16 @@ fn load_archive(path: &Path) -> Result<HashSet<String>> {
     }
 
     let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
-    let reader = BufReader::new(file);
+    let reader: Box<dyn BufRead> = if is_gzip_archive(path) {
+        Box::new(BufReader::new(MultiGzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
     let mut entries = HashSet::new();
 
     for line in reader.lines() {
         let line = line?;
-        if let Some(id) = line.split_whitespace().last()
-            && !id.is_empty()
-        {
+        if let Some(id) = parse_archive_line(&line) {
             entries.insert(id.to_owned());
         }
     }
@@ -489,131 +1469,1142 @@ fn append_to_archive(path: &Path, video_id: &str) -> Result<()> {
         .append(true)
         .open(path)
         .with_context(|| format!("opening archive {}", path.display()))?;
-    writeln!(file, "youtube {}", video_id)
-        .with_context(|| format!("writing archive entry for {}", video_id))?;
+    if is_gzip_archive(path) {
+        // Each append adds its own gzip member; readers decode the
+        // concatenation as one stream.
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        writeln!(encoder, "{}", archive_line(video_id))
+            .and_then(|()| encoder.finish().map(drop))
+            .with_context(|| format!("writing archive entry for {}", video_id))?;
+    } else {
+        writeln!(file, "{}", archive_line(video_id))
+            .with_context(|| format!("writing archive entry for {}", video_id))?;
+    }
     Ok(())
 }
 
+/// A format yt-dlp failed to fetch, as reported by
+/// [`download_video_all_formats`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FailedFormat {
+    format_id: String,
+    reason: String,
+}
+
+/// One line of `retry-queue.jsonl`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct RetryEntry {
+    video_id: String,
+    format_id: String,
+    reason: String,
+    kind: MediaKind,
+}
+
+/// `<media_root>/retry-queue.jsonl`: formats that failed during a run, so
+/// `--process-retries` can fetch just those instead of walking the whole
+/// channel again. Workers share one queue; every change rewrites the file
+/// under the lock.
+struct RetryQueue {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl RetryQueue {
+    fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn load(&self) -> Result<Vec<RetryEntry>> {
+        let _guard = lock(&self.lock);
+        self.read()
+    }
+
+    /// Replaces the entries of `video_id` with `failed`; an empty slice
+    /// clears the video once all of its formats are on disk.
+    fn record(&self, video_id: &str, kind: MediaKind, failed: &[FailedFormat]) -> Result<()> {
+        let _guard = lock(&self.lock);
+        let mut entries = self.read()?;
+        let before = entries.len();
+        entries.retain(|entry| entry.video_id != video_id);
+        if failed.is_empty() && entries.len() == before {
+            return Ok(());
+        }
+        entries.extend(failed.iter().map(|format| RetryEntry {
+            video_id: video_id.to_owned(),
+            format_id: format.format_id.clone(),
+            reason: format.reason.clone(),
+            kind,
+        }));
+        self.write(&entries)
+    }
+
+    /// Lines that do not parse are reported and dropped rather than
+    /// blocking the rest of the queue.
+    fn read(&self) -> Result<Vec<RetryEntry>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("opening retry queue {}", self.path.display()));
+            }
+        };
+        let mut entries = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line =
+                line.with_context(|| format!("reading retry queue {}", self.path.display()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(entry) => entries.push(entry),
+                Err(err) => warn!(
+                    "Ignoring malformed retry queue line in {}: {}",
+                    self.path.display(),
+                    err
+                ),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Writes a sibling file and renames it over the queue so a crash never
+    /// leaves half a line behind. An empty queue removes the file.
+    fn write(&self, entries: &[RetryEntry]) -> Result<()> {
+        if entries.is_empty() {
+            return match fs::remove_file(&self.path) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err)
+                    .with_context(|| format!("removing retry queue {}", self.path.display())),
+                _ => Ok(()),
+            };
+        }
+        let mut body = String::new();
+        for entry in entries {
+            body.push_str(&serde_json::to_string(entry)?);
+            body.push('\n');
+        }
+        let tmp = self.path.with_extension("jsonl.tmp");
+        fs::write(&tmp, body).with_context(|| format!("writing {}", tmp.display()))?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("replacing retry queue {}", self.path.display()))
+    }
+}
+
+/// Which channel tab to enumerate and how yt-dlp should narrow it down.
+struct PlaylistListing<'a> {
+    url: String,
+    /// Passed to `--match-filter`.
+    filter: Option<String>,
+    /// `YYYYMMDD` passed to `--dateafter` in incremental mode.
+    date_after: Option<&'a str>,
+}
+
+/// One channel tab walked by a run, set with `--collection`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CollectionSpec {
+    /// Name used in progress output.
+    label: String,
+    /// Tab appended to the channel URL, without the leading `/`. Also keys
+    /// the resume cursor, so each tab may appear only once.
+    tab: String,
+    /// Built-in `--match-filter` for the tab; a user filter is ANDed onto it.
+    filter: Option<String>,
+    kind: MediaKind,
+    /// URL listed as-is instead of `<channel>/<tab>`, for video and playlist
+    /// targets.
+    url: Option<String>,
+}
+
+impl CollectionSpec {
+    /// Regular uploads first, then Shorts.
+    fn defaults() -> Vec<Self> {
+        vec![Self::videos(), Self::shorts()]
+    }
+
+    fn videos() -> Self {
+        Self {
+            label: "regular videos".to_owned(),
+            tab: "videos".to_owned(),
+            filter: Some(VIDEOS_MATCH_FILTER.to_owned()),
+            kind: MediaKind::Video,
+            url: None,
+        }
+    }
+
+    fn shorts() -> Self {
+        Self {
+            label: "shorts".to_owned(),
+            tab: "shorts".to_owned(),
+            filter: Some(SHORTS_MATCH_FILTER.to_owned()),
+            kind: MediaKind::Short,
+            url: None,
+        }
+    }
+
+    /// Parses `videos`, `shorts` (the built-in passes) or
+    /// `<label>:/<tab>[:<match-filter>]`. Everything after the second `:` is
+    /// the filter, so it may itself contain colons. Entries from the
+    /// `/shorts` tab are stored as Shorts, every other tab as regular videos.
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "videos" => return Ok(Self::videos()),
+            "shorts" => return Ok(Self::shorts()),
+            _ => {}
+        }
+
+        let mut parts = value.splitn(3, ':');
+        let label = parts.next().unwrap_or_default().trim();
+        let tab = parts.next().unwrap_or_default().trim();
+        if label.is_empty() {
+            bail!("--collection {value:?} needs a label (expected <label>:/<tab>[:<filter>])");
+        }
+        let Some(tab) = tab.strip_prefix('/') else {
+            bail!("--collection {value:?} needs a tab starting with '/' (e.g. live:/streams)");
+        };
+        if tab.is_empty()
+            || tab.starts_with('/')
+            || !tab
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '/'))
+        {
+            bail!("--collection {value:?} has an invalid tab {tab:?}");
+        }
+        let filter = parts
+            .next()
+            .map(validate_match_filter)
+            .transpose()
+            .with_context(|| format!("parsing the filter of --collection {value:?}"))?;
+
+        Ok(Self {
+            label: label.to_owned(),
+            tab: tab.to_owned(),
+            filter,
+            kind: if tab == "shorts" {
+                MediaKind::Short
+            } else {
+                MediaKind::Video
+            },
+            url: None,
+        })
+    }
+
+    /// The single pass for a video or playlist URL; channel URLs go through
+    /// their tabs instead. `kind` (`--kind`) overrides where entries are
+    /// stored; otherwise `/shorts/<id>` links are Shorts and everything else
+    /// regular videos.
+    fn for_target(target: &DownloadTarget, url: &str, kind: Option<MediaKind>) -> Self {
+        match target {
+            DownloadTarget::Video { id, kind: detected } => {
+                let kind = kind.unwrap_or(*detected);
+                let (label, url) = match kind {
+                    MediaKind::Video => ("video", format!("https://www.youtube.com/watch?v={id}")),
+                    MediaKind::Short => ("short", format!("https://www.youtube.com/shorts/{id}")),
+                };
+                Self {
+                    label: label.to_owned(),
+                    tab: "video".to_owned(),
+                    filter: None,
+                    kind,
+                    url: Some(url),
+                }
+            }
+            DownloadTarget::Playlist => Self {
+                label: "playlist entries".to_owned(),
+                tab: "playlist".to_owned(),
+                filter: None,
+                kind: kind.unwrap_or(MediaKind::Video),
+                url: Some(url.to_owned()),
+            },
+            DownloadTarget::Channel => {
+                unreachable!("channel URLs are listed per tab (`--collection` or the defaults)")
+            }
+        }
+    }
+
+    /// Listing of this tab on `channel_url`, with the user's `--match-filter`
+    /// (if any) ANDed onto the built-in one.
+    fn listing<'a>(
+        &self,
+        channel_url: &str,
+        extra_filter: Option<&str>,
+        date_after: Option<&'a str>,
+    ) -> PlaylistListing<'a> {
+        let filter = match &self.filter {
+            Some(builtin) => Some(combine_match_filters(builtin, extra_filter)),
+            None => extra_filter.map(str::to_owned),
+        };
+        PlaylistListing {
+            url: self
+                .url
+                .clone()
+                .unwrap_or_else(|| format!("{}/{}", channel_url, self.tab)),
+            filter,
+            date_after,
+        }
+    }
+}
+
+/// State shared by every collection of one downloader run.
+struct ChannelRun {
+    summary: RunSummary,
+    /// Canonical channel key the processing cursor is saved under; `None`
+    /// for video and playlist targets, which keep no cursor.
+    channel_key: Option<String>,
+    /// Cursor left by an interrupted previous run; `None` with `--no-resume`.
+    resume_from: Option<ResumeCursor>,
+    /// Tabs of this run in processing order, to place the cursor.
+    tab_order: Vec<String>,
+    /// Worker threads per collection (`--jobs`), each running its own yt-dlp.
+    jobs: usize,
+}
+
+/// Last entry handled by a run, persisted in `channels.resume_cursor` as
+/// `<tab>/<videoid>` after every entry and cleared once the run completes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ResumeCursor {
+    tab: String,
+    videoid: String,
+}
+
+impl ResumeCursor {
+    /// Tabs may contain `/` but video ids never do, so split on the last one.
+    fn parse(value: &str) -> Option<Self> {
+        let (tab, videoid) = value.rsplit_once('/')?;
+        if tab.is_empty() || videoid.is_empty() {
+            return None;
+        }
+        Some(Self {
+            tab: tab.to_owned(),
+            videoid: videoid.to_owned(),
+        })
+    }
+
+    fn encode(&self) -> String {
+        format!("{}/{}", self.tab, self.videoid)
+    }
+
+    /// How many leading entries of the `tab` listing the interrupted run
+    /// already handled. Tabs run in `tab_order`, so a cursor in a later tab
+    /// means this one was done. A cursor for a tab that is not part of this
+    /// run (the collections changed) resumes nothing.
+    fn processed_prefix(&self, tab_order: &[String], tab: &str, ids: &[String]) -> usize {
+        let position = |wanted: &str| tab_order.iter().position(|name| name == wanted);
+        match (position(&self.tab), position(tab)) {
+            (Some(cursor), Some(current)) if cursor == current => ids
+                .iter()
+                .position(|id| *id == self.videoid)
+                .map_or(0, |index| index + 1),
+            (Some(cursor), Some(current)) if cursor > current => ids.len(),
+            _ => 0,
+        }
+    }
+}
+
 /// Given a playlist (videos, Shorts, etc.), download each entry and refresh its
-/// metadata.
+/// metadata. Returns the upload dates this pass lets `--incremental` skip.
+///
+/// When resuming, archived entries the interrupted run already handled are
+/// skipped; anything not in the archive yet is still downloaded.
 fn download_collection(
-    label: &str,
-    list_url: String,
-    filter: Option<&str>,
+    collection: &CollectionSpec,
+    listing: PlaylistListing<'_>,
     paths: &Paths,
     archive: &mut HashSet<String>,
-    media_kind: MediaKind,
     metadata: &mut MetadataStore,
-) -> Result<()> {
-    println!("Getting list of {}...", label);
+    run: &mut ChannelRun,
+) -> Result<CollectionUploads> {
+    let label = collection.label.as_str();
+    let media_kind = collection.kind;
+    info!("Getting list of {}...", label);
 
-    let ids = get_video_ids(&list_url, filter)?;
+    let ids = get_video_ids(&listing, &paths.ytdlp)?;
 
     if ids.is_empty() {
-        println!("No {} found", label);
-        println!();
-        return Ok(());
+        info!("No {} found", label);
+        return Ok(CollectionUploads::default());
+    }
+
+    let total = ids.len();
+    info!("Found {} {}", total, label);
+
+    let processed = run.resume_from.as_ref().map_or(0, |cursor| {
+        cursor.processed_prefix(&run.tab_order, &collection.tab, &ids)
+    });
+    let workers = run.jobs.clamp(1, total);
+    if workers > 1 {
+        info!("Processing with {} parallel workers", workers);
+    }
+
+    let stores = SharedStores::new(archive, metadata, paths);
+    let progress = Mutex::new(CollectionProgress {
+        run,
+        uploads: vec![None; total],
+        oldest_failure: None,
+        done: vec![false; total],
+        saved: 0,
+    });
+    let next_index = AtomicUsize::new(0);
+    let fatal: Mutex<Option<anyhow::Error>> = Mutex::new(None);
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while lock(&fatal).is_none() {
+                    let index = next_index.fetch_add(1, Ordering::SeqCst);
+                    let Some(video_id) = ids.get(index) else {
+                        break;
+                    };
+                    let current = index + 1;
+                    let _span = info_span!("video", video_id = %video_id).entered();
+
+                    let result = if index < processed && stores.archive().contains(video_id) {
+                        info!(
+                            "[{}/{}] Skipping {} (handled before the previous run stopped)",
+                            current, total, video_id
+                        );
+                        EntryResult::Resumed
+                    } else if stores.is_deleted(video_id) {
+                        info!(
+                            "[{}/{}] Skipping {} (deleted from the library)",
+                            current, total, video_id
+                        );
+                        EntryResult::Deleted
+                    } else {
+                        match process_media_entry(
+                            video_id, current, total, paths, &stores, media_kind,
+                        ) {
+                            Ok(outcome) => EntryResult::Processed(outcome),
+                            // A malformed invocation fails identically for
+                            // every video, so stop handing out entries instead
+                            // of hammering YouTube with doomed requests.
+                            Err(err) if err.downcast_ref::<FatalYtDlpError>().is_some() => {
+                                lock(&fatal).get_or_insert(err);
+                                break;
+                            }
+                            Err(err) => {
+                                warn!("Failed to process {}: {}", video_id, err);
+                                EntryResult::Failed
+                            }
+                        }
+                    };
+
+                    let saved =
+                        lock(&progress).complete(index, &ids, result, &collection.tab, &stores);
+                    if let Err(err) = saved {
+                        lock(&fatal).get_or_insert(err);
+                    }
+                }
+            });
+        }
+    });
+
+    if let Some(err) = lock(&fatal).take() {
+        return Err(err.context(format!("aborting {label} download")));
+    }
+    let uploads = progress
+        .into_inner()
+        .unwrap_or_else(PoisonError::into_inner)
+        .uploads();
+
+    info!("{} download complete", capitalize(label));
+
+    Ok(uploads)
+}
+
+/// What one collection contributes to the channel's `--incremental` cursor.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct CollectionUploads {
+    /// Newest `upload_date` the cursor may move to. When an entry failed,
+    /// only entries listed after the oldest failure count, so the next
+    /// window still reaches back to it.
+    newest: Option<String>,
+    /// Whether an entry failed and is retried on the next run.
+    failed: bool,
+}
+
+/// The `upload_date` to record for the channel. Every tab shares one
+/// `--dateafter`, so a failure in any of them holds the cursor back for all.
+fn channel_cursor(uploads: &[CollectionUploads]) -> Option<String> {
+    if uploads.iter().any(|collection| collection.failed) {
+        uploads
+            .iter()
+            .filter(|collection| collection.failed)
+            .map(|collection| collection.newest.clone())
+            .min()
+            .flatten()
+    } else {
+        uploads
+            .iter()
+            .filter_map(|collection| collection.newest.clone())
+            .max()
+    }
+}
+
+/// Archive, metadata store and retry queue shared by the workers of one
+/// collection. Each lock is held for a single lookup or write, never across a
+/// yt-dlp call.
+struct SharedStores<'a> {
+    archive: Mutex<&'a mut HashSet<String>>,
+    metadata: Mutex<&'a mut MetadataStore>,
+    retry_queue: RetryQueue,
+}
+
+impl<'a> SharedStores<'a> {
+    fn new(
+        archive: &'a mut HashSet<String>,
+        metadata: &'a mut MetadataStore,
+        paths: &Paths,
+    ) -> Self {
+        Self {
+            archive: Mutex::new(archive),
+            metadata: Mutex::new(metadata),
+            retry_queue: RetryQueue::new(&paths.retry_queue),
+        }
+    }
+
+    fn archive(&self) -> MutexGuard<'_, &'a mut HashSet<String>> {
+        lock(&self.archive)
+    }
+
+    fn metadata(&self) -> MutexGuard<'_, &'a mut MetadataStore> {
+        lock(&self.metadata)
+    }
+
+    /// Whether an operator deleted `video_id`; its id is still archived, so
+    /// processing it would put the row back. A failed lookup is only logged:
+    /// the entry is then processed as usual.
+    fn is_deleted(&self, video_id: &str) -> bool {
+        self.metadata().is_deleted(video_id).unwrap_or_else(|err| {
+            warn!("Could not check whether {} was deleted: {}", video_id, err);
+            false
+        })
+    }
+}
+
+/// A worker that panicked has already been reported; keep using the data
+/// rather than cascading the panic through every other worker.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// How a worker finished with one listed entry.
+enum EntryResult {
+    /// Skipped because the interrupted previous run handled it.
+    Resumed,
+    /// Skipped because it was deleted from the library.
+    Deleted,
+    Processed(EntryOutcome),
+    /// Already reported as a warning; retried on the next run.
+    Failed,
+}
+
+/// Bookkeeping of one collection, updated as workers finish entries.
+struct CollectionProgress<'a> {
+    run: &'a mut ChannelRun,
+    /// `upload_date` of each listing position that was processed without
+    /// errors.
+    uploads: Vec<Option<String>>,
+    /// Last listing position (listings are newest first) that failed.
+    oldest_failure: Option<usize>,
+    /// Which listing positions are finished; workers complete them out of
+    /// order.
+    done: Vec<bool>,
+    /// Length of the finished prefix of the listing.
+    saved: usize,
+}
+
+impl CollectionProgress<'_> {
+    /// Records `result` for `ids[index]` and moves the resume cursor to the
+    /// end of the finished prefix, so a resumed run never skips an entry a
+    /// slower worker had not reached yet.
+    fn complete(
+        &mut self,
+        index: usize,
+        ids: &[String],
+        result: EntryResult,
+        tab: &str,
+        stores: &SharedStores<'_>,
+    ) -> Result<()> {
+        match result {
+            EntryResult::Resumed => self.run.summary.resumed += 1,
+            EntryResult::Processed(outcome) => {
+                self.run.summary.record(&ids[index], outcome.download);
+                let downloaded = matches!(outcome.download, None | Some(YtDlpExit::Success));
+                if downloaded && !outcome.metadata_failed {
+                    self.uploads[index] = outcome.upload_date;
+                } else {
+                    self.oldest_failure = self.oldest_failure.max(Some(index));
+                }
+            }
+            EntryResult::Failed => self.oldest_failure = self.oldest_failure.max(Some(index)),
+            EntryResult::Deleted => {}
+        }
+
+        self.done[index] = true;
+        let before = self.saved;
+        while self.done.get(self.saved).copied().unwrap_or(false) {
+            self.saved += 1;
+        }
+        if self.saved == before {
+            return Ok(());
+        }
+        let Some(channel_key) = &self.run.channel_key else {
+            return Ok(());
+        };
+        let cursor = ResumeCursor {
+            tab: tab.to_owned(),
+            videoid: ids[self.saved - 1].clone(),
+        };
+        stores
+            .metadata()
+            .save_resume_cursor(channel_key, &cursor.encode())
+            .context("saving resume cursor")
+    }
+
+    fn uploads(self) -> CollectionUploads {
+        let after_failures = self.oldest_failure.map_or(0, |index| index + 1);
+        CollectionUploads {
+            newest: self.uploads[after_failures..]
+                .iter()
+                .flatten()
+                .max()
+                .cloned(),
+            failed: self.oldest_failure.is_some(),
+        }
+    }
+}
+
+/// Uppercases the first character; labels come from `--collection`, so this
+/// must not assume the first character is a single byte.
+fn capitalize(label: &str) -> String {
+    let mut chars = label.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
     }
+}
+
+/// Stats-only counterpart of [`download_collection`]: re-fetches the info
+/// JSON for every listed entry and rewrites its video row. Entries that were
+/// never indexed are skipped since there is no media to point them at.
+/// Returns how many rows were refreshed.
+fn refresh_collection_stats(
+    label: &str,
+    listing: PlaylistListing<'_>,
+    paths: &Paths,
+    media_kind: MediaKind,
+    metadata: &mut MetadataStore,
+) -> Result<usize> {
+    info!("Getting list of {}...", label);
 
+    let ids = get_video_ids(&listing, &paths.ytdlp)?;
     let total = ids.len();
-    println!("Found {} {}", total, label);
-    println!();
+    info!("Found {} {}", total, label);
 
+    let reader = MetadataReader::new(&paths.metadata_db)?;
+    let output_dir = paths.media_dir(media_kind);
+    let mut refreshed = 0;
     for (index, video_id) in ids.iter().enumerate() {
-        let current = index + 1;
-        if let Err(err) = process_media_entry(
-            video_id, current, total, paths, archive, media_kind, metadata,
+        let _span = info_span!("video", video_id = %video_id).entered();
+        let known = match media_kind {
+            MediaKind::Video => reader.get_video(video_id)?.is_some(),
+            MediaKind::Short => reader.get_short(video_id)?.is_some(),
+        };
+        if !known {
+            info!(
+                "[{}/{}] Skipping {} (not downloaded yet)",
+                index + 1,
+                total,
+                video_id
+            );
+            continue;
+        }
+
+        info!(
+            "[{}/{}] Refreshing stats for {}",
+            index + 1,
+            total,
+            video_id
+        );
+        let video_url = format!("https://www.youtube.com/watch?v={video_id}");
+        match store_video_record(
+            video_id, &video_url, output_dir, paths, media_kind, metadata,
         ) {
-            eprintln!("  Warning: failed to process {}: {}", video_id, err);
+            Ok(_) => refreshed += 1,
+            Err(err) => warn!("Metadata refresh failed for {}: {}", video_id, err),
         }
     }
 
-    println!();
-    println!(
-        "{} download complete!",
-        label
-            .chars()
-            .next()
-            .map(|c| c.to_uppercase().to_string() + &label[1..])
-            .unwrap_or_else(|| label.to_string()),
-    );
-    println!();
+    Ok(refreshed)
+}
 
-    Ok(())
+/// What happened to a single entry during `process_media_entry`.
+struct EntryOutcome {
+    /// `upload_date` when metadata was refreshed successfully.
+    upload_date: Option<String>,
+    /// The metadata refresh failed (already logged). Counts as a failure for
+    /// the incremental cursor even when the download went through.
+    metadata_failed: bool,
+    /// Result of the media download; `None` when the entry was already in
+    /// the archive and only metadata was refreshed.
+    download: Option<YtDlpExit>,
 }
 
 /// Handles a single video/short: download media if missing, then refresh all
-/// metadata artifacts.
+/// metadata artifacts. Fails with [`FatalYtDlpError`] when yt-dlp rejects the
+/// invocation itself.
 fn process_media_entry(
     video_id: &str,
     current: usize,
     total: usize,
     paths: &Paths,
-    archive: &mut HashSet<String>,
+    stores: &SharedStores<'_>,
     media_kind: MediaKind,
-    metadata: &mut MetadataStore,
-) -> Result<()> {
+) -> Result<EntryOutcome> {
     let output_dir = paths.media_dir(media_kind);
     // Archive entries let us skip heavy downloads when the file tree already
     // contains every muxed format. We still refresh metadata because stats can
     // change over time.
-    let already_downloaded = archive.contains(video_id);
+    let archived = stores.archive().contains(video_id);
+    let already_downloaded = archived && !stored_media_missing(video_id, media_kind, paths)?;
     let video_url = format!("https://www.youtube.com/watch?v={video_id}");
+    let mut download = None;
 
     if already_downloaded {
-        println!(
+        info!(
             "[{}/{}] Refreshing metadata for {}",
             current, total, video_id
         );
     } else {
-        println!(
-            "[{}/{}] Downloading and indexing {}",
-            current, total, video_id
-        );
-        if let Err(err) = download_video_all_formats(video_id, output_dir, paths) {
-            eprintln!("  Warning: failed to download {}: {}", video_id, err);
+        if archived {
+            info!(
+                "[{}/{}] Media for {} is missing on disk; downloading it again",
+                current, total, video_id
+            );
         } else {
-            append_to_archive(&paths.archive, video_id)?;
-            archive.insert(video_id.to_owned());
+            info!(
+                "[{}/{}] Downloading and indexing {}",
+                current, total, video_id
+            );
+        }
+        match download_video_all_formats(video_id, output_dir, paths, media_kind) {
+            Ok(outcome) => {
+                stores
+                    .retry_queue
+                    .record(video_id, media_kind, &outcome.failed)?;
+                // Anything else is not archived, so the next run retries the
+                // missing formats too.
+                if outcome.exit == YtDlpExit::Success {
+                    let mut archive = stores.archive();
+                    if !archive.contains(video_id) {
+                        append_to_archive(&paths.archive, video_id)?;
+                        archive.insert(video_id.to_owned());
+                    }
+                }
+                download = Some(outcome.exit);
+            }
+            Err(err) if err.downcast_ref::<FatalYtDlpError>().is_some() => return Err(err),
+            Err(err) => {
+                warn!("Failed to download {}: {}", video_id, err);
+                download = Some(YtDlpExit::VideoFailed);
+            }
         }
     }
 
-    if let Err(err) = refresh_metadata(
-        video_id, &video_url, output_dir, paths, media_kind, metadata,
-    ) {
-        eprintln!(
-            "  Warning: metadata refresh failed for {}: {}",
-            video_id, err
-        );
-    }
+    let (upload_date, metadata_failed) =
+        match refresh_metadata(video_id, &video_url, output_dir, paths, media_kind, stores) {
+            Ok(record) => (record.upload_date, false),
+            Err(err) => {
+                warn!("Metadata refresh failed for {}: {}", video_id, err);
+                (None, true)
+            }
+        };
 
-    Ok(())
+    Ok(EntryOutcome {
+        upload_date,
+        metadata_failed,
+        download,
+    })
 }
 
-/// Fetches info JSON, updates DB rows, and syncs subtitles/comments.
-fn refresh_metadata(
-    video_id: &str,
-    video_url: &str,
-    output_dir: &Path,
+/// `--process-retries`: re-attempts only the formats in the retry queue. A
+/// video whose formats all succeed is published from its staging directory,
+/// archived, re-indexed and dropped from the queue; formats that fail again
+/// stay queued with the new reason. Returns how many videos were completed
+/// and how many formats are still queued.
+fn process_retry_queue(
     paths: &Paths,
-    media_kind: MediaKind,
+    archive: &mut HashSet<String>,
     metadata: &mut MetadataStore,
-) -> Result<()> {
-    let info = fetch_video_info(video_id, video_url, output_dir, paths)?;
-    let record = build_video_record(video_id, &info, output_dir, media_kind, paths)?;
+) -> Result<(usize, usize)> {
+    let stores = SharedStores::new(archive, metadata, paths);
+    let entries = stores.retry_queue.load()?;
+    if entries.is_empty() {
+        info!("Nothing to retry");
+        return Ok((0, 0));
+    }
 
-    match media_kind {
-        MediaKind::Video => metadata.upsert_video(&record)?,
-        MediaKind::Short => metadata.upsert_short(&record)?,
+    // One pass per video, in queue order.
+    let mut videos: Vec<(String, MediaKind, Vec<String>)> = Vec::new();
+    for entry in entries {
+        match videos.iter_mut().find(|(id, _, _)| *id == entry.video_id) {
+            Some((_, _, formats)) => formats.push(entry.format_id),
+            None => videos.push((entry.video_id, entry.kind, vec![entry.format_id])),
+        }
     }
 
-    let subtitles = collect_subtitles(video_id, &info, paths, media_kind)?;
-    metadata.upsert_subtitles(&subtitles)?;
+    let total = videos.len();
+    let (mut completed, mut pending) = (0, 0);
+    for (index, (video_id, media_kind, formats)) in videos.into_iter().enumerate() {
+        let _span = info_span!("video", video_id = %video_id).entered();
+        info!(
+            "[{}/{}] Retrying {} format(s) of {}",
+            index + 1,
+            total,
+            formats.len(),
+            video_id
+        );
+        let outcome = retry_formats(&video_id, media_kind, &formats, paths)?;
+        stores
+            .retry_queue
+            .record(&video_id, media_kind, &outcome.failed)?;
+        pending += outcome.failed.len();
+        if outcome.exit != YtDlpExit::Success {
+            continue;
+        }
 
-    let comments = fetch_comments(video_id, video_url, paths)?;
-    metadata.replace_comments(video_id, &comments)?;
+        completed += 1;
+        {
+            let mut archive = stores.archive();
+            if !archive.contains(&video_id) {
+                append_to_archive(&paths.archive, &video_id)?;
+                archive.insert(video_id.clone());
+            }
+        }
+        let video_url = format!("https://www.youtube.com/watch?v={video_id}");
+        let output_dir = paths.media_dir(media_kind);
+        if let Err(err) = refresh_metadata(
+            &video_id, &video_url, output_dir, paths, media_kind, &stores,
+        ) {
+            warn!("Metadata refresh failed for {}: {}", video_id, err);
+        }
+    }
 
-    Ok(())
+    Ok((completed, pending))
+}
+
+/// Downloads `formats` of `video_id` into its staging directory and
+/// publishes it once none of them failed. Without a staging directory the
+/// other formats are gone as well, so the whole video is downloaded again.
+fn retry_formats(
+    video_id: &str,
+    media_kind: MediaKind,
+    formats: &[String],
+    paths: &Paths,
+) -> Result<DownloadOutcome> {
+    let output_dir = paths.media_dir(media_kind);
+    let video_dir = output_dir
+        .join(layout::STAGING_DIR)
+        .join(layout::video_dir_name(video_id, paths.case_insensitive));
+    if !video_dir.is_dir() {
+        info!(
+            "No staged download left for {}; downloading every format again",
+            video_id
+        );
+        return download_video_all_formats(video_id, output_dir, paths, media_kind);
+    }
+
+    let video_url = format!("https://www.youtube.com/watch?v={video_id}");
+    let mut outcome = DownloadOutcome {
+        exit: YtDlpExit::Success,
+        failed: Vec::new(),
+    };
+    for format_id in formats {
+        let (exit, status) = download_format(video_id, &video_url, &video_dir, format_id, paths)?;
+        if exit == YtDlpExit::Success {
+            continue;
+        }
+        // Cancelled formats stay queued too, next to the failed ones.
+        warn!("Failed to download format {} ({})", format_id, status);
+        outcome.exit = outcome.exit.worst(exit);
+        outcome.failed.push(FailedFormat {
+            format_id: format_id.clone(),
+            reason: status.to_string(),
+        });
+    }
+
+    if outcome.exit == YtDlpExit::Success {
+        publish_staged(&video_dir, &paths.video_dir(output_dir, video_id))?;
+        info!("Completed: {}", video_id);
+    }
+    Ok(outcome)
+}
+
+/// Whether an archived entry lost its media: the DB lists its sources but
+/// none of those files exists any more (deleted by hand, partial restore).
+/// Entries the DB has not indexed yet or indexed without any source are left
+/// to the archive, and so is everything once an object store holds the
+/// offloaded files.
+fn stored_media_missing(video_id: &str, media_kind: MediaKind, paths: &Paths) -> Result<bool> {
+    if paths.object_store {
+        return Ok(false);
+    }
+    let reader = MetadataReader::new(&paths.metadata_db)?;
+    let record = match media_kind {
+        MediaKind::Video => reader.get_video(video_id)?,
+        MediaKind::Short => reader.get_short(video_id)?,
+    };
+    Ok(record.is_some_and(|record| {
+        !record.sources.is_empty()
+            && !record.sources.iter().any(|source| {
+                source
+                    .path
+                    .as_deref()
+                    .is_some_and(|path| Path::new(path).is_file())
+            })
+    }))
+}
+
+/// How a yt-dlp invocation ended, classified from its exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum YtDlpExit {
+    Success,
+    /// Exit code 1 (or anything unrecognized): this video could not be
+    /// fetched. Skip it and retry on the next run.
+    VideoFailed,
+    /// Exit code 101: the download was cancelled by a limit such as
+    /// `--max-downloads`. Also retried on the next run.
+    Cancelled,
+    /// Exit code 2 (bad options) or killed by a signal: the invocation itself
+    /// is broken and every other video would fail the same way.
+    Fatal,
+}
+
+impl YtDlpExit {
+    fn classify(status: ExitStatus) -> Self {
+        match status.code() {
+            Some(0) => YtDlpExit::Success,
+            Some(2) | None => YtDlpExit::Fatal,
+            Some(101) => YtDlpExit::Cancelled,
+            Some(_) => YtDlpExit::VideoFailed,
+        }
+    }
+
+    /// Keeps the most severe of two outcomes.
+    fn worst(self, other: Self) -> Self {
+        fn rank(exit: YtDlpExit) -> u8 {
+            match exit {
+                YtDlpExit::Success => 0,
+                YtDlpExit::Cancelled => 1,
+                YtDlpExit::VideoFailed => 2,
+                YtDlpExit::Fatal => 3,
+            }
+        }
+        if rank(other) > rank(self) {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+/// Raised when yt-dlp rejects the invocation (usage error, missing binary,
+/// killed). Callers abort the whole run instead of moving to the next video.
+#[derive(Debug)]
+struct FatalYtDlpError {
+    detail: String,
+}
+
+impl std::fmt::Display for FatalYtDlpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "yt-dlp rejected the invocation ({}); check the yt-dlp version and options",
+            self.detail
+        )
+    }
+}
+
+impl std::error::Error for FatalYtDlpError {}
+
+/// Why a run stopped early, logged next to its summary: the yt-dlp
+/// invocation itself was rejected (same for every video), or anything else.
+fn abort_kind(err: &anyhow::Error) -> &'static str {
+    if err.downcast_ref::<FatalYtDlpError>().is_some() {
+        "yt-dlp invocation rejected"
+    } else {
+        "error"
+    }
+}
+
+/// Per-run tally printed once the downloader finishes.
+#[derive(Debug, Default)]
+struct RunSummary {
+    downloaded: usize,
+    refreshed: usize,
+    /// Entries skipped because an interrupted previous run handled them.
+    resumed: usize,
+    skipped: Vec<(String, YtDlpExit)>,
+}
+
+impl RunSummary {
+    fn record(&mut self, video_id: &str, download: Option<YtDlpExit>) {
+        match download {
+            None => self.refreshed += 1,
+            Some(YtDlpExit::Success) => self.downloaded += 1,
+            Some(exit) => self.skipped.push((video_id.to_owned(), exit)),
+        }
+    }
+
+    fn log(&self) {
+        info!(
+            downloaded = self.downloaded,
+            refreshed = self.refreshed,
+            resumed = self.resumed,
+            skipped = self.skipped.len(),
+            "Summary: {} downloaded, {} metadata refreshed only, {} skipped (retried next run)",
+            self.downloaded,
+            self.refreshed,
+            self.skipped.len()
+        );
+        if self.resumed > 0 {
+            info!("Already handled by the interrupted run: {}", self.resumed);
+        }
+        for (video_id, exit) in &self.skipped {
+            warn!(video_id = %video_id, "Skipped: {}", skip_reason(*exit));
+        }
+    }
+
+    /// Body POSTed to `--webhook` and written to `run-summary.json`; `error`
+    /// is set when the run aborted.
+    fn webhook_payload(&self, channel_url: &str, error: Option<&str>) -> Value {
+        let failures: Vec<Value> = self
+            .skipped
+            .iter()
+            .map(|(video_id, exit)| json!({ "videoid": video_id, "reason": skip_reason(*exit) }))
+            .collect();
+        json!({
+            "channel": channel_url,
+            "downloaded": self.downloaded,
+            "refreshed": self.refreshed,
+            "resumed": self.resumed,
+            "failed": failures.len(),
+            "failures": failures,
+            "error": error,
+        })
+    }
+}
+
+fn skip_reason(exit: YtDlpExit) -> &'static str {
+    match exit {
+        YtDlpExit::Cancelled => "download cancelled (exit 101)",
+        _ => "download failed",
+    }
+}
+
+/// Header carrying `WEBHOOK_SECRET` so the receiver can reject forged posts.
+const WEBHOOK_SECRET_HEADER: &str = "X-Newtube-Webhook-Secret";
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn validate_webhook_url(value: &str) -> Result<String> {
+    let value = value.trim();
+    if !(value.starts_with("http://") || value.starts_with("https://")) {
+        bail!("--webhook must be an http:// or https:// URL, got {value:?}");
+    }
+    Ok(value.to_owned())
+}
+
+/// POSTs the run summary to `url`, along with the fatal error when the run
+/// aborted. The downloads are already over, so a receiver that is down or
+/// rejects the post is only logged.
+fn notify_webhook(
+    url: &str,
+    secret: Option<&str>,
+    channel_url: &str,
+    summary: &RunSummary,
+    error: Option<&str>,
+) {
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(WEBHOOK_TIMEOUT)
+        .timeout_read(WEBHOOK_TIMEOUT)
+        .build();
+    let mut request = agent.post(url);
+    if let Some(secret) = secret {
+        request = request.set(WEBHOOK_SECRET_HEADER, secret);
+    }
+    match request.send_json(summary.webhook_payload(channel_url, error)) {
+        Ok(_) => info!("Webhook notified: {url}"),
+        Err(err) => warn!("Webhook {url} failed: {err}"),
+    }
+}
+
+/// Replaces `run-summary.json` with this run's summary, rotating the previous
+/// one. Like the webhook, a failure is only logged.
+fn write_run_summary(
+    path: &Path,
+    rotation: &RotationPolicy,
+    channel_url: &str,
+    summary: &RunSummary,
+    error: Option<&str>,
+) {
+    let written = serde_json::to_vec_pretty(&summary.webhook_payload(channel_url, error))
+        .context("serializing run summary")
+        .and_then(|body| rotation.replace_file(path, &body));
+    if let Err(err) = written {
+        warn!("Could not write {}: {err:#}", path.display());
+    }
+}
+
+/// Fetches info JSON, updates DB rows, and syncs subtitles/comments. Returns
+/// the record that was stored.
+fn refresh_metadata(
+    video_id: &str,
+    video_url: &str,
+    output_dir: &Path,
+    paths: &Paths,
+    media_kind: MediaKind,
+    stores: &SharedStores<'_>,
+) -> Result<VideoRecord> {
+    let info = fetch_video_info(video_id, video_url, output_dir, paths)?;
+    let record = build_video_record(video_id, &info, output_dir, media_kind, paths)?;
+    upsert_record(&stores.metadata(), media_kind, &record)?;
+
+    let subtitles = collect_subtitles(video_id, &info, paths, media_kind)?;
+    stores.metadata().upsert_subtitles(&subtitles)?;
+
+    let comments = fetch_comments(video_id, video_url, paths)?;
+    stores.metadata().replace_comments(video_id, &comments)?;
+
+    Ok(record)
+}
+
+/// Fetches info JSON and upserts the video/short row only.
+fn store_video_record(
+    video_id: &str,
+    video_url: &str,
+    output_dir: &Path,
+    paths: &Paths,
+    media_kind: MediaKind,
+    metadata: &mut MetadataStore,
+) -> Result<(VideoInfo, VideoRecord)> {
+    let info = fetch_video_info(video_id, video_url, output_dir, paths)?;
+    let record = build_video_record(video_id, &info, output_dir, media_kind, paths)?;
+    upsert_record(metadata, media_kind, &record)?;
+    Ok((info, record))
+}
+
+fn upsert_record(
+    metadata: &MetadataStore,
+    media_kind: MediaKind,
+    record: &VideoRecord,
+) -> Result<()> {
+    match media_kind {
+        MediaKind::Video => metadata.upsert_video(record),
+        MediaKind::Short => metadata.upsert_short(record),
+    }
 }
 
 /// Runs `yt-dlp --dump-single-json` and caches the response alongside the
@@ -624,25 +2615,18 @@ fn fetch_video_info(
     output_dir: &Path,
     paths: &Paths,
 ) -> Result<VideoInfo> {
-    let mut command = yt_dlp_command();
-    command
-        .arg("--dump-single-json")
-        .arg("--skip-download")
-        .arg("--no-warnings")
-        .arg("--no-progress")
-        .arg(video_url);
-
-    if paths.cookies.exists() {
-        command
-            .arg("--cookies")
-            .arg(paths.cookies.to_string_lossy().to_string());
-    }
-
-    let output = command
-        .output()
+    let args = [
+        "--dump-single-json",
+        "--skip-download",
+        "--no-warnings",
+        "--no-progress",
+        video_url,
+    ]
+    .map(str::to_owned);
+    let output = run_ytdlp(&paths.ytdlp, "metadata JSON", &args, true)
         .with_context(|| format!("fetching metadata for {}", video_url))?;
 
-    if !output.status.success() {
+    if !output.success() {
         bail!(
             "metadata command failed for {} (status {})",
             video_url,
@@ -654,7 +2638,7 @@ fn fetch_video_info(
         String::from_utf8(output.stdout).context("parsing metadata JSON response as UTF-8")?;
     let info: VideoInfo = serde_json::from_str(&raw_json).context("deserializing metadata JSON")?;
 
-    let info_dir = output_dir.join(video_id);
+    let info_dir = paths.video_dir(output_dir, video_id);
     fs::create_dir_all(&info_dir)
         .with_context(|| format!("ensuring info directory {}", info_dir.display()))?;
 
@@ -688,11 +2672,13 @@ fn build_video_record(
 
     let description = info.description.clone().unwrap_or_default();
 
-    let upload_date = info
-        .upload_date
-        .as_deref()
-        .and_then(upload_date_to_iso)
-        .or_else(|| info.release_timestamp.and_then(timestamp_to_iso));
+    // Everything is stored as UTC: the exact timestamp when yt-dlp has one,
+    // otherwise midnight UTC of `upload_date` (a UTC calendar date).
+    let upload_epoch = info
+        .timestamp
+        .or_else(|| info.upload_date.as_deref().and_then(yt_dlp_date_to_epoch))
+        .or(info.release_timestamp);
+    let upload_date = upload_epoch.and_then(epoch_to_iso);
 
     let duration = info.duration;
     let duration_text = info
@@ -707,11 +2693,51 @@ fn build_video_record(
     let thumbnails = collect_thumbnails(video_id, paths, slug)?;
     let thumbnail_url = thumbnails.first().cloned();
 
-    let sources = collect_sources(video_id, info, output_dir, slug)?;
+    let video_dir = paths.video_dir(output_dir, video_id);
+    let sources = collect_sources(video_id, info, &video_dir, slug)?;
+    let audio_source = match media_kind {
+        MediaKind::Video => collect_audio_source(video_id, &video_dir, slug)?,
+        MediaKind::Short => None,
+    };
 
+    // yt-dlp sometimes omits `end_time` on the last chapter; the backend
+    // bounds it with the video duration when rendering.
+    let chapters: Vec<Chapter> = info
+        .chapters
+        .iter()
+        .flatten()
+        .filter_map(|chapter| {
+            Some(Chapter {
+                start_time: chapter.start_time?,
+                end_time: chapter.end_time,
+                title: chapter.title.clone().unwrap_or_default(),
+            })
+        })
+        .collect();
+    // `None` leaves the segments of an earlier `--sponsorblock` run alone.
+    let sponsor_segments: Option<Vec<SponsorSegment>> =
+        (paths.sponsorblock || info.sponsorblock_chapters.is_some()).then(|| {
+            info.sponsorblock_chapters
+                .iter()
+                .flatten()
+                .filter_map(|segment| {
+                    Some(SponsorSegment {
+                        start_time: segment.start_time?,
+                        end_time: segment.end_time?,
+                        category: segment.category.clone()?,
+                    })
+                })
+                .collect()
+        });
     let extras = json!({
         "channelId": info.channel_id,
         "commentCount": info.comment_count,
+        "availability": info.availability,
+        "provenance": {
+            "extractor": info.extractor,
+            "extractorKey": info.extractor_key,
+            "ytDlpVersion": info.version.as_ref().and_then(|version| version.version.clone()),
+        },
     });
 
     Ok(VideoRecord {
@@ -722,16 +2748,30 @@ fn build_video_record(
         dislikes: info.dislike_count,
         views: info.view_count,
         upload_date,
+        upload_epoch,
+        upload_date_display: None,
         author,
         subscriber_count: info.channel_follower_count,
         duration,
         duration_text,
         channel_url: info.channel_url.clone(),
         thumbnail_url,
+        poster_override: false,
         tags: info.tags.clone().unwrap_or_default(),
         thumbnails,
         extras,
         sources,
+        audio_source,
+        chapters,
+        sponsor_segments,
+        has_subtitles: false,
+        subtitle_languages: Vec::new(),
+        watched: false,
+        refreshed_at: None,
+        webpage_url: info.webpage_url.clone(),
+        original_url: info.original_url.clone(),
+        description_truncated: false,
+        local_views: None,
     })
 }
 
@@ -744,7 +2784,7 @@ fn collect_subtitles(
     media_kind: MediaKind,
 ) -> Result<SubtitleCollection> {
     let slug = media_kind_slug(media_kind);
-    let subtitles_dir = paths.subtitles.join(video_id);
+    let subtitles_dir = paths.video_dir(&paths.subtitles, video_id);
     let mut tracks = Vec::new();
     let display_names = subtitle_name_map(info);
 
@@ -776,7 +2816,7 @@ fn collect_subtitles(
             let name = display_names
                 .get(code)
                 .cloned()
-                .unwrap_or_else(|| code.to_ascii_uppercase());
+                .unwrap_or_else(|| subtitle_display_name(code));
 
             tracks.push(SubtitleTrack {
                 code: code.to_owned(),
@@ -828,6 +2868,190 @@ fn subtitle_name_map(info: &VideoInfo) -> HashMap<String, String> {
     names
 }
 
+/// Human name for a BCP-47 subtitle code such as `pt-BR` ("Portuguese
+/// (Brazil)") or `zh-Hans` ("Chinese (Simplified)"), used when yt-dlp gives
+/// no `name`. Unknown languages fall back to the uppercased code; unknown
+/// trailing subtags (e.g. yt-dlp's `en-orig`) are kept verbatim.
+fn subtitle_display_name(code: &str) -> String {
+    let mut subtags = code.split(['-', '_']);
+    let Some(language) = subtags.next().and_then(language_name) else {
+        return code.to_ascii_uppercase();
+    };
+    let qualifiers: Vec<&str> = subtags
+        .filter(|subtag| !subtag.is_empty())
+        .map(|subtag| {
+            script_name(subtag)
+                .or_else(|| region_name(subtag))
+                .unwrap_or(subtag)
+        })
+        .collect();
+    if qualifiers.is_empty() {
+        language.to_owned()
+    } else {
+        format!("{language} ({})", qualifiers.join(", "))
+    }
+}
+
+/// ISO 639-1 (plus the few ISO 639-2/3 codes YouTube uses) to English names.
+fn language_name(code: &str) -> Option<&'static str> {
+    const LANGUAGES: &[(&str, &str)] = &[
+        ("af", "Afrikaans"),
+        ("am", "Amharic"),
+        ("ar", "Arabic"),
+        ("as", "Assamese"),
+        ("az", "Azerbaijani"),
+        ("be", "Belarusian"),
+        ("bg", "Bulgarian"),
+        ("bn", "Bangla"),
+        ("bs", "Bosnian"),
+        ("ca", "Catalan"),
+        ("cs", "Czech"),
+        ("cy", "Welsh"),
+        ("da", "Danish"),
+        ("de", "German"),
+        ("el", "Greek"),
+        ("en", "English"),
+        ("eo", "Esperanto"),
+        ("es", "Spanish"),
+        ("et", "Estonian"),
+        ("eu", "Basque"),
+        ("fa", "Persian"),
+        ("fi", "Finnish"),
+        ("fil", "Filipino"),
+        ("fr", "French"),
+        ("ga", "Irish"),
+        ("gl", "Galician"),
+        ("gu", "Gujarati"),
+        ("ha", "Hausa"),
+        ("haw", "Hawaiian"),
+        ("he", "Hebrew"),
+        ("hi", "Hindi"),
+        ("hr", "Croatian"),
+        ("hu", "Hungarian"),
+        ("hy", "Armenian"),
+        ("id", "Indonesian"),
+        ("ig", "Igbo"),
+        ("is", "Icelandic"),
+        ("it", "Italian"),
+        ("iw", "Hebrew"),
+        ("ja", "Japanese"),
+        ("jv", "Javanese"),
+        ("ka", "Georgian"),
+        ("kk", "Kazakh"),
+        ("km", "Khmer"),
+        ("kn", "Kannada"),
+        ("ko", "Korean"),
+        ("ku", "Kurdish"),
+        ("ky", "Kyrgyz"),
+        ("la", "Latin"),
+        ("lb", "Luxembourgish"),
+        ("lo", "Lao"),
+        ("lt", "Lithuanian"),
+        ("lv", "Latvian"),
+        ("mk", "Macedonian"),
+        ("ml", "Malayalam"),
+        ("mn", "Mongolian"),
+        ("mr", "Marathi"),
+        ("ms", "Malay"),
+        ("mt", "Maltese"),
+        ("my", "Burmese"),
+        ("ne", "Nepali"),
+        ("nl", "Dutch"),
+        ("no", "Norwegian"),
+        ("nb", "Norwegian Bokmål"),
+        ("nn", "Norwegian Nynorsk"),
+        ("or", "Odia"),
+        ("pa", "Punjabi"),
+        ("pl", "Polish"),
+        ("ps", "Pashto"),
+        ("pt", "Portuguese"),
+        ("ro", "Romanian"),
+        ("ru", "Russian"),
+        ("rw", "Kinyarwanda"),
+        ("sd", "Sindhi"),
+        ("si", "Sinhala"),
+        ("sk", "Slovak"),
+        ("sl", "Slovenian"),
+        ("so", "Somali"),
+        ("sq", "Albanian"),
+        ("sr", "Serbian"),
+        ("su", "Sundanese"),
+        ("sv", "Swedish"),
+        ("sw", "Swahili"),
+        ("ta", "Tamil"),
+        ("te", "Telugu"),
+        ("tg", "Tajik"),
+        ("th", "Thai"),
+        ("tk", "Turkmen"),
+        ("tr", "Turkish"),
+        ("uk", "Ukrainian"),
+        ("ur", "Urdu"),
+        ("uz", "Uzbek"),
+        ("vi", "Vietnamese"),
+        ("xh", "Xhosa"),
+        ("yi", "Yiddish"),
+        ("yo", "Yoruba"),
+        ("yue", "Cantonese"),
+        ("zh", "Chinese"),
+        ("zu", "Zulu"),
+    ];
+    let code = code.to_ascii_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map(|(_, name)| *name)
+}
+
+/// ISO 15924 script subtags YouTube attaches to languages.
+fn script_name(subtag: &str) -> Option<&'static str> {
+    Some(match subtag.to_ascii_lowercase().as_str() {
+        "hans" => "Simplified",
+        "hant" => "Traditional",
+        "latn" => "Latin",
+        "cyrl" => "Cyrillic",
+        "arab" => "Arabic",
+        "deva" => "Devanagari",
+        _ => return None,
+    })
+}
+
+/// ISO 3166-1 / UN M.49 region subtags common in caption tracks.
+fn region_name(subtag: &str) -> Option<&'static str> {
+    Some(match subtag.to_ascii_uppercase().as_str() {
+        "419" => "Latin America",
+        "AR" => "Argentina",
+        "AT" => "Austria",
+        "AU" => "Australia",
+        "BE" => "Belgium",
+        "BR" => "Brazil",
+        "CA" => "Canada",
+        "CH" => "Switzerland",
+        "CN" => "China",
+        "CO" => "Colombia",
+        "DE" => "Germany",
+        "ES" => "Spain",
+        "FR" => "France",
+        "GB" => "United Kingdom",
+        "HK" => "Hong Kong",
+        "IE" => "Ireland",
+        "IN" => "India",
+        "IT" => "Italy",
+        "JP" => "Japan",
+        "KR" => "South Korea",
+        "MX" => "Mexico",
+        "NL" => "Netherlands",
+        "NZ" => "New Zealand",
+        "PT" => "Portugal",
+        "RU" => "Russia",
+        "SG" => "Singapore",
+        "TW" => "Taiwan",
+        "UA" => "Ukraine",
+        "US" => "United States",
+        "ZA" => "South Africa",
+        _ => return None,
+    })
+}
+
 /// Helper that returns the first remote subtitle entry so the frontend can
 /// still offer captions even if local downloads failed.
 fn first_remote_subtitle(info: &VideoInfo) -> Option<SubtitleTrack> {
@@ -841,7 +3065,7 @@ fn first_remote_subtitle(info: &VideoInfo) -> Option<SubtitleTrack> {
                 let name = entry
                     .name
                     .clone()
-                    .unwrap_or_else(|| code.to_ascii_uppercase());
+                    .unwrap_or_else(|| subtitle_display_name(code));
                 return Some(SubtitleTrack {
                     code: code.to_owned(),
                     name,
@@ -857,7 +3081,7 @@ fn first_remote_subtitle(info: &VideoInfo) -> Option<SubtitleTrack> {
 
 /// Returns a sorted list of thumbnail URLs served via the backend.
 fn collect_thumbnails(video_id: &str, paths: &Paths, slug: &str) -> Result<Vec<String>> {
-    let thumb_dir = paths.thumbnails.join(video_id);
+    let thumb_dir = paths.video_dir(&paths.thumbnails, video_id);
     if !thumb_dir.exists() {
         return Ok(Vec::new());
     }
@@ -877,23 +3101,37 @@ fn collect_thumbnails(video_id: &str, paths: &Paths, slug: &str) -> Result<Vec<S
         thumbs.push(file_name);
     }
 
-    thumbs.sort();
+    thumbs.sort_by(|a, b| {
+        thumbnail_rank(a)
+            .cmp(&thumbnail_rank(b))
+            .then_with(|| a.cmp(b))
+    });
     Ok(thumbs
         .into_iter()
         .map(|name| format!("/api/{slug}/{}/thumbnails/{name}", video_id))
         .collect())
 }
 
+/// Sort key putting the best thumbnail first, so `thumbnail_url` (the first
+/// entry) is the highest quality one on disk.
+fn thumbnail_rank(file_name: &str) -> usize {
+    let stem = file_name.split('.').next().unwrap_or(file_name);
+    match THUMBNAIL_QUALITY.iter().position(|name| *name == stem) {
+        Some(rank) => rank,
+        None if stem == SMALLEST_THUMBNAIL => THUMBNAIL_QUALITY.len() + 1,
+        None => THUMBNAIL_QUALITY.len(),
+    }
+}
+
 /// Builds the list of transcodings that exist on disk for a given video so the
 /// API can expose them as playable streams.
 fn collect_sources(
     video_id: &str,
     info: &VideoInfo,
-    output_dir: &Path,
+    base_dir: &Path,
     slug: &str,
 ) -> Result<Vec<VideoSource>> {
     let mut sources = Vec::new();
-    let base_dir = output_dir.join(video_id);
     if !base_dir.exists() {
         return Ok(sources);
     }
@@ -947,6 +3185,7 @@ fn collect_sources(
                 file_size,
                 url: format!("/api/{slug}/{}/streams/{}", video_id, sanitized),
                 path: Some(path.to_string_lossy().into_owned()),
+                stream_key: Some(sanitized),
             });
         }
     }
@@ -954,101 +3193,313 @@ fn collect_sources(
     Ok(sources)
 }
 
+/// Finds the `--keep-audio` file (`<id>_audio.<ext>`) in `base_dir`. It is
+/// kept out of [`collect_sources`], whose list only holds muxed files.
+fn collect_audio_source(
+    video_id: &str,
+    base_dir: &Path,
+    slug: &str,
+) -> Result<Option<VideoSource>> {
+    if !base_dir.exists() {
+        return Ok(None);
+    }
+
+    let stem = format!("{video_id}_{AUDIO_STREAM_KEY}");
+    for entry in
+        fs::read_dir(base_dir).with_context(|| format!("reading {}", base_dir.display()))?
+    {
+        let path = entry?.path();
+        if path.file_stem().and_then(|name| name.to_str()) != Some(stem.as_str()) {
+            continue;
+        }
+        let Some(ext) = path.extension().and_then(|ext| ext.to_str()) else {
+            continue;
+        };
+        // `.part` and `.ytdl` leftovers carry a second extension and never
+        // match the stem; an empty file is a failed download.
+        let file_size = fs::metadata(&path)?.len();
+        if file_size == 0 {
+            continue;
+        }
+
+        return Ok(Some(VideoSource {
+            format_id: AUDIO_STREAM_KEY.to_owned(),
+            quality_label: Some("Audio only".to_owned()),
+            width: None,
+            height: None,
+            fps: None,
+            mime_type: Some(audio_mime_from_extension(ext)),
+            ext: Some(ext.to_owned()),
+            file_size: i64::try_from(file_size).ok(),
+            url: format!("/api/{slug}/{video_id}/{AUDIO_STREAM_KEY}"),
+            path: Some(path.to_string_lossy().into_owned()),
+            stream_key: Some(AUDIO_STREAM_KEY.to_owned()),
+        }));
+    }
+
+    Ok(None)
+}
+
 /// Downloads every available comment via yt-dlp, writes them to disk, and then
 /// normalizes into `CommentRecord` rows while removing duplicates.
 fn fetch_comments(video_id: &str, video_url: &str, paths: &Paths) -> Result<Vec<CommentRecord>> {
-    let comments_dir = paths.comments.join(video_id);
+    let comments_dir = paths.video_dir(&paths.comments, video_id);
     fs::create_dir_all(&comments_dir)
         .with_context(|| format!("creating comments dir {}", comments_dir.display()))?;
 
     let output_pattern = comments_dir.join(video_id);
-    let mut command = yt_dlp_command();
-    command
-        .arg("--skip-download")
-        .arg("--write-comments")
-        .arg("--no-warnings")
-        .arg("--no-progress")
-        .arg("--force-overwrites")
-        .arg("--output")
-        .arg(output_pattern.to_string_lossy().to_string())
-        .arg(video_url);
-
-    if paths.cookies.exists() {
-        command
-            .arg("--cookies")
-            .arg(paths.cookies.to_string_lossy().to_string());
-    }
-
-    match command.status() {
-        Ok(status) if status.success() => {}
-        Ok(status) => {
-            eprintln!(
-                "  Warning: comment extraction failed for {} (status {})",
-                video_id, status
+    let args = [
+        "--skip-download".to_owned(),
+        "--write-comments".to_owned(),
+        "--no-warnings".to_owned(),
+        "--no-progress".to_owned(),
+        "--force-overwrites".to_owned(),
+        "--output".to_owned(),
+        output_pattern.to_string_lossy().to_string(),
+        video_url.to_owned(),
+    ];
+
+    match run_ytdlp(&paths.ytdlp, "comments", &args, false) {
+        Ok(output) if output.success() => {}
+        Ok(output) => {
+            warn!(
+                "Comment extraction failed for {} (status {})",
+                video_id, output.status
             );
         }
         Err(err) => {
-            eprintln!(
-                "  Warning: unable to execute comment extraction for {}: {}",
+            warn!(
+                "Unable to execute comment extraction for {}: {}",
                 video_id, err
             );
         }
     }
 
-    let comments_path = comments_dir.join(format!("{}.comments.json", video_id));
+    read_comments_file(
+        video_id,
+        &comments_dir.join(format!("{}.comments.json", video_id)),
+    )
+}
+
+/// Parses a `<id>.comments.json` written by yt-dlp. A missing file means no
+/// comments. Entries are deserialized one at a time and converted straight
+/// into `CommentRecord`s, so the file's JSON tree is never held in memory;
+/// the returned records themselves still grow with the comment count.
+fn read_comments_file(video_id: &str, comments_path: &Path) -> Result<Vec<CommentRecord>> {
     if !comments_path.exists() {
         return Ok(Vec::new());
     }
 
-    let file = File::open(&comments_path)
+    let file = File::open(comments_path)
         .with_context(|| format!("opening {}", comments_path.display()))?;
-    let reader = BufReader::new(file);
-    let json_value: Value = serde_json::from_reader(reader)
-        .with_context(|| format!("parsing {}", comments_path.display()))?;
-
-    let comments_array = match json_value {
-        Value::Array(arr) => arr,
-        Value::Object(mut map) => match map.remove("comments") {
-            Some(Value::Array(arr)) => arr,
-            Some(other) => serde_json::from_value::<Vec<Value>>(other).unwrap_or_default(),
-            None => Vec::new(),
-        },
-        _ => Vec::new(),
+    let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(file));
+    let mut collector = CommentCollector {
+        video_id,
+        comments: Vec::new(),
+        seen_ids: HashSet::new(),
     };
+    CommentsSeed {
+        collector: &mut collector,
+        top_level: true,
+    }
+    .deserialize(&mut deserializer)
+    .and_then(|()| deserializer.end())
+    .with_context(|| format!("parsing {}", comments_path.display()))?;
+
+    Ok(collector.comments)
+}
+
+/// Turns yt-dlp comment entries into `CommentRecord`s as they are read,
+/// dropping ids that were already seen.
+struct CommentCollector<'a> {
+    video_id: &'a str,
+    comments: Vec<CommentRecord>,
+    seen_ids: HashSet<String>,
+}
 
-    let mut comments = Vec::new();
-    let mut seen_ids = HashSet::new();
-    for value in comments_array {
+impl CommentCollector<'_> {
+    fn push(&mut self, value: Value) {
         match serde_json::from_value::<RawComment>(value) {
             Ok(raw) => {
-                if !seen_ids.insert(raw.id.clone()) {
-                    continue;
+                if !self.seen_ids.insert(raw.id.clone()) {
+                    return;
                 }
-                let time_posted = raw
+                // Fall back to yt-dlp's relative text, then to "now" so every
+                // comment sorts somewhere sensible.
+                let time_posted_epoch = raw
                     .timestamp
-                    .and_then(timestamp_to_iso)
-                    .or_else(|| raw.time_text.clone())
-                    .or_else(|| Some(Utc::now().to_rfc3339()));
+                    .or_else(|| raw.time_text.is_none().then(|| Utc::now().timestamp()));
+                let time_posted = time_posted_epoch
+                    .and_then(epoch_to_iso)
+                    .or_else(|| raw.time_text.clone());
 
-                comments.push(CommentRecord {
+                self.comments.push(CommentRecord {
                     id: raw.id,
-                    videoid: video_id.to_owned(),
+                    videoid: self.video_id.to_owned(),
                     author: raw.author.unwrap_or_default(),
                     text: raw.text.unwrap_or_default(),
                     likes: raw.like_count,
                     time_posted,
+                    time_posted_epoch,
+                    time_posted_display: None,
                     parent_comment_id: raw.parent,
                     status_likedbycreator: raw.author_is_channel_owner || raw.author_is_uploader,
                     reply_count: raw.reply_count,
                 });
             }
             Err(err) => {
-                eprintln!("  Warning: could not parse comment entry: {}", err);
+                warn!("Could not parse comment entry: {}", err);
+            }
+        }
+    }
+}
+
+/// Feeds a comments document into a [`CommentCollector`]: either a bare
+/// array or, at the top level, an object whose `comments` key holds one.
+/// Any other shape holds no comments.
+struct CommentsSeed<'c, 'a> {
+    collector: &'c mut CommentCollector<'a>,
+    top_level: bool,
+}
+
+impl<'de> DeserializeSeed<'de> for CommentsSeed<'_, '_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for CommentsSeed<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a comment array or an object with a `comments` array")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(value) = seq.next_element::<Value>()? {
+            self.collector.push(value);
+        }
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            if self.top_level && key == "comments" {
+                map.next_value_seed(CommentsSeed {
+                    collector: &mut *self.collector,
+                    top_level: false,
+                })?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<(), E> {
+        Ok(())
+    }
+}
+
+/// `--reindex`: rebuilds the rows for every video and Short already on disk
+/// from its `<id>.info.json`, local subtitles and saved comments, without
+/// calling yt-dlp. Lets a library downloaded by hand be adopted as-is; ids
+/// missing from the archive are added so later runs do not re-download them.
+fn reindex_library(paths: &Paths, metadata: &mut MetadataStore) -> Result<usize> {
+    let mut archive = load_archive(&paths.archive)?;
+    let mut indexed = 0;
+
+    for media_kind in [MediaKind::Video, MediaKind::Short] {
+        let output_dir = paths.media_dir(media_kind);
+        let mut entries = fs::read_dir(output_dir)
+            .with_context(|| format!("reading {}", output_dir.display()))?
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            // Skips `.staging` and anything else hidden.
+            if entry.file_name().to_string_lossy().starts_with('.') || !entry.path().is_dir() {
+                continue;
+            }
+            let Some(info_path) = find_info_json(&entry.path())? else {
+                continue;
+            };
+            match reindex_entry(&info_path, output_dir, paths, media_kind, metadata) {
+                Ok(video_id) => {
+                    info!(video_id = %video_id, "Indexed {}", video_id);
+                    if archive.insert(video_id.clone()) {
+                        append_to_archive(&paths.archive, &video_id)?;
+                    }
+                    indexed += 1;
+                }
+                Err(err) => {
+                    warn!("Skipping {}: {:#}", info_path.display(), err);
+                }
             }
         }
     }
 
-    Ok(comments)
+    Ok(indexed)
+}
+
+fn find_info_json(dir: &Path) -> Result<Option<PathBuf>> {
+    for entry in fs::read_dir(dir).with_context(|| format!("reading {}", dir.display()))? {
+        let path = entry?.path();
+        if path.to_string_lossy().ends_with(".info.json") {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+fn reindex_entry(
+    info_path: &Path,
+    output_dir: &Path,
+    paths: &Paths,
+    media_kind: MediaKind,
+    metadata: &mut MetadataStore,
+) -> Result<String> {
+    let file = File::open(info_path).with_context(|| format!("opening {}", info_path.display()))?;
+    let info: VideoInfo = serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("parsing {}", info_path.display()))?;
+    let video_id = info.id.clone();
+
+    let record = build_video_record(&video_id, &info, output_dir, media_kind, paths)?;
+    match media_kind {
+        MediaKind::Video => metadata.upsert_video(&record)?,
+        MediaKind::Short => metadata.upsert_short(&record)?,
+    }
+    metadata.upsert_subtitles(&collect_subtitles(&video_id, &info, paths, media_kind)?)?;
+
+    let comments_path = paths
+        .video_dir(&paths.comments, &video_id)
+        .join(format!("{}.comments.json", video_id));
+    let comments = read_comments_file(&video_id, &comments_path)?;
+    metadata.replace_comments(&video_id, &comments)?;
+
+    Ok(video_id)
 }
 
 /// Creates a human-friendly label such as `1080p HDR` when the metadata is
@@ -1080,6 +3531,16 @@ fn mime_from_extension(ext: &str) -> String {
     }
 }
 
+fn audio_mime_from_extension(ext: &str) -> String {
+    match ext {
+        "m4a" | "mp4" => "audio/mp4".to_owned(),
+        "webm" => "audio/webm".to_owned(),
+        "opus" | "ogg" => "audio/ogg".to_owned(),
+        "mp3" => "audio/mpeg".to_owned(),
+        other => format!("audio/{other}"),
+    }
+}
+
 /// Maps the enum to the slug portion used in API URLs and folder names.
 fn media_kind_slug(kind: MediaKind) -> &'static str {
     match kind {
@@ -1088,24 +3549,6 @@ fn media_kind_slug(kind: MediaKind) -> &'static str {
     }
 }
 
-/// Converts yt-dlp's `YYYYMMDD` upload date format into ISO-8601.
-fn upload_date_to_iso(value: &str) -> Option<String> {
-    if value.len() != 8 {
-        return None;
-    }
-    let year = &value[0..4];
-    let month = &value[4..6];
-    let day = &value[6..8];
-    let naive = NaiveDate::from_ymd_opt(year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
-    let naive = naive?.and_hms_opt(0, 0, 0)?;
-    Some(format!("{}Z", naive.format("%Y-%m-%dT%H:%M:%S")))
-}
-
-/// Converts epoch seconds into an ISO-8601 string.
-fn timestamp_to_iso(timestamp: i64) -> Option<String> {
-    chrono::DateTime::<Utc>::from_timestamp(timestamp, 0).map(|datetime| datetime.to_rfc3339())
-}
-
 /// Renders durations as `H:MM:SS` or `M:SS` for short clips.
 fn format_duration(duration: i64) -> String {
     let hours = duration / 3600;
@@ -1121,24 +3564,12 @@ fn format_duration(duration: i64) -> String {
 
 /// Lists all video IDs in a playlist/channel, optionally applying a yt-dlp
 /// `--match-filter` (used to split Shorts vs. regular uploads).
-fn get_video_ids(list_url: &str, filter: Option<&str>) -> Result<Vec<String>> {
-    let mut command = yt_dlp_command();
-    command
-        .arg("--flat-playlist")
-        .arg("--get-id")
-        .arg("--ignore-errors");
-
-    if let Some(filter) = filter {
-        command.arg("--match-filter").arg(filter);
-    }
-
-    command.arg(list_url);
-
-    let output = command
-        .output()
+fn get_video_ids(listing: &PlaylistListing<'_>, ytdlp: &YtDlpSettings) -> Result<Vec<String>> {
+    let list_url = listing.url.as_str();
+    let output = run_ytdlp(ytdlp, "listing", &listing_args(listing), true)
         .with_context(|| format!("retrieving playlist from {}", list_url))?;
 
-    if !output.status.success() {
+    if !output.success() {
         bail!(
             "failed to list videos for {} (status: {})",
             list_url,
@@ -1157,101 +3588,331 @@ fn get_video_ids(list_url: &str, filter: Option<&str>) -> Result<Vec<String>> {
     Ok(ids)
 }
 
-/// Downloads every available muxed format for the provided video id, skipping
-/// streams we already grabbed.
-fn download_video_all_formats(video_id: &str, output_dir: &Path, paths: &Paths) -> Result<()> {
-    let video_url = format!("https://www.youtube.com/watch?v={}", video_id);
-    let video_dir = output_dir.join(video_id);
-    fs::create_dir_all(&video_dir).with_context(|| format!("creating {}", video_dir.display()))?;
-
-    let base_output = video_dir.join(video_id);
-    let base_output_pattern = base_output.to_string_lossy().to_string();
-    let info_json_path = base_output.with_extension("info.json");
+fn listing_args(listing: &PlaylistListing<'_>) -> Vec<String> {
+    let mut args = vec![
+        "--flat-playlist".to_owned(),
+        "--get-id".to_owned(),
+        "--ignore-errors".to_owned(),
+    ];
 
-    println!("Processing video: {}", video_id);
+    if let Some(filter) = &listing.filter {
+        args.extend(["--match-filter".to_owned(), filter.clone()]);
+    }
 
-    run_metadata_command(&video_url, &base_output_pattern, &paths.cookies);
-    run_subtitle_command(video_id, &video_url, &paths.subtitles, &paths.cookies);
-    run_thumbnail_command(video_id, &video_url, &paths.thumbnails, &paths.cookies);
+    if let Some(date_after) = listing.date_after {
+        args.extend(["--dateafter".to_owned(), date_after.to_owned()]);
+    }
 
-    let formats = collect_format_ids(&info_json_path, &video_url)?;
+    args.push(listing.url.clone());
+    args
+}
 
-    if formats.is_empty() {
-        println!("  No downloadable formats found for {}", video_id);
-        return Ok(());
+/// ANDs a user `--match-filter` onto a built-in one. yt-dlp ORs separate
+/// `--match-filter` flags, so both must go into a single expression.
+fn combine_match_filters(builtin: &str, extra: Option<&str>) -> String {
+    match extra {
+        Some(extra) => format!("{builtin} & {extra}"),
+        None => builtin.to_owned(),
     }
+}
 
-    for format_id in formats {
-        let safe_format_id = sanitize_format_id(&format_id);
-        let mut output_path = video_dir.join(format!("{}_{}", video_id, safe_format_id));
-        output_path.set_extension("%(ext)s");
-
-        println!("  Downloading format: {}", format_id);
-
-        let mut command = yt_dlp_command();
-        command
-            .arg("--format")
-            .arg(&format_id)
-            .arg("--output")
-            .arg(output_path.to_string_lossy().to_string())
-            .arg("--no-embed-metadata")
-            .arg("--no-embed-subs")
-            .arg("--no-embed-thumbnail")
-            .arg("--no-overwrites")
-            .arg("--continue")
-            .arg("--ignore-errors")
-            .arg("--no-warnings")
-            .arg(&video_url);
-
-        if paths.cookies.exists() {
-            command
-                .arg("--cookies")
-                .arg(paths.cookies.to_string_lossy().to_string());
-        }
-
-        match command.status() {
-            Ok(status) if status.success() => {}
-            Ok(_) => {
-                eprintln!("    Failed to download format {}", format_id);
+/// Rejects `--match-filter` values that could only be a mistake or an attempt
+/// to smuggle something past yt-dlp: shell metacharacters, control characters
+/// and a leading `-` that would read as another option. yt-dlp's own operators
+/// (`&`, `!`, `<`, `>`, `=`, `*=`, `^=`, `$=`, `~=`, `?`) are allowed.
+fn validate_match_filter(value: &str) -> Result<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        bail!("--match-filter must not be empty");
+    }
+    if value.starts_with('-') {
+        bail!("--match-filter must not start with '-': {value:?}");
+    }
+    if let Some(bad) = value
+        .chars()
+        .find(|ch| ch.is_control() || matches!(ch, ';' | '|' | '`' | '\\'))
+    {
+        bail!("--match-filter contains a disallowed character {bad:?}: {value:?}");
+    }
+    if value.contains("$(") {
+        bail!("--match-filter must not contain command substitution: {value:?}");
+    }
+    Ok(value.to_owned())
+}
+
+/// Turns the newest stored `upload_date` (ISO 8601) into the `YYYYMMDD` value
+/// handed to yt-dlp's `--dateafter`, minus a safety margin.
+fn incremental_date_after(last_upload: &str) -> Option<String> {
+    let date = NaiveDate::parse_from_str(last_upload.get(..10)?, "%Y-%m-%d").ok()?;
+    let start = date.checked_sub_days(Days::new(INCREMENTAL_MARGIN_DAYS))?;
+    Some(start.format("%Y%m%d").to_string())
+}
+
+/// `YYYYMMDD` for `--since <days>`: `days` before `today`, which yt-dlp's
+/// `--dateafter` treats as inclusive.
+fn since_date_after(today: NaiveDate, days: u32) -> Option<String> {
+    let start = today.checked_sub_days(Days::new(u64::from(days)))?;
+    Some(start.format("%Y%m%d").to_string())
+}
+
+/// Downloads every available muxed format for the provided video id, skipping
+/// streams we already grabbed. Returns the worst per-format outcome; a
+/// [`FatalYtDlpError`] is returned as an error so the run can abort.
+fn download_video_all_formats(
+    video_id: &str,
+    output_dir: &Path,
+    paths: &Paths,
+    media_kind: MediaKind,
+) -> Result<DownloadOutcome> {
+    let video_url = format!("https://www.youtube.com/watch?v={}", video_id);
+    let published = paths.video_dir(output_dir, video_id);
+    // Everything is written under `.staging` and only renamed into the served
+    // tree once every format succeeded. A failed or interrupted run leaves
+    // the staging directory behind so the next attempt resumes from it.
+    let video_dir = output_dir
+        .join(layout::STAGING_DIR)
+        .join(layout::video_dir_name(video_id, paths.case_insensitive));
+    fs::create_dir_all(&video_dir).with_context(|| format!("creating {}", video_dir.display()))?;
+
+    // The backend only serves audio tracks of long-form videos.
+    let keep_audio = paths.keep_audio && media_kind == MediaKind::Video;
+    let outcome = download_into_staging(video_id, &video_url, &video_dir, paths, keep_audio)?;
+    if outcome.exit == YtDlpExit::Success {
+        publish_staged(&video_dir, &published)?;
+        info!("Completed: {}", video_id);
+    }
+    Ok(outcome)
+}
+
+/// How [`download_video_all_formats`] ended, plus the formats that failed
+/// (formats skipped after a cancellation are not listed).
+#[derive(Debug)]
+struct DownloadOutcome {
+    exit: YtDlpExit,
+    failed: Vec<FailedFormat>,
+}
+
+/// Fetches metadata and every format of `video_id` into `video_dir`, plus
+/// the audio-only track when `keep_audio` is set.
+fn download_into_staging(
+    video_id: &str,
+    video_url: &str,
+    video_dir: &Path,
+    paths: &Paths,
+    keep_audio: bool,
+) -> Result<DownloadOutcome> {
+    let base_output = video_dir.join(video_id);
+    let base_output_pattern = base_output.to_string_lossy().to_string();
+    let info_json_path = base_output.with_extension("info.json");
+
+    info!("Processing video: {}", video_id);
+
+    run_metadata_command(
+        video_url,
+        &base_output_pattern,
+        paths.sponsorblock,
+        &paths.ytdlp,
+    );
+    run_subtitle_command(
+        video_id,
+        video_url,
+        &paths.video_dir(&paths.subtitles, video_id),
+        &paths.sub_langs,
+        paths.auto_subs,
+        &paths.ytdlp,
+    );
+    run_thumbnail_command(
+        video_id,
+        video_url,
+        &paths.video_dir(&paths.thumbnails, video_id),
+        &paths.ytdlp,
+    );
+
+    let formats = if paths.formats.is_empty() {
+        collect_format_ids(&info_json_path, video_url, paths.max_height, &paths.ytdlp)?
+    } else {
+        available_requested_formats(video_id, &info_json_path, &paths.formats)
+    };
+
+    let mut outcome = DownloadOutcome {
+        exit: YtDlpExit::Success,
+        failed: Vec::new(),
+    };
+    if formats.is_empty() {
+        warn!("No downloadable formats found for {}", video_id);
+        return Ok(outcome);
+    }
+
+    for format_id in formats {
+        let (exit, status) = download_format(video_id, video_url, video_dir, &format_id, paths)?;
+        match exit {
+            // Fatal exits come back as errors.
+            YtDlpExit::Success | YtDlpExit::Fatal => {}
+            YtDlpExit::Cancelled => {
+                warn!("Download of format {} was cancelled", format_id);
+                outcome.exit = outcome.exit.worst(YtDlpExit::Cancelled);
+                break;
             }
-            Err(err) => {
-                eprintln!("    Failed to download format {}: {}", format_id, err);
+            YtDlpExit::VideoFailed => {
+                warn!("Failed to download format {} ({})", format_id, status);
+                outcome.exit = outcome.exit.worst(YtDlpExit::VideoFailed);
+                outcome.failed.push(FailedFormat {
+                    format_id,
+                    reason: status.to_string(),
+                });
             }
         }
     }
 
-    println!("  Completed: {}", video_id);
+    if keep_audio && outcome.exit != YtDlpExit::Cancelled {
+        debug!("Downloading audio-only track");
+        let (exit, status) = download_selector(
+            video_id,
+            video_url,
+            video_dir,
+            AUDIO_FORMAT_SELECTOR,
+            AUDIO_STREAM_KEY,
+            paths,
+        )?;
+        match exit {
+            YtDlpExit::Success | YtDlpExit::Fatal => {}
+            YtDlpExit::Cancelled => {
+                warn!("Download of the audio track was cancelled");
+                outcome.exit = outcome.exit.worst(YtDlpExit::Cancelled);
+            }
+            // Only the optional audio track is lost; the video still
+            // publishes.
+            YtDlpExit::VideoFailed => {
+                warn!("Could not download the audio track ({status})");
+            }
+        }
+    }
 
-    Ok(())
+    Ok(outcome)
+}
+
+/// Downloads one format of `video_id` into `video_dir`, resuming a partial
+/// file. Fails with [`FatalYtDlpError`] when yt-dlp rejects the invocation.
+fn download_format(
+    video_id: &str,
+    video_url: &str,
+    video_dir: &Path,
+    format_id: &str,
+    paths: &Paths,
+) -> Result<(YtDlpExit, YtDlpStatus)> {
+    debug!(format = %format_id, "Downloading format: {}", format_id);
+    download_selector(
+        video_id,
+        video_url,
+        video_dir,
+        format_id,
+        &sanitize_format_id(format_id),
+        paths,
+    )
+}
+
+/// Runs yt-dlp's `--format <selector>` into `<video_id>_<file_key>.<ext>`.
+fn download_selector(
+    video_id: &str,
+    video_url: &str,
+    video_dir: &Path,
+    selector: &str,
+    file_key: &str,
+    paths: &Paths,
+) -> Result<(YtDlpExit, YtDlpStatus)> {
+    let mut output_path = video_dir.join(format!("{}_{}", video_id, file_key));
+    output_path.set_extension("%(ext)s");
+
+    let args = [
+        "--format".to_owned(),
+        selector.to_owned(),
+        "--output".to_owned(),
+        output_path.to_string_lossy().to_string(),
+        "--no-embed-metadata".to_owned(),
+        "--no-embed-subs".to_owned(),
+        "--no-embed-thumbnail".to_owned(),
+        "--no-overwrites".to_owned(),
+        "--continue".to_owned(),
+        "--ignore-errors".to_owned(),
+        "--no-warnings".to_owned(),
+        video_url.to_owned(),
+    ];
+
+    let output =
+        run_ytdlp(&paths.ytdlp, "format", &args, false).map_err(|err| FatalYtDlpError {
+            detail: format!("could not start yt-dlp: {err}"),
+        })?;
+    let status = output.status;
+    let exit = output.exit();
+    if exit == YtDlpExit::Fatal {
+        return Err(FatalYtDlpError {
+            detail: format!("format {selector}: {status}"),
+        }
+        .into());
+    }
+    Ok((exit, status))
+}
+
+/// Moves a complete staging directory into the served tree. A first download
+/// is a single directory `rename`; a retry that filled in missing formats
+/// renames each file over its published counterpart instead. Either way every
+/// file appears in one step, never half-written.
+fn publish_staged(staging: &Path, published: &Path) -> Result<()> {
+    if !published.exists() {
+        return fs::rename(staging, published).with_context(|| {
+            format!(
+                "publishing {} to {}",
+                staging.display(),
+                published.display()
+            )
+        });
+    }
+
+    for entry in fs::read_dir(staging).with_context(|| format!("reading {}", staging.display()))? {
+        let entry = entry?;
+        let target = published.join(entry.file_name());
+        fs::rename(entry.path(), &target)
+            .with_context(|| format!("publishing {}", target.display()))?;
+    }
+    fs::remove_dir(staging).with_context(|| format!("removing {}", staging.display()))
 }
 
 /// Wrapper for the metadata/description/thumbnail yt-dlp call.
-fn run_metadata_command(video_url: &str, output_pattern: &str, cookies: &Path) {
-    let mut command = yt_dlp_command();
-    command
-        .arg("--write-info-json")
-        .arg("--write-description")
-        .arg("--write-thumbnail")
-        .arg("--skip-download")
-        .arg("--output")
-        .arg(output_pattern)
-        .arg(video_url);
-
-    if cookies.exists() {
-        command
-            .arg("--cookies")
-            .arg(cookies.to_string_lossy().to_string());
-    }
-
-    run_silent(command, "metadata");
-}
-
-/// Downloads subtitles (manual+auto) into a per-video directory.
-fn run_subtitle_command(video_id: &str, video_url: &str, subtitles_dir: &Path, cookies: &Path) {
-    let target_dir = subtitles_dir.join(video_id);
-    if let Err(err) = fs::create_dir_all(&target_dir) {
-        eprintln!(
-            "  Warning: could not create subtitles directory {}: {}",
+fn run_metadata_command(
+    video_url: &str,
+    output_pattern: &str,
+    sponsorblock: bool,
+    ytdlp: &YtDlpSettings,
+) {
+    let mut args = vec![
+        "--write-info-json",
+        "--write-description",
+        "--write-thumbnail",
+        "--skip-download",
+    ];
+    // Fills `sponsorblock_chapters` in the info JSON.
+    if sponsorblock {
+        args.extend(["--sponsorblock-mark", "all"]);
+    }
+    args.extend(["--output", output_pattern, video_url]);
+    let args: Vec<String> = args.into_iter().map(str::to_owned).collect();
+
+    run_silent(ytdlp, &args, "metadata");
+}
+
+/// Downloads the `sub_langs` subtitles (uploaded ones, plus automatic
+/// captions when `auto_subs` is set) into a per-video directory.
+fn run_subtitle_command(
+    video_id: &str,
+    video_url: &str,
+    target_dir: &Path,
+    sub_langs: &str,
+    auto_subs: bool,
+    ytdlp: &YtDlpSettings,
+) {
+    if let Err(err) = fs::create_dir_all(target_dir) {
+        warn!(
+            "Could not create subtitles directory {}: {}",
             target_dir.display(),
             err
         );
@@ -1260,32 +3921,32 @@ fn run_subtitle_command(video_id: &str, video_url: &str, subtitles_dir: &Path, c
 
     let output_pattern = target_dir.join(video_id).to_string_lossy().to_string();
 
-    let mut command = yt_dlp_command();
-    command
-        .arg("--write-sub")
-        .arg("--write-auto-sub")
-        .arg("--sub-langs")
-        .arg("all")
-        .arg("--skip-download")
-        .arg("--output")
-        .arg(output_pattern)
-        .arg(video_url);
-
-    if cookies.exists() {
-        command
-            .arg("--cookies")
-            .arg(cookies.to_string_lossy().to_string());
+    let mut args = vec!["--write-sub".to_owned()];
+    if auto_subs {
+        args.push("--write-auto-sub".to_owned());
     }
-
-    run_silent(command, "subtitles");
+    args.extend([
+        "--sub-langs".to_owned(),
+        sub_langs.to_owned(),
+        "--skip-download".to_owned(),
+        "--output".to_owned(),
+        output_pattern,
+        video_url.to_owned(),
+    ]);
+
+    run_silent(ytdlp, &args, "subtitles");
 }
 
 /// Ensures we have the highest quality thumbnails for offline use.
-fn run_thumbnail_command(video_id: &str, video_url: &str, thumbnails_dir: &Path, cookies: &Path) {
-    let target_dir = thumbnails_dir.join(video_id);
-    if let Err(err) = fs::create_dir_all(&target_dir) {
-        eprintln!(
-            "  Warning: could not create thumbnails directory {}: {}",
+fn run_thumbnail_command(
+    video_id: &str,
+    video_url: &str,
+    target_dir: &Path,
+    ytdlp: &YtDlpSettings,
+) {
+    if let Err(err) = fs::create_dir_all(target_dir) {
+        warn!(
+            "Could not create thumbnails directory {}: {}",
             target_dir.display(),
             err
         );
@@ -1294,39 +3955,39 @@ fn run_thumbnail_command(video_id: &str, video_url: &str, thumbnails_dir: &Path,
 
     let output_pattern = target_dir.join(video_id).to_string_lossy().to_string();
 
-    let mut command = yt_dlp_command();
-    command
-        .arg("--write-thumbnail")
-        .arg("--skip-download")
-        .arg("--output")
-        .arg(output_pattern)
-        .arg(video_url);
-
-    if cookies.exists() {
-        command
-            .arg("--cookies")
-            .arg(cookies.to_string_lossy().to_string());
-    }
+    let args = [
+        "--write-thumbnail".to_owned(),
+        "--skip-download".to_owned(),
+        "--output".to_owned(),
+        output_pattern,
+        video_url.to_owned(),
+    ];
 
-    run_silent(command, "thumbnails");
+    run_silent(ytdlp, &args, "thumbnails");
 }
 
-/// Executes a command and only logs warnings, keeping stdout noise minimal.
-fn run_silent(mut command: Command, label: &str) {
-    match command.status() {
-        Ok(status) if status.success() => {}
-        Ok(status) => {
-            eprintln!("  Warning: {} command exited with status {}", label, status);
+/// Runs an auxiliary yt-dlp call whose failure only costs that artifact:
+/// success is logged at `debug`, failures at `warn`.
+fn run_silent(ytdlp: &YtDlpSettings, args: &[String], label: &str) {
+    match run_ytdlp(ytdlp, label, args, false) {
+        Ok(output) if output.success() => debug!("{} command finished", label),
+        Ok(output) => {
+            warn!("{} command exited with status {}", label, output.status);
         }
         Err(err) => {
-            eprintln!("  Warning: {} command failed: {}", label, err);
+            warn!("{} command failed: {}", label, err);
         }
     }
 }
 
 /// Reads format IDs from the downloaded `.info.json`. If the file is missing or
 /// incomplete we fall back to invoking `yt-dlp -F`.
-fn collect_format_ids(info_json_path: &Path, video_url: &str) -> Result<Vec<String>> {
+fn collect_format_ids(
+    info_json_path: &Path,
+    video_url: &str,
+    max_height: Option<u32>,
+    ytdlp: &YtDlpSettings,
+) -> Result<Vec<String>> {
     let mut formats = BTreeSet::new();
 
     if info_json_path.exists()
@@ -1334,66 +3995,199 @@ fn collect_format_ids(info_json_path: &Path, video_url: &str) -> Result<Vec<Stri
     {
         let reader = BufReader::new(file);
         match serde_json::from_reader::<_, InfoJson>(reader) {
-            Ok(info) => {
-                for entry in info.formats {
-                    if let Some(id) = entry.format_id {
-                        let trimmed = id.trim();
-                        if !trimmed.is_empty() {
-                            formats.insert(trimmed.to_owned());
-                        }
-                    }
-                }
-            }
+            Ok(info) => match max_height {
+                Some(max_height) => formats.extend(cap_formats(&info.formats, max_height)),
+                None => formats.extend(
+                    info.formats
+                        .iter()
+                        .filter_map(FormatEntry::id)
+                        .map(str::to_owned),
+                ),
+            },
             Err(err) => {
-                eprintln!(
-                    "  Warning: could not parse {}: {}",
-                    info_json_path.display(),
-                    err
-                );
+                warn!("Could not parse {}: {}", info_json_path.display(), err);
             }
         }
     }
 
     if formats.is_empty() {
-        println!("  Could not read formats from metadata, falling back to format listing");
-        let output = yt_dlp_command()
-            .arg("-F")
-            .arg(video_url)
-            .output()
+        if max_height.is_some() {
+            warn!("Heights are unknown without metadata; --max-height is not applied");
+        }
+        info!("Could not read formats from metadata, asking yt-dlp for format ids");
+        formats.extend(print_format_ids(video_url, ytdlp)?);
+    }
+
+    if formats.is_empty() {
+        info!("Could not print format ids, falling back to format listing");
+        let args = ["-F".to_owned(), video_url.to_owned()];
+        let output = run_ytdlp(ytdlp, "format listing", &args, true)
             .with_context(|| format!("listing formats for {}", video_url))?;
 
-        if !output.status.success() {
-            eprintln!(
-                "  Warning: format listing failed for {} (status: {})",
+        if !output.success() {
+            warn!(
+                "Format listing failed for {} (status: {})",
                 video_url, output.status
             );
         } else {
-            // Parse the human-readable yt-dlp table by grabbing the first token
-            // on each non-empty line (skipping header rows like `format code`).
             let listing = String::from_utf8_lossy(&output.stdout);
-            for line in listing.lines() {
-                let trimmed = line.trim();
-                if trimmed.is_empty() {
-                    continue;
-                }
+            formats.extend(parse_format_listing(&listing));
+        }
+    }
 
-                if let Some(first) = trimmed.split_whitespace().next() {
-                    if first.eq_ignore_ascii_case("format") || first.eq_ignore_ascii_case("code") {
-                        continue;
-                    }
-                    if first
-                        .chars()
-                        .next()
-                        .is_some_and(|c| c.is_ascii_alphanumeric())
-                    {
-                        formats.insert(first.to_owned());
-                    }
-                }
+    Ok(formats.into_iter().collect())
+}
+
+/// The `--formats` ids that `<id>.info.json` lists; a merged id such as
+/// `137+140` needs every part. Missing ids are skipped with a warning. When
+/// the metadata cannot be read, every requested id is attempted.
+fn available_requested_formats(
+    video_id: &str,
+    info_json_path: &Path,
+    requested: &[String],
+) -> Vec<String> {
+    let info = File::open(info_json_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|file| {
+            Ok(serde_json::from_reader::<_, InfoJson>(BufReader::new(
+                file,
+            ))?)
+        });
+    let available: HashSet<&str> = match &info {
+        Ok(info) => info.formats.iter().filter_map(FormatEntry::id).collect(),
+        Err(err) => {
+            warn!(
+                "Could not read formats from {} ({err}); trying every requested format",
+                info_json_path.display()
+            );
+            return requested.to_vec();
+        }
+    };
+    requested
+        .iter()
+        .filter(|id| {
+            let found = id.split('+').all(|part| available.contains(part));
+            if !found {
+                warn!("Format {id} is not available for {video_id}; skipping it");
             }
+            found
+        })
+        .cloned()
+        .collect()
+}
+
+/// Keeps the muxed formats at or below `max_height`. When there are none,
+/// falls back to the single muxed format (or, failing that, video format)
+/// whose height is closest to the cap, preferring the lower one on ties.
+fn cap_formats(entries: &[FormatEntry], max_height: u32) -> Vec<String> {
+    let within: Vec<String> = entries
+        .iter()
+        .filter(|entry| entry.is_muxed() && entry.height.is_some_and(|h| h <= max_height))
+        .filter_map(FormatEntry::id)
+        .map(str::to_owned)
+        .collect();
+    if !within.is_empty() {
+        return within;
+    }
+
+    let sized = |muxed: bool| {
+        entries
+            .iter()
+            .filter(move |entry| entry.has_video() && (!muxed || entry.is_muxed()))
+            .filter_map(|entry| Some((entry.id()?, entry.height?)))
+            .min_by_key(|(_, height)| (height.abs_diff(max_height), *height))
+    };
+    match sized(true).or_else(|| sized(false)) {
+        Some((id, height)) => {
+            info!("No muxed format at or below {max_height}p; using {id} ({height}p)");
+            vec![id.to_owned()]
+        }
+        None => {
+            info!("No format reports a height; downloading every format");
+            entries
+                .iter()
+                .filter_map(FormatEntry::id)
+                .map(str::to_owned)
+                .collect()
         }
     }
+}
 
-    Ok(formats.into_iter().collect())
+/// Asks yt-dlp for machine-readable format ids (`--print`), one per line.
+/// Returns an empty list when the invocation fails so callers can fall back
+/// to scraping the `-F` table.
+fn print_format_ids(video_url: &str, ytdlp: &YtDlpSettings) -> Result<Vec<String>> {
+    let args = [
+        "--skip-download",
+        "--no-warnings",
+        "--format",
+        "all",
+        "--print",
+        "%(format_id)s",
+        video_url,
+    ]
+    .map(str::to_owned);
+    let output = run_ytdlp(ytdlp, "format ids", &args, true)
+        .with_context(|| format!("printing format ids for {}", video_url))?;
+
+    if !output.success() {
+        return Ok(Vec::new());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| is_format_id(line))
+        .map(str::to_owned)
+        .collect())
+}
+
+/// Parses the human-readable `yt-dlp -F` table. Log lines (`[info] ...`) are
+/// ignored, and when the dashed separator row is present only the rows below
+/// it are considered, so localized or reworded headers never leak in.
+fn parse_format_listing(listing: &str) -> Vec<String> {
+    let lines: Vec<&str> = listing
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('['))
+        .collect();
+
+    let rows = match lines.iter().position(|line| is_separator_row(line)) {
+        Some(index) => &lines[index + 1..],
+        None => &lines[..],
+    };
+
+    rows.iter()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|token| {
+            // Headers of older yt-dlp releases had no separator row.
+            !["format", "code", "id"]
+                .iter()
+                .any(|header| token.eq_ignore_ascii_case(header))
+        })
+        .filter(|token| is_format_id(token))
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Matches the `----`/`────` row yt-dlp prints between header and formats.
+fn is_separator_row(line: &str) -> bool {
+    line.chars().filter(|c| matches!(c, '-' | '─')).count() >= 3
+        && line
+            .chars()
+            .all(|c| matches!(c, '-' | '─' | '|' | '│' | '+' | ' '))
+}
+
+/// yt-dlp format ids are short ASCII tokens such as `137`, `hls-1080p` or
+/// `dash-audio=128000`.
+fn is_format_id(token: &str) -> bool {
+    token
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphanumeric())
+        && token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '+' | '='))
 }
 
 /// Normalizes yt-dlp format identifiers so they become safe filenames.
@@ -1411,7 +4205,6 @@ fn sanitize_format_id(format_id: &str) -> String {
 mod tests {
     use super::*;
     use anyhow::Result;
-    use newtube_tools::metadata::MetadataReader;
     use std::collections::{HashMap, HashSet};
     #[cfg(unix)]
     use std::os::unix::fs::PermissionsExt;
@@ -1434,6 +4227,39 @@ mod tests {
         (dir, paths)
     }
 
+    #[test]
+    fn collect_thumbnails_puts_the_best_quality_first() -> Result<()> {
+        let (_dir, paths) = temp_paths();
+        let thumb_dir = paths.video_dir(&paths.thumbnails, "vid");
+        fs::create_dir_all(&thumb_dir)?;
+        for name in [
+            "default.jpg",
+            "hqdefault.jpg",
+            "vid.webp",
+            "maxresdefault.jpg",
+            "mqdefault.jpg",
+        ] {
+            fs::write(thumb_dir.join(name), b"jpg")?;
+        }
+
+        let thumbs = collect_thumbnails("vid", &paths, "videos")?;
+        let names: Vec<&str> = thumbs
+            .iter()
+            .map(|url| url.rsplit('/').next().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "maxresdefault.jpg",
+                "hqdefault.jpg",
+                "mqdefault.jpg",
+                "vid.webp",
+                "default.jpg"
+            ]
+        );
+        Ok(())
+    }
+
     #[test]
     fn downloader_args_use_defaults() {
         let config = write_runtime_config(DEFAULT_MEDIA_ROOT, DEFAULT_WWW_ROOT);
@@ -1446,74 +4272,343 @@ mod tests {
         assert_eq!(args.channel_url, "https://www.youtube.com/@Channel");
         assert_eq!(args.media_root, PathBuf::from(DEFAULT_MEDIA_ROOT));
         assert_eq!(args.www_root, PathBuf::from(DEFAULT_WWW_ROOT));
+        assert!(!args.incremental);
     }
 
     #[test]
-    fn downloader_args_override_roots() {
+    fn downloader_args_accept_incremental() {
         let config = write_runtime_config(DEFAULT_MEDIA_ROOT, DEFAULT_WWW_ROOT);
         let args = DownloaderArgs::from_slice(&[
             "--config",
             config.path().to_str().unwrap(),
-            "--media-root",
-            "/data/media",
-            "--www-root",
-            "/srv/www",
+            "--incremental",
             "https://www.youtube.com/@Channel",
         ])
         .unwrap();
+        assert!(args.incremental);
+        assert!(!args.metadata_only);
+        assert!(args.resume);
 
-        assert_eq!(args.media_root, PathBuf::from("/data/media"));
-        assert_eq!(args.www_root, PathBuf::from("/srv/www"));
+        let args = DownloaderArgs::from_slice(&[
+            "--config",
+            config.path().to_str().unwrap(),
+            "--overwrite-metadata-only",
+            "--no-resume",
+            "https://www.youtube.com/@Channel",
+        ])
+        .unwrap();
+        assert!(args.metadata_only);
+        assert!(!args.resume);
+
+        // `--preflight` and `--process-retries` do not need a channel.
+        let args = DownloaderArgs::from_slice(&[
+            "--config",
+            config.path().to_str().unwrap(),
+            "--preflight",
+        ])
+        .unwrap();
+        assert!(args.preflight);
+        assert!(args.channel_url.is_empty());
+        let args = DownloaderArgs::from_slice(&[
+            "--config",
+            config.path().to_str().unwrap(),
+            "--process-retries",
+        ])
+        .unwrap();
+        assert!(args.process_retries);
+        assert!(args.channel_url.is_empty());
+        assert!(
+            DownloaderArgs::from_slice(&["--config", config.path().to_str().unwrap()]).is_err()
+        );
     }
 
-    fn sample_video_info() -> VideoInfo {
-        VideoInfo {
-            id: "abc".into(),
-            title: Some("Sample Title".into()),
-            fulltitle: Some("Sample Title".into()),
-            description: Some("desc".into()),
-            like_count: Some(1),
-            dislike_count: Some(0),
-            view_count: Some(10),
-            upload_date: Some("20240101".into()),
-            release_timestamp: None,
-            uploader: None,
-            channel: Some("Channel".into()),
-            channel_id: Some("channel123".into()),
-            channel_url: Some("https://example.com/channel".into()),
-            channel_follower_count: Some(100),
-            duration: Some(120),
-            duration_string: None,
-            thumbnails: Some(vec![ThumbnailInfo {
-                url: Some("https://img/1.jpg".into()),
-                width: Some(100),
-                height: Some(100),
-            }]),
-            tags: Some(vec!["tech".into()]),
-            comment_count: Some(5),
-            subtitles: Some(HashMap::new()),
-            automatic_captions: Some(HashMap::new()),
-            formats: Some(Vec::new()),
+    #[test]
+    fn match_filter_is_anded_with_the_builtin_split() {
+        let config = write_runtime_config(DEFAULT_MEDIA_ROOT, DEFAULT_WWW_ROOT);
+        let args = DownloaderArgs::from_slice(&[
+            "--config",
+            config.path().to_str().unwrap(),
+            "--match-filter",
+            " view_count >? 1000 & title~='(?i)review' ",
+            "https://www.youtube.com/@Channel",
+        ])
+        .unwrap();
+        let extra = args.match_filter.as_deref();
+        assert_eq!(extra, Some("view_count >? 1000 & title~='(?i)review'"));
+
+        let listing = PlaylistListing {
+            url: "https://www.youtube.com/@Channel/videos".into(),
+            filter: Some(combine_match_filters(VIDEOS_MATCH_FILTER, extra)),
+            date_after: None,
+        };
+        let passed = listing_args(&listing);
+        let position = passed
+            .iter()
+            .position(|arg| *arg == "--match-filter")
+            .expect("--match-filter passed");
+        assert_eq!(
+            passed[position + 1],
+            "!is_live & original_url!*=/shorts/ & view_count >? 1000 & title~='(?i)review'"
+        );
+        assert_eq!(
+            combine_match_filters(SHORTS_MATCH_FILTER, None),
+            SHORTS_MATCH_FILTER
+        );
+
+        for bad in [
+            "",
+            "  ",
+            "-x",
+            "a; rm -rf /",
+            "a | b",
+            "`id`",
+            "$(id)",
+            "a\nb",
+        ] {
+            assert!(validate_match_filter(bad).is_err(), "{bad:?}");
         }
+        assert!(validate_match_filter("title$='end' & !is_live").is_ok());
     }
 
-    fn sample_format(id: &str, ext: &str) -> FormatInfo {
-        FormatInfo {
-            format_id: Some(id.into()),
-            format_note: None,
-            width: Some(1920),
-            height: Some(1080),
-            fps: Some(30.0),
-            ext: Some(ext.into()),
-            vcodec: Some("avc1".into()),
-            acodec: Some("mp4a".into()),
-            filesize: Some(1234),
-            filesize_approx: None,
-            dynamic_range: Some("HDR".into()),
+    #[test]
+    fn collection_specs_set_tabs_filters_and_order() {
+        let config = write_runtime_config(DEFAULT_MEDIA_ROOT, DEFAULT_WWW_ROOT);
+        let config = config.path().to_str().unwrap();
+        let args =
+            DownloaderArgs::from_slice(&["--config", config, "https://youtube.com/@c"]).unwrap();
+        assert_eq!(args.collections, CollectionSpec::defaults());
+
+        let args = DownloaderArgs::from_slice(&[
+            "--config",
+            config,
+            "--collection",
+            "shorts",
+            "--collection=live:/streams:original_url*=https://www.youtube.com/live/",
+            "--collection",
+            "videos",
+            "--match-filter",
+            "view_count >? 10",
+            "https://youtube.com/@c",
+        ])
+        .unwrap();
+        let tabs: Vec<_> = args.collections.iter().map(|c| c.tab.as_str()).collect();
+        assert_eq!(tabs, ["shorts", "streams", "videos"]);
+
+        let live = &args.collections[1];
+        assert_eq!(live.label, "live");
+        assert_eq!(live.kind, MediaKind::Video);
+        let listing = live.listing("https://youtube.com/@c", args.match_filter.as_deref(), None);
+        let passed = listing_args(&listing);
+        assert_eq!(
+            passed,
+            [
+                "--flat-playlist",
+                "--get-id",
+                "--ignore-errors",
+                "--match-filter",
+                "original_url*=https://www.youtube.com/live/ & view_count >? 10",
+                "https://youtube.com/@c/streams",
+            ]
+        );
+
+        let plain = CollectionSpec::parse("podcasts:/podcasts").unwrap();
+        let args = listing_args(&plain.listing("https://youtube.com/@c", None, None));
+        assert!(!args.contains(&"--match-filter".to_string()));
+
+        for bad in [
+            "live",
+            ":/streams",
+            "live:streams",
+            "live:/",
+            "live:/a b",
+            "live:/x:-y",
+        ] {
+            assert!(CollectionSpec::parse(bad).is_err(), "{bad:?}");
         }
+        assert!(
+            DownloaderArgs::from_slice(&[
+                "--config",
+                config,
+                "--collection",
+                "videos",
+                "--collection",
+                "again:/videos",
+                "https://youtube.com/@c",
+            ])
+            .is_err()
+        );
     }
 
-    fn install_ytdlp_stub(dir: &Path) -> Result<PathBuf> {
+    #[test]
+    fn incremental_date_after_applies_margin() -> Result<()> {
+        assert_eq!(
+            incremental_date_after("2024-03-10T00:00:00Z").as_deref(),
+            Some("20240303")
+        );
+        // Crosses a month/year boundary and accepts offset timestamps.
+        assert_eq!(
+            incremental_date_after("2024-01-03T12:00:00+00:00").as_deref(),
+            Some("20231227")
+        );
+        assert!(incremental_date_after("unknown").is_none());
+
+        let (_temp, paths) = temp_paths();
+        let store = MetadataStore::open(&paths.metadata_db)?;
+        let key = canonical_channel_url("https://www.youtube.com/@Channel/");
+        store.record_channel_run(&key, Some("2024-05-20T00:00:00Z"))?;
+        let stored = store.channel_last_upload_date(&key)?.expect("last upload");
+        assert_eq!(incremental_date_after(&stored).as_deref(), Some("20240513"));
+        Ok(())
+    }
+
+    #[test]
+    fn since_limits_the_listing_window() -> Result<()> {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        assert_eq!(since_date_after(today, 30).as_deref(), Some("20240209"));
+        assert_eq!(since_date_after(today, 10).as_deref(), Some("20240229"));
+
+        let config = write_runtime_config(DEFAULT_MEDIA_ROOT, DEFAULT_WWW_ROOT);
+        let config = config.path().to_str().unwrap();
+        let channel = "https://www.youtube.com/@Channel";
+        let args = DownloaderArgs::from_slice(&["--config", config, "--since", "30", channel])?;
+        assert_eq!(args.since, Some(30));
+        let args = DownloaderArgs::from_slice(&["--config", config, "--since=0", channel])?;
+        assert_eq!(args.since, None);
+        let args = DownloaderArgs::from_slice(&["--config", config, channel])?;
+        assert_eq!(args.since, None);
+        assert!(
+            DownloaderArgs::from_slice(&["--config", config, "--since", "-1", channel]).is_err()
+        );
+        assert!(
+            DownloaderArgs::from_slice(&[
+                "--config",
+                config,
+                "--since",
+                "7",
+                "https://www.youtube.com/watch?v=alpha"
+            ])
+            .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn downloader_args_reject_relative_media_root() {
+        let config = write_runtime_config(DEFAULT_MEDIA_ROOT, DEFAULT_WWW_ROOT);
+        let err = DownloaderArgs::from_slice(&[
+            "--config",
+            config.path().to_str().unwrap(),
+            "--media-root=media",
+            "https://www.youtube.com/@Channel",
+        ])
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("media root must be an absolute path")
+        );
+
+        let config = write_runtime_config("yt", DEFAULT_WWW_ROOT);
+        assert!(
+            DownloaderArgs::from_slice(&[
+                "--config",
+                config.path().to_str().unwrap(),
+                "https://www.youtube.com/@Channel",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn downloader_args_override_roots() {
+        let config = write_runtime_config(DEFAULT_MEDIA_ROOT, DEFAULT_WWW_ROOT);
+        let args = DownloaderArgs::from_slice(&[
+            "--config",
+            config.path().to_str().unwrap(),
+            "--media-root",
+            "/data/media",
+            "--www-root",
+            "/srv/www",
+            "https://www.youtube.com/@Channel",
+        ])
+        .unwrap();
+
+        assert_eq!(args.media_root, PathBuf::from("/data/media"));
+        assert_eq!(args.www_root, PathBuf::from("/srv/www"));
+    }
+
+    #[test]
+    fn downloader_args_reject_nested_roots() {
+        let config = write_runtime_config(DEFAULT_MEDIA_ROOT, DEFAULT_WWW_ROOT);
+        let err = DownloaderArgs::from_slice(&[
+            "--config",
+            config.path().to_str().unwrap(),
+            "--www-root",
+            "/yt/www",
+            "https://www.youtube.com/@Channel",
+        ])
+        .unwrap_err();
+        assert!(err.to_string().contains("nested"));
+    }
+
+    fn sample_video_info() -> VideoInfo {
+        VideoInfo {
+            id: "abc".into(),
+            title: Some("Sample Title".into()),
+            fulltitle: Some("Sample Title".into()),
+            description: Some("desc".into()),
+            like_count: Some(1),
+            dislike_count: Some(0),
+            view_count: Some(10),
+            upload_date: Some("20240101".into()),
+            timestamp: None,
+            release_timestamp: None,
+            uploader: None,
+            channel: Some("Channel".into()),
+            channel_id: Some("channel123".into()),
+            channel_url: Some("https://example.com/channel".into()),
+            channel_follower_count: Some(100),
+            duration: Some(120),
+            duration_string: None,
+            thumbnails: Some(vec![ThumbnailInfo {
+                url: Some("https://img/1.jpg".into()),
+                width: Some(100),
+                height: Some(100),
+            }]),
+            tags: Some(vec!["tech".into()]),
+            comment_count: Some(5),
+            subtitles: Some(HashMap::new()),
+            automatic_captions: Some(HashMap::new()),
+            formats: Some(Vec::new()),
+            chapters: None,
+            sponsorblock_chapters: None,
+            webpage_url: Some("https://www.youtube.com/watch?v=abc".into()),
+            original_url: Some("https://youtu.be/abc".into()),
+            extractor: Some("youtube".into()),
+            extractor_key: Some("Youtube".into()),
+            version: Some(VersionInfo {
+                version: Some("2025.09.26".into()),
+            }),
+            availability: Some("public".into()),
+        }
+    }
+
+    fn sample_format(id: &str, ext: &str) -> FormatInfo {
+        FormatInfo {
+            format_id: Some(id.into()),
+            format_note: None,
+            width: Some(1920),
+            height: Some(1080),
+            fps: Some(30.0),
+            ext: Some(ext.into()),
+            vcodec: Some("avc1".into()),
+            acodec: Some("mp4a".into()),
+            filesize: Some(1234),
+            filesize_approx: None,
+            dynamic_range: Some("HDR".into()),
+        }
+    }
+
+    fn install_ytdlp_stub(dir: &Path) -> Result<PathBuf> {
         let script_path = dir.join("yt-dlp");
         let script = r#"#!/usr/bin/env bash
 set -eu
@@ -1594,7 +4689,19 @@ sb0 mhtml 320x180      1    |                  mhtml | images
 313 webm  3840x2160   25    |  147.52MiB 6950k https | vp9         6950k video only          2160p, webm_dash'
 
 if printf '%s\n' "${args[@]}" | grep -q -- '--flat-playlist'; then
-  echo "alpha"
+  if [[ -f "$(dirname "$0")/playlist-ids" ]]; then
+    cat "$(dirname "$0")/playlist-ids"
+  else
+    echo "alpha"
+  fi
+  exit 0
+fi
+
+if printf '%s\n' "${args[@]}" | grep -q -- '^--print$'; then
+  if [[ -f "$(dirname "$0")/no-print" ]]; then
+    exit 2
+  fi
+  printf '%s\n' "$format_listing" | awk '{print $1}'
   exit 0
 fi
 
@@ -1638,6 +4745,10 @@ fi
 if [[ -n "$format_id" ]]; then
   target="${output//%(ext)s/mp4}"
   mkdir -p "$(dirname "$target")"
+  if [[ -f "$(dirname "$0")/format-exit-code" ]]; then
+    echo "partial" > "$target"
+    exit "$(cat "$(dirname "$0")/format-exit-code")"
+  fi
   echo "video" > "$target"
   exit 0
 fi
@@ -1687,6 +4798,157 @@ exit 0
         Ok(())
     }
 
+    #[test]
+    fn download_target_detects_url_shapes() {
+        let video = |id: &str, kind| DownloadTarget::Video {
+            id: id.into(),
+            kind,
+        };
+        for (url, expected) in [
+            (
+                "https://www.youtube.com/watch?v=abc123&t=10s",
+                video("abc123", MediaKind::Video),
+            ),
+            (
+                "https://www.youtube.com/watch?feature=share&v=abc123",
+                video("abc123", MediaKind::Video),
+            ),
+            (
+                "https://youtu.be/abc123?si=x",
+                video("abc123", MediaKind::Video),
+            ),
+            (
+                "https://www.youtube.com/shorts/xyz789",
+                video("xyz789", MediaKind::Short),
+            ),
+            (
+                "https://www.youtube.com/playlist?list=PL123",
+                DownloadTarget::Playlist,
+            ),
+            ("https://www.youtube.com/@Channel", DownloadTarget::Channel),
+            (
+                "https://www.youtube.com/@Channel/shorts",
+                DownloadTarget::Channel,
+            ),
+            (
+                "https://www.youtube.com/channel/UC123",
+                DownloadTarget::Channel,
+            ),
+        ] {
+            assert_eq!(DownloadTarget::classify(url), expected, "{url}");
+        }
+    }
+
+    #[test]
+    fn downloader_args_build_a_single_collection_for_videos_and_playlists() -> Result<()> {
+        let config = write_runtime_config(DEFAULT_MEDIA_ROOT, DEFAULT_WWW_ROOT);
+        let config = config.path().to_str().unwrap();
+        let listed = |args: &DownloaderArgs| {
+            let [collection] = args.collections.as_slice() else {
+                panic!("expected one collection, got {:?}", args.collections);
+            };
+            let listing = collection.listing(&args.channel_url, None, None);
+            (collection.kind, listing.url, listing.filter)
+        };
+
+        let args = DownloaderArgs::from_slice(&["--config", config, "https://youtu.be/abc"])?;
+        assert_eq!(
+            listed(&args),
+            (
+                MediaKind::Video,
+                "https://www.youtube.com/watch?v=abc".to_owned(),
+                None
+            )
+        );
+        assert_eq!(args.collections[0].label, "video");
+
+        // A Short keeps landing in the shorts table, and `--kind` overrides
+        // the guess.
+        let args =
+            DownloaderArgs::from_slice(&["--config", config, "https://youtube.com/shorts/s1"])?;
+        assert_eq!(listed(&args).0, MediaKind::Short);
+        let args = DownloaderArgs::from_slice(&[
+            "--config",
+            config,
+            "--kind",
+            "short",
+            "https://www.youtube.com/watch?v=s2",
+        ])?;
+        assert_eq!(
+            listed(&args),
+            (
+                MediaKind::Short,
+                "https://www.youtube.com/shorts/s2".to_owned(),
+                None
+            )
+        );
+        assert_eq!(args.collections[0].label, "short");
+
+        let playlist = "https://www.youtube.com/playlist?list=PL1";
+        let args = DownloaderArgs::from_slice(&["--config", config, "--kind=short", playlist])?;
+        assert_eq!(listed(&args), (MediaKind::Short, playlist.to_owned(), None));
+        assert_eq!(args.collections[0].label, "playlist entries");
+
+        let channel = "https://www.youtube.com/@c";
+        assert!(
+            DownloaderArgs::from_slice(&["--config", config, "--kind", "short", channel]).is_err()
+        );
+        assert!(
+            DownloaderArgs::from_slice(&["--config", config, "--collection", "videos", playlist])
+                .is_err()
+        );
+        assert!(
+            DownloaderArgs::from_slice(&["--config", config, "--kind", "live", playlist]).is_err()
+        );
+        assert!(
+            DownloaderArgs::from_slice(&["--config", config, "--incremental", playlist]).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn gzip_archive_roundtrip_loads_ids() -> Result<()> {
+        use std::io::Read;
+
+        let dir = tempdir()?;
+        let archive_path = dir.path().join("archive.txt.gz");
+        append_to_archive(&archive_path, "abc123")?;
+        append_to_archive(&archive_path, "def456")?;
+
+        let mut raw = Vec::new();
+        File::open(&archive_path)?.read_to_end(&mut raw)?;
+        assert_eq!(&raw[..2], [0x1f, 0x8b], "stored compressed");
+        let entries = load_archive(&archive_path)?;
+        assert_eq!(entries, HashSet::from(["abc123".into(), "def456".into()]));
+        Ok(())
+    }
+
+    #[test]
+    fn downloader_args_accept_custom_archive() -> Result<()> {
+        let config = write_runtime_config(DEFAULT_MEDIA_ROOT, DEFAULT_WWW_ROOT);
+        let config = config.path().to_str().unwrap();
+        let channel = "https://www.youtube.com/@Channel";
+
+        let args = DownloaderArgs::from_slice(&["--config", config, channel])?;
+        assert_eq!(args.archive, None);
+        let args = DownloaderArgs::from_slice(&[
+            "--config",
+            config,
+            "--archive",
+            "/srv/archives/channel.txt.gz",
+            channel,
+        ])?;
+        assert_eq!(
+            args.archive,
+            Some(PathBuf::from("/srv/archives/channel.txt.gz"))
+        );
+        assert!(
+            DownloaderArgs::from_slice(&["--config", config, "--archive=rel.txt", channel])
+                .is_err()
+        );
+        Ok(())
+    }
+
     #[test]
     fn build_video_record_populates_fields() -> Result<()> {
         let (_temp, paths) = temp_paths();
@@ -1705,6 +4967,23 @@ exit 0
         info.duration = Some(125);
         info.duration_string = None;
         info.formats = Some(vec![sample_format("1080p", "mp4")]);
+        info.chapters = Some(vec![ChapterInfo {
+            start_time: Some(0.0),
+            end_time: None,
+            title: Some("Intro".into()),
+        }]);
+        info.sponsorblock_chapters = Some(vec![
+            SponsorBlockInfo {
+                start_time: Some(3.0),
+                end_time: Some(15.5),
+                category: Some("sponsor".into()),
+            },
+            SponsorBlockInfo {
+                start_time: Some(100.0),
+                end_time: None,
+                category: Some("outro".into()),
+            },
+        ]);
 
         let record = build_video_record(
             "abc",
@@ -1724,6 +5003,56 @@ exit 0
             record.sources[0].url,
             "/api/videos/abc/streams/1080p".to_string()
         );
+        assert_eq!(
+            record.chapters,
+            [Chapter {
+                start_time: 0.0,
+                end_time: None,
+                title: "Intro".into(),
+            }]
+        );
+        assert!(record.extras.get("chapters").is_none());
+        assert_eq!(
+            record.sponsor_segments,
+            Some(vec![SponsorSegment {
+                start_time: 3.0,
+                end_time: 15.5,
+                category: "sponsor".into(),
+            }])
+        );
+        assert_eq!(
+            record.webpage_url.as_deref(),
+            Some("https://www.youtube.com/watch?v=abc")
+        );
+        assert_eq!(record.original_url.as_deref(), Some("https://youtu.be/abc"));
+        assert_eq!(
+            record.extras["provenance"],
+            json!({"extractor": "youtube", "extractorKey": "Youtube", "ytDlpVersion": "2025.09.26"})
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn upload_date_and_timestamp_store_the_same_utc_value() -> Result<()> {
+        let (_temp, paths) = temp_paths();
+        paths.prepare()?;
+        let media_dir = paths.media_dir(MediaKind::Video);
+
+        let mut info = sample_video_info();
+        info.upload_date = Some("20240102".into());
+        let from_date = build_video_record("abc", &info, media_dir, MediaKind::Video, &paths)?;
+
+        info.upload_date = None;
+        info.timestamp = Some(1_704_153_600);
+        let from_timestamp = build_video_record("abc", &info, media_dir, MediaKind::Video, &paths)?;
+
+        assert_eq!(from_date.upload_epoch, Some(1_704_153_600));
+        assert_eq!(from_date.upload_epoch, from_timestamp.upload_epoch);
+        assert_eq!(
+            from_date.upload_date.as_deref(),
+            Some("2024-01-02T00:00:00Z")
+        );
+        assert_eq!(from_date.upload_date, from_timestamp.upload_date);
         Ok(())
     }
 
@@ -1753,6 +5082,47 @@ exit 0
         Ok(())
     }
 
+    #[test]
+    fn subtitle_display_names_cover_regions_scripts_and_unknown_codes() -> Result<()> {
+        assert_eq!(subtitle_display_name("en"), "English");
+        assert_eq!(subtitle_display_name("pt-BR"), "Portuguese (Brazil)");
+        assert_eq!(subtitle_display_name("zh-Hans"), "Chinese (Simplified)");
+        assert_eq!(subtitle_display_name("en-orig"), "English (orig)");
+        assert_eq!(subtitle_display_name("xx-YY"), "XX-YY");
+
+        // yt-dlp's own name still wins over the table.
+        let (_temp, paths) = temp_paths();
+        let mut info = sample_video_info();
+        info.subtitles = Some(HashMap::from([(
+            "pt-BR".to_owned(),
+            vec![SubtitleInfo {
+                url: None,
+                ext: Some("vtt".into()),
+                name: Some("Português (Brasil)".into()),
+            }],
+        )]));
+        let subtitle_dir = paths.subtitles.join("abc");
+        fs::create_dir_all(&subtitle_dir)?;
+        fs::write(subtitle_dir.join("abc.pt-BR.vtt"), "WEBVTT")?;
+        fs::write(subtitle_dir.join("abc.zh-Hans.vtt"), "WEBVTT")?;
+
+        let collection = collect_subtitles("abc", &info, &paths, MediaKind::Video)?;
+        let mut names: Vec<_> = collection
+            .languages
+            .iter()
+            .map(|track| (track.code.as_str(), track.name.as_str()))
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                ("pt-BR", "Português (Brasil)"),
+                ("zh-Hans", "Chinese (Simplified)")
+            ]
+        );
+        Ok(())
+    }
+
     #[test]
     fn collect_subtitles_falls_back_to_remote_track() -> Result<()> {
         let (_temp, paths) = temp_paths();
@@ -1813,9 +5183,15 @@ exit 0
             },
         ]);
 
-        let sources = collect_sources("abc", &info, paths.media_dir(MediaKind::Video), "videos")?;
+        let sources = collect_sources(
+            "abc",
+            &info,
+            &paths.media_dir(MediaKind::Video).join("abc"),
+            "videos",
+        )?;
         assert_eq!(sources.len(), 1);
         assert!(sources[0].url.contains("f_1"));
+        assert_eq!(sources[0].stream_key.as_deref(), Some("f_1"));
         assert_eq!(sources[0].quality_label.as_deref(), Some("1080p HDR"));
         Ok(())
     }
@@ -1829,15 +5205,6 @@ exit 0
         assert_eq!(format_quality_label(None, None), None);
         assert_eq!(mime_from_extension("webm"), "video/webm");
         assert_eq!(mime_from_extension("foo"), "video/foo");
-        assert_eq!(
-            upload_date_to_iso("20240102"),
-            Some("2024-01-02T00:00:00Z".into())
-        );
-        assert!(upload_date_to_iso("2024").is_none());
-        assert_eq!(
-            timestamp_to_iso(0).as_deref(),
-            Some("1970-01-01T00:00:00+00:00")
-        );
         assert_eq!(format_duration(65), "1:05");
         assert_eq!(format_duration(3725), "1:02:05");
     }
@@ -1854,42 +5221,181 @@ exit 0
             ]
         });
         fs::write(&info_path, serde_json::to_vec(&json)?)?;
-        let ids = collect_format_ids(&info_path, "https://example.com/video")?;
+        let ytdlp = YtDlpSettings::new(dir.path().join(COOKIES_FILE));
+        let ids = collect_format_ids(&info_path, "https://example.com/video", None, &ytdlp)?;
         assert_eq!(ids, vec!["136".to_string(), "249".to_string()]);
         Ok(())
     }
 
     #[test]
-    fn sanitize_format_id_replaces_delimiters() {
-        assert_eq!(sanitize_format_id("http/1080p:60"), "http_1080p_60");
-        assert_eq!(sanitize_format_id("abc def"), "abc_def");
+    fn max_height_keeps_muxed_formats_under_the_cap() -> Result<()> {
+        let dir = tempdir()?;
+        let info_path = dir.path().join("info.json");
+        let json = serde_json::json!({
+            "formats": [
+                { "format_id": "sb0", "height": 45, "vcodec": "none", "acodec": "none" },
+                { "format_id": "140", "vcodec": "none", "acodec": "mp4a.40.2" },
+                { "format_id": "18", "height": 360, "vcodec": "avc1", "acodec": "mp4a.40.2" },
+                { "format_id": "22", "height": 720, "vcodec": "avc1", "acodec": "mp4a.40.2" },
+                { "format_id": "137", "height": 1080, "vcodec": "avc1", "acodec": "none" }
+            ]
+        });
+        fs::write(&info_path, serde_json::to_vec(&json)?)?;
+        let url = "https://example.com/video";
+        let ytdlp = YtDlpSettings::new(dir.path().join(COOKIES_FILE));
+
+        assert_eq!(
+            collect_format_ids(&info_path, url, Some(720), &ytdlp)?,
+            ["18", "22"]
+        );
+        assert_eq!(
+            collect_format_ids(&info_path, url, Some(480), &ytdlp)?,
+            ["18"]
+        );
+        // Nothing muxed fits: the closest muxed format is used instead.
+        assert_eq!(
+            collect_format_ids(&info_path, url, Some(240), &ytdlp)?,
+            ["18"]
+        );
+        assert_eq!(collect_format_ids(&info_path, url, None, &ytdlp)?.len(), 5);
+
+        let video_only = [FormatEntry {
+            format_id: Some("137".into()),
+            height: Some(1080),
+            vcodec: Some("avc1".into()),
+            acodec: Some("none".into()),
+        }];
+        assert_eq!(cap_formats(&video_only, 720), ["137"]);
+
+        let config = write_runtime_config(DEFAULT_MEDIA_ROOT, DEFAULT_WWW_ROOT);
+        let config = config.path().to_str().unwrap();
+        let args = DownloaderArgs::from_slice(&["--config", config, "--max-height", "1080p", url])?;
+        assert_eq!(args.max_height, Some(1080));
+        let args = DownloaderArgs::from_slice(&["--config", config, url])?;
+        assert_eq!(args.max_height, None);
+        assert!(DownloaderArgs::from_slice(&["--config", config, "--max-height=0", url]).is_err());
+        Ok(())
     }
 
     #[test]
-    fn process_entry_refreshes_metadata_even_when_archived() -> Result<()> {
-        let (temp, paths) = temp_paths();
+    fn formats_flag_downloads_only_the_listed_ids() -> Result<()> {
+        let url = "https://www.youtube.com/@Channel";
+        let config = write_runtime_config(DEFAULT_MEDIA_ROOT, DEFAULT_WWW_ROOT);
+        let config = config.path().to_str().unwrap();
+        let args =
+            DownloaderArgs::from_slice(&["--config", config, "--formats", "22, 137+140,22", url])?;
+        assert_eq!(args.formats, ["22", "137+140"]);
+        let args = DownloaderArgs::from_slice(&["--config", config, url])?;
+        assert!(args.formats.is_empty());
+        assert!(!args.sponsorblock);
+        let args = DownloaderArgs::from_slice(&["--config", config, "--sponsorblock", url])?;
+        assert!(args.sponsorblock);
+        assert!(DownloaderArgs::from_slice(&["--config", config, "--formats=,", url]).is_err());
+        assert!(
+            DownloaderArgs::from_slice(&[
+                "--config",
+                config,
+                "--formats=22",
+                "--max-height=720",
+                url
+            ])
+            .is_err()
+        );
+
+        let (temp, mut paths) = temp_paths();
         let stub = install_ytdlp_stub(temp.path())?;
         let _guard = set_ytdlp_stub_path(stub);
         paths.prepare()?;
+        paths.formats = vec!["137+140".into(), "1080p".into()];
+        let output_dir = paths.media_dir(MediaKind::Video);
+        let outcome = download_video_all_formats("alpha", output_dir, &paths, MediaKind::Video)?;
+        assert_eq!(outcome.exit, YtDlpExit::Success);
+        let published = output_dir.join("alpha");
+        assert!(published.join("alpha_1080p.mp4").exists());
+        let media: Vec<_> = fs::read_dir(&published)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "mp4"))
+            .collect();
+        assert_eq!(media.len(), 1);
+
+        let info = published.join("alpha.info.json");
+        assert_eq!(
+            available_requested_formats("alpha", &info, &["1080p+1080p".into(), "22".into()]),
+            ["1080p+1080p"]
+        );
+        assert_eq!(
+            available_requested_formats("alpha", &temp.path().join("missing.json"), &["22".into()]),
+            ["22"]
+        );
+        Ok(())
+    }
 
-        let media_dir = paths.media_dir(MediaKind::Video).join("alpha");
-        fs::create_dir_all(&media_dir)?;
-        fs::write(media_dir.join("alpha_1080p.mp4"), "video-bytes")?;
-        let subtitle_dir = paths.subtitles.join("alpha");
-        fs::create_dir_all(&subtitle_dir)?;
-        fs::write(subtitle_dir.join("alpha.en.vtt"), "WEBVTT")?;
+    #[test]
+    fn keep_audio_stores_a_separate_audio_track() -> Result<()> {
+        let url = "https://www.youtube.com/@Channel";
+        let config = write_runtime_config(DEFAULT_MEDIA_ROOT, DEFAULT_WWW_ROOT);
+        let config = config.path().to_str().unwrap();
+        assert!(!DownloaderArgs::from_slice(&["--config", config, url])?.keep_audio);
+        assert!(DownloaderArgs::from_slice(&["--config", config, "--keep-audio", url])?.keep_audio);
 
-        let mut metadata = MetadataStore::open(&paths.metadata_db)?;
-        let mut archive = HashSet::from([String::from("alpha")]);
-        process_media_entry(
-            "alpha",
-            1,
-            1,
-            &paths,
-            &mut archive,
-            MediaKind::Video,
-            &mut metadata,
-        )?;
+        let (temp, mut paths) = temp_paths();
+        let stub = install_ytdlp_stub(temp.path())?;
+        let _guard = set_ytdlp_stub_path(stub);
+        paths.prepare()?;
+        paths.formats = vec!["1080p".into()];
+        paths.keep_audio = true;
+        let output_dir = paths.media_dir(MediaKind::Video);
+        let outcome = download_video_all_formats("alpha", output_dir, &paths, MediaKind::Video)?;
+        assert_eq!(outcome.exit, YtDlpExit::Success);
+        let published = output_dir.join("alpha");
+        assert!(published.join("alpha_audio.mp4").exists());
+
+        let audio = collect_audio_source("alpha", &published, "videos")?.expect("audio source");
+        assert_eq!(audio.format_id, "audio");
+        assert_eq!(audio.mime_type.as_deref(), Some("audio/mp4"));
+        assert_eq!(audio.url, "/api/videos/alpha/audio");
+
+        let info: VideoInfo =
+            serde_json::from_str(&fs::read_to_string(published.join("alpha.info.json"))?)?;
+        let sources = collect_sources("alpha", &info, &published, "videos")?;
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].format_id, "1080p");
+
+        fs::write(published.join("alpha_audio.mp4"), "")?;
+        assert!(collect_audio_source("alpha", &published, "videos")?.is_none());
+
+        // Shorts have no audio route, so no audio track is fetched for them.
+        let shorts_dir = paths.media_dir(MediaKind::Short);
+        let outcome = download_video_all_formats("beta", shorts_dir, &paths, MediaKind::Short)?;
+        assert_eq!(outcome.exit, YtDlpExit::Success);
+        assert!(!shorts_dir.join("beta").join("beta_audio.mp4").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn sanitize_format_id_replaces_delimiters() {
+        assert_eq!(sanitize_format_id("http/1080p:60"), "http_1080p_60");
+        assert_eq!(sanitize_format_id("abc def"), "abc_def");
+    }
+
+    #[test]
+    fn process_entry_refreshes_metadata_even_when_archived() -> Result<()> {
+        let (temp, paths) = temp_paths();
+        let stub = install_ytdlp_stub(temp.path())?;
+        let _guard = set_ytdlp_stub_path(stub);
+        paths.prepare()?;
+
+        let media_dir = paths.media_dir(MediaKind::Video).join("alpha");
+        fs::create_dir_all(&media_dir)?;
+        fs::write(media_dir.join("alpha_1080p.mp4"), "video-bytes")?;
+        let subtitle_dir = paths.subtitles.join("alpha");
+        fs::create_dir_all(&subtitle_dir)?;
+        fs::write(subtitle_dir.join("alpha.en.vtt"), "WEBVTT")?;
+
+        let mut metadata = MetadataStore::open(&paths.metadata_db)?;
+        let mut archive = HashSet::from([String::from("alpha")]);
+        let stores = SharedStores::new(&mut archive, &mut metadata, &paths);
+        process_media_entry("alpha", 1, 1, &paths, &stores, MediaKind::Video)?;
 
         let reader = MetadataReader::new(&paths.metadata_db)?;
         let video = reader.get_video("alpha")?.expect("video stored");
@@ -1900,6 +5406,40 @@ exit 0
         Ok(())
     }
 
+    #[test]
+    fn comments_file_is_read_in_every_shape() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let path = temp.path().join("alpha.comments.json");
+        let entries = r#"[
+            {"id": "c1", "text": "first", "timestamp": 1700000000, "author_is_uploader": true},
+            {"text": "no id"},
+            {"id": "c1", "text": "duplicate"},
+            {"id": "c2", "parent": "c1", "time_text": "2 days ago"}
+        ]"#;
+        let read = |body: &str| -> Result<Vec<CommentRecord>> {
+            fs::write(&path, body)?;
+            read_comments_file("alpha", &path)
+        };
+
+        for body in [
+            entries.to_owned(),
+            format!(r#"{{"id": "alpha", "extra": {{"comments": 1}}, "comments": {entries}}}"#),
+        ] {
+            let comments = read(&body)?;
+            let ids: Vec<&str> = comments.iter().map(|c| c.id.as_str()).collect();
+            assert_eq!(ids, ["c1", "c2"]);
+            assert_eq!(comments[0].text, "first");
+            assert!(comments[0].status_likedbycreator);
+            assert_eq!(comments[1].parent_comment_id.as_deref(), Some("c1"));
+            assert_eq!(comments[1].time_posted.as_deref(), Some("2 days ago"));
+        }
+        assert!(read(r#"{"comments": "none"}"#)?.is_empty());
+        assert!(read(r#"{"title": "no comments"}"#)?.is_empty());
+        assert!(read("null")?.is_empty());
+        assert!(read(r#"[{"id": "c1"}"#).is_err());
+        Ok(())
+    }
+
     #[test]
     fn fetch_comments_dedupes_and_sets_flags() -> Result<()> {
         let (temp, paths) = temp_paths();
@@ -1926,14 +5466,18 @@ exit 0
         paths.prepare()?;
         let mut metadata = MetadataStore::open(&paths.metadata_db)?;
         let mut archive = HashSet::new();
+        let mut run = test_run(None);
         download_collection(
-            "test videos",
-            "https://example.com/channel/videos".to_string(),
-            None,
+            &CollectionSpec::videos(),
+            PlaylistListing {
+                url: "https://example.com/channel/videos".to_string(),
+                filter: None,
+                date_after: None,
+            },
             &paths,
             &mut archive,
-            MediaKind::Video,
             &mut metadata,
+            &mut run,
         )?;
         let reader = MetadataReader::new(&paths.metadata_db)?;
         assert!(reader.get_video("alpha")?.is_some());
@@ -1942,9 +5486,1016 @@ exit 0
             .join("alpha")
             .join("alpha_1080p.mp4");
         assert!(media_file.exists());
+        assert_eq!(run.summary.downloaded, 1);
+        assert!(run.summary.skipped.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn interrupted_downloads_are_never_published() -> Result<()> {
+        let (temp, paths) = temp_paths();
+        let stub = install_ytdlp_stub(temp.path())?;
+        let _guard = set_ytdlp_stub_path(stub);
+        paths.prepare()?;
+        let output_dir = paths.media_dir(MediaKind::Video);
+        let published = output_dir.join("alpha");
+        let staged = output_dir.join(layout::STAGING_DIR).join("alpha");
+
+        // 101 is how yt-dlp reports a cancelled download.
+        fs::write(temp.path().join("format-exit-code"), "101")?;
+        let outcome = download_video_all_formats("alpha", output_dir, &paths, MediaKind::Video)?;
+        assert_eq!(outcome.exit, YtDlpExit::Cancelled);
+        assert!(!published.exists());
+        assert!(staged.join("alpha_1080p.mp4").exists());
+
+        // The retry resumes in staging and publishes the finished directory.
+        fs::remove_file(temp.path().join("format-exit-code"))?;
+        let outcome = download_video_all_formats("alpha", output_dir, &paths, MediaKind::Video)?;
+        assert_eq!(outcome.exit, YtDlpExit::Success);
+        assert_eq!(
+            fs::read_to_string(published.join("alpha_1080p.mp4"))?,
+            "video\n"
+        );
+        assert!(published.join("alpha.info.json").exists());
+        assert!(!staged.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn retry_queue_replaces_and_clears_entries_per_video() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join(RETRY_QUEUE_FILE);
+        let queue = RetryQueue::new(&path);
+        let failed = |format_id: &str| FailedFormat {
+            format_id: format_id.to_owned(),
+            reason: "exit status: 1".to_owned(),
+        };
+
+        queue.record("alpha", MediaKind::Video, &[failed("137"), failed("22")])?;
+        queue.record("beta", MediaKind::Short, &[failed("18")])?;
+        let entries = queue.load()?;
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[2],
+            RetryEntry {
+                video_id: "beta".into(),
+                format_id: "18".into(),
+                reason: "exit status: 1".into(),
+                kind: MediaKind::Short,
+            }
+        );
+
+        // A later attempt replaces the video's entries instead of adding up.
+        queue.record("alpha", MediaKind::Video, &[failed("22")])?;
+        fs::write(&path, format!("{}not json\n", fs::read_to_string(&path)?))?;
+        let formats: Vec<_> = queue
+            .load()?
+            .into_iter()
+            .map(|entry| (entry.video_id, entry.format_id))
+            .collect();
+        assert_eq!(
+            formats,
+            [("beta".into(), "18".into()), ("alpha".into(), "22".into())]
+        );
+
+        queue.record("alpha", MediaKind::Video, &[])?;
+        queue.record("beta", MediaKind::Short, &[])?;
+        assert!(queue.load()?.is_empty());
+        assert!(!path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn failed_formats_are_queued_and_drained_by_process_retries() -> Result<()> {
+        let (temp, paths) = temp_paths();
+        let stub = install_ytdlp_stub(temp.path())?;
+        let _guard = set_ytdlp_stub_path(stub);
+        paths.prepare()?;
+        let mut metadata = MetadataStore::open(&paths.metadata_db)?;
+        let mut archive = HashSet::new();
+        // Metadata refreshes write the info JSON there, so check the file.
+        let published = paths
+            .media_dir(MediaKind::Video)
+            .join("alpha/alpha_1080p.mp4");
+
+        fs::write(temp.path().join("format-exit-code"), "1")?;
+        {
+            let stores = SharedStores::new(&mut archive, &mut metadata, &paths);
+            let outcome = process_media_entry("alpha", 1, 1, &paths, &stores, MediaKind::Video)?;
+            assert_eq!(outcome.download, Some(YtDlpExit::VideoFailed));
+        }
+        let queued = RetryQueue::new(&paths.retry_queue).load()?;
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].video_id, "alpha");
+        assert_eq!(queued[0].format_id, "1080p");
+        assert_eq!(queued[0].kind, MediaKind::Video);
+        assert!(queued[0].reason.contains('1'), "{}", queued[0].reason);
+        assert!(!published.exists());
+        assert!(!archive.contains("alpha"));
+
+        // Still failing: the entry stays queued and nothing is published.
+        assert_eq!(
+            process_retry_queue(&paths, &mut archive, &mut metadata)?,
+            (0, 1)
+        );
+        assert!(!published.exists());
+
+        fs::remove_file(temp.path().join("format-exit-code"))?;
+        assert_eq!(
+            process_retry_queue(&paths, &mut archive, &mut metadata)?,
+            (1, 0)
+        );
+        assert_eq!(fs::read_to_string(&published)?, "video\n");
+        assert!(!paths.retry_queue.exists());
+        assert!(archive.contains("alpha"));
+        assert!(load_archive(&paths.archive)?.contains("alpha"));
+        let reader = MetadataReader::new(&paths.metadata_db)?;
+        assert!(reader.get_video("alpha")?.is_some());
+
+        assert_eq!(
+            process_retry_queue(&paths, &mut archive, &mut metadata)?,
+            (0, 0)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn publish_staged_merges_into_an_existing_directory() -> Result<()> {
+        let dir = tempdir()?;
+        let staging = dir.path().join(".staging/alpha");
+        let published = dir.path().join("alpha");
+        fs::create_dir_all(&staging)?;
+        fs::create_dir_all(&published)?;
+        fs::write(published.join("alpha_720p.mp4"), "old")?;
+        fs::write(staging.join("alpha_1080p.mp4"), "new")?;
+
+        publish_staged(&staging, &published)?;
+        assert_eq!(fs::read_to_string(published.join("alpha_720p.mp4"))?, "old");
+        assert_eq!(
+            fs::read_to_string(published.join("alpha_1080p.mp4"))?,
+            "new"
+        );
+        assert!(!staging.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn reindex_rebuilds_the_db_from_files_on_disk() -> Result<()> {
+        let (temp, paths) = temp_paths();
+        // Reindexing must never shell out to yt-dlp.
+        let _guard = set_ytdlp_stub_path(temp.path().join("missing-yt-dlp"));
+        paths.prepare()?;
+
+        let write_info = |dir: &Path, id: &str| -> Result<()> {
+            fs::create_dir_all(dir)?;
+            let info = json!({
+                "id": id,
+                "title": format!("Title {id}"),
+                "upload_date": "20240102",
+                "formats": [{"format_id": "1080p", "ext": "mp4", "height": 1080}],
+            });
+            fs::write(dir.join(format!("{id}.info.json")), info.to_string())?;
+            fs::write(dir.join(format!("{id}_1080p.mp4")), "video")?;
+            Ok(())
+        };
+        write_info(&paths.media_dir(MediaKind::Video).join("alpha"), "alpha")?;
+        write_info(&paths.media_dir(MediaKind::Short).join("beta"), "beta")?;
+        write_info(
+            &paths
+                .media_dir(MediaKind::Video)
+                .join(layout::STAGING_DIR)
+                .join("gamma"),
+            "gamma",
+        )?;
+        let subtitle_dir = paths.subtitles.join("alpha");
+        fs::create_dir_all(&subtitle_dir)?;
+        fs::write(subtitle_dir.join("alpha.en.vtt"), "WEBVTT")?;
+        let comments_dir = paths.comments.join("alpha");
+        fs::create_dir_all(&comments_dir)?;
+        fs::write(
+            comments_dir.join("alpha.comments.json"),
+            r#"[{"id":"c1","text":"first","timestamp":1700000000}]"#,
+        )?;
+
+        let mut metadata = MetadataStore::open(&paths.metadata_db)?;
+        assert_eq!(reindex_library(&paths, &mut metadata)?, 2);
+
+        let reader = MetadataReader::new(&paths.metadata_db)?;
+        let alpha = reader.get_video("alpha")?.expect("alpha indexed");
+        assert_eq!(alpha.title, "Title alpha");
+        assert_eq!(alpha.sources.len(), 1);
+        assert!(reader.get_short("beta")?.is_some());
+        assert!(reader.get_video("gamma")?.is_none());
+        let subtitles = reader.get_subtitles("alpha")?.expect("subtitles indexed");
+        assert_eq!(subtitles.languages.len(), 1);
+        assert_eq!(reader.get_comments("alpha")?.len(), 1);
+
+        let archive = load_archive(&paths.archive)?;
+        assert!(archive.contains("alpha") && archive.contains("beta"));
+        assert!(!archive.contains("gamma"));
+
+        // Running it again is idempotent.
+        assert_eq!(reindex_library(&paths, &mut metadata)?, 2);
+        assert_eq!(fs::read_to_string(&paths.archive)?.lines().count(), 2);
+        Ok(())
+    }
+
+    const TEST_CHANNEL_KEY: &str = "https://youtube.com/@channel";
+
+    fn test_run(resume_from: Option<ResumeCursor>) -> ChannelRun {
+        ChannelRun {
+            summary: RunSummary::default(),
+            channel_key: Some(TEST_CHANNEL_KEY.into()),
+            resume_from,
+            tab_order: vec!["videos".into(), "shorts".into()],
+            jobs: 1,
+        }
+    }
+
+    #[test]
+    fn download_collection_resumes_after_interruption() -> Result<()> {
+        let (temp, paths) = temp_paths();
+        let stub = install_ytdlp_stub(temp.path())?;
+        let _guard = set_ytdlp_stub_path(stub);
+        fs::write(
+            temp.path().join("playlist-ids"),
+            "alpha\nbeta\ngamma\ndelta\n",
+        )?;
+        paths.prepare()?;
+        let mut metadata = MetadataStore::open(&paths.metadata_db)?;
+        let listing = || PlaylistListing {
+            url: "https://example.com/channel/videos".to_string(),
+            filter: None,
+            date_after: None,
+        };
+
+        // A run that got killed after `beta`: the cursor points there and
+        // `gamma` was never reached. `alpha` is missing from the archive
+        // (its download failed), so resuming must still retry it.
+        let mut archive = HashSet::from(["beta".to_string(), "gamma".to_string()]);
+        for id in ["beta", "gamma"] {
+            let media_dir = paths.media_dir(MediaKind::Video).join(id);
+            fs::create_dir_all(&media_dir)?;
+            fs::write(media_dir.join(format!("{id}_1080p.mp4")), "video-bytes")?;
+        }
+        let mut run = test_run(None);
+        metadata.save_resume_cursor(TEST_CHANNEL_KEY, "videos/beta")?;
+        run.resume_from = metadata
+            .channel_resume_cursor(TEST_CHANNEL_KEY)?
+            .as_deref()
+            .and_then(ResumeCursor::parse);
+
+        download_collection(
+            &CollectionSpec::videos(),
+            listing(),
+            &paths,
+            &mut archive,
+            &mut metadata,
+            &mut run,
+        )?;
+        assert_eq!(run.summary.resumed, 1, "beta is skipped");
+        assert_eq!(run.summary.downloaded, 2, "alpha retried, delta new");
+        assert_eq!(run.summary.refreshed, 1, "gamma refreshed");
+        assert_eq!(
+            metadata.channel_resume_cursor(TEST_CHANNEL_KEY)?.as_deref(),
+            Some("videos/delta")
+        );
+
+        // `--no-resume` walks the whole listing again.
+        let mut run = test_run(None);
+        download_collection(
+            &CollectionSpec::videos(),
+            listing(),
+            &paths,
+            &mut archive,
+            &mut metadata,
+            &mut run,
+        )?;
+        assert_eq!(run.summary.resumed, 0);
+        assert_eq!(run.summary.refreshed, 4);
+        Ok(())
+    }
+
+    #[test]
+    fn archived_entry_with_missing_media_is_downloaded_again() -> Result<()> {
+        let (temp, paths) = temp_paths();
+        let stub = install_ytdlp_stub(temp.path())?;
+        let _guard = set_ytdlp_stub_path(stub);
+        paths.prepare()?;
+        let mut metadata = MetadataStore::open(&paths.metadata_db)?;
+        let mut archive = HashSet::new();
+        let stores = SharedStores::new(&mut archive, &mut metadata, &paths);
+        let run = || process_media_entry("alpha", 1, 1, &paths, &stores, MediaKind::Video);
+
+        assert_eq!(run()?.download, Some(YtDlpExit::Success));
+        let reader = MetadataReader::new(&paths.metadata_db)?;
+        let stored = reader.get_video("alpha")?.expect("video stored");
+        let media: Vec<PathBuf> = stored
+            .sources
+            .iter()
+            .filter_map(|source| source.path.as_deref().map(PathBuf::from))
+            .collect();
+        assert!(!media.is_empty());
+
+        // Present media: archived entries only get a metadata refresh.
+        assert_eq!(run()?.download, None);
+
+        for path in &media {
+            fs::remove_file(path)?;
+        }
+        // Offloaded to an object store: the bucket serves it, no re-download.
+        let mut offloaded = Paths::from_base(temp.path());
+        offloaded.object_store = true;
+        assert!(!stored_media_missing(
+            "alpha",
+            MediaKind::Video,
+            &offloaded
+        )?);
+        assert_eq!(run()?.download, Some(YtDlpExit::Success));
+        assert!(media.iter().all(|path| path.is_file()));
+        assert_eq!(fs::read_to_string(&paths.archive)?.lines().count(), 1);
+
+        // A row without any source has nothing that could have gone missing.
+        let mut bare = stored;
+        bare.videoid = "bare".into();
+        bare.sources.clear();
+        stores.metadata().upsert_video(&bare)?;
+        assert!(!stored_media_missing("bare", MediaKind::Video, &paths)?);
+        Ok(())
+    }
+
+    #[test]
+    fn parallel_workers_process_every_entry_once() -> Result<()> {
+        let (temp, paths) = temp_paths();
+        let stub = install_ytdlp_stub(temp.path())?;
+        let _guard = set_ytdlp_stub_path(stub);
+        let ids = ["alpha", "beta", "gamma", "delta", "epsilon", "zeta"];
+        fs::write(temp.path().join("playlist-ids"), ids.join("\n") + "\n")?;
+        paths.prepare()?;
+        let mut metadata = MetadataStore::open(&paths.metadata_db)?;
+        let mut archive = HashSet::new();
+
+        let mut run = test_run(None);
+        run.jobs = 3;
+        download_collection(
+            &CollectionSpec::videos(),
+            PlaylistListing {
+                url: "https://example.com/channel/videos".to_string(),
+                filter: None,
+                date_after: None,
+            },
+            &paths,
+            &mut archive,
+            &mut metadata,
+            &mut run,
+        )?;
+
+        assert_eq!(run.summary.downloaded, ids.len());
+        assert!(run.summary.skipped.is_empty());
+        assert_eq!(archive.len(), ids.len());
+        let mut archived: Vec<String> = fs::read_to_string(&paths.archive)?
+            .lines()
+            .map(str::to_owned)
+            .collect();
+        archived.sort();
+        let mut expected: Vec<String> = ids.iter().map(|id| archive_line(id)).collect();
+        expected.sort();
+        assert_eq!(archived, expected, "one archive line per entry");
+        // Out-of-order completions still leave the cursor at the last entry.
+        assert_eq!(
+            metadata.channel_resume_cursor(TEST_CHANNEL_KEY)?.as_deref(),
+            Some("videos/zeta")
+        );
+        assert!(parse_jobs("0").is_err());
+        assert!(parse_jobs("17").is_err());
+        assert_eq!(parse_jobs(" 4 ")?, 4);
+        Ok(())
+    }
+
+    #[test]
+    fn video_and_playlist_targets_save_no_resume_cursor() -> Result<()> {
+        let (temp, paths) = temp_paths();
+        let stub = install_ytdlp_stub(temp.path())?;
+        let _guard = set_ytdlp_stub_path(stub);
+        fs::write(temp.path().join("playlist-ids"), "alpha\n")?;
+        paths.prepare()?;
+        let mut metadata = MetadataStore::open(&paths.metadata_db)?;
+        let mut archive = HashSet::new();
+
+        let mut run = test_run(None);
+        run.channel_key = None;
+        download_collection(
+            &CollectionSpec::videos(),
+            PlaylistListing {
+                url: "https://www.youtube.com/playlist?list=PL1".to_string(),
+                filter: None,
+                date_after: None,
+            },
+            &paths,
+            &mut archive,
+            &mut metadata,
+            &mut run,
+        )?;
+        assert_eq!(run.summary.downloaded, 1);
+        let conn = rusqlite::Connection::open(&paths.metadata_db)?;
+        let channels: i64 =
+            conn.query_row("SELECT COUNT(*) FROM channels", [], |row| row.get(0))?;
+        assert_eq!(channels, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn refresh_after_delete_keeps_the_row_gone() -> Result<()> {
+        let (temp, paths) = temp_paths();
+        let stub = install_ytdlp_stub(temp.path())?;
+        let _guard = set_ytdlp_stub_path(stub);
+        fs::write(temp.path().join("playlist-ids"), "alpha\nbeta\n")?;
+        paths.prepare()?;
+        let mut metadata = MetadataStore::open(&paths.metadata_db)?;
+        let mut archive = HashSet::new();
+        let refresh = |archive: &mut HashSet<String>, metadata: &mut MetadataStore| {
+            let mut run = test_run(None);
+            download_collection(
+                &CollectionSpec::videos(),
+                PlaylistListing {
+                    url: "https://example.com/channel/videos".to_string(),
+                    filter: None,
+                    date_after: None,
+                },
+                &paths,
+                archive,
+                metadata,
+                &mut run,
+            )?;
+            anyhow::Ok(run.summary)
+        };
+
+        refresh(&mut archive, &mut metadata)?;
+        assert!(metadata.delete_video("alpha")?);
+
+        let summary = refresh(&mut archive, &mut metadata)?;
+        assert_eq!((summary.downloaded, summary.refreshed), (0, 1));
+        let reader = MetadataReader::new(&paths.metadata_db)?;
+        assert!(reader.get_video("alpha")?.is_none());
+        assert!(reader.get_video("beta")?.is_some());
+        assert!(archive.contains("alpha"));
+        Ok(())
+    }
+
+    #[test]
+    fn resume_cursor_prefix_follows_tab_order() {
+        let ids: Vec<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+        let order: Vec<String> = ["videos", "shorts", "streams"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let cursor = ResumeCursor::parse("videos/b").unwrap();
+        assert_eq!(cursor.encode(), "videos/b");
+        assert_eq!(cursor.processed_prefix(&order, "videos", &ids), 2);
+        assert_eq!(cursor.processed_prefix(&order, "shorts", &ids), 0);
+
+        let cursor = ResumeCursor::parse("shorts/zzz").unwrap();
+        assert_eq!(cursor.processed_prefix(&order, "videos", &ids), 3);
+        assert_eq!(cursor.processed_prefix(&order, "shorts", &ids), 0);
+        assert_eq!(cursor.processed_prefix(&order, "streams", &ids), 0);
+
+        // A tab the current run does not walk resumes nothing.
+        let cursor = ResumeCursor::parse("podcasts/a").unwrap();
+        assert_eq!(cursor.processed_prefix(&order, "videos", &ids), 0);
+        assert!(ResumeCursor::parse("garbage").is_none());
+    }
+
+    #[test]
+    fn refresh_collection_stats_only_touches_video_rows() -> Result<()> {
+        let (temp, paths) = temp_paths();
+        let stub = install_ytdlp_stub(temp.path())?;
+        let _guard = set_ytdlp_stub_path(stub);
+        paths.prepare()?;
+        let mut metadata = MetadataStore::open(&paths.metadata_db)?;
+
+        let output_dir = paths.media_dir(MediaKind::Video);
+        let (_, mut stale) = store_video_record(
+            "alpha",
+            "https://www.youtube.com/watch?v=alpha",
+            output_dir,
+            &paths,
+            MediaKind::Video,
+            &mut metadata,
+        )?;
+        stale.title = "Stale Title".into();
+        stale.views = Some(1);
+        metadata.upsert_video(&stale)?;
+        metadata.upsert_subtitles(&SubtitleCollection {
+            videoid: "alpha".into(),
+            languages: vec![SubtitleTrack {
+                code: "xx".into(),
+                name: "Kept".into(),
+                url: "/subtitles/alpha/alpha.xx.vtt".into(),
+                path: None,
+            }],
+        })?;
+        metadata.replace_comments(
+            "alpha",
+            &[CommentRecord {
+                id: "kept".into(),
+                videoid: "alpha".into(),
+                author: "someone".into(),
+                text: "still here".into(),
+                likes: None,
+                time_posted: None,
+                time_posted_epoch: None,
+                time_posted_display: None,
+                parent_comment_id: None,
+                status_likedbycreator: false,
+                reply_count: None,
+            }],
+        )?;
+
+        let refreshed = refresh_collection_stats(
+            "test videos",
+            PlaylistListing {
+                url: "https://example.com/channel/videos".to_string(),
+                filter: None,
+                date_after: None,
+            },
+            &paths,
+            MediaKind::Video,
+            &mut metadata,
+        )?;
+        assert_eq!(refreshed, 1);
+
+        let reader = MetadataReader::new(&paths.metadata_db)?;
+        let video = reader.get_video("alpha")?.expect("video stored");
+        assert_eq!(video.title, "Alpha Title");
+        assert_eq!(video.views, Some(10));
+
+        let subtitles = reader.get_subtitles("alpha")?.expect("subtitles kept");
+        assert_eq!(subtitles.languages.len(), 1);
+        assert_eq!(subtitles.languages[0].code, "xx");
+        let comments = reader.get_comments("alpha")?;
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].id, "kept");
+        assert!(
+            !paths
+                .media_dir(MediaKind::Video)
+                .join("alpha")
+                .join("alpha_1080p.mp4")
+                .exists(),
+            "no media is downloaded"
+        );
+        Ok(())
+    }
+
+    /// Runs `download_collection` against the stub with every `--format`
+    /// download exiting with `code`.
+    fn download_with_format_exit_code(
+        code: i32,
+    ) -> Result<(Result<CollectionUploads>, RunSummary, HashSet<String>)> {
+        let (temp, paths) = temp_paths();
+        let stub = install_ytdlp_stub(temp.path())?;
+        let _guard = set_ytdlp_stub_path(stub);
+        fs::write(temp.path().join("format-exit-code"), code.to_string())?;
+        paths.prepare()?;
+        let mut metadata = MetadataStore::open(&paths.metadata_db)?;
+        let mut archive = HashSet::new();
+        let mut run = test_run(None);
+        let result = download_collection(
+            &CollectionSpec::videos(),
+            PlaylistListing {
+                url: "https://example.com/channel/videos".to_string(),
+                filter: None,
+                date_after: None,
+            },
+            &paths,
+            &mut archive,
+            &mut metadata,
+            &mut run,
+        );
+        Ok((result, run.summary, archive))
+    }
+
+    /// Only entries listed after the oldest failure (a failed download or
+    /// metadata refresh) move the cursor, and a failing tab caps the cursor
+    /// of the whole channel.
+    #[test]
+    fn failed_entries_hold_back_the_incremental_cursor() -> Result<()> {
+        let (_temp, paths) = temp_paths();
+        paths.prepare()?;
+        let mut metadata = MetadataStore::open(&paths.metadata_db)?;
+        let mut archive = HashSet::new();
+        let stores = SharedStores::new(&mut archive, &mut metadata, &paths);
+        let mut run = test_run(None);
+        let ids: Vec<String> = ["a", "b", "c", "d", "e", "f"].map(String::from).to_vec();
+        let mut progress = CollectionProgress {
+            run: &mut run,
+            uploads: vec![None; ids.len()],
+            oldest_failure: None,
+            done: vec![false; ids.len()],
+            saved: 0,
+        };
+        let processed = |date: &str, download| {
+            EntryResult::Processed(EntryOutcome {
+                upload_date: Some(date.to_owned()),
+                metadata_failed: false,
+                download,
+            })
+        };
+        let results = [
+            processed("2024-05-01", Some(YtDlpExit::Success)),
+            EntryResult::Failed,
+            processed("2024-04-01", Some(YtDlpExit::Cancelled)),
+            // Downloaded, but its metadata could not be refreshed.
+            EntryResult::Processed(EntryOutcome {
+                upload_date: None,
+                metadata_failed: true,
+                download: Some(YtDlpExit::Success),
+            }),
+            processed("2024-02-01", None),
+            processed("2024-01-01", Some(YtDlpExit::Success)),
+        ];
+        for (index, result) in results.into_iter().enumerate() {
+            progress.complete(index, &ids, result, "videos", &stores)?;
+        }
+        let videos = progress.uploads();
+        assert_eq!(videos.newest.as_deref(), Some("2024-02-01"));
+        assert!(videos.failed);
+
+        let shorts = CollectionUploads {
+            newest: Some("2024-06-01".into()),
+            failed: false,
+        };
+        assert_eq!(
+            channel_cursor(&[videos, shorts]).as_deref(),
+            Some("2024-02-01")
+        );
+        let clean = |date: &str| CollectionUploads {
+            newest: Some(date.into()),
+            failed: false,
+        };
+        assert_eq!(
+            channel_cursor(&[clean("2024-01-01"), clean("2024-06-01")]).as_deref(),
+            Some("2024-06-01")
+        );
+        let stuck = CollectionUploads {
+            newest: None,
+            failed: true,
+        };
+        assert_eq!(channel_cursor(&[stuck, clean("2024-06-01")]), None);
+        Ok(())
+    }
+
+    #[test]
+    fn download_collection_skips_videos_that_fail() -> Result<()> {
+        let (result, summary, archive) = download_with_format_exit_code(1)?;
+        assert_eq!(
+            result?,
+            CollectionUploads {
+                newest: None,
+                failed: true,
+            },
+            "a failed video keeps the cursor from moving past it"
+        );
+        assert_eq!(
+            summary.skipped,
+            vec![("alpha".to_string(), YtDlpExit::VideoFailed)]
+        );
+        assert!(
+            !archive.contains("alpha"),
+            "failed videos are retried next run"
+        );
+
+        let (result, summary, _archive) = download_with_format_exit_code(101)?;
+        assert!(result.is_ok());
+        assert_eq!(
+            summary.skipped,
+            vec![("alpha".to_string(), YtDlpExit::Cancelled)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn download_collection_aborts_on_usage_errors() -> Result<()> {
+        let (result, summary, archive) = download_with_format_exit_code(2)?;
+        let err = result.expect_err("exit code 2 must abort the run");
+        assert!(err.downcast_ref::<FatalYtDlpError>().is_some());
+        assert_eq!(abort_kind(&err), "yt-dlp invocation rejected");
+        assert_eq!(
+            abort_kind(&anyhow::anyhow!("recording channel run")),
+            "error"
+        );
+        assert_eq!(summary.downloaded, 0);
+        assert!(archive.is_empty());
+        Ok(())
+    }
+
+    /// Installs a yt-dlp stub running `body`; every call appends its
+    /// arguments to `calls.log` in `dir`.
+    fn install_script_stub(dir: &Path, body: &str) -> Result<(PathBuf, PathBuf)> {
+        let script_path = dir.join("yt-dlp-script");
+        let log = dir.join("calls.log");
+        let script = format!(
+            "#!/usr/bin/env bash\necho \"$*\" >> '{}'\ncalls=$(wc -l < '{}')\n{body}\n",
+            log.display(),
+            log.display()
+        );
+        fs::write(&script_path, script)?;
+        let mut perms = fs::metadata(&script_path)?.permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(&script_path, perms)?;
+        Ok((script_path, log))
+    }
+
+    fn logged_calls(log: &Path) -> Vec<String> {
+        fs::read_to_string(log)
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+
+    #[test]
+    fn run_ytdlp_retries_per_video_failures_only() -> Result<()> {
+        let dir = tempdir()?;
+        let (stub, log) = install_script_stub(
+            dir.path(),
+            "if [ \"$calls\" -lt 3 ]; then exit 1; fi\necho ok",
+        )?;
+        let _guard = set_ytdlp_stub_path(stub);
+        let mut settings = YtDlpSettings::new(dir.path().join(COOKIES_FILE));
+        settings.backoff = Duration::ZERO;
+        settings.retries = 2;
+        let args = ["--get-id".to_owned(), "url".to_owned()];
+
+        // Two failures, then success on the last allowed attempt.
+        let output = run_ytdlp(&settings, "listing", &args, true)?;
+        assert!(output.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+        assert_eq!(logged_calls(&log).len(), 3);
+
+        // Out of retries: the last failure is returned.
+        fs::remove_file(&log)?;
+        settings.retries = 1;
+        let output = run_ytdlp(&settings, "listing", &args, true)?;
+        assert_eq!(output.exit(), YtDlpExit::VideoFailed);
+        assert_eq!(logged_calls(&log).len(), 2);
+
+        // Usage errors are never retried.
+        let (stub, log) = install_script_stub(dir.path(), "exit 2")?;
+        drop(_guard);
+        let _guard = set_ytdlp_stub_path(stub);
+        fs::remove_file(&log)?;
+        settings.retries = 3;
+        let output = run_ytdlp(&settings, "format", &args, false)?;
+        assert_eq!(output.exit(), YtDlpExit::Fatal);
+        assert_eq!(logged_calls(&log).len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn run_ytdlp_kills_attempts_past_the_timeout() -> Result<()> {
+        let dir = tempdir()?;
+        let (stub, log) = install_script_stub(dir.path(), "exec sleep 10")?;
+        let _guard = set_ytdlp_stub_path(stub);
+        let mut settings = YtDlpSettings::new(dir.path().join(COOKIES_FILE));
+        settings.backoff = Duration::ZERO;
+        settings.retries = 1;
+        settings.timeout = Some(Duration::from_millis(200));
+
+        let started = Instant::now();
+        let output = run_ytdlp(&settings, "metadata JSON", &["url".to_owned()], true)?;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(
+            output.status,
+            YtDlpStatus::TimedOut(Duration::from_millis(200))
+        );
+        // Timeouts count as per-video failures: retried, never fatal.
+        assert_eq!(output.exit(), YtDlpExit::VideoFailed);
+        assert_eq!(logged_calls(&log).len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn run_ytdlp_adds_cookies_and_proxy() -> Result<()> {
+        let dir = tempdir()?;
+        let (stub, log) = install_script_stub(dir.path(), "exit 0")?;
+        let _guard = set_ytdlp_stub_path(stub);
+        let cookies = dir.path().join(COOKIES_FILE);
+        let mut settings = YtDlpSettings::new(cookies.clone());
+        let args = ["url".to_owned()];
+
+        run_ytdlp(&settings, "thumbnails", &args, false)?;
+        fs::write(&cookies, "# Netscape HTTP Cookie File")?;
+        settings.proxy = Some("socks5://127.0.0.1:1080".into());
+        run_ytdlp(&settings, "thumbnails", &args, false)?;
+        fs::remove_file(&cookies)?;
+        settings.proxy = None;
+        settings.rate_limit = Some("4M".into());
+        settings.sleep_interval = Some(5);
+        settings.max_sleep_interval = Some(30);
+        run_ytdlp(&settings, "thumbnails", &args, false)?;
+
+        assert_eq!(
+            logged_calls(&log),
+            [
+                "url".to_owned(),
+                format!(
+                    "url --cookies {} --proxy socks5://127.0.0.1:1080",
+                    cookies.display()
+                ),
+                "url --limit-rate 4M --sleep-interval 5 --max-sleep-interval 30".to_owned(),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn downloader_args_parse_ytdlp_settings() -> Result<()> {
+        let config = write_runtime_config(DEFAULT_MEDIA_ROOT, DEFAULT_WWW_ROOT);
+        let config = config.path().to_str().unwrap();
+        let channel = "https://www.youtube.com/@c";
+
+        let args = DownloaderArgs::from_slice(&["--config", config, channel])?;
+        assert_eq!(
+            (args.retries, args.timeout, args.proxy),
+            (DEFAULT_YTDLP_RETRIES, None, None)
+        );
+
+        let args = DownloaderArgs::from_slice(&[
+            "--config",
+            config,
+            "--retries",
+            "2",
+            "--timeout=600",
+            "--proxy",
+            "http://proxy.lan:3128",
+            channel,
+        ])?;
+        assert_eq!(args.retries, 2);
+        assert_eq!(args.timeout, Some(Duration::from_secs(600)));
+        assert_eq!(args.proxy.as_deref(), Some("http://proxy.lan:3128"));
+
+        let args = DownloaderArgs::from_slice(&[
+            "--config",
+            config,
+            "--rate-limit=1.5M",
+            "--sleep-interval",
+            "2",
+            "--max-sleep-interval=10",
+            channel,
+        ])?;
+        assert_eq!(
+            (
+                args.rate_limit.as_deref(),
+                args.sleep_interval,
+                args.max_sleep_interval
+            ),
+            (Some("1.5M"), Some(2), Some(10))
+        );
+
+        for bad in [
+            ["--retries", "11"],
+            ["--retries", "-1"],
+            ["--timeout", "0"],
+            ["--timeout", "soon"],
+            ["--proxy", ""],
+            ["--proxy", "proxy.lan:3128"],
+            ["--proxy", "ftp://proxy.lan"],
+            ["--proxy", "http://"],
+            ["--rate-limit", "4MB"],
+            ["--rate-limit", "0"],
+            ["--sleep-interval", "-5"],
+            ["--max-sleep-interval", "10"],
+        ] {
+            let mut argv = vec!["--config", config];
+            argv.extend(bad);
+            argv.push(channel);
+            assert!(DownloaderArgs::from_slice(&argv).is_err(), "{bad:?}");
+        }
         Ok(())
     }
 
+    #[test]
+    fn subtitle_command_honors_the_language_selection() -> Result<()> {
+        let dir = tempdir()?;
+        let (stub, log) = install_script_stub(dir.path(), "exit 0")?;
+        let _guard = set_ytdlp_stub_path(stub);
+        let settings = YtDlpSettings::new(dir.path().join(COOKIES_FILE));
+        let target = dir.path().join("subs");
+
+        run_subtitle_command("vid", "url", &target, DEFAULT_SUB_LANGS, true, &settings);
+        run_subtitle_command("vid", "url", &target, "en,es", false, &settings);
+
+        let output = target.join("vid").display().to_string();
+        assert_eq!(
+            logged_calls(&log),
+            [
+                format!(
+                    "--write-sub --write-auto-sub --sub-langs all --skip-download --output {output} url"
+                ),
+                format!("--write-sub --sub-langs en,es --skip-download --output {output} url"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn downloader_args_select_subtitles() -> Result<()> {
+        let mut config = write_runtime_config(DEFAULT_MEDIA_ROOT, DEFAULT_WWW_ROOT);
+        let path = config.path().to_str().unwrap().to_owned();
+        let channel = "https://www.youtube.com/@c";
+
+        let args = DownloaderArgs::from_slice(&["--config", &path, channel])?;
+        assert_eq!((args.sub_langs.as_str(), args.auto_subs), ("all", true));
+
+        let args = DownloaderArgs::from_slice(&[
+            "--config",
+            &path,
+            "--sub-langs=en, fr",
+            "--no-auto-subs",
+            channel,
+        ])?;
+        assert_eq!((args.sub_langs.as_str(), args.auto_subs), ("en,fr", false));
+        assert!(
+            DownloaderArgs::from_slice(&["--config", &path, "--sub-langs", "en;fr", channel])
+                .is_err()
+        );
+
+        writeln!(config, "SUB_LANGS=\"de\"")?;
+        let args = DownloaderArgs::from_slice(&["--config", &path, channel])?;
+        assert_eq!(args.sub_langs, "de");
+        let args = DownloaderArgs::from_slice(&["--config", &path, "--sub-langs", "ja", channel])?;
+        assert_eq!(args.sub_langs, "ja");
+        Ok(())
+    }
+
+    #[test]
+    fn downloader_args_inherit_network_settings_from_config() -> Result<()> {
+        let mut config = write_runtime_config(DEFAULT_MEDIA_ROOT, DEFAULT_WWW_ROOT);
+        writeln!(
+            config,
+            "RATE_LIMIT=\"500K\"\nSLEEP_INTERVAL=\"3\"\nMAX_SLEEP_INTERVAL=\"8\"\nPROXY=\"socks5h://vpn:1080\""
+        )?;
+        let config = config.path().to_str().unwrap();
+        let channel = "https://www.youtube.com/@c";
+
+        let args = DownloaderArgs::from_slice(&["--config", config, channel])?;
+        assert_eq!(
+            (
+                args.rate_limit.as_deref(),
+                args.sleep_interval,
+                args.max_sleep_interval
+            ),
+            (Some("500K"), Some(3), Some(8))
+        );
+        assert_eq!(args.proxy.as_deref(), Some("socks5h://vpn:1080"));
+
+        let args = DownloaderArgs::from_slice(&[
+            "--config",
+            config,
+            "--rate-limit",
+            "2M",
+            "--sleep-interval=1",
+            "--proxy=http://proxy.lan:3128",
+            channel,
+        ])?;
+        assert_eq!(args.proxy.as_deref(), Some("http://proxy.lan:3128"));
+        assert_eq!(
+            (
+                args.rate_limit.as_deref(),
+                args.sleep_interval,
+                args.max_sleep_interval
+            ),
+            (Some("2M"), Some(1), Some(8))
+        );
+
+        // The config's maximum still applies, so a larger minimum is refused.
+        let err =
+            DownloaderArgs::from_slice(&["--config", config, "--sleep-interval", "20", channel])
+                .unwrap_err();
+        assert!(err.to_string().contains("--max-sleep-interval"));
+        Ok(())
+    }
+
+    #[test]
+    fn ytdlp_exit_codes_are_classified() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let exit = |code: i32| YtDlpExit::classify(ExitStatus::from_raw(code << 8));
+        assert_eq!(exit(0), YtDlpExit::Success);
+        assert_eq!(exit(1), YtDlpExit::VideoFailed);
+        assert_eq!(exit(2), YtDlpExit::Fatal);
+        assert_eq!(exit(101), YtDlpExit::Cancelled);
+        // Killed by SIGKILL: no exit code at all.
+        assert_eq!(
+            YtDlpExit::classify(ExitStatus::from_raw(9)),
+            YtDlpExit::Fatal
+        );
+        assert_eq!(
+            YtDlpExit::Cancelled.worst(YtDlpExit::VideoFailed),
+            YtDlpExit::VideoFailed
+        );
+    }
+
     fn expected_format_ids() -> Vec<String> {
         vec![
             "133", "134", "135", "136", "137", "139", "140", "160", "18", "242", "243", "244",
@@ -1958,13 +6509,230 @@ exit 0
 
     #[test]
     fn collect_format_ids_matches_known_listing() -> Result<()> {
-        let (temp, _paths) = temp_paths();
+        let (temp, paths) = temp_paths();
         let stub = install_ytdlp_stub(temp.path())?;
         let _guard = set_ytdlp_stub_path(stub);
         let info_path = temp.path().join("empty.json");
         fs::write(&info_path, r#"{"formats":[]}"#)?;
-        let actual = collect_format_ids(&info_path, "https://www.youtube.com/watch?v=6QZz04e6gqE")?;
+        let actual = collect_format_ids(
+            &info_path,
+            "https://www.youtube.com/watch?v=6QZz04e6gqE",
+            None,
+            &paths.ytdlp,
+        )?;
         assert_eq!(actual, expected_format_ids());
         Ok(())
     }
+
+    #[test]
+    fn collect_format_ids_falls_back_to_listing() -> Result<()> {
+        let (temp, paths) = temp_paths();
+        let stub = install_ytdlp_stub(temp.path())?;
+        let _guard = set_ytdlp_stub_path(stub);
+        // Makes the stub reject `--print` so the `-F` table gets scraped.
+        fs::write(temp.path().join("no-print"), "")?;
+        let info_path = temp.path().join("empty.json");
+        fs::write(&info_path, r#"{"formats":[]}"#)?;
+        let actual = collect_format_ids(
+            &info_path,
+            "https://www.youtube.com/watch?v=6QZz04e6gqE",
+            None,
+            &paths.ytdlp,
+        )?;
+        assert_eq!(actual, expected_format_ids());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_format_listing_skips_log_lines_and_headers() {
+        let listing = "\
+[youtube] Extracting URL: https://www.youtube.com/watch?v=abc
+[info] Available formats for abc:
+ID  EXT  RESOLUTION FPS CH |  FILESIZE   TBR PROTO | VCODEC  ACODEC
+---------------------------------------------------------------------
+sb0 mhtml 320x180     1    |                 mhtml | images
+18  mp4   640x360    25  2 |    3.93MiB  185k https | avc1.42001E mp4a.40.2
+hls-1080p mp4 1920x1080 25 | ~ 45.98MiB 2167k m3u8 | avc1.640028 mp4a.40.2
+";
+        assert_eq!(
+            parse_format_listing(listing),
+            vec!["sb0", "18", "hls-1080p"]
+        );
+    }
+
+    #[test]
+    fn parse_format_listing_ignores_localized_headers() {
+        let listing = "\
+[info] Verfügbare Formate für abc:
+Kennung ERW  AUFLÖSUNG │ DATEIGRÖSSE │ VCODEC
+───────────────────────────────────────────
+137 mp4 1920x1080 │ 29.26MiB │ avc1.640028
+251 webm audio only │ 2.98MiB │ opus
+";
+        assert_eq!(parse_format_listing(listing), vec!["137", "251"]);
+
+        // Legacy tables had no separator row, only a `format code` header.
+        let legacy = "format code  extension  resolution note\n22 mp4 1280x720 hd720\n";
+        assert_eq!(parse_format_listing(legacy), vec!["22"]);
+    }
+
+    /// Accepts one HTTP request on a loopback port and hands back its raw
+    /// head and body.
+    fn one_shot_server() -> (String, thread::JoinHandle<(String, Vec<u8>)>) {
+        use std::io::Read;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut head = String::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" || line.is_empty() {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            let length = head
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().unwrap())
+                })
+                .unwrap_or(0);
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .into_inner()
+                .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            (head, body)
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn webhook_posts_the_run_summary() {
+        let mut summary = RunSummary {
+            downloaded: 3,
+            refreshed: 1,
+            ..RunSummary::default()
+        };
+        summary.record("broken", Some(YtDlpExit::VideoFailed));
+
+        let (url, server) = one_shot_server();
+        notify_webhook(
+            &url,
+            Some("s3cret"),
+            "https://www.youtube.com/@Channel",
+            &summary,
+            None,
+        );
+        let (head, body) = server.join().unwrap();
+
+        assert!(head.starts_with("POST /hook "), "{head}");
+        assert!(
+            head.to_ascii_lowercase()
+                .contains("x-newtube-webhook-secret: s3cret"),
+            "{head}"
+        );
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "channel": "https://www.youtube.com/@Channel",
+                "downloaded": 3,
+                "refreshed": 1,
+                "resumed": 0,
+                "failed": 1,
+                "failures": [{ "videoid": "broken", "reason": "download failed" }],
+                "error": null,
+            })
+        );
+
+        // A run that aborts still reports what it got done, plus the error.
+        let (url, server) = one_shot_server();
+        notify_webhook(
+            &url,
+            None,
+            "https://www.youtube.com/@Channel",
+            &summary,
+            Some("yt-dlp failed: HTTP Error 403"),
+        );
+        let (_, body) = server.join().unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["downloaded"], 3);
+        assert_eq!(body["error"], "yt-dlp failed: HTTP Error 403");
+
+        // A receiver that is down must not fail the run.
+        let dead = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let dead_url = format!("http://{}/hook", dead.local_addr().unwrap());
+        drop(dead);
+        notify_webhook(
+            &dead_url,
+            None,
+            "https://www.youtube.com/@Channel",
+            &summary,
+            None,
+        );
+    }
+
+    #[test]
+    fn run_summary_keeps_earlier_runs() -> Result<()> {
+        let temp = tempdir()?;
+        let path = temp.path().join(RUN_SUMMARY_FILE);
+        let rotation = RotationPolicy {
+            keep: 1,
+            ..RotationPolicy::default()
+        };
+        let channel = "https://www.youtube.com/@Channel";
+        for downloaded in 1..=3 {
+            let summary = RunSummary {
+                downloaded,
+                ..RunSummary::default()
+            };
+            write_run_summary(&path, &rotation, channel, &summary, None);
+        }
+        write_run_summary(
+            &path,
+            &rotation,
+            channel,
+            &RunSummary::default(),
+            Some("boom"),
+        );
+
+        let read = |path: &Path| -> Result<Value> { Ok(serde_json::from_slice(&fs::read(path)?)?) };
+        assert_eq!(read(&path)?["error"], "boom");
+        assert_eq!(
+            read(&temp.path().join("run-summary.json.1"))?["downloaded"],
+            3
+        );
+        assert!(!temp.path().join("run-summary.json.2").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn downloader_args_accept_webhook() {
+        let config = write_runtime_config(DEFAULT_MEDIA_ROOT, DEFAULT_WWW_ROOT);
+        writeln!(config.as_file(), "WEBHOOK_SECRET=\"s3cret\"").unwrap();
+        let config = config.path().to_str().unwrap();
+        let channel = "https://www.youtube.com/@Channel";
+
+        let args = DownloaderArgs::from_slice(&[
+            "--config",
+            config,
+            "--webhook",
+            "https://hooks.example/run",
+            channel,
+        ])
+        .unwrap();
+        assert_eq!(args.webhook.as_deref(), Some("https://hooks.example/run"));
+        assert_eq!(args.webhook_secret.as_deref(), Some("s3cret"));
+        assert!(
+            DownloaderArgs::from_slice(&["--config", config, "--webhook=ftp://nope", channel])
+                .is_err()
+        );
+    }
 }
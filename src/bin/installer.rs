@@ -3,14 +3,19 @@
 use anyhow::{Context, Result, anyhow, bail};
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 use blake3::Hasher;
-use clap::{ArgGroup, Parser};
+use chrono_tz::Tz;
+use clap::{ArgGroup, Parser, ValueEnum};
 use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
 use newtube_tools::config::{
     DEFAULT_CONFIG_PATH, DEFAULT_NEWTUBE_HOST, DEFAULT_NEWTUBE_PORT, DEFAULT_RELEASE_REPO,
-    EnvConfig, load_runtime_paths_from, read_env_config,
+    EnvConfig, absolute_root, ensure_roots_disjoint, is_toml_config, load_runtime_paths_from,
+    read_env_config,
 };
+use newtube_tools::logging::{self, Verbosity};
+use newtube_tools::metadata::SqliteTuning;
 use rand_core::OsRng;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{
     env,
     fs::{self, File},
@@ -18,25 +23,42 @@ use std::{
     net::IpAddr,
     os::unix::fs::{PermissionsExt, symlink},
     path::{Path, PathBuf},
-    process::Command,
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
 };
 use tar::Builder;
 use tempfile::TempDir;
-use ureq::{Agent, Response};
+use tracing::{info, warn};
+use ureq::{Agent, AgentBuilder, Response};
 use walkdir::WalkDir;
 use xz2::{read::XzDecoder, write::XzEncoder};
 
 const DEFAULT_MEDIA_DIR: &str = "/yt";
 const DEFAULT_WWW_DIR: &str = "/www/newtube.com";
-const BIN_ROOT: &str = "/opt/newtube/bin";
+const DEFAULT_BIN_ROOT: &str = "/opt/newtube/bin";
+const SYSTEMD_DIR: &str = "/etc/systemd/system";
+const INSTALLED_BINARIES: &[&str] = &["backend", "download_channel", "routine_update", "installer"];
 const DEFAULT_PUBLIC_KEY_FILENAME: &str = "release-public-key.json";
-const RELEASE_SIG_VERSION: u32 = 1;
+// Format 2 added the digest algorithm to the signed message. Updaters that
+// only know format 1 are still deployed, so blake3 releases keep being signed
+// as format 1 and only SHA-256 ones need format 2.
+const RELEASE_SIG_VERSION: u32 = 2;
+const RELEASE_SIG_LEGACY_VERSION: u32 = 1;
 const RELEASE_SIG_PREFIX: &str = "newtube-release";
 const SOURCE_ARCHIVE_PREFIX: &str = "newtube-src";
 const BINARY_ARCHIVE_PREFIX: &str = "newtube-bin";
 const SOURCE_ROOT_DIR: &str = "source";
 const BINARY_ROOT_DIR: &str = "bundle";
 const GITHUB_API_BASE: &str = "https://api.github.com";
+const DEFAULT_GITHUB_CONNECT_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_GITHUB_READ_TIMEOUT_SECS: u64 = 60;
+/// Attempts at the release metadata fetch before giving up on GitHub.
+const RELEASE_FETCH_ATTEMPTS: u32 = 3;
+const RELEASE_FETCH_RETRY_DELAY: Duration = Duration::from_secs(5);
+/// How long a freshly restarted backend gets before the updater checks that
+/// it is still running; a release that crashes on startup is gone by then.
+const UPDATE_HEALTH_DELAY: Duration = Duration::from_secs(5);
 const SOFTWARE_SERVICE: &str = "software-updater.service";
 const SOFTWARE_TIMER: &str = "software-updater.timer";
 const NGINX_SERVICE: &str = "nginx";
@@ -68,7 +90,12 @@ fn default_pubkey_path_for_www(www_root: &Path) -> PathBuf {
     www_root.join(DEFAULT_PUBLIC_KEY_FILENAME)
 }
 
+/// `RELEASE_PUBKEY` from the env file, falling back to the copy under the
+/// www root that older installs did not record.
 fn default_pubkey_from_config(config_path: &Path) -> Result<PathBuf> {
+    if let Some(path) = read_env_config(config_path)?.and_then(|env| env.release_pubkey) {
+        return Ok(path);
+    }
     let runtime = load_runtime_paths_from(config_path)?;
     Ok(default_pubkey_path_for_www(&runtime.www_root))
 }
@@ -91,7 +118,8 @@ fn resolve_runtime_pubkey_path(cli_value: &Option<PathBuf>, config_path: &Path)
             "auto_update",
             "package_release",
             "keygen",
-            "apply_archive"
+            "apply_archive",
+            "status"
         ])
         .multiple(false)
 ))]
@@ -125,24 +153,28 @@ struct Cli {
     #[arg(
         long = "port",
         value_name = "PORT",
+        value_parser = parse_port,
         help = "Override the backend API port (default 8080)"
     )]
     port: Option<u16>,
     #[arg(
         long = "host",
         value_name = "IP",
+        value_parser = parse_host,
         help = "Override the backend listen address (default 127.0.0.1)"
     )]
     host: Option<String>,
     #[arg(
         long = "domain",
         value_name = "NAME",
+        value_parser = parse_domain,
         help = "Domain name serving newtube (e.g., example.com)"
     )]
     domain: Option<String>,
     #[arg(
         long = "release-repo",
         value_name = "OWNER/REPO",
+        value_parser = parse_release_repo,
         help = "GitHub repository used for signed releases"
     )]
     release_repo: Option<String>,
@@ -210,6 +242,14 @@ struct Cli {
         help = "Path to the Ed25519 signing key generated via --keygen"
     )]
     signing_key: Option<PathBuf>,
+    #[arg(
+        long = "digest-algo",
+        value_name = "ALGO",
+        value_enum,
+        default_value_t = DigestAlgo::Blake3,
+        help = "Digest algorithm recorded in release signatures"
+    )]
+    digest_algo: DigestAlgo,
     #[arg(
         long = "keygen",
         help = "Generate an Ed25519 signing keypair used for release packaging"
@@ -228,10 +268,47 @@ struct Cli {
         help = "Path to the trusted release public key used for verification (defaults to <WWW_ROOT>/release-public-key.json)"
     )]
     trusted_pubkey: Option<PathBuf>,
+    #[arg(
+        long = "status",
+        help = "Report whether the managed units are active/enabled and the installed version"
+    )]
+    status: bool,
+    #[arg(
+        long = "json",
+        requires = "status",
+        help = "Print the --status report as JSON"
+    )]
+    json: bool,
+    #[arg(
+        long = "bin-root",
+        value_name = "PATH",
+        help = "Directory for the installed binaries (default /opt/newtube/bin)"
+    )]
+    bin_root: Option<PathBuf>,
+    #[arg(
+        long = "dry-run",
+        conflicts_with_all = ["cleanup", "keygen", "package_release", "status"],
+        help = "Log every change install, uninstall and updates would make without making it"
+    )]
+    dry_run: bool,
+    #[arg(
+        short = 'q',
+        long = "quiet",
+        conflicts_with = "verbose",
+        help = "Only log warnings and errors (RUST_LOG overrides)"
+    )]
+    quiet: bool,
+    #[arg(
+        short = 'v',
+        long = "verbose",
+        help = "Also log debug details (RUST_LOG overrides)"
+    )]
+    verbose: bool,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    logging::init("installer", Verbosity::from_flags(cli.quiet, cli.verbose)?);
     let repo_root = env::current_dir().context("Failed to determine current directory")?;
 
     if cli.cleanup {
@@ -249,11 +326,24 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    if cli.status {
+        let version = read_env_config(&cli.config)?.and_then(|cfg| cfg.app_version);
+        let status = DeploymentStatus::collect(systemctl_query, version);
+        return status.print(cli.json);
+    }
+
     ensure_root()?;
+    let host = Host {
+        dry_run: cli.dry_run,
+    };
+    if host.dry_run {
+        info!("Dry run: changes are logged with [dry-run] and not applied");
+    }
 
     if cli.apply_archive {
         let pubkey_path = resolve_runtime_pubkey_path(&cli.trusted_pubkey, &cli.config)?;
         apply_signed_source_archive(
+            host,
             &cli.config,
             cli.source_archive
                 .as_ref()
@@ -270,7 +360,7 @@ fn main() -> Result<()> {
     if cli.auto_update {
         let token = load_optional_token(cli.github_token_file.as_deref())?;
         let pubkey_path = resolve_runtime_pubkey_path(&cli.trusted_pubkey, &cli.config)?;
-        auto_update_from_github(&cli.config, &pubkey_path, token.as_deref())?;
+        auto_update_from_github(host, &cli.config, &pubkey_path, token.as_deref())?;
         return Ok(());
     }
 
@@ -341,7 +431,26 @@ fn main() -> Result<()> {
                 .unwrap_or_else(|| DEFAULT_RELEASE_REPO.to_string()),
         )
     };
+    let media_root = absolute_root("media root", media_root)?;
+    let www_root = absolute_root("www root", www_root)?;
     let app_version = determine_version(&repo_root)?;
+    let bin_root = absolute_root(
+        "bin root",
+        cli.bin_root
+            .clone()
+            .or_else(|| existing_env.as_ref().and_then(|cfg| cfg.bin_root.clone()))
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_BIN_ROOT)),
+    )?;
+
+    // Catch overlapping roots before touching the system: redeploying the
+    // frontend wipes WWW_ROOT, which must never contain downloaded media.
+    if !cli.uninstall || cli.reinstall {
+        ensure_roots_disjoint(
+            &media_root,
+            &www_root,
+            &[cli.config.as_path(), bin_root.as_path()],
+        )?;
+    }
 
     let domain = if cli.uninstall && !cli.reinstall {
         None
@@ -353,14 +462,59 @@ fn main() -> Result<()> {
         )?)
     };
 
-    let pubkey_destination = default_pubkey_path_for_www(&www_root);
-    let pubkey_source = cli
-        .trusted_pubkey
-        .clone()
-        .unwrap_or_else(|| repo_root.join(DEFAULT_PUBLIC_KEY_FILENAME));
+    // Operators add ADMIN_TOKEN by hand; carry it over so reinstalls and
+    // upgrades do not silently disable the backend's admin endpoints.
+    let admin_token = existing_env
+        .as_ref()
+        .and_then(|cfg| cfg.admin_token.clone());
+    // Same for the optional SQLite tuning keys.
+    let sqlite = existing_env
+        .as_ref()
+        .map(|cfg| SqliteTuning {
+            page_size: cfg.sqlite_page_size,
+            cache_kb: cfg.sqlite_cache_kb,
+            read_pool: cfg.sqlite_read_pool,
+        })
+        .unwrap_or_default();
+    let log_limits = existing_env
+        .as_ref()
+        .map(LogLimits::from_env)
+        .unwrap_or_default();
+    let display_timezone = existing_env.as_ref().and_then(|cfg| cfg.display_timezone);
+    let stream_rate_limit = existing_env.as_ref().and_then(|cfg| cfg.stream_rate_limit);
+    let list_description_chars = existing_env
+        .as_ref()
+        .and_then(|cfg| cfg.list_description_chars);
+    let github_timeouts = existing_env
+        .as_ref()
+        .map(GithubTimeouts::from_env)
+        .unwrap_or_default();
+    let carried = existing_env
+        .as_ref()
+        .map(CarriedKeys::from_env)
+        .unwrap_or_default();
+
+    // `--trusted-pubkey` is where the updater reads the key from, so it is
+    // also where installs put it. The repo copy seeds it when present;
+    // otherwise the key must already be in place.
+    let pubkey_destination = match &cli.trusted_pubkey {
+        Some(path) => {
+            std::path::absolute(path).with_context(|| format!("Resolving {}", path.display()))?
+        }
+        None => existing_env
+            .as_ref()
+            .and_then(|cfg| cfg.release_pubkey.clone())
+            .unwrap_or_else(|| default_pubkey_path_for_www(&www_root)),
+    };
+    let repo_pubkey = repo_root.join(DEFAULT_PUBLIC_KEY_FILENAME);
+    let pubkey_source = if repo_pubkey.exists() {
+        repo_pubkey
+    } else {
+        pubkey_destination.clone()
+    };
 
     if cli.reinstall {
-        uninstall(&media_root, &cli.config)?;
+        uninstall(host, &bin_root, &cli.config)?;
         let install_config = InstallConfig {
             media_root,
             www_root,
@@ -370,15 +524,24 @@ fn main() -> Result<()> {
             domain_name: domain.expect("domain required"),
             app_version,
             release_repo: release_repo.clone(),
+            admin_token: admin_token.clone(),
+            sqlite,
+            log_limits,
+            display_timezone,
+            stream_rate_limit,
+            list_description_chars,
+            github_timeouts,
+            carried: carried.clone(),
             assume_yes: cli.assume_yes,
             pubkey_path: pubkey_destination.clone(),
+            bin_root: bin_root.clone(),
         };
-        install(install_config, &repo_root, &pubkey_source)?;
+        install(host, install_config, &repo_root, &pubkey_source)?;
         return Ok(());
     }
 
     if cli.uninstall {
-        uninstall(&media_root, &cli.config)?;
+        uninstall(host, &bin_root, &cli.config)?;
         return Ok(());
     }
 
@@ -391,11 +554,20 @@ fn main() -> Result<()> {
         domain_name: domain.expect("domain required"),
         app_version,
         release_repo,
+        admin_token,
+        sqlite,
+        log_limits,
+        display_timezone,
+        stream_rate_limit,
+        list_description_chars,
+        github_timeouts,
+        carried,
         assume_yes: cli.assume_yes,
         pubkey_path: pubkey_destination,
+        bin_root,
     };
 
-    install(install_config, &repo_root, &pubkey_source)
+    install(host, install_config, &repo_root, &pubkey_source)
 }
 
 #[derive(Clone, Debug)]
@@ -408,64 +580,202 @@ struct InstallConfig {
     domain_name: String,
     app_version: String,
     release_repo: String,
+    admin_token: Option<String>,
+    sqlite: SqliteTuning,
+    log_limits: LogLimits,
+    display_timezone: Option<Tz>,
+    stream_rate_limit: Option<u64>,
+    list_description_chars: Option<usize>,
+    github_timeouts: GithubTimeouts,
+    carried: CarriedKeys,
     assume_yes: bool,
     pubkey_path: PathBuf,
+    bin_root: PathBuf,
+}
+
+/// Optional `LOG_*` rotation keys, carried over verbatim on reinstalls.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct LogLimits {
+    max_bytes: Option<u64>,
+    max_age_days: Option<u64>,
+    keep: Option<usize>,
+}
+
+impl LogLimits {
+    fn from_env(env: &EnvConfig) -> Self {
+        Self {
+            max_bytes: env.log_max_bytes,
+            max_age_days: env.log_max_age_days,
+            keep: env.log_keep,
+        }
+    }
+
+    fn append_to(&self, content: &mut String) {
+        if let Some(max_bytes) = self.max_bytes {
+            content.push_str(&format!("LOG_MAX_BYTES=\"{}\"\n", max_bytes));
+        }
+        if let Some(days) = self.max_age_days {
+            content.push_str(&format!("LOG_MAX_AGE_DAYS=\"{}\"\n", days));
+        }
+        if let Some(keep) = self.keep {
+            content.push_str(&format!("LOG_KEEP=\"{}\"\n", keep));
+        }
+    }
+}
+
+/// Optional `GITHUB_*_TIMEOUT_SECS` keys used by `--auto-update`, carried
+/// over verbatim on reinstalls.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct GithubTimeouts {
+    connect_secs: Option<u64>,
+    read_secs: Option<u64>,
+}
+
+impl GithubTimeouts {
+    fn from_env(env: &EnvConfig) -> Self {
+        Self {
+            connect_secs: env.github_connect_timeout_secs,
+            read_secs: env.github_read_timeout_secs,
+        }
+    }
+
+    fn append_to(&self, content: &mut String) {
+        if let Some(secs) = self.connect_secs {
+            content.push_str(&format!("GITHUB_CONNECT_TIMEOUT_SECS=\"{}\"\n", secs));
+        }
+        if let Some(secs) = self.read_secs {
+            content.push_str(&format!("GITHUB_READ_TIMEOUT_SECS=\"{}\"\n", secs));
+        }
+    }
+
+    /// An agent that gives up on a hung connection instead of blocking the
+    /// updater until systemd kills it. The read timeout applies per read, so
+    /// large downloads that keep making progress are not cut off.
+    fn agent(&self) -> Agent {
+        let connect = self
+            .connect_secs
+            .unwrap_or(DEFAULT_GITHUB_CONNECT_TIMEOUT_SECS);
+        let read = self.read_secs.unwrap_or(DEFAULT_GITHUB_READ_TIMEOUT_SECS);
+        AgentBuilder::new()
+            .timeout_connect(Duration::from_secs(connect))
+            .timeout_read(Duration::from_secs(read))
+            .build()
+    }
+}
+
+/// Optional downloader and backend keys the installer never sets itself,
+/// carried over verbatim whenever it rewrites the env file (reinstalls and
+/// every auto-update).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct CarriedKeys {
+    proxy: Option<String>,
+    rate_limit: Option<String>,
+    sleep_interval: Option<u32>,
+    max_sleep_interval: Option<u32>,
+    sub_langs: Option<String>,
+    max_height: Option<u32>,
+    webhook_secret: Option<String>,
+    object_store_url: Option<String>,
+}
+
+impl CarriedKeys {
+    fn from_env(env: &EnvConfig) -> Self {
+        Self {
+            proxy: env.proxy.clone(),
+            rate_limit: env.rate_limit.clone(),
+            sleep_interval: env.sleep_interval,
+            max_sleep_interval: env.max_sleep_interval,
+            sub_langs: env.sub_langs.clone(),
+            max_height: env.max_height,
+            webhook_secret: env.webhook_secret.clone(),
+            object_store_url: env.object_store_url.clone(),
+        }
+    }
+
+    fn append_to(&self, content: &mut String) {
+        if let Some(proxy) = &self.proxy {
+            content.push_str(&format!("PROXY=\"{}\"\n", proxy));
+        }
+        if let Some(rate_limit) = &self.rate_limit {
+            content.push_str(&format!("RATE_LIMIT=\"{}\"\n", rate_limit));
+        }
+        if let Some(sleep_interval) = self.sleep_interval {
+            content.push_str(&format!("SLEEP_INTERVAL=\"{}\"\n", sleep_interval));
+        }
+        if let Some(max_sleep_interval) = self.max_sleep_interval {
+            content.push_str(&format!("MAX_SLEEP_INTERVAL=\"{}\"\n", max_sleep_interval));
+        }
+        if let Some(sub_langs) = &self.sub_langs {
+            content.push_str(&format!("SUB_LANGS=\"{}\"\n", sub_langs));
+        }
+        if let Some(max_height) = self.max_height {
+            content.push_str(&format!("MAX_HEIGHT=\"{}\"\n", max_height));
+        }
+        if let Some(webhook_secret) = &self.webhook_secret {
+            content.push_str(&format!("WEBHOOK_SECRET=\"{}\"\n", webhook_secret));
+        }
+        if let Some(object_store_url) = &self.object_store_url {
+            content.push_str(&format!("OBJECT_STORE_URL=\"{}\"\n", object_store_url));
+        }
+    }
 }
 
-fn install(cfg: InstallConfig, repo_root: &Path, pubkey_source: &Path) -> Result<()> {
-    log_info("Starting installation");
-    fs::create_dir_all(&cfg.media_root)
+fn install(host: Host, cfg: InstallConfig, repo_root: &Path, pubkey_source: &Path) -> Result<()> {
+    info!("Starting installation");
+    host.create_dir_all(&cfg.media_root)
         .with_context(|| format!("Creating media dir {}", cfg.media_root.display()))?;
-    fs::create_dir_all(&cfg.www_root)
+    host.create_dir_all(&cfg.www_root)
         .with_context(|| format!("Creating www dir {}", cfg.www_root.display()))?;
-    ensure_directory(Path::new(BIN_ROOT), 0o750)?;
+    ensure_directory(host, &cfg.bin_root, 0o750)?;
 
-    ensure_service_accounts(&cfg)?;
-    ensure_nginx_installed(cfg.assume_yes)?;
-    deploy_nginx_config(&cfg.domain_name, &cfg.www_root, cfg.assume_yes)?;
+    ensure_service_accounts(host, &cfg)?;
+    ensure_nginx_installed(host, cfg.assume_yes)?;
+    deploy_nginx_config(host, &cfg.domain_name, &cfg.www_root, cfg.assume_yes)?;
 
-    write_env_config(&cfg)?;
-    install_trusted_pubkey(pubkey_source, &cfg.pubkey_path)?;
-    install_systemd_units(&cfg)?;
-    build_from_workspace(repo_root, &cfg)?;
+    write_env_config(host, &cfg)?;
+    install_trusted_pubkey(host, pubkey_source, &cfg.pubkey_path)?;
+    install_systemd_units(host, &cfg, Path::new(SYSTEMD_DIR))?;
+    build_from_workspace(host, repo_root, &cfg)?;
 
-    run_command("systemctl", &["daemon-reload"])?;
-    run_command("systemctl", &["enable", "--now", BACKEND_SERVICE])?;
-    run_command("systemctl", &["enable", "--now", ROUTINE_SERVICE])?;
-    run_command("systemctl", &["enable", "--now", SOFTWARE_TIMER])?;
+    host.run("systemctl", &["daemon-reload"])?;
+    host.run("systemctl", &["enable", "--now", BACKEND_SERVICE])?;
+    host.run("systemctl", &["enable", "--now", ROUTINE_SERVICE])?;
+    host.run("systemctl", &["enable", "--now", SOFTWARE_TIMER])?;
     show_status()?;
 
     Ok(())
 }
 
-fn uninstall(_media_root: &Path, config_path: &Path) -> Result<()> {
-    log_info("Stopping timer and removing files");
-    let _ = run_command_allow_fail("systemctl", &["disable", "--now", SOFTWARE_TIMER]);
-    let _ = run_command_allow_fail("systemctl", &["disable", "--now", SOFTWARE_SERVICE]);
-    let _ = run_command_allow_fail("systemctl", &["disable", "--now", BACKEND_SERVICE]);
-    let _ = run_command_allow_fail("systemctl", &["disable", "--now", ROUTINE_SERVICE]);
+fn uninstall(host: Host, bin_root: &Path, config_path: &Path) -> Result<()> {
+    info!("Stopping timer and removing files");
+    let _ = host.run_allow_fail("systemctl", &["disable", "--now", SOFTWARE_TIMER]);
+    let _ = host.run_allow_fail("systemctl", &["disable", "--now", SOFTWARE_SERVICE]);
+    let _ = host.run_allow_fail("systemctl", &["disable", "--now", BACKEND_SERVICE]);
+    let _ = host.run_allow_fail("systemctl", &["disable", "--now", ROUTINE_SERVICE]);
 
-    let systemd_dir = PathBuf::from("/etc/systemd/system");
-    remove_path_if_exists(&systemd_dir.join(SOFTWARE_SERVICE))?;
-    remove_path_if_exists(&systemd_dir.join(SOFTWARE_TIMER))?;
-    remove_path_if_exists(&systemd_dir.join(BACKEND_SERVICE))?;
-    remove_path_if_exists(&systemd_dir.join(ROUTINE_SERVICE))?;
+    let systemd_dir = PathBuf::from(SYSTEMD_DIR);
+    host.remove_file(&systemd_dir.join(SOFTWARE_SERVICE))?;
+    host.remove_file(&systemd_dir.join(SOFTWARE_TIMER))?;
+    host.remove_file(&systemd_dir.join(BACKEND_SERVICE))?;
+    host.remove_file(&systemd_dir.join(ROUTINE_SERVICE))?;
 
-    run_command("systemctl", &["daemon-reload"])?;
+    host.run("systemctl", &["daemon-reload"])?;
 
-    if config_path.exists() {
-        fs::remove_file(config_path)
-            .with_context(|| format!("Removing {}", config_path.display()))?;
+    host.remove_file(config_path)?;
+    // The bin root may be a shared directory, so only remove what we put
+    // there and drop the directory itself once it is empty.
+    for bin in INSTALLED_BINARIES {
+        host.remove_file(&bin_root.join(bin))?;
     }
-    if Path::new(BIN_ROOT).exists() {
-        fs::remove_dir_all(BIN_ROOT).with_context(|| format!("Removing {}", BIN_ROOT))?;
+    if !host.skip(|| format!("remove {} if it is empty", bin_root.display())) {
+        let _ = fs::remove_dir(bin_root);
     }
-    log_info("Uninstall complete");
+    info!("Uninstall complete");
     Ok(())
 }
 
 fn cleanup_repo(repo_root: &Path) -> Result<()> {
-    log_info("Cleaning repo artifacts");
+    info!("Cleaning repo artifacts");
     for dir in ["node_modules", "coverage"] {
         let path = repo_root.join(dir);
         if path.exists() {
@@ -487,82 +797,83 @@ fn cleanup_repo(repo_root: &Path) -> Result<()> {
     Ok(())
 }
 
-fn ensure_directory(path: &Path, mode: u32) -> Result<()> {
-    fs::create_dir_all(path).with_context(|| format!("Creating {}", path.display()))?;
-    fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
-    Ok(())
+fn ensure_directory(host: Host, path: &Path, mode: u32) -> Result<()> {
+    host.create_dir_all(path)?;
+    host.set_mode(path, mode)
 }
 
-fn install_trusted_pubkey(source: &Path, dest: &Path) -> Result<()> {
+fn install_trusted_pubkey(host: Host, source: &Path, dest: &Path) -> Result<()> {
     if dest.exists() {
         return Ok(());
     }
     if let Some(parent) = dest.parent() {
-        fs::create_dir_all(parent)?;
+        host.create_dir_all(parent)?;
     }
     if !source.exists() {
         bail!(
-            "Trusted public key not found at {}. Provide --trusted-pubkey pointing to the signer public key or copy one to {}.",
+            "Trusted public key not found at {}. Copy the signer public key to {} (or into the repo root as {}).",
             source.display(),
-            dest.display()
+            dest.display(),
+            DEFAULT_PUBLIC_KEY_FILENAME
         );
     }
-    fs::copy(source, dest)
-        .with_context(|| format!("Copying {} to {}", source.display(), dest.display()))?;
-    fs::set_permissions(dest, fs::Permissions::from_mode(0o640))?;
-    Ok(())
+    host.copy(source, dest)?;
+    host.set_mode(dest, 0o640)
 }
 
-fn build_from_workspace(repo_root: &Path, cfg: &InstallConfig) -> Result<()> {
-    log_info("Building release binaries from working tree");
-    run_command_in_dir("cargo", &["build", "--release"], repo_root)?;
-    install_release_binaries(repo_root, Path::new(BIN_ROOT))?;
-    copy_frontend_assets(repo_root, &cfg.www_root)?;
-    ensure_media_permissions(&cfg.media_root)?;
+fn build_from_workspace(host: Host, repo_root: &Path, cfg: &InstallConfig) -> Result<()> {
+    info!("Building release binaries from working tree");
+    host.run_in_dir("cargo", &["build", "--release"], repo_root)?;
+    install_release_binaries(host, repo_root, &cfg.bin_root)?;
+    copy_frontend_assets(host, repo_root, &cfg.www_root)?;
+    ensure_media_permissions(host, &cfg.media_root)?;
     Ok(())
 }
 
-fn install_release_binaries(build_root: &Path, dest_dir: &Path) -> Result<()> {
+fn install_release_binaries(host: Host, build_root: &Path, dest_dir: &Path) -> Result<()> {
     let target_dir = build_root.join("target").join("release");
-    let binaries = ["backend", "download_channel", "routine_update", "installer"];
-    for bin in binaries {
+    for bin in INSTALLED_BINARIES {
         let src = target_dir.join(bin);
-        if !src.exists() {
+        // A dry run skipped the build, so the binaries are not there yet.
+        if !src.exists() && !host.dry_run {
             bail!(
                 "Missing compiled binary {}. Run cargo build --release first.",
                 src.display()
             );
         }
         let dest = dest_dir.join(bin);
-        copy_executable(&src, &dest)?;
+        copy_executable(host, &src, &dest)?;
     }
     Ok(())
 }
 
-fn copy_executable(src: &Path, dest: &Path) -> Result<()> {
+fn copy_executable(host: Host, src: &Path, dest: &Path) -> Result<()> {
     if let Some(parent) = dest.parent() {
-        fs::create_dir_all(parent)?;
+        host.create_dir_all(parent)?;
     }
-    fs::copy(src, dest)
-        .with_context(|| format!("Copying {} to {}", src.display(), dest.display()))?;
-    fs::set_permissions(dest, fs::Permissions::from_mode(0o750))?;
-    chown_to("root", NEWTUBE_GROUP, dest)?;
+    host.copy(src, dest)?;
+    host.set_mode(dest, 0o750)?;
+    chown_to(host, "root", NEWTUBE_GROUP, dest)?;
     Ok(())
 }
 
-fn chown_to(owner: &str, group: &str, path: &Path) -> Result<()> {
-    let status = Command::new("chown")
-        .arg(format!("{}:{}", owner, group))
-        .arg(path)
-        .status()
-        .with_context(|| format!("Updating ownership on {}", path.display()))?;
-    if !status.success() {
-        bail!("chown failed for {}", path.display());
-    }
-    Ok(())
+fn chown_to(host: Host, owner: &str, group: &str, path: &Path) -> Result<()> {
+    let owner = format!("{}:{}", owner, group);
+    let target = path.to_string_lossy();
+    host.run("chown", &[owner.as_str(), &target])
+        .with_context(|| format!("Updating ownership on {}", path.display()))
 }
 
-fn copy_frontend_assets(src_root: &Path, dest_root: &Path) -> Result<()> {
+fn copy_frontend_assets(host: Host, src_root: &Path, dest_root: &Path) -> Result<()> {
+    if host.skip(|| {
+        format!(
+            "replace {} with the frontend assets from {} (keeping {DEFAULT_PUBLIC_KEY_FILENAME})",
+            dest_root.display(),
+            src_root.display()
+        )
+    }) {
+        return Ok(());
+    }
     let preserved_key_path = dest_root.join(DEFAULT_PUBLIC_KEY_FILENAME);
     let preserved_key = fs::read(&preserved_key_path).ok();
     if dest_root.exists() {
@@ -624,29 +935,17 @@ fn copy_file_with_mode(src: &Path, dest: &Path, mode: u32) -> Result<()> {
     Ok(())
 }
 
-fn ensure_media_permissions(media_root: &Path) -> Result<()> {
-    if !media_root.exists() {
+fn ensure_media_permissions(host: Host, media_root: &Path) -> Result<()> {
+    // During a dry run the media root may only exist in the plan.
+    if !media_root.exists() && !host.dry_run {
         return Ok(());
     }
     let owner = format!("{}:{}", DOWNLOADER_USER, NEWTUBE_GROUP);
-    let status = Command::new("chown")
-        .arg("-R")
-        .arg(&owner)
-        .arg(media_root)
-        .status()
+    let target = media_root.to_string_lossy();
+    host.run("chown", &["-R", owner.as_str(), &target])
         .with_context(|| format!("Setting ownership on {}", media_root.display()))?;
-    if !status.success() {
-        bail!("chown failed for {}", media_root.display());
-    }
-    let chmod_status = Command::new("chmod")
-        .arg("-R")
-        .arg("g+rwX")
-        .arg(media_root)
-        .status()
+    host.run("chmod", &["-R", "g+rwX", &target])
         .with_context(|| format!("Setting permissions on {}", media_root.display()))?;
-    if !chmod_status.success() {
-        bail!("chmod failed for {}", media_root.display());
-    }
     Ok(())
 }
 
@@ -678,10 +977,10 @@ fn load_optional_token(path: Option<&Path>) -> Result<Option<String>> {
                 return Ok(Some(trimmed));
             }
         } else {
-            log_info(format!(
+            info!(
                 "GitHub token file {} not found; continuing without one",
                 file.display()
-            ));
+            );
         }
     }
     if let Ok(env_token) = env::var("GITHUB_TOKEN") {
@@ -693,28 +992,85 @@ fn load_optional_token(path: Option<&Path>) -> Result<Option<String>> {
     Ok(None)
 }
 
-fn write_env_config(cfg: &InstallConfig) -> Result<()> {
-    let content = format!(
-        "MEDIA_ROOT=\"{}\"\nWWW_ROOT=\"{}\"\nNEWTUBE_PORT=\"{}\"\nNEWTUBE_HOST=\"{}\"\nAPP_VERSION=\"{}\"\nDOMAIN_NAME=\"{}\"\nRELEASE_REPO=\"{}\"\n",
+fn write_env_config(host: Host, cfg: &InstallConfig) -> Result<()> {
+    host.write(&cfg.config_path, render_config(cfg))?;
+    host.set_mode(&cfg.config_path, 0o640)?;
+    let owner = format!("root:{}", NEWTUBE_GROUP);
+    let target = cfg.config_path.to_string_lossy().into_owned();
+    let args = [owner.as_str(), target.as_str()];
+    host.run("chown", &args)?;
+    Ok(())
+}
+
+/// Renders the config in the flavor `read_env_config` expects for
+/// `cfg.config_path`: TOML for `*.toml`, the `KEY="value"` env file otherwise.
+fn render_config(cfg: &InstallConfig) -> String {
+    let content = render_env_config(cfg);
+    if !is_toml_config(&cfg.config_path) {
+        return content;
+    }
+    content
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            let value = value.trim_matches('"');
+            // Numbers stay typed unless that would change them (`007`).
+            let value = match value.parse::<u64>() {
+                Ok(number) if number.to_string() == value => number.to_string(),
+                _ => toml::Value::String(value.to_string()).to_string(),
+            };
+            format!("{} = {value}\n", key.to_ascii_lowercase())
+        })
+        .collect()
+}
+
+fn render_env_config(cfg: &InstallConfig) -> String {
+    let mut content = format!(
+        "MEDIA_ROOT=\"{}\"\nWWW_ROOT=\"{}\"\nNEWTUBE_PORT=\"{}\"\nNEWTUBE_HOST=\"{}\"\nAPP_VERSION=\"{}\"\nDOMAIN_NAME=\"{}\"\nRELEASE_REPO=\"{}\"\nBIN_ROOT=\"{}\"\nRELEASE_PUBKEY=\"{}\"\n",
         cfg.media_root.display(),
         cfg.www_root.display(),
         cfg.newtube_port,
         cfg.newtube_host,
         cfg.app_version,
         cfg.domain_name,
-        cfg.release_repo
+        cfg.release_repo,
+        cfg.bin_root.display(),
+        cfg.pubkey_path.display()
     );
-    fs::write(&cfg.config_path, content)
-        .with_context(|| format!("Writing {}", cfg.config_path.display()))?;
-    fs::set_permissions(&cfg.config_path, fs::Permissions::from_mode(0o640))?;
-    let owner = format!("root:{}", NEWTUBE_GROUP);
-    let target = cfg.config_path.to_string_lossy().into_owned();
-    let args = [owner.as_str(), target.as_str()];
-    run_command("chown", &args)?;
-    Ok(())
+    if let Some(token) = &cfg.admin_token {
+        content.push_str(&format!("ADMIN_TOKEN=\"{}\"\n", token));
+    }
+    if let Some(page_size) = cfg.sqlite.page_size {
+        content.push_str(&format!("SQLITE_PAGE_SIZE=\"{}\"\n", page_size));
+    }
+    if let Some(cache_kb) = cfg.sqlite.cache_kb {
+        content.push_str(&format!("SQLITE_CACHE_KB=\"{}\"\n", cache_kb));
+    }
+    if let Some(read_pool) = cfg.sqlite.read_pool {
+        content.push_str(&format!("SQLITE_READ_POOL=\"{}\"\n", read_pool));
+    }
+    cfg.log_limits.append_to(&mut content);
+    if let Some(tz) = cfg.display_timezone {
+        content.push_str(&format!("DISPLAY_TIMEZONE=\"{}\"\n", tz.name()));
+    }
+    if let Some(limit) = cfg.stream_rate_limit {
+        content.push_str(&format!("STREAM_RATE_LIMIT=\"{}\"\n", limit));
+    }
+    if let Some(chars) = cfg.list_description_chars {
+        content.push_str(&format!("LIST_DESCRIPTION_CHARS=\"{}\"\n", chars));
+    }
+    cfg.github_timeouts.append_to(&mut content);
+    cfg.carried.append_to(&mut content);
+    content
 }
 
 fn env_to_install_config(env: EnvConfig, config_path: PathBuf) -> Result<InstallConfig> {
+    let log_limits = LogLimits::from_env(&env);
+    let display_timezone = env.display_timezone;
+    let stream_rate_limit = env.stream_rate_limit;
+    let list_description_chars = env.list_description_chars;
+    let github_timeouts = GithubTimeouts::from_env(&env);
+    let carried = CarriedKeys::from_env(&env);
     let media_root = env
         .media_root
         .ok_or_else(|| anyhow!("MEDIA_ROOT missing from {}", config_path.display()))?;
@@ -736,8 +1092,25 @@ fn env_to_install_config(env: EnvConfig, config_path: PathBuf) -> Result<Install
         release_repo: env
             .release_repo
             .unwrap_or_else(|| DEFAULT_RELEASE_REPO.to_string()),
+        admin_token: env.admin_token,
+        sqlite: SqliteTuning {
+            page_size: env.sqlite_page_size,
+            cache_kb: env.sqlite_cache_kb,
+            read_pool: env.sqlite_read_pool,
+        },
+        log_limits,
+        display_timezone,
+        stream_rate_limit,
+        list_description_chars,
+        github_timeouts,
+        carried,
         assume_yes: true,
-        pubkey_path: default_pubkey_path_for_www(&www_root),
+        pubkey_path: env
+            .release_pubkey
+            .unwrap_or_else(|| default_pubkey_path_for_www(&www_root)),
+        bin_root: env
+            .bin_root
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_BIN_ROOT)),
     })
 }
 
@@ -762,10 +1135,10 @@ fn resolve_media_root(
         return Ok(existing_path.clone());
     }
     if assume_yes {
-        log_info(format!(
+        info!(
             "Using default media root {} due to --assume-yes",
             DEFAULT_MEDIA_DIR
-        ));
+        );
         return Ok(PathBuf::from(DEFAULT_MEDIA_DIR));
     }
     prompt_for_media_root(existing)
@@ -792,10 +1165,10 @@ fn resolve_www_root(
         return Ok(existing_path.clone());
     }
     if assume_yes {
-        log_info(format!(
+        info!(
             "Using default WWW root {} due to --assume-yes",
             DEFAULT_WWW_DIR
-        ));
+        );
         return Ok(PathBuf::from(DEFAULT_WWW_DIR));
     }
     prompt_for_www_root(existing)
@@ -815,10 +1188,10 @@ fn resolve_port(cli_value: Option<u16>, existing: Option<u16>, assume_yes: bool)
         return Ok(existing_port);
     }
     if assume_yes {
-        log_info(format!(
+        info!(
             "Using default NEWTUBE_PORT {} due to --assume-yes",
             DEFAULT_NEWTUBE_PORT
-        ));
+        );
         return Ok(DEFAULT_NEWTUBE_PORT);
     }
     prompt_for_port(existing)
@@ -830,7 +1203,7 @@ fn resolve_host(
     assume_yes: bool,
 ) -> Result<String> {
     if let Some(host) = cli_value {
-        return validate_host(&host);
+        return Ok(host);
     }
     if let Some(ref existing_host) = existing
         && (assume_yes
@@ -842,10 +1215,10 @@ fn resolve_host(
         return Ok(existing_host.clone());
     }
     if assume_yes {
-        log_info(format!(
+        info!(
             "Using default NEWTUBE_HOST {} due to --assume-yes",
             DEFAULT_NEWTUBE_HOST
-        ));
+        );
         return Ok(DEFAULT_NEWTUBE_HOST.to_string());
     }
     prompt_for_host(existing)
@@ -857,7 +1230,7 @@ fn resolve_release_repo(
     assume_yes: bool,
 ) -> Result<String> {
     if let Some(repo) = cli_value {
-        return normalize_release_repo(&repo);
+        return Ok(repo);
     }
     if let Some(ref saved) = existing
         && (assume_yes || prompt_yes_no(&format!("Use detected release repo '{saved}'?"), true)?)
@@ -865,10 +1238,10 @@ fn resolve_release_repo(
         return Ok(saved.clone());
     }
     if assume_yes {
-        log_info(format!(
+        info!(
             "Using default release repo {} due to --assume-yes",
             DEFAULT_RELEASE_REPO
-        ));
+        );
         return Ok(DEFAULT_RELEASE_REPO.to_string());
     }
     prompt_for_release_repo(existing)
@@ -914,10 +1287,9 @@ fn prompt_for_port(default: Option<u16>) -> Result<u16> {
         if trimmed.is_empty() {
             return Ok(suggested);
         }
-        match trimmed.parse::<u16>() {
-            Ok(value @ 1..=65535) => return Ok(value),
-            Ok(_) => println!("Port must be between 1 and 65535."),
-            Err(_) => println!("Please enter a valid number between 1 and 65535."),
+        match parse_port(trimmed) {
+            Ok(value) => return Ok(value),
+            Err(err) => println!("{err}."),
         }
     }
 }
@@ -969,6 +1341,29 @@ fn prompt_for_release_repo(default: Option<String>) -> Result<String> {
     }
 }
 
+// clap value parsers: CLI flags are validated while parsing arguments so a bad
+// `--assume-yes` invocation fails before anything on the system is touched.
+// Each one reuses the checks applied to the interactive prompts.
+
+fn parse_port(input: &str) -> Result<u16, String> {
+    match input.trim().parse::<u16>() {
+        Ok(value @ 1..=65535) => Ok(value),
+        _ => Err(format!("port must be between 1 and 65535, got '{input}'")),
+    }
+}
+
+fn parse_host(input: &str) -> Result<String, String> {
+    validate_host(input).map_err(|err| format!("{err:#}"))
+}
+
+fn parse_release_repo(input: &str) -> Result<String, String> {
+    normalize_release_repo(input).map_err(|err| err.to_string())
+}
+
+fn parse_domain(input: &str) -> Result<String, String> {
+    normalize_domain(input).map_err(|err| err.to_string())
+}
+
 fn normalize_release_repo(input: &str) -> Result<String> {
     let trimmed = input.trim();
     if trimmed.is_empty() {
@@ -1028,7 +1423,7 @@ fn resolve_domain(
     assume_yes: bool,
 ) -> Result<String> {
     if let Some(domain) = cli_domain {
-        return normalize_domain(&domain);
+        return Ok(domain);
     }
     if let Some(existing_domain) = existing.and_then(|cfg| cfg.domain_name.clone())
         && (assume_yes
@@ -1102,46 +1497,151 @@ fn prompt_yes_no(prompt: &str, default_yes: bool) -> Result<bool> {
     }
 }
 
-fn ensure_nginx_installed(assume_yes: bool) -> Result<()> {
+fn ensure_nginx_installed(host: Host, assume_yes: bool) -> Result<()> {
     if service_exists(NGINX_SERVICE)? {
-        log_info("nginx service detected");
+        info!("nginx service detected");
         return Ok(());
     }
-    log_info("nginx service not detected");
+    info!("nginx service not detected");
     if assume_yes || prompt_yes_no("Install nginx via package manager?", false)? {
-        install_nginx_package().context("Unable to install nginx")?;
+        install_nginx_package(host).context("Unable to install nginx")?;
     } else {
         bail!("nginx is required for setup");
     }
     Ok(())
 }
 
-fn ensure_service_accounts(cfg: &InstallConfig) -> Result<()> {
-    ensure_group_exists(NEWTUBE_GROUP)?;
-    ensure_user_exists(BACKEND_USER, NEWTUBE_GROUP, BACKEND_HOME)?;
-    ensure_user_exists(DOWNLOADER_USER, NEWTUBE_GROUP, DOWNLOADER_HOME)?;
-    ensure_media_permissions(&cfg.media_root)?;
+fn ensure_service_accounts(host: Host, cfg: &InstallConfig) -> Result<()> {
+    ensure_group_exists(host, NEWTUBE_GROUP)?;
+    ensure_user_exists(host, BACKEND_USER, NEWTUBE_GROUP, BACKEND_HOME)?;
+    ensure_user_exists(host, DOWNLOADER_USER, NEWTUBE_GROUP, DOWNLOADER_HOME)?;
+    ensure_media_permissions(host, &cfg.media_root)?;
+    verify_media_access(host, &cfg.media_root)?;
     Ok(())
 }
 
-fn ensure_group_exists(name: &str) -> Result<()> {
+/// File the access check creates as the downloader and reads back as the
+/// backend; removed again afterwards.
+const ACCESS_PROBE_FILE: &str = ".newtube-access-check";
+
+/// Tool used to run a command as a service account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SwitchUser {
+    Runuser,
+    Su,
+}
+
+/// Builds `program args...` run as `user`. `su` only accepts a shell command
+/// line, so each word is single-quoted for it.
+fn as_user_command(switch: SwitchUser, user: &str, program: &str, args: &[&Path]) -> Command {
+    match switch {
+        SwitchUser::Runuser => {
+            let mut command = Command::new("runuser");
+            command.args(["-u", user, "--", program]).args(args);
+            command
+        }
+        SwitchUser::Su => {
+            let mut line = shell_quote(program);
+            for arg in args {
+                line.push(' ');
+                line.push_str(&shell_quote(&arg.to_string_lossy()));
+            }
+            let mut command = Command::new("su");
+            command.args(["-s", "/bin/sh", "-c", &line, user]);
+            command
+        }
+    }
+}
+
+fn shell_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+/// Proves the permissions set by [`ensure_media_permissions`] work for the
+/// real accounts: the downloader creates a file in the media root and the
+/// backend reads it back. ACLs, read-only or `root_squash` mounts and similar
+/// surprises otherwise only show up once the services start failing.
+fn verify_media_access(host: Host, media_root: &Path) -> Result<()> {
+    if host.skip(|| {
+        format!(
+            "check that {DOWNLOADER_USER} can write and {BACKEND_USER} can read {}",
+            media_root.display()
+        )
+    }) || !media_root.exists()
+    {
+        return Ok(());
+    }
+    let switch = if command_exists("runuser") {
+        SwitchUser::Runuser
+    } else if command_exists("su") {
+        SwitchUser::Su
+    } else {
+        info!("Neither runuser nor su is available; skipping the media root access check");
+        return Ok(());
+    };
+    info!(
+        "Checking that {DOWNLOADER_USER} can write and {BACKEND_USER} can read {}",
+        media_root.display()
+    );
+
+    let probe = media_root.join(ACCESS_PROBE_FILE);
+    remove_path_if_exists(&probe)?;
+    let result = run_as(switch, DOWNLOADER_USER, "touch", &probe)
+        .with_context(|| {
+            format!(
+                "{DOWNLOADER_USER} cannot create files in {}; check ACLs and mount options (read-only, root_squash)",
+                media_root.display()
+            )
+        })
+        .and_then(|()| {
+            run_as(switch, BACKEND_USER, "cat", &probe).with_context(|| {
+                format!(
+                    "{BACKEND_USER} cannot read files the downloader writes to {} (expected access through group {NEWTUBE_GROUP}); check ACLs and mount options",
+                    media_root.display()
+                )
+            })
+        });
+    remove_path_if_exists(&probe)?;
+    result
+}
+
+/// Runs `program path` as `user`, failing with its stderr.
+fn run_as(switch: SwitchUser, user: &str, program: &str, path: &Path) -> Result<()> {
+    let output = as_user_command(switch, user, program, &[path])
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run {program} as {user}"))?;
+    if !output.status.success() {
+        bail!(
+            "{program} {} as {user} exited with {}: {}",
+            path.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+fn ensure_group_exists(host: Host, name: &str) -> Result<()> {
     match Command::new("getent").args(["group", name]).status() {
         Ok(status) if status.success() => return Ok(()),
         Ok(_) | Err(_) => {}
     }
-    run_command("groupadd", &["--system", name])
+    host.run("groupadd", &["--system", name])
 }
 
-fn ensure_user_exists(user: &str, group: &str, home: &str) -> Result<()> {
+fn ensure_user_exists(host: Host, user: &str, group: &str, home: &str) -> Result<()> {
     match Command::new("id").args(["-u", user]).status() {
         Ok(status) if status.success() => {
-            fs::create_dir_all(home).with_context(|| format!("Creating home directory {home}"))?;
+            host.create_dir_all(Path::new(home))
+                .with_context(|| format!("Creating home directory {home}"))?;
             return Ok(());
         }
         Ok(_) | Err(_) => {}
     }
 
-    fs::create_dir_all(home).with_context(|| format!("Creating home directory {home}"))?;
+    host.create_dir_all(Path::new(home))
+        .with_context(|| format!("Creating home directory {home}"))?;
     let home_owned = home.to_string();
     let args = [
         "--system",
@@ -1154,7 +1654,7 @@ fn ensure_user_exists(user: &str, group: &str, home: &str) -> Result<()> {
         group,
         user,
     ];
-    run_command("useradd", &args)
+    host.run("useradd", &args)
 }
 
 fn service_exists(name: &str) -> Result<bool> {
@@ -1166,32 +1666,32 @@ fn service_exists(name: &str) -> Result<bool> {
         .any(|line| line.starts_with(&needle)))
 }
 
-fn install_nginx_package() -> Result<()> {
+fn install_nginx_package(host: Host) -> Result<()> {
     let manager = detect_package_manager()
         .ok_or_else(|| anyhow!("Could not detect a supported package manager"))?;
     match manager {
         "apt-get" => {
-            run_command("apt-get", &["update"])?;
-            run_command("apt-get", &["install", "-y", "nginx"])?;
+            host.run("apt-get", &["update"])?;
+            host.run("apt-get", &["install", "-y", "nginx"])?;
         }
         "apt" => {
-            run_command("apt", &["update"])?;
-            run_command("apt", &["install", "-y", "nginx"])?;
+            host.run("apt", &["update"])?;
+            host.run("apt", &["install", "-y", "nginx"])?;
         }
-        "dnf" => run_command("dnf", &["install", "-y", "nginx"])?,
-        "yum" => run_command("yum", &["install", "-y", "nginx"])?,
-        "pacman" => run_command("pacman", &["-Sy", "--noconfirm", "nginx"])?,
+        "dnf" => host.run("dnf", &["install", "-y", "nginx"])?,
+        "yum" => host.run("yum", &["install", "-y", "nginx"])?,
+        "pacman" => host.run("pacman", &["-Sy", "--noconfirm", "nginx"])?,
         "apk" => {
-            run_command("apk", &["update"])?;
-            run_command("apk", &["add", "nginx"])?;
+            host.run("apk", &["update"])?;
+            host.run("apk", &["add", "nginx"])?;
         }
         "zypper" => {
-            run_command("zypper", &["refresh"])?;
-            run_command("zypper", &["install", "-y", "nginx"])?;
+            host.run("zypper", &["refresh"])?;
+            host.run("zypper", &["install", "-y", "nginx"])?;
         }
         other => bail!("Unsupported package manager {other}"),
     }
-    run_command("systemctl", &["enable", "--now", NGINX_SERVICE])?;
+    host.run("systemctl", &["enable", "--now", NGINX_SERVICE])?;
     Ok(())
 }
 
@@ -1211,7 +1711,7 @@ fn command_exists(bin: &str) -> bool {
     false
 }
 
-fn deploy_nginx_config(domain: &str, www_root: &Path, assume_yes: bool) -> Result<()> {
+fn deploy_nginx_config(host: Host, domain: &str, www_root: &Path, assume_yes: bool) -> Result<()> {
     let (config_path, symlink_path) = if Path::new("/etc/nginx/sites-available").is_dir() {
         (
             PathBuf::from("/etc/nginx/sites-available/newtube.conf"),
@@ -1230,42 +1730,39 @@ fn deploy_nginx_config(domain: &str, www_root: &Path, assume_yes: bool) -> Resul
         config_path.display()
     );
     if !(assume_yes || prompt_yes_no(&prompt, false)?) {
-        log_info("Skipping nginx config deployment");
+        info!("Skipping nginx config deployment");
         return Ok(());
     }
     if let Some(parent) = config_path.parent() {
-        fs::create_dir_all(parent)?;
+        host.create_dir_all(parent)?;
     }
     let server_block = format!(
         "server {{\n    listen 80;\n    listen [::]:80;\n    server_name {domain};\n\n    return 301 https://{domain}$request_uri;\n}}\n\nserver {{\n    listen 443 ssl http2;\n    listen [::]:443 ssl http2;\n    server_name {domain};\n\n    ssl_certificate /etc/letsencrypt/live/{domain}/fullchain.pem;\n    ssl_certificate_key /etc/letsencrypt/live/{domain}/privkey.pem;\n    ssl_prefer_server_ciphers on;\n\n    root {www};\n    index index.html;\n\n    location / {{\n        try_files $uri $uri/ /index.html;\n    }}\n}}\n",
         domain = domain,
         www = www_root.display()
     );
-    fs::write(&config_path, server_block)?;
+    host.write(&config_path, server_block)?;
     if let Some(symlink_dest) = symlink_path {
         if let Some(parent) = symlink_dest.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        if symlink_dest.exists() {
-            fs::remove_file(&symlink_dest)?;
+            host.create_dir_all(parent)?;
         }
-        symlink(&config_path, symlink_dest)?;
+        host.remove_file(&symlink_dest)?;
+        host.symlink(&config_path, &symlink_dest)?;
     }
-    run_command("nginx", &["-t"])?;
-    run_command("systemctl", &["reload", NGINX_SERVICE])?;
+    host.run("nginx", &["-t"])?;
+    host.run("systemctl", &["reload", NGINX_SERVICE])?;
     Ok(())
 }
 
-fn install_systemd_units(cfg: &InstallConfig) -> Result<()> {
-    let systemd_dir = PathBuf::from("/etc/systemd/system");
-    fs::create_dir_all(&systemd_dir)?;
+fn install_systemd_units(host: Host, cfg: &InstallConfig, systemd_dir: &Path) -> Result<()> {
+    host.create_dir_all(systemd_dir)?;
 
     let updater_service = systemd_dir.join(SOFTWARE_SERVICE);
     let timer_path = systemd_dir.join(SOFTWARE_TIMER);
     let backend_service = systemd_dir.join(BACKEND_SERVICE);
     let routine_service = systemd_dir.join(ROUTINE_SERVICE);
 
-    let installer_exec = escape_systemd_path(&Path::new(BIN_ROOT).join("installer"))?;
+    let installer_exec = escape_systemd_path(&cfg.bin_root.join("installer"))?;
     let pubkey_path = escape_systemd_path(&cfg.pubkey_path)?;
     let config_path = escape_systemd_path(&cfg.config_path)?;
     let updater_contents = format!(
@@ -1274,13 +1771,13 @@ fn install_systemd_units(cfg: &InstallConfig) -> Result<()> {
         config = config_path,
         pubkey = pubkey_path
     );
-    fs::write(&updater_service, updater_contents)?;
+    host.write(&updater_service, updater_contents)?;
 
     let timer_contents = "[Unit]\nDescription=Scan for signed newtube releases nightly\n\n[Timer]\nOnCalendar=*-*-* 03:00\nPersistent=true\nUnit=software-updater.service\n\n[Install]\nWantedBy=timers.target\n";
-    fs::write(&timer_path, timer_contents)?;
+    host.write(&timer_path, timer_contents)?;
 
     let media_work_dir = escape_systemd_path(&cfg.media_root)?;
-    let backend_exec = escape_systemd_path(&Path::new(BIN_ROOT).join("backend"))?;
+    let backend_exec = escape_systemd_path(&cfg.bin_root.join("backend"))?;
     let backend_contents = format!(
         "[Unit]\nDescription=newtube backend API\nAfter=network-online.target\nWants=network-online.target\n\n[Service]\nUser={user}\nGroup={group}\nWorkingDirectory={work}\nExecStart={exec} --config {config}\nRestart=on-failure\nRestartSec=2\nAmbientCapabilities=\nCapabilityBoundingSet=\nNoNewPrivileges=yes\nProtectSystem=full\nProtectHome=read-only\nPrivateTmp=yes\nRestrictAddressFamilies=AF_INET AF_INET6 AF_UNIX\nRestrictSUIDSGID=yes\nRestrictRealtime=yes\nLockPersonality=yes\nUMask=0027\nReadWritePaths={work}\n\n[Install]\nWantedBy=multi-user.target\n",
         user = BACKEND_USER,
@@ -1289,12 +1786,12 @@ fn install_systemd_units(cfg: &InstallConfig) -> Result<()> {
         exec = backend_exec,
         config = config_path
     );
-    fs::write(&backend_service, backend_contents)?;
+    host.write(&backend_service, backend_contents)?;
 
-    let routine_exec = escape_systemd_path(&Path::new(BIN_ROOT).join("routine_update"))?;
+    let routine_exec = escape_systemd_path(&cfg.bin_root.join("routine_update"))?;
     let www_dir = escape_systemd_path(&cfg.www_root)?;
     let routine_contents = format!(
-        "[Unit]\nDescription=newtube nightly channel refresh\nAfter=network-online.target\nWants=network-online.target\n\n[Service]\nType=oneshot\nUser={user}\nGroup={group}\nWorkingDirectory={work}\nExecStart={exec} --config {config} --media-root {work} --www-root {www}\nAmbientCapabilities=\nCapabilityBoundingSet=\nNoNewPrivileges=yes\nProtectSystem=full\nProtectHome=read-only\nPrivateTmp=yes\nRestrictAddressFamilies=AF_INET AF_INET6 AF_UNIX\nRestrictSUIDSGID=yes\nRestrictRealtime=yes\nLockPersonality=yes\nUMask=0027\nReadWritePaths={work}\n\n[Install]\nWantedBy=multi-user.target\n",
+        "[Unit]\nDescription=newtube nightly channel refresh\nAfter=network-online.target\nWants=network-online.target\n\n[Service]\nType=oneshot\nUser={user}\nGroup={group}\nWorkingDirectory={work}\nExecStartPre={exec} --config {config} --media-root {work} --www-root {www} --preflight\nExecStart={exec} --config {config} --media-root {work} --www-root {www}\nAmbientCapabilities=\nCapabilityBoundingSet=\nNoNewPrivileges=yes\nProtectSystem=full\nProtectHome=read-only\nPrivateTmp=yes\nRestrictAddressFamilies=AF_INET AF_INET6 AF_UNIX\nRestrictSUIDSGID=yes\nRestrictRealtime=yes\nLockPersonality=yes\nUMask=0027\nReadWritePaths={work}\n\n[Install]\nWantedBy=multi-user.target\n",
         user = DOWNLOADER_USER,
         group = NEWTUBE_GROUP,
         work = media_work_dir,
@@ -1302,7 +1799,7 @@ fn install_systemd_units(cfg: &InstallConfig) -> Result<()> {
         config = config_path,
         www = www_dir
     );
-    fs::write(&routine_service, routine_contents)?;
+    host.write(&routine_service, routine_contents)?;
     Ok(())
 }
 
@@ -1363,13 +1860,17 @@ fn package_release_artifacts(repo_root: &Path, cli: &Cli) -> Result<()> {
     package_binary_archive(repo_root, &bin_path)?;
 
     let signing_key = load_signing_key(signing_key_path)?;
-    sign_release_file(&src_path, &signature_path_for(&src_path), &signing_key, tag)?;
-    sign_release_file(&bin_path, &signature_path_for(&bin_path), &signing_key, tag)?;
+    for artifact in [&src_path, &bin_path] {
+        sign_release_file(
+            artifact,
+            &signature_path_for(artifact),
+            &signing_key,
+            tag,
+            cli.digest_algo,
+        )?;
+    }
 
-    log_info(format!(
-        "Release artifacts written to {}",
-        output_dir.display()
-    ));
+    info!("Release artifacts written to {}", output_dir.display());
     Ok(())
 }
 
@@ -1433,7 +1934,7 @@ fn package_binary_archive(repo_root: &Path, dest: &Path) -> Result<()> {
     fs::create_dir_all(&bin_stage)?;
     fs::create_dir_all(&www_stage)?;
     copy_release_binaries_for_archive(repo_root, &bin_stage)?;
-    copy_frontend_assets(repo_root, &www_stage)?;
+    copy_frontend_assets(Host::default(), repo_root, &www_stage)?;
 
     let file = File::create(dest)?;
     let encoder = XzEncoder::new(file, 9);
@@ -1447,8 +1948,7 @@ fn package_binary_archive(repo_root: &Path, dest: &Path) -> Result<()> {
 
 fn copy_release_binaries_for_archive(repo_root: &Path, dest_dir: &Path) -> Result<()> {
     let target_dir = repo_root.join("target").join("release");
-    let binaries = ["backend", "download_channel", "routine_update", "installer"];
-    for bin in binaries {
+    for bin in INSTALLED_BINARIES {
         let src = target_dir.join(bin);
         if !src.exists() {
             bail!("Missing compiled binary {}", src.display());
@@ -1469,13 +1969,19 @@ fn sign_release_file(
     signature_path: &Path,
     signing_key: &SigningKey,
     version: &str,
+    digest_algo: DigestAlgo,
 ) -> Result<()> {
-    let digest = compute_blake3_hex(artifact)?;
-    let message = signature_message(version, &digest);
+    let digest = compute_digest_hex(artifact, digest_algo)?;
+    let format = match digest_algo {
+        DigestAlgo::Blake3 => RELEASE_SIG_LEGACY_VERSION,
+        DigestAlgo::Sha256 => RELEASE_SIG_VERSION,
+    };
+    let message = signature_message(format, version, digest_algo, &digest);
     let signature = signing_key.sign(&message);
     let payload = ReleaseSignature {
-        format: RELEASE_SIG_VERSION,
+        format,
         version: version.into(),
+        digest_algo,
         digest,
         signature: BASE64.encode(signature.to_bytes()),
     };
@@ -1484,6 +1990,7 @@ fn sign_release_file(
 }
 
 fn auto_update_from_github(
+    host: Host,
     config_path: &Path,
     pubkey_path: &Path,
     token: Option<&str>,
@@ -1500,28 +2007,26 @@ fn auto_update_from_github(
         .clone()
         .unwrap_or_else(|| DEFAULT_RELEASE_REPO.to_string());
 
-    let agent = Agent::new();
-    let release = fetch_latest_release(&agent, &release_repo, token)?;
-    if !current_version.is_empty() && current_version == release.tag_name {
-        log_info(format!(
-            "Already running latest release {}; skipping update",
-            release.tag_name
-        ));
+    let agent = GithubTimeouts::from_env(&env_cfg).agent();
+    let release = fetch_latest_release(
+        &agent,
+        GITHUB_API_BASE,
+        &release_repo,
+        token,
+        RELEASE_FETCH_RETRY_DELAY,
+    )?;
+    if !release_is_newer(&current_version, &release.tag_name) {
+        info!(
+            "Latest release {} is not newer than installed {}; skipping update",
+            release.tag_name, current_version
+        );
         return Ok(());
     }
 
     let src_name = format!("{SOURCE_ARCHIVE_PREFIX}-{}.tar.xz", release.tag_name);
     let sig_name = format!("{SOURCE_ARCHIVE_PREFIX}-{}.tar.xz.sig", release.tag_name);
-    let src_asset = release
-        .assets
-        .iter()
-        .find(|asset| asset.name == src_name)
-        .ok_or_else(|| anyhow!("Source archive {src_name} not found in latest release"))?;
-    let sig_asset = release
-        .assets
-        .iter()
-        .find(|asset| asset.name == sig_name)
-        .ok_or_else(|| anyhow!("Signature {sig_name} not found in latest release"))?;
+    let src_asset = find_asset(&release, &src_name)?;
+    let sig_asset = find_asset(&release, &sig_name)?;
 
     let temp = TempDir::new()?;
     let src_path = temp.path().join(&src_name);
@@ -1530,6 +2035,7 @@ fn auto_update_from_github(
     download_asset(&agent, &sig_asset.browser_download_url, token, &sig_path)?;
 
     apply_signed_source_archive(
+        host,
         config_path,
         &src_path,
         &sig_path,
@@ -1538,14 +2044,72 @@ fn auto_update_from_github(
     )
 }
 
-fn fetch_latest_release(agent: &Agent, repo: &str, token: Option<&str>) -> Result<GithubRelease> {
-    let url = format!("{GITHUB_API_BASE}/repos/{repo}/releases/latest");
-    let response = github_get(agent, &url, token)?;
+/// Whether `tag` should replace the installed `current` version. Both are
+/// compared as semver with an optional leading `v`, so `v1.2.0` matches
+/// `1.2.0` and older tags never downgrade the install. When either side is
+/// not semver, any difference counts as an update, as before.
+fn release_is_newer(current: &str, tag: &str) -> bool {
+    if current.is_empty() {
+        return true;
+    }
+    let parse = |value: &str| semver::Version::parse(value.trim().trim_start_matches('v'));
+    match (parse(current), parse(tag)) {
+        (Ok(current), Ok(tag)) => tag > current,
+        _ => {
+            warn!(
+                "cannot compare versions {current:?} and {tag:?} as semver; falling back to an exact match"
+            );
+            current != tag
+        }
+    }
+}
+
+/// Fetches the latest release metadata, retrying transient failures
+/// (timeouts, refused connections, 5xx/429) up to
+/// [`RELEASE_FETCH_ATTEMPTS`] times so a blip does not skip a night's update.
+fn fetch_latest_release(
+    agent: &Agent,
+    api_base: &str,
+    repo: &str,
+    token: Option<&str>,
+    retry_delay: Duration,
+) -> Result<GithubRelease> {
+    let url = format!("{api_base}/repos/{repo}/releases/latest");
+    let mut attempt = 1;
+    let response = loop {
+        match github_get(agent, &url, token) {
+            Ok(response) => break response,
+            Err(GithubError::Transient(err)) if attempt < RELEASE_FETCH_ATTEMPTS => {
+                info!(
+                    "GitHub request failed (attempt {attempt}/{RELEASE_FETCH_ATTEMPTS}): {err}; retrying"
+                );
+                thread::sleep(retry_delay);
+                attempt += 1;
+            }
+            Err(GithubError::Transient(err)) => {
+                bail!("GitHub unreachable: {url} failed after {attempt} attempts: {err}")
+            }
+            Err(GithubError::Fatal(err)) => return Err(err),
+        }
+    };
     response
         .into_json::<GithubRelease>()
         .map_err(|err| anyhow!("Failed to parse release JSON: {err}"))
 }
 
+fn find_asset<'a>(release: &'a GithubRelease, name: &str) -> Result<&'a GithubAsset> {
+    release
+        .assets
+        .iter()
+        .find(|asset| asset.name == name)
+        .ok_or_else(|| {
+            anyhow!(
+                "No matching asset: release {} has no {name}",
+                release.tag_name
+            )
+        })
+}
+
 fn download_asset(agent: &Agent, url: &str, token: Option<&str>, dest: &Path) -> Result<()> {
     let mut request = agent.get(url).set("User-Agent", "newtube-installer");
     if let Some(token) = token {
@@ -1554,28 +2118,84 @@ fn download_asset(agent: &Agent, url: &str, token: Option<&str>, dest: &Path) ->
     let response = request
         .call()
         .map_err(|err| anyhow!("Failed to download asset {url}: {err}"))?;
+    let expected = response
+        .header("Content-Length")
+        .and_then(|value| value.trim().parse::<u64>().ok());
+    write_download(response.into_reader(), expected, dest)
+        .with_context(|| format!("Failed to download asset {url}"))
+}
+
+/// Streams `reader` into `<dest>.partial` and renames it over `dest` only
+/// once every byte announced by `Content-Length` has arrived, so a dropped
+/// connection never leaves an archive that looks complete.
+fn write_download(mut reader: impl Read, expected: Option<u64>, dest: &Path) -> Result<()> {
     if let Some(parent) = dest.parent() {
         fs::create_dir_all(parent)?;
     }
-    let mut file = File::create(dest)?;
-    std::io::copy(&mut response.into_reader(), &mut file)?;
-    Ok(())
+    let mut partial = dest.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    let result = (|| -> Result<()> {
+        let mut file = File::create(&partial)?;
+        let mut buf = [0u8; 64 * 1024];
+        let mut written = 0u64;
+        loop {
+            let read = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                // ureq reports a body that ends before Content-Length as
+                // UnexpectedEof; the length check below explains it better.
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(err.into()),
+            };
+            file.write_all(&buf[..read])?;
+            written += read as u64;
+        }
+        if let Some(expected) = expected
+            && written != expected
+        {
+            bail!("download truncated: expected {expected} bytes got {written}");
+        }
+        file.sync_all()?;
+        fs::rename(&partial, dest)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    result
+}
+
+/// Splits failures worth retrying from ones a retry cannot fix (a wrong
+/// repo, a bad token).
+enum GithubError {
+    Transient(anyhow::Error),
+    Fatal(anyhow::Error),
 }
 
-fn github_get(agent: &Agent, url: &str, token: Option<&str>) -> Result<Response> {
+fn github_get(agent: &Agent, url: &str, token: Option<&str>) -> Result<Response, GithubError> {
     let mut request = agent.get(url).set("User-Agent", "newtube-installer");
     if let Some(token) = token {
         request = request.set("Authorization", &format!("token {token}"));
     }
-    let response = request
-        .call()
-        .map_err(|err| anyhow!("GitHub request failed: {err}"))?;
+    let response = match request.call() {
+        Ok(response) => response,
+        Err(err @ (ureq::Error::Transport(_) | ureq::Error::Status(429 | 500.., _))) => {
+            return Err(GithubError::Transient(err.into()));
+        }
+        Err(ureq::Error::Status(status, _)) => {
+            return Err(GithubError::Fatal(anyhow!(
+                "GitHub API returned status {status} for {url}"
+            )));
+        }
+    };
     if !(200..300).contains(&response.status()) {
-        bail!(
+        return Err(GithubError::Fatal(anyhow!(
             "GitHub API returned status {} for {}",
             response.status(),
             url
-        );
+        )));
     }
     Ok(response)
 }
@@ -1593,6 +2213,7 @@ struct GithubAsset {
 }
 
 fn apply_signed_source_archive(
+    host: Host,
     config_path: &Path,
     artifact: &Path,
     signature: &Path,
@@ -1609,10 +2230,10 @@ fn apply_signed_source_archive(
         );
     }
 
-    log_info(format!(
+    info!(
         "Applying release {} (digest {})",
         metadata.version, metadata.digest
-    ));
+    );
 
     let temp = TempDir::new()?;
     let decoder = XzDecoder::new(File::open(artifact)?);
@@ -1624,39 +2245,200 @@ fn apply_signed_source_archive(
         bail!("Release archive missing '{}' directory", SOURCE_ROOT_DIR);
     }
 
-    run_command_in_dir("cargo", &["build", "--release"], &source_root)?;
-    install_release_binaries(&source_root, Path::new(BIN_ROOT))?;
-
-    let runtime = load_runtime_paths_from(config_path)?;
-    copy_frontend_assets(&source_root, &runtime.www_root)?;
-    ensure_media_permissions(&runtime.media_root)?;
-
     let env_cfg = read_env_config(config_path)?.ok_or_else(|| {
         anyhow!(
             "Missing env config at {} when updating release",
             config_path.display()
         )
     })?;
-    let mut snapshot = env_to_install_config(env_cfg, config_path.to_path_buf())?;
-    snapshot.app_version = metadata.version.clone();
-    write_env_config(&snapshot)?;
+    let bin_root = env_cfg
+        .bin_root
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_BIN_ROOT));
+
+    host.run_in_dir("cargo", &["build", "--release"], &source_root)?;
 
-    run_command("systemctl", &["restart", BACKEND_SERVICE])?;
-    run_command("systemctl", &["restart", ROUTINE_SERVICE])?;
-    run_command("systemctl", &["reload", NGINX_SERVICE])?;
+    let runtime = load_runtime_paths_from(config_path)?;
+    ensure_roots_disjoint(
+        &runtime.media_root,
+        &runtime.www_root,
+        &[config_path, bin_root.as_path()],
+    )?;
+    let previous_version = env_cfg
+        .app_version
+        .clone()
+        .unwrap_or_else(|| "unknown".into());
+    let backup = if host.skip(|| {
+        format!(
+            "back up the binaries in {}, {} and {} before updating",
+            bin_root.display(),
+            runtime.www_root.display(),
+            config_path.display()
+        )
+    }) {
+        None
+    } else {
+        Some(ReleaseBackup::take(
+            &bin_root,
+            &runtime.www_root,
+            config_path,
+        )?)
+    };
+
+    let applied = (|| -> Result<()> {
+        install_release_binaries(host, &source_root, &bin_root)?;
+        copy_frontend_assets(host, &source_root, &runtime.www_root)?;
+        ensure_media_permissions(host, &runtime.media_root)?;
+
+        let mut snapshot = env_to_install_config(env_cfg, config_path.to_path_buf())?;
+        snapshot.app_version = metadata.version.clone();
+        write_env_config(host, &snapshot)?;
+
+        restart_services(host)?;
+        if host.dry_run {
+            return Ok(());
+        }
+        ensure_backend_started(systemctl_query, UPDATE_HEALTH_DELAY)
+    })();
+
+    let Some(backup) = backup else {
+        return applied;
+    };
+    match applied {
+        Ok(()) => {
+            backup.discard();
+            Ok(())
+        }
+        Err(err) => {
+            info!(
+                "Release {} failed: {err:#}; restoring {previous_version}",
+                metadata.version
+            );
+            backup
+                .restore()
+                .and_then(|()| restart_services(host))
+                .with_context(|| {
+                    format!(
+                        "Release {} failed ({err:#}) and rolling back to {previous_version} also failed; the backup is kept at {}",
+                        metadata.version,
+                        backup.dir.display()
+                    )
+                })?;
+            backup.discard();
+            bail!(
+                "Release {} failed ({err:#}); rolled back to {previous_version}",
+                metadata.version
+            )
+        }
+    }
+}
+
+fn restart_services(host: Host) -> Result<()> {
+    host.run("systemctl", &["restart", BACKEND_SERVICE])?;
+    host.run("systemctl", &["restart", ROUTINE_SERVICE])?;
+    host.run("systemctl", &["reload", NGINX_SERVICE])
+}
+
+/// Fails unless the backend is still active `delay` after its restart.
+/// `query` is [`systemctl_query`] outside tests.
+fn ensure_backend_started(query: impl Fn(&str, &str) -> String, delay: Duration) -> Result<()> {
+    thread::sleep(delay);
+    let state = query("is-active", BACKEND_SERVICE);
+    if state != "active" {
+        bail!("{BACKEND_SERVICE} is {state} after the restart");
+    }
     Ok(())
 }
 
+/// Copy of the installed binaries, web root and env file taken before an
+/// update overwrites them, kept in a timestamped directory next to the bin
+/// root until the new release is known to start.
+struct ReleaseBackup {
+    dir: PathBuf,
+    bin_root: PathBuf,
+    www_root: PathBuf,
+    config_path: PathBuf,
+}
+
+impl ReleaseBackup {
+    fn take(bin_root: &Path, www_root: &Path, config_path: &Path) -> Result<Self> {
+        let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%SZ");
+        let dir = bin_root
+            .parent()
+            .unwrap_or(bin_root)
+            .join(format!("rollback-{stamp}"));
+        info!("Backing up the current release to {}", dir.display());
+        fs::create_dir_all(dir.join("bin"))
+            .with_context(|| format!("Creating backup dir {}", dir.display()))?;
+        for bin in INSTALLED_BINARIES {
+            let installed = bin_root.join(bin);
+            if installed.exists() {
+                fs::copy(&installed, dir.join("bin").join(bin))
+                    .with_context(|| format!("Backing up {}", installed.display()))?;
+            }
+        }
+        if www_root.exists() {
+            copy_directory_recursive(www_root, &dir.join("www"))?;
+        }
+        fs::copy(config_path, dir.join("env"))
+            .with_context(|| format!("Backing up {}", config_path.display()))?;
+        Ok(Self {
+            dir,
+            bin_root: bin_root.to_path_buf(),
+            www_root: www_root.to_path_buf(),
+            config_path: config_path.to_path_buf(),
+        })
+    }
+
+    /// Puts every backed-up file back in place. Binaries are copied over the
+    /// installed ones so their ownership is kept.
+    fn restore(&self) -> Result<()> {
+        for bin in INSTALLED_BINARIES {
+            let saved = self.dir.join("bin").join(bin);
+            if saved.exists() {
+                let installed = self.bin_root.join(bin);
+                fs::copy(&saved, &installed)
+                    .with_context(|| format!("Restoring {}", installed.display()))?;
+            }
+        }
+        let saved_www = self.dir.join("www");
+        if saved_www.exists() {
+            if self.www_root.exists() {
+                fs::remove_dir_all(&self.www_root)
+                    .with_context(|| format!("Removing {}", self.www_root.display()))?;
+            }
+            fs::create_dir_all(&self.www_root)?;
+            copy_directory_recursive(&saved_www, &self.www_root)?;
+        }
+        fs::copy(self.dir.join("env"), &self.config_path)
+            .with_context(|| format!("Restoring {}", self.config_path.display()))?;
+        Ok(())
+    }
+
+    /// Deletes the backup. A leftover directory only costs disk space, so a
+    /// failure is logged rather than failing the update.
+    fn discard(&self) {
+        if let Err(err) = fs::remove_dir_all(&self.dir) {
+            info!("Could not remove backup {}: {err}", self.dir.display());
+        }
+    }
+}
+
 fn verify_release_signature(
     artifact: &Path,
     signature_path: &Path,
     verifying_key: &VerifyingKey,
 ) -> Result<ReleaseSignature> {
     let payload: ReleaseSignature = serde_json::from_slice(&fs::read(signature_path)?)?;
-    if payload.format != RELEASE_SIG_VERSION {
-        bail!("Unsupported release signature format {}", payload.format);
+    match payload.format {
+        RELEASE_SIG_VERSION => {}
+        RELEASE_SIG_LEGACY_VERSION if payload.digest_algo == DigestAlgo::Blake3 => {}
+        RELEASE_SIG_LEGACY_VERSION => {
+            bail!("Release signature format 1 only supports blake3 digests")
+        }
+        other => bail!("Unsupported release signature format {other}"),
     }
-    let digest = compute_blake3_hex(artifact)?;
+    let digest = compute_digest_hex(artifact, payload.digest_algo)?;
     if digest != payload.digest {
         bail!(
             "Release checksum mismatch (expected {}, got {})",
@@ -1669,7 +2451,12 @@ fn verify_release_signature(
         .try_into()
         .map_err(|_| anyhow!("Invalid signature length"))?;
     let signature = Signature::from_bytes(&signature_bytes);
-    let message = signature_message(&payload.version, &payload.digest);
+    let message = signature_message(
+        payload.format,
+        &payload.version,
+        payload.digest_algo,
+        &payload.digest,
+    );
     verifying_key
         .verify_strict(&message, &signature)
         .map_err(|_| anyhow!("Signature verification failed"))?;
@@ -1700,26 +2487,70 @@ fn load_public_key(path: &Path) -> Result<VerifyingKey> {
     VerifyingKey::from_bytes(&public_bytes).map_err(|err| anyhow!("{err}"))
 }
 
-fn signature_message(version: &str, digest_hex: &str) -> Vec<u8> {
+/// Builds the exact bytes covered by the Ed25519 signature. Format 1 predates
+/// `digest_algo` and never included it, so legacy signatures keep verifying.
+fn signature_message(format: u32, version: &str, algo: DigestAlgo, digest_hex: &str) -> Vec<u8> {
+    if format == RELEASE_SIG_LEGACY_VERSION {
+        return format!("{RELEASE_SIG_PREFIX}|v{format}|{version}|{digest_hex}").into_bytes();
+    }
     format!(
-        "{}|v{}|{}|{}",
-        RELEASE_SIG_PREFIX, RELEASE_SIG_VERSION, version, digest_hex
+        "{RELEASE_SIG_PREFIX}|v{format}|{version}|{}|{digest_hex}",
+        algo.as_str()
     )
     .into_bytes()
 }
 
+fn compute_digest_hex(path: &Path, algo: DigestAlgo) -> Result<String> {
+    match algo {
+        DigestAlgo::Blake3 => compute_blake3_hex(path),
+        DigestAlgo::Sha256 => compute_sha256_hex(path),
+    }
+}
+
 fn compute_blake3_hex(path: &Path) -> Result<String> {
-    let mut file = File::open(path)?;
     let mut hasher = Hasher::new();
+    hash_file(path, |chunk| {
+        hasher.update(chunk);
+    })?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+fn compute_sha256_hex(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    hash_file(path, |chunk| hasher.update(chunk))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Streams `path` through `update` in fixed-size chunks.
+fn hash_file(path: &Path, mut update: impl FnMut(&[u8])) -> Result<()> {
+    let mut file = File::open(path)?;
     let mut buffer = [0u8; 8192];
     loop {
         let read = file.read(&mut buffer)?;
         if read == 0 {
             break;
         }
-        hasher.update(&buffer[..read]);
+        update(&buffer[..read]);
+    }
+    Ok(())
+}
+
+/// Hash used for the `digest` field of a release signature.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+enum DigestAlgo {
+    #[default]
+    Blake3,
+    Sha256,
+}
+
+impl DigestAlgo {
+    fn as_str(self) -> &'static str {
+        match self {
+            DigestAlgo::Blake3 => "blake3",
+            DigestAlgo::Sha256 => "sha256",
+        }
     }
-    Ok(hasher.finalize().to_hex().to_string())
 }
 
 #[derive(Serialize, Deserialize)]
@@ -1739,10 +2570,77 @@ struct SerializedPublicKey {
 struct ReleaseSignature {
     format: u32,
     version: String,
+    /// Missing in format 1 files, which were always blake3.
+    #[serde(default)]
+    digest_algo: DigestAlgo,
     digest: String,
     signature: String,
 }
 
+/// `is-active`/`is-enabled` answers for one managed unit.
+#[derive(Debug, Serialize)]
+struct UnitStatus {
+    unit: &'static str,
+    active: String,
+    enabled: String,
+}
+
+/// Machine-readable deployment state reported by `--status`.
+#[derive(Debug, Serialize)]
+struct DeploymentStatus {
+    backend: UnitStatus,
+    routine: UnitStatus,
+    updater_timer: UnitStatus,
+    /// `APP_VERSION` from the env file; `None` before the first install.
+    version: Option<String>,
+}
+
+impl DeploymentStatus {
+    /// `query(verb, unit)` returns what `systemctl <verb> <unit>` printed.
+    fn collect(query: impl Fn(&str, &str) -> String, version: Option<String>) -> Self {
+        let unit = |unit: &'static str| UnitStatus {
+            unit,
+            active: query("is-active", unit),
+            enabled: query("is-enabled", unit),
+        };
+        Self {
+            backend: unit(BACKEND_SERVICE),
+            routine: unit(ROUTINE_SERVICE),
+            updater_timer: unit(SOFTWARE_TIMER),
+            version,
+        }
+    }
+
+    fn print(&self, json: bool) -> Result<()> {
+        if json {
+            println!("{}", serde_json::to_string_pretty(self)?);
+            return Ok(());
+        }
+        for unit in [&self.backend, &self.routine, &self.updater_timer] {
+            println!("{}: {}, {}", unit.unit, unit.active, unit.enabled);
+        }
+        println!(
+            "version: {}",
+            self.version.as_deref().unwrap_or("not installed")
+        );
+        Ok(())
+    }
+}
+
+/// Runs `systemctl <verb> <unit>` and returns its one-word answer. Both
+/// verbs exit non-zero for states like `inactive`/`disabled`, so only the
+/// output matters; a unit systemctl cannot report on reads as `unknown`.
+fn systemctl_query(verb: &str, unit: &str) -> String {
+    Command::new("systemctl")
+        .args([verb, unit])
+        .output()
+        .ok()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+        .filter(|state| !state.is_empty())
+        .unwrap_or_else(|| "unknown".to_owned())
+}
+
+/// Read-only, so it also runs during a dry run.
 fn show_status() -> Result<()> {
     let _ = run_command_allow_fail("systemctl", &["status", BACKEND_SERVICE]);
     let _ = run_command_allow_fail("systemctl", &["status", SOFTWARE_TIMER]);
@@ -1750,9 +2648,96 @@ fn show_status() -> Result<()> {
     Ok(())
 }
 
+/// Makes the installer's changes to the system. With `--dry-run` every
+/// change is logged as `[dry-run] ...` instead; read-only checks (root,
+/// versions, signatures, existing users and units) never go through it and
+/// always run.
+#[derive(Clone, Copy, Debug, Default)]
+struct Host {
+    dry_run: bool,
+}
+
+impl Host {
+    /// Logs `action` and returns true when it must not be carried out.
+    fn skip(self, action: impl FnOnce() -> String) -> bool {
+        if self.dry_run {
+            info!("[dry-run] {}", action());
+        }
+        self.dry_run
+    }
+
+    fn run(self, cmd: &str, args: &[&str]) -> Result<()> {
+        if self.skip(|| format!("run {}", format_command(cmd, args))) {
+            return Ok(());
+        }
+        run_command(cmd, args)
+    }
+
+    fn run_in_dir(self, cmd: &str, args: &[&str], dir: &Path) -> Result<()> {
+        if self.skip(|| format!("run {} in {}", format_command(cmd, args), dir.display())) {
+            return Ok(());
+        }
+        run_command_in_dir(cmd, args, dir)
+    }
+
+    fn run_allow_fail(self, cmd: &str, args: &[&str]) -> Result<()> {
+        if self.skip(|| format!("run {} (failures ignored)", format_command(cmd, args))) {
+            return Ok(());
+        }
+        run_command_allow_fail(cmd, args)
+    }
+
+    fn create_dir_all(self, path: &Path) -> Result<()> {
+        if self.skip(|| format!("create directory {}", path.display())) {
+            return Ok(());
+        }
+        fs::create_dir_all(path).with_context(|| format!("Creating {}", path.display()))
+    }
+
+    fn write(self, path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+        let contents = contents.as_ref();
+        if self.skip(|| format!("write {} ({} bytes)", path.display(), contents.len())) {
+            return Ok(());
+        }
+        fs::write(path, contents).with_context(|| format!("Writing {}", path.display()))
+    }
+
+    fn set_mode(self, path: &Path, mode: u32) -> Result<()> {
+        if self.skip(|| format!("chmod {mode:o} {}", path.display())) {
+            return Ok(());
+        }
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+            .with_context(|| format!("Setting permissions on {}", path.display()))
+    }
+
+    fn copy(self, src: &Path, dest: &Path) -> Result<()> {
+        if self.skip(|| format!("copy {} to {}", src.display(), dest.display())) {
+            return Ok(());
+        }
+        fs::copy(src, dest)
+            .with_context(|| format!("Copying {} to {}", src.display(), dest.display()))?;
+        Ok(())
+    }
+
+    /// Removes `path` if it exists.
+    fn remove_file(self, path: &Path) -> Result<()> {
+        if !path.exists() || self.skip(|| format!("remove {}", path.display())) {
+            return Ok(());
+        }
+        remove_path_if_exists(path)
+    }
+
+    fn symlink(self, target: &Path, link: &Path) -> Result<()> {
+        if self.skip(|| format!("link {} to {}", link.display(), target.display())) {
+            return Ok(());
+        }
+        symlink(target, link).with_context(|| format!("Linking {}", link.display()))
+    }
+}
+
 fn run_command(cmd: &str, args: &[&str]) -> Result<()> {
     let printable = format_command(cmd, args);
-    log_info(format!("Running: {printable}"));
+    info!("Running: {printable}");
     let status = Command::new(cmd)
         .args(args)
         .status()
@@ -1789,10 +2774,7 @@ fn run_command_capture(cmd: &str, args: &[&str]) -> Result<String> {
 fn run_command_allow_fail(cmd: &str, args: &[&str]) -> Result<()> {
     let status = Command::new(cmd).args(args).status()?;
     if !status.success() {
-        eprintln!(
-            "[installer] Warning: {} exited with status {status}",
-            format_command(cmd, args)
-        );
+        warn!("{} exited with status {status}", format_command(cmd, args));
     }
     Ok(())
 }
@@ -1844,10 +2826,6 @@ fn determine_version(repo_root: &Path) -> Result<String> {
         .map(|s| s.to_string())
 }
 
-fn log_info(msg: impl AsRef<str>) {
-    println!("[installer] {}", msg.as_ref());
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1862,11 +2840,158 @@ mod tests {
         );
     }
 
+    #[test]
+    fn as_user_command_wraps_runuser_and_su() {
+        let args = |command: &Command| -> Vec<String> {
+            command
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect()
+        };
+        let probe = Path::new("/srv/it's media/.newtube-access-check");
+
+        let command = as_user_command(SwitchUser::Runuser, DOWNLOADER_USER, "touch", &[probe]);
+        assert_eq!(command.get_program(), "runuser");
+        assert_eq!(
+            args(&command),
+            [
+                "-u",
+                DOWNLOADER_USER,
+                "--",
+                "touch",
+                "/srv/it's media/.newtube-access-check"
+            ]
+        );
+
+        // `su` takes one shell line, so the path must survive quoting.
+        let command = as_user_command(SwitchUser::Su, BACKEND_USER, "cat", &[probe]);
+        assert_eq!(command.get_program(), "su");
+        assert_eq!(
+            args(&command),
+            [
+                "-s",
+                "/bin/sh",
+                "-c",
+                r"'cat' '/srv/it'\''s media/.newtube-access-check'",
+                BACKEND_USER
+            ]
+        );
+    }
+
+    #[test]
+    fn release_is_newer_compares_semver() {
+        assert!(!release_is_newer("1.2.0", "v1.2.0"));
+        assert!(!release_is_newer("v1.2.0", "1.2.0"));
+        assert!(release_is_newer("0.2.0", "v0.3.0"));
+        assert!(release_is_newer("", "v0.1.0"));
+        // An older latest release must never downgrade the install.
+        assert!(!release_is_newer("1.10.0", "v1.9.0"));
+        // Prereleases sort before their release.
+        assert!(!release_is_newer("1.2.0", "v1.2.0-rc.1"));
+        assert!(release_is_newer("1.2.0-rc.1", "v1.2.0"));
+        assert!(release_is_newer("1.1.0", "v1.2.0-beta.2"));
+        // Non-semver tags fall back to string equality.
+        assert!(!release_is_newer("nightly", "nightly"));
+        assert!(release_is_newer("nightly", "v1.0.0"));
+    }
+
+    #[test]
+    fn release_backup_restores_the_previous_install() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let bin_root = temp.path().join("opt/bin");
+        let www_root = temp.path().join("www");
+        let config = temp.path().join("env");
+        fs::create_dir_all(&bin_root)?;
+        fs::create_dir_all(www_root.join("assets"))?;
+        fs::write(bin_root.join("backend"), "old backend")?;
+        fs::write(www_root.join("index.html"), "old index")?;
+        fs::write(www_root.join("assets/app.js"), "old js")?;
+        fs::write(&config, "APP_VERSION=\"1.0.0\"\n")?;
+
+        let backup = ReleaseBackup::take(&bin_root, &www_root, &config)?;
+        assert!(backup.dir.starts_with(temp.path().join("opt")));
+
+        // What a failed update leaves behind.
+        fs::write(bin_root.join("backend"), "new backend")?;
+        fs::write(bin_root.join("routine_update"), "new routine")?;
+        fs::remove_dir_all(&www_root)?;
+        fs::create_dir_all(&www_root)?;
+        fs::write(www_root.join("index.html"), "new index")?;
+        fs::write(&config, "APP_VERSION=\"2.0.0\"\n")?;
+
+        backup.restore()?;
+        assert_eq!(fs::read_to_string(bin_root.join("backend"))?, "old backend");
+        assert_eq!(
+            fs::read_to_string(www_root.join("index.html"))?,
+            "old index"
+        );
+        assert_eq!(
+            fs::read_to_string(www_root.join("assets/app.js"))?,
+            "old js"
+        );
+        assert_eq!(fs::read_to_string(&config)?, "APP_VERSION=\"1.0.0\"\n");
+
+        backup.discard();
+        assert!(!backup.dir.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn ensure_backend_started_checks_the_backend_unit() {
+        let state = |active: &'static str| {
+            move |verb: &str, unit: &str| {
+                assert_eq!((verb, unit), ("is-active", BACKEND_SERVICE));
+                active.to_owned()
+            }
+        };
+        assert!(ensure_backend_started(state("active"), Duration::ZERO).is_ok());
+        let err = ensure_backend_started(state("failed"), Duration::ZERO).unwrap_err();
+        assert!(err.to_string().contains("failed"), "{err}");
+    }
+
     #[test]
     fn normalize_domain_rejects_whitespace() {
         assert!(normalize_domain("foo bar").is_err());
     }
 
+    fn parse_cli(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("installer").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn cli_rejects_invalid_port() {
+        assert_eq!(parse_port("8080"), Ok(8080));
+        assert!(parse_port("0").is_err());
+        assert!(parse_port("99999").is_err());
+        assert!(parse_port("http").is_err());
+        let err = parse_cli(&["--assume-yes", "--port", "99999"]).unwrap_err();
+        assert!(err.to_string().contains("between 1 and 65535"));
+    }
+
+    #[test]
+    fn cli_rejects_invalid_host() {
+        assert_eq!(parse_host(" ::1 ").unwrap(), "::1");
+        assert!(parse_host("localhost").is_err());
+        let err = parse_cli(&["--host", "999.0.0.1"]).unwrap_err();
+        assert!(err.to_string().contains("Invalid listen address"));
+    }
+
+    #[test]
+    fn cli_rejects_invalid_release_repo() {
+        assert_eq!(parse_release_repo("owner/repo/").unwrap(), "owner/repo");
+        assert!(parse_release_repo("owner").is_err());
+        assert!(parse_release_repo("a/b/c").is_err());
+        assert!(parse_cli(&["--release-repo", "nope"]).is_err());
+    }
+
+    #[test]
+    fn cli_normalizes_domain() {
+        let cli = parse_cli(&["--domain", "https://Example.com/"]).unwrap();
+        assert_eq!(cli.domain.as_deref(), Some("example.com"));
+        assert!(parse_domain("example.com/path").is_err());
+        assert!(parse_cli(&["--domain", "foo bar"]).is_err());
+    }
+
     #[test]
     fn read_env_config_parses_values() {
         let mut file = NamedTempFile::new().unwrap();
@@ -1881,4 +3006,412 @@ mod tests {
         assert_eq!(cfg.app_version.unwrap(), "1.2.3");
         assert_eq!(cfg.domain_name.unwrap(), "demo.example");
     }
+
+    fn write_artifact(contents: &[u8]) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(contents).unwrap();
+        file
+    }
+
+    #[test]
+    fn sha256_signature_roundtrips() {
+        let artifact = write_artifact(b"abc");
+        let signature = NamedTempFile::new().unwrap();
+        let key = SigningKey::generate(&mut OsRng);
+        sign_release_file(
+            artifact.path(),
+            signature.path(),
+            &key,
+            "v1.0.0",
+            DigestAlgo::Sha256,
+        )
+        .unwrap();
+
+        let payload =
+            verify_release_signature(artifact.path(), signature.path(), &key.verifying_key())
+                .unwrap();
+        assert_eq!(payload.digest_algo, DigestAlgo::Sha256);
+        assert_eq!(
+            payload.digest,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        // Tampering with the artifact breaks verification.
+        fs::write(artifact.path(), b"tampered").unwrap();
+        assert!(
+            verify_release_signature(artifact.path(), signature.path(), &key.verifying_key())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn blake3_releases_stay_verifiable_by_format_1_updaters() {
+        let artifact = write_artifact(b"release");
+        let signature = NamedTempFile::new().unwrap();
+        let key = SigningKey::generate(&mut OsRng);
+        sign_release_file(
+            artifact.path(),
+            signature.path(),
+            &key,
+            "v1.0.0",
+            DigestAlgo::Blake3,
+        )
+        .unwrap();
+
+        // What an updater that predates format 2 checks.
+        let written: serde_json::Value =
+            serde_json::from_slice(&fs::read(signature.path()).unwrap()).unwrap();
+        assert_eq!(written["format"], 1);
+        let digest = compute_blake3_hex(artifact.path()).unwrap();
+        let message = format!("{RELEASE_SIG_PREFIX}|v1|v1.0.0|{digest}");
+        let bytes: [u8; 64] = BASE64
+            .decode(written["signature"].as_str().unwrap())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        key.verifying_key()
+            .verify_strict(message.as_bytes(), &Signature::from_bytes(&bytes))
+            .unwrap();
+
+        verify_release_signature(artifact.path(), signature.path(), &key.verifying_key()).unwrap();
+    }
+
+    #[test]
+    fn legacy_signatures_default_to_blake3() {
+        let artifact = write_artifact(b"legacy release");
+        let key = SigningKey::generate(&mut OsRng);
+        let digest = compute_blake3_hex(artifact.path()).unwrap();
+        let message = format!("{RELEASE_SIG_PREFIX}|v1|v0.1.0|{digest}");
+        let legacy = serde_json::json!({
+            "format": 1,
+            "version": "v0.1.0",
+            "digest": digest,
+            "signature": BASE64.encode(key.sign(message.as_bytes()).to_bytes()),
+        });
+        let signature = write_artifact(&serde_json::to_vec(&legacy).unwrap());
+
+        let payload =
+            verify_release_signature(artifact.path(), signature.path(), &key.verifying_key())
+                .unwrap();
+        assert_eq!(payload.digest_algo, DigestAlgo::Blake3);
+    }
+
+    #[test]
+    fn systemd_units_reference_custom_bin_root() {
+        let temp = tempfile::tempdir().unwrap();
+        let bin_root = temp.path().join("instance-b/bin");
+        let systemd_dir = temp.path().join("systemd");
+        let env = EnvConfig {
+            media_root: Some(PathBuf::from("/srv/b/yt")),
+            www_root: Some(PathBuf::from("/srv/b/www")),
+            domain_name: Some("b.example".into()),
+            bin_root: Some(bin_root.clone()),
+            ..EnvConfig::default()
+        };
+        let cfg = env_to_install_config(env, PathBuf::from("/etc/newtube-b-env")).unwrap();
+        assert_eq!(cfg.bin_root, bin_root);
+
+        install_systemd_units(Host::default(), &cfg, &systemd_dir).unwrap();
+        for (unit, bin) in [
+            (SOFTWARE_SERVICE, "installer"),
+            (BACKEND_SERVICE, "backend"),
+            (ROUTINE_SERVICE, "routine_update"),
+        ] {
+            let contents = fs::read_to_string(systemd_dir.join(unit)).unwrap();
+            let exec = format!("ExecStart={}", bin_root.join(bin).display());
+            assert!(contents.contains(&exec), "{unit} missing {exec}");
+            assert!(!contents.contains(DEFAULT_BIN_ROOT));
+        }
+
+        let routine = fs::read_to_string(systemd_dir.join(ROUTINE_SERVICE)).unwrap();
+        let preflight = format!(
+            "ExecStartPre={} --config /etc/newtube-b-env --media-root /srv/b/yt --www-root /srv/b/www --preflight",
+            bin_root.join("routine_update").display()
+        );
+        assert!(routine.contains(&preflight), "{routine}");
+    }
+
+    #[test]
+    fn dry_run_leaves_the_system_untouched() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let env = EnvConfig {
+            media_root: Some(root.join("yt")),
+            www_root: Some(root.join("www")),
+            domain_name: Some("dry.example".into()),
+            bin_root: Some(root.join("bin")),
+            ..EnvConfig::default()
+        };
+        let cfg = env_to_install_config(env, root.join("env")).unwrap();
+        let dry = Host { dry_run: true };
+
+        ensure_directory(dry, &cfg.bin_root, 0o750).unwrap();
+        write_env_config(dry, &cfg).unwrap();
+        install_systemd_units(dry, &cfg, &root.join("systemd")).unwrap();
+        install_release_binaries(dry, root, &cfg.bin_root).unwrap();
+        assert!(!cfg.bin_root.exists());
+        assert!(!cfg.config_path.exists());
+        assert!(!root.join("systemd").exists());
+
+        fs::create_dir_all(&cfg.bin_root).unwrap();
+        fs::write(cfg.bin_root.join("backend"), "bin").unwrap();
+        fs::write(&cfg.config_path, "MEDIA_ROOT=\"/yt\"\n").unwrap();
+        fs::create_dir_all(&cfg.www_root).unwrap();
+        fs::write(cfg.www_root.join("index.html"), "old").unwrap();
+        uninstall(dry, &cfg.bin_root, &cfg.config_path).unwrap();
+        copy_frontend_assets(dry, root, &cfg.www_root).unwrap();
+        assert!(cfg.bin_root.join("backend").exists());
+        assert!(cfg.config_path.exists());
+        assert_eq!(
+            fs::read_to_string(cfg.www_root.join("index.html")).unwrap(),
+            "old"
+        );
+
+        assert!(parse_cli(&["--uninstall", "--dry-run"]).is_ok());
+        assert!(parse_cli(&["--status", "--dry-run"]).is_err());
+    }
+
+    #[test]
+    fn rewritten_env_keeps_downloader_keys() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("env");
+        fs::write(
+            &path,
+            "MEDIA_ROOT=\"/yt\"\nWWW_ROOT=\"/www\"\nDOMAIN_NAME=\"demo.example\"\n\
+             PROXY=\"socks5://127.0.0.1:1080\"\n\
+             RATE_LIMIT=\"4M\"\n\
+             SLEEP_INTERVAL=\"5\"\n\
+             MAX_SLEEP_INTERVAL=\"30\"\n\
+             SUB_LANGS=\"en,fr\"\n\
+             MAX_HEIGHT=\"720\"\n\
+             WEBHOOK_SECRET=\"s3cret\"\n\
+             OBJECT_STORE_URL=\"https://media.example/bucket\"\n\
+             RELEASE_PUBKEY=\"/etc/newtube/release-public-key.json\"\n",
+        )
+        .unwrap();
+        let env = read_env_config(&path).unwrap().unwrap();
+        let cfg = env_to_install_config(env, path.clone()).unwrap();
+        fs::write(&path, render_env_config(&cfg)).unwrap();
+
+        let reread = read_env_config(&path).unwrap().unwrap();
+        assert_eq!(reread.proxy.as_deref(), Some("socks5://127.0.0.1:1080"));
+        assert_eq!(reread.rate_limit.as_deref(), Some("4M"));
+        assert_eq!(reread.sleep_interval, Some(5));
+        assert_eq!(reread.max_sleep_interval, Some(30));
+        assert_eq!(reread.sub_langs.as_deref(), Some("en,fr"));
+        assert_eq!(reread.max_height, Some(720));
+        assert_eq!(reread.webhook_secret.as_deref(), Some("s3cret"));
+        assert_eq!(
+            reread.object_store_url.as_deref(),
+            Some("https://media.example/bucket")
+        );
+        let custom_key = PathBuf::from("/etc/newtube/release-public-key.json");
+        assert_eq!(cfg.pubkey_path, custom_key);
+        assert_eq!(reread.release_pubkey.as_ref(), Some(&custom_key));
+        assert_eq!(default_pubkey_from_config(&path).unwrap(), custom_key);
+        assert_eq!(
+            env_to_install_config(reread, path).unwrap().carried,
+            cfg.carried
+        );
+    }
+
+    #[test]
+    fn rendered_config_loads_in_either_flavor() {
+        let temp = tempfile::tempdir().unwrap();
+        let env = EnvConfig {
+            media_root: Some(PathBuf::from("/yt")),
+            www_root: Some(PathBuf::from("/www")),
+            domain_name: Some("demo.example".into()),
+            newtube_port: Some(4242),
+            admin_token: Some("007".into()),
+            log_keep: Some(3),
+            max_height: Some(720),
+            proxy: Some("socks5://127.0.0.1:1080".into()),
+            ..EnvConfig::default()
+        };
+        let mut loaded = Vec::new();
+        for name in ["env", "config.toml"] {
+            let path = temp.path().join(name);
+            let cfg = env_to_install_config(env.clone(), path.clone()).unwrap();
+            fs::write(&path, render_config(&cfg)).unwrap();
+            loaded.push(load_runtime_paths_from(&path).unwrap());
+        }
+        let toml = fs::read_to_string(temp.path().join("config.toml")).unwrap();
+        assert!(toml.contains("newtube_port = 4242\n"), "{toml}");
+
+        let [from_env, from_toml] = &loaded[..] else {
+            unreachable!("two flavors rendered");
+        };
+        assert_eq!(format!("{from_env:?}"), format!("{from_toml:?}"));
+        assert_eq!(from_toml.media_root, PathBuf::from("/yt"));
+        assert_eq!(from_toml.newtube_port, 4242);
+        assert_eq!(from_toml.max_height, Some(720));
+        assert_eq!(from_toml.rotation.keep, 3);
+        assert_eq!(from_toml.proxy.as_deref(), Some("socks5://127.0.0.1:1080"));
+        assert_eq!(from_toml.admin_token.as_deref(), Some("007"));
+    }
+
+    #[test]
+    fn env_config_defaults_bin_root() {
+        let env = EnvConfig {
+            media_root: Some(PathBuf::from("/yt")),
+            www_root: Some(PathBuf::from("/www")),
+            domain_name: Some("demo.example".into()),
+            ..EnvConfig::default()
+        };
+        let cfg = env_to_install_config(env, PathBuf::from(DEFAULT_CONFIG_PATH)).unwrap();
+        assert_eq!(cfg.bin_root, PathBuf::from(DEFAULT_BIN_ROOT));
+        assert_eq!(
+            cfg.pubkey_path,
+            default_pubkey_path_for_www(Path::new("/www"))
+        );
+        assert_eq!(cfg.github_timeouts, GithubTimeouts::default());
+    }
+
+    #[test]
+    fn status_json_reports_every_unit_and_the_version() {
+        let cli = parse_cli(&["--status", "--json"]).unwrap();
+        assert!(cli.status && cli.json);
+        assert!(parse_cli(&["--json"]).is_err());
+
+        let stub = |verb: &str, unit: &str| match (verb, unit) {
+            ("is-active", ROUTINE_SERVICE) => "failed".to_owned(),
+            ("is-active", SOFTWARE_TIMER) => "inactive".to_owned(),
+            ("is-active", _) => "active".to_owned(),
+            (_, BACKEND_SERVICE) => "enabled".to_owned(),
+            _ => "disabled".to_owned(),
+        };
+        let status = DeploymentStatus::collect(stub, Some("v0.3.0".into()));
+        let json: serde_json::Value = serde_json::to_value(&status).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "backend": {"unit": BACKEND_SERVICE, "active": "active", "enabled": "enabled"},
+                "routine": {"unit": ROUTINE_SERVICE, "active": "failed", "enabled": "disabled"},
+                "updater_timer": {"unit": SOFTWARE_TIMER, "active": "inactive", "enabled": "disabled"},
+                "version": "v0.3.0",
+            })
+        );
+    }
+
+    /// Serves one scripted reply per connection; `None` accepts the
+    /// connection and never answers. Returns the base URL and a counter of
+    /// accepted connections.
+    fn mock_github(
+        replies: Vec<Option<String>>,
+    ) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+        use std::io::BufRead;
+        use std::sync::atomic::Ordering;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let accepted = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = accepted.clone();
+        thread::spawn(move || {
+            let mut hung = Vec::new();
+            for (stream, reply) in listener.incoming().zip(replies) {
+                let mut stream = stream.unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+                let Some(body) = reply else {
+                    hung.push(stream);
+                    continue;
+                };
+                let mut reader = io::BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                stream.write_all(body.as_bytes()).unwrap();
+            }
+            // Keep hung connections open until the client gives up.
+            thread::sleep(Duration::from_secs(30));
+            drop(hung);
+        });
+        (base, accepted)
+    }
+
+    fn reply(status: &str, body: &str) -> Option<String> {
+        Some(format!(
+            "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        ))
+    }
+
+    #[test]
+    fn asset_download_rejects_truncated_bodies() {
+        let dir = TempDir::new().unwrap();
+        let dest = dir.path().join("newtube-src-v0.3.0.tar.xz");
+        let partial = dir.path().join("newtube-src-v0.3.0.tar.xz.partial");
+        let (base, _) = mock_github(vec![
+            Some(
+                "HTTP/1.1 200 OK\r\nContent-Length: 100\r\nConnection: close\r\n\r\nshort body"
+                    .to_string(),
+            ),
+            reply("200 OK", "complete body"),
+        ]);
+        let agent = GithubTimeouts::default().agent();
+
+        let err = download_asset(&agent, &format!("{base}/asset"), None, &dest)
+            .expect_err("a short body must fail");
+        assert!(
+            format!("{err:#}").contains("download truncated: expected 100 bytes got 10"),
+            "{err:#}"
+        );
+        assert!(!dest.exists());
+        assert!(!partial.exists());
+
+        download_asset(&agent, &format!("{base}/asset"), None, &dest).unwrap();
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "complete body");
+        assert!(!partial.exists());
+    }
+
+    const RELEASE_JSON: &str = r#"{"tag_name":"v0.3.0","assets":[]}"#;
+
+    #[test]
+    fn release_fetch_times_out_on_a_silent_server() {
+        let (base, accepted) = mock_github(vec![None; 3]);
+        let agent = GithubTimeouts {
+            connect_secs: Some(1),
+            read_secs: Some(1),
+        }
+        .agent();
+
+        let started = std::time::Instant::now();
+        let err = fetch_latest_release(&agent, &base, "owner/repo", None, Duration::ZERO)
+            .err()
+            .expect("a silent server must time out");
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(err.to_string().starts_with("GitHub unreachable"), "{err}");
+        assert_eq!(
+            accepted.load(std::sync::atomic::Ordering::SeqCst),
+            RELEASE_FETCH_ATTEMPTS as usize
+        );
+    }
+
+    #[test]
+    fn release_fetch_retries_transient_errors_only() {
+        let (base, _) = mock_github(vec![
+            reply("503 Service Unavailable", ""),
+            reply("200 OK", RELEASE_JSON),
+        ]);
+        let agent = GithubTimeouts::default().agent();
+        let release =
+            fetch_latest_release(&agent, &base, "owner/repo", None, Duration::ZERO).unwrap();
+        assert_eq!(release.tag_name, "v0.3.0");
+
+        let (base, accepted) = mock_github(vec![
+            reply("404 Not Found", ""),
+            reply("200 OK", RELEASE_JSON),
+        ]);
+        let err = fetch_latest_release(&agent, &base, "owner/repo", None, Duration::ZERO)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("status 404"), "{err}");
+        assert_eq!(accepted.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let err = find_asset(&release, "newtube-src-v0.3.0.tar.xz")
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with("No matching asset"), "{err}");
+    }
 }
@@ -1,13 +1,22 @@
-use anyhow::{Context, Result, anyhow};
+use crate::metadata::SqliteTuning;
+use crate::rotation::RotationPolicy;
+use crate::timestamps::parse_display_timezone;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono_tz::Tz;
 use std::{
     fs,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
+    time::Duration,
 };
 
 pub const DEFAULT_CONFIG_PATH: &str = "/etc/newtube-env";
+pub const DEFAULT_MEDIA_ROOT: &str = "/yt";
+pub const DEFAULT_WWW_ROOT: &str = "/www/newtube.com";
 pub const DEFAULT_NEWTUBE_PORT: u16 = 8080;
 pub const DEFAULT_NEWTUBE_HOST: &str = "127.0.0.1";
 pub const DEFAULT_RELEASE_REPO: &str = "Pingasmaster/newtube";
+/// Characters of each description kept in list responses.
+pub const DEFAULT_LIST_DESCRIPTION_CHARS: usize = 300;
 
 #[derive(Debug, Clone, Default)]
 pub struct EnvConfig {
@@ -18,6 +27,63 @@ pub struct EnvConfig {
     pub newtube_port: Option<u16>,
     pub newtube_host: Option<String>,
     pub release_repo: Option<String>,
+    pub admin_token: Option<String>,
+    /// Directory holding the installed binaries (installer-managed).
+    pub bin_root: Option<PathBuf>,
+    /// `RELEASE_PUBKEY`: public key release archives are verified against
+    /// (installer-managed).
+    pub release_pubkey: Option<PathBuf>,
+    /// `SQLITE_PAGE_SIZE`: bytes per page, only honored when the metadata DB
+    /// is first created.
+    pub sqlite_page_size: Option<u32>,
+    /// `SQLITE_CACHE_KB`: page cache per connection, in KiB.
+    pub sqlite_cache_kb: Option<u32>,
+    /// `SQLITE_READ_POOL`: idle read connections kept per reader.
+    pub sqlite_read_pool: Option<u32>,
+    /// `LOG_MAX_BYTES`: size at which history files are rotated.
+    pub log_max_bytes: Option<u64>,
+    /// `LOG_MAX_AGE_DAYS`: rotated copies older than this are deleted.
+    pub log_max_age_days: Option<u64>,
+    /// `LOG_KEEP`: number of rotated copies kept.
+    pub log_keep: Option<usize>,
+    /// `DISPLAY_TIMEZONE`: IANA zone used for the display copies of upload
+    /// and comment times. Stored values stay in UTC.
+    pub display_timezone: Option<Tz>,
+    /// `STREAM_RATE_LIMIT`: per-connection egress cap for streamed files, in
+    /// bytes per second.
+    pub stream_rate_limit: Option<u64>,
+    /// `GITHUB_CONNECT_TIMEOUT_SECS`: connect timeout for the updater's
+    /// GitHub requests.
+    pub github_connect_timeout_secs: Option<u64>,
+    /// `GITHUB_READ_TIMEOUT_SECS`: how long the updater waits on a silent
+    /// GitHub connection.
+    pub github_read_timeout_secs: Option<u64>,
+    /// `LIST_DESCRIPTION_CHARS`: descriptions in list responses are clipped
+    /// to this many characters; `0` keeps them whole.
+    pub list_description_chars: Option<usize>,
+    /// `MAX_HEIGHT`: the downloader keeps only muxed formats at or below
+    /// this many lines instead of every format.
+    pub max_height: Option<u32>,
+    /// `WEBHOOK_SECRET`: sent with the downloader's `--webhook` POST so the
+    /// receiver can authenticate it.
+    pub webhook_secret: Option<String>,
+    /// `PROXY`: yt-dlp `--proxy` for every downloader call, e.g.
+    /// `socks5://127.0.0.1:1080`.
+    pub proxy: Option<String>,
+    /// `RATE_LIMIT`: yt-dlp `--limit-rate` for every downloader call, e.g.
+    /// `4M`.
+    pub rate_limit: Option<String>,
+    /// `SLEEP_INTERVAL`: seconds yt-dlp waits before each download.
+    pub sleep_interval: Option<u32>,
+    /// `MAX_SLEEP_INTERVAL`: upper bound of a random wait starting at
+    /// `SLEEP_INTERVAL`.
+    pub max_sleep_interval: Option<u32>,
+    /// `SUB_LANGS`: yt-dlp `--sub-langs` list for the downloader, e.g.
+    /// `en,es`.
+    pub sub_langs: Option<String>,
+    /// `OBJECT_STORE_URL`: bucket URL the backend streams media from when a
+    /// file is missing on local disk.
+    pub object_store_url: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -27,14 +93,50 @@ pub struct RuntimePaths {
     pub newtube_port: u16,
     pub newtube_host: String,
     pub release_repo: String,
+    /// `APP_VERSION` written by the installer; `None` for a local run.
+    pub app_version: Option<String>,
+    /// Shared secret required by the backend's operator-only endpoints.
+    /// `None` keeps those endpoints disabled.
+    pub admin_token: Option<String>,
+    /// Optional page/cache sizes for the metadata DB.
+    pub sqlite: SqliteTuning,
+    /// Limits for the status/history files written next to the library.
+    pub rotation: RotationPolicy,
+    /// Zone for the `*_display` timestamp fields; `None` leaves them unset.
+    pub display_timezone: Option<Tz>,
+    /// Bytes per second each streamed response may use; `None` is unlimited.
+    pub stream_rate_limit: Option<u64>,
+    /// Description length in list responses; `0` disables clipping.
+    pub list_description_chars: usize,
+    /// Download quality cap; `None` downloads every format.
+    pub max_height: Option<u32>,
+    /// Shared secret for the downloader's completion webhook.
+    pub webhook_secret: Option<String>,
+    /// Proxy for every yt-dlp call; `None` connects directly.
+    pub proxy: Option<String>,
+    /// Download throttling; `None` leaves yt-dlp's defaults.
+    pub rate_limit: Option<String>,
+    pub sleep_interval: Option<u32>,
+    pub max_sleep_interval: Option<u32>,
+    /// Subtitle languages to download; `None` downloads all of them.
+    pub sub_langs: Option<String>,
+    /// Remote fallback for media missing locally; `None` serves only local
+    /// files.
+    pub object_store_url: Option<String>,
 }
 
+/// Reads the runtime config at `path`: the installer's shell-style
+/// `KEY="value"` file, or TOML when the name ends in `.toml`. `Ok(None)` when
+/// the file does not exist.
 pub fn read_env_config(path: &Path) -> Result<Option<EnvConfig>> {
     if !path.exists() {
         return Ok(None);
     }
     let content =
         fs::read_to_string(path).with_context(|| format!("Reading {}", path.display()))?;
+    if is_toml_config(path) {
+        return parse_toml_config(&content, path).map(Some);
+    }
     let mut cfg = EnvConfig::default();
     for line in content.lines() {
         let trimmed = line.trim();
@@ -43,42 +145,232 @@ pub fn read_env_config(path: &Path) -> Result<Option<EnvConfig>> {
         }
         if let Some((key, value_raw)) = trimmed.split_once('=') {
             let value = value_raw.trim().trim_matches('"');
-            match key {
-                "MEDIA_ROOT" => cfg.media_root = Some(PathBuf::from(value)),
-                "WWW_ROOT" => cfg.www_root = Some(PathBuf::from(value)),
-                "APP_VERSION" => cfg.app_version = Some(value.to_string()),
-                "DOMAIN_NAME" => cfg.domain_name = Some(value.to_string()),
-                "NEWTUBE_PORT" => {
-                    let port: u16 = value
-                        .parse()
-                        .with_context(|| format!("Parsing NEWTUBE_PORT from {}", path.display()))?;
-                    cfg.newtube_port = Some(port);
-                }
-                "NEWTUBE_HOST" => {
-                    if !value.is_empty() {
-                        cfg.newtube_host = Some(value.to_string());
-                    }
-                }
-                "RELEASE_REPO" => {
-                    if !value.is_empty() {
-                        cfg.release_repo = Some(value.to_string());
-                    }
-                }
-                _ => {}
-            }
+            // Unknown keys are ignored so the file can carry other settings.
+            cfg.set(key, value, path)?;
         }
     }
     Ok(Some(cfg))
 }
 
+/// Whether the config at `path` is the TOML flavor (see [`read_env_config`]).
+pub fn is_toml_config(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "toml")
+}
+
+/// TOML flavor of the config: the same keys in lowercase with typed values,
+/// e.g. `media_root = "/yt"`, `newtube_port = 8080`, `max_height = 720`.
+/// Unknown keys are rejected so typos do not go unnoticed.
+fn parse_toml_config(content: &str, path: &Path) -> Result<EnvConfig> {
+    let table: toml::Table =
+        toml::from_str(content).with_context(|| format!("Parsing {}", path.display()))?;
+    let mut cfg = EnvConfig::default();
+    for (name, value) in &table {
+        let value = match value {
+            toml::Value::String(value) => value.clone(),
+            toml::Value::Integer(value) => value.to_string(),
+            _ => bail!(
+                "`{name}` in {} must be a string or an integer",
+                path.display()
+            ),
+        };
+        // An empty string leaves the key unset, as in the env file.
+        if value.is_empty() {
+            continue;
+        }
+        let known = !name.contains(|ch: char| ch.is_ascii_uppercase())
+            && cfg.set(&name.to_ascii_uppercase(), &value, path)?;
+        if !known {
+            bail!("Unknown key `{name}` in {}", path.display());
+        }
+    }
+    Ok(cfg)
+}
+
+impl EnvConfig {
+    /// Applies one `KEY`/value pair. Returns `false` for keys this crate
+    /// does not know (or empty values of optional keys).
+    fn set(&mut self, key: &str, value: &str, path: &Path) -> Result<bool> {
+        let cfg = self;
+        match key {
+            "MEDIA_ROOT" => cfg.media_root = Some(PathBuf::from(value)),
+            "WWW_ROOT" => cfg.www_root = Some(PathBuf::from(value)),
+            "APP_VERSION" => cfg.app_version = Some(value.to_string()),
+            "DOMAIN_NAME" => cfg.domain_name = Some(value.to_string()),
+            "NEWTUBE_PORT" => {
+                let port: u16 = value
+                    .parse()
+                    .with_context(|| format!("Parsing NEWTUBE_PORT from {}", path.display()))?;
+                cfg.newtube_port = Some(port);
+            }
+            "NEWTUBE_HOST" if !value.is_empty() => {
+                cfg.newtube_host = Some(value.to_string());
+            }
+            "RELEASE_REPO" if !value.is_empty() => {
+                cfg.release_repo = Some(value.to_string());
+            }
+            "ADMIN_TOKEN" if !value.is_empty() => {
+                cfg.admin_token = Some(value.to_string());
+            }
+            "BIN_ROOT" if !value.is_empty() => {
+                cfg.bin_root = Some(PathBuf::from(value));
+            }
+            "RELEASE_PUBKEY" if !value.is_empty() => {
+                cfg.release_pubkey = Some(PathBuf::from(value));
+            }
+            "SQLITE_PAGE_SIZE" if !value.is_empty() => {
+                let page_size = parse_page_size(value)
+                    .with_context(|| format!("Parsing SQLITE_PAGE_SIZE from {}", path.display()))?;
+                cfg.sqlite_page_size = Some(page_size);
+            }
+            "SQLITE_CACHE_KB" if !value.is_empty() => {
+                let cache_kb: u32 = value
+                    .parse()
+                    .with_context(|| format!("Parsing SQLITE_CACHE_KB from {}", path.display()))?;
+                cfg.sqlite_cache_kb = Some(cache_kb);
+            }
+            "SQLITE_READ_POOL" if !value.is_empty() => {
+                let read_pool = parse_read_pool(value)
+                    .with_context(|| format!("Parsing SQLITE_READ_POOL from {}", path.display()))?;
+                cfg.sqlite_read_pool = Some(read_pool);
+            }
+            "LOG_MAX_BYTES" if !value.is_empty() => {
+                let max_bytes: u64 = value
+                    .parse()
+                    .with_context(|| format!("Parsing LOG_MAX_BYTES from {}", path.display()))?;
+                cfg.log_max_bytes = Some(max_bytes);
+            }
+            "LOG_MAX_AGE_DAYS" if !value.is_empty() => {
+                let days: u64 = value
+                    .parse()
+                    .with_context(|| format!("Parsing LOG_MAX_AGE_DAYS from {}", path.display()))?;
+                cfg.log_max_age_days = Some(days);
+            }
+            "LOG_KEEP" if !value.is_empty() => {
+                let keep: usize = value
+                    .parse()
+                    .with_context(|| format!("Parsing LOG_KEEP from {}", path.display()))?;
+                cfg.log_keep = Some(keep);
+            }
+            "DISPLAY_TIMEZONE" if !value.is_empty() => {
+                let tz = parse_display_timezone(value)
+                    .with_context(|| format!("Parsing DISPLAY_TIMEZONE from {}", path.display()))?;
+                cfg.display_timezone = Some(tz);
+            }
+            "STREAM_RATE_LIMIT" if !value.is_empty() => {
+                let limit: u64 = value.parse().with_context(|| {
+                    format!("Parsing STREAM_RATE_LIMIT from {}", path.display())
+                })?;
+                // `0` reads as "no limit" rather than "never send".
+                cfg.stream_rate_limit = Some(limit).filter(|limit| *limit > 0);
+            }
+            "GITHUB_CONNECT_TIMEOUT_SECS" if !value.is_empty() => {
+                let secs = parse_timeout_secs(value).with_context(|| {
+                    format!(
+                        "Parsing GITHUB_CONNECT_TIMEOUT_SECS from {}",
+                        path.display()
+                    )
+                })?;
+                cfg.github_connect_timeout_secs = Some(secs);
+            }
+            "GITHUB_READ_TIMEOUT_SECS" if !value.is_empty() => {
+                let secs = parse_timeout_secs(value).with_context(|| {
+                    format!("Parsing GITHUB_READ_TIMEOUT_SECS from {}", path.display())
+                })?;
+                cfg.github_read_timeout_secs = Some(secs);
+            }
+            "LIST_DESCRIPTION_CHARS" if !value.is_empty() => {
+                let chars: usize = value.parse().with_context(|| {
+                    format!("Parsing LIST_DESCRIPTION_CHARS from {}", path.display())
+                })?;
+                cfg.list_description_chars = Some(chars);
+            }
+            "MAX_HEIGHT" if !value.is_empty() => {
+                let height = parse_max_height(value)
+                    .with_context(|| format!("Parsing MAX_HEIGHT from {}", path.display()))?;
+                cfg.max_height = Some(height);
+            }
+            "WEBHOOK_SECRET" if !value.is_empty() => {
+                cfg.webhook_secret = Some(value.to_string());
+            }
+            "PROXY" if !value.is_empty() => {
+                let proxy = validate_proxy(value)
+                    .with_context(|| format!("Parsing PROXY from {}", path.display()))?;
+                cfg.proxy = Some(proxy);
+            }
+            "RATE_LIMIT" if !value.is_empty() => {
+                let rate = parse_rate_limit(value)
+                    .with_context(|| format!("Parsing RATE_LIMIT from {}", path.display()))?;
+                cfg.rate_limit = Some(rate);
+            }
+            "SLEEP_INTERVAL" if !value.is_empty() => {
+                let secs = parse_sleep_secs(value)
+                    .with_context(|| format!("Parsing SLEEP_INTERVAL from {}", path.display()))?;
+                cfg.sleep_interval = Some(secs);
+            }
+            "MAX_SLEEP_INTERVAL" if !value.is_empty() => {
+                let secs = parse_sleep_secs(value).with_context(|| {
+                    format!("Parsing MAX_SLEEP_INTERVAL from {}", path.display())
+                })?;
+                cfg.max_sleep_interval = Some(secs);
+            }
+            "SUB_LANGS" if !value.is_empty() => {
+                let langs = parse_sub_langs(value)
+                    .with_context(|| format!("Parsing SUB_LANGS from {}", path.display()))?;
+                cfg.sub_langs = Some(langs);
+            }
+            "OBJECT_STORE_URL" if !value.is_empty() => {
+                let url = parse_object_store_url(value)
+                    .with_context(|| format!("Parsing OBJECT_STORE_URL from {}", path.display()))?;
+                cfg.object_store_url = Some(url);
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
 pub fn load_runtime_paths() -> Result<RuntimePaths> {
     load_runtime_paths_from(Path::new(DEFAULT_CONFIG_PATH))
 }
 
+/// Like [`load_runtime_paths_from`], but a missing file yields the install
+/// defaults (`/yt`, `/www/newtube.com`, port 8080 on 127.0.0.1) so a local
+/// `cargo run` works before anything is installed. A file that exists but
+/// is incomplete or invalid is still an error.
+pub fn load_runtime_paths_or_defaults(path: impl AsRef<Path>) -> Result<RuntimePaths> {
+    let path = path.as_ref();
+    if path.exists() {
+        return load_runtime_paths_from(path);
+    }
+    Ok(RuntimePaths {
+        media_root: PathBuf::from(DEFAULT_MEDIA_ROOT),
+        www_root: PathBuf::from(DEFAULT_WWW_ROOT),
+        newtube_port: DEFAULT_NEWTUBE_PORT,
+        newtube_host: DEFAULT_NEWTUBE_HOST.to_string(),
+        release_repo: DEFAULT_RELEASE_REPO.to_string(),
+        app_version: None,
+        admin_token: None,
+        sqlite: SqliteTuning::default(),
+        rotation: RotationPolicy::default(),
+        display_timezone: None,
+        stream_rate_limit: None,
+        list_description_chars: DEFAULT_LIST_DESCRIPTION_CHARS,
+        max_height: None,
+        webhook_secret: None,
+        proxy: None,
+        rate_limit: None,
+        sleep_interval: None,
+        max_sleep_interval: None,
+        sub_langs: None,
+        object_store_url: None,
+    })
+}
+
 pub fn load_runtime_paths_from(path: impl AsRef<Path>) -> Result<RuntimePaths> {
     let path = path.as_ref();
     let cfg = read_env_config(path)?
         .ok_or_else(|| anyhow!("Missing config file at {}", path.display()))?;
+    let rotation = rotation_policy(&cfg);
     let media_root = cfg
         .media_root
         .ok_or_else(|| anyhow!("MEDIA_ROOT not set in {}", path.display()))?;
@@ -100,9 +392,267 @@ pub fn load_runtime_paths_from(path: impl AsRef<Path>) -> Result<RuntimePaths> {
         newtube_port,
         newtube_host,
         release_repo,
+        app_version: cfg.app_version,
+        admin_token: cfg.admin_token,
+        sqlite: SqliteTuning {
+            page_size: cfg.sqlite_page_size,
+            cache_kb: cfg.sqlite_cache_kb,
+            read_pool: cfg.sqlite_read_pool,
+        },
+        rotation,
+        display_timezone: cfg.display_timezone,
+        stream_rate_limit: cfg.stream_rate_limit,
+        list_description_chars: cfg
+            .list_description_chars
+            .unwrap_or(DEFAULT_LIST_DESCRIPTION_CHARS),
+        max_height: cfg.max_height,
+        webhook_secret: cfg.webhook_secret,
+        proxy: cfg.proxy,
+        rate_limit: cfg.rate_limit,
+        sleep_interval: cfg.sleep_interval,
+        max_sleep_interval: cfg.max_sleep_interval,
+        sub_langs: cfg.sub_langs,
+        object_store_url: cfg.object_store_url,
     })
 }
 
+/// Fills the unset rotation keys with the defaults.
+fn rotation_policy(cfg: &EnvConfig) -> RotationPolicy {
+    let defaults = RotationPolicy::default();
+    RotationPolicy {
+        max_bytes: cfg.log_max_bytes.unwrap_or(defaults.max_bytes),
+        max_age: cfg
+            .log_max_age_days
+            .map(|days| Duration::from_secs(days * 24 * 60 * 60)),
+        keep: cfg.log_keep.unwrap_or(defaults.keep),
+    }
+}
+
+/// SQLite only accepts powers of two between 512 and 65536 and silently
+/// ignores anything else, so bad values are rejected here instead.
+fn parse_page_size(value: &str) -> Result<u32> {
+    let page_size: u32 = value.parse()?;
+    if !page_size.is_power_of_two() || !(512..=65536).contains(&page_size) {
+        bail!("page size must be a power of two between 512 and 65536, got {page_size}");
+    }
+    Ok(page_size)
+}
+
+/// Upper bound for `SQLITE_READ_POOL`; every idle connection holds its own
+/// page cache, so a huge pool mostly wastes memory.
+const MAX_READ_POOL: u32 = 64;
+
+fn parse_read_pool(value: &str) -> Result<u32> {
+    let read_pool: u32 = value.parse()?;
+    if read_pool > MAX_READ_POOL {
+        bail!("read pool must be between 0 and {MAX_READ_POOL}, got {read_pool}");
+    }
+    Ok(read_pool)
+}
+
+/// Parses a vertical resolution such as `1080`. Also used for the
+/// downloader's `--max-height`.
+pub fn parse_max_height(value: &str) -> Result<u32> {
+    match value.trim().trim_end_matches('p').parse::<u32>() {
+        Ok(height) if height > 0 => Ok(height),
+        _ => bail!("max height must be a positive number of lines such as 1080, got {value:?}"),
+    }
+}
+
+/// Accepts the proxy URLs yt-dlp understands (`http`, `https`, `socks4`,
+/// `socks5`, `socks5h`). Used for the downloader's `--proxy` and `PROXY`.
+pub fn validate_proxy(value: &str) -> Result<String> {
+    let value = value.trim();
+    let Some((scheme, rest)) = value.split_once("://") else {
+        bail!("--proxy must be a URL such as socks5://host:1080, got {value:?}");
+    };
+    if !matches!(scheme, "http" | "https" | "socks4" | "socks5" | "socks5h") {
+        bail!("--proxy scheme must be http, https, socks4, socks5 or socks5h, got {scheme:?}");
+    }
+    if rest.is_empty()
+        || value
+            .chars()
+            .any(|ch| ch.is_control() || ch.is_whitespace())
+    {
+        bail!("--proxy is not a valid URL: {value:?}");
+    }
+    Ok(value.to_owned())
+}
+
+/// Checks a yt-dlp `--limit-rate` value: bytes per second with an optional
+/// `K`/`M`/`G` suffix (`500K`, `4M`, `1.5M`), the same form yt-dlp parses.
+/// Used for the downloader's `--rate-limit` and `RATE_LIMIT`.
+pub fn parse_rate_limit(value: &str) -> Result<String> {
+    let value = value.trim();
+    let number = value.trim_end_matches(['k', 'K', 'm', 'M', 'g', 'G']);
+    let suffixes = value.len() - number.len();
+    let valid = suffixes <= 1
+        && !number.starts_with('.')
+        && !number.ends_with('.')
+        && number.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && number.parse::<f64>().is_ok_and(|rate| rate > 0.0);
+    if !valid {
+        bail!("rate limit must be bytes per second such as 500K or 4M, got {value:?}");
+    }
+    Ok(value.to_owned())
+}
+
+/// Checks a yt-dlp `--sub-langs` list: comma-separated language codes or
+/// yt-dlp's patterns (`en`, `pt-BR`, `en.*`, `all`, `-live_chat`). Spaces
+/// around entries are dropped.
+pub fn parse_sub_langs(value: &str) -> Result<String> {
+    let langs: Vec<&str> = value.split(',').map(str::trim).collect();
+    let valid = langs.iter().all(|lang| {
+        !lang.is_empty()
+            && lang
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.' | '*'))
+    });
+    if !valid {
+        bail!(
+            "--sub-langs must be comma-separated language codes such as en,es or en.*, got {value:?}"
+        );
+    }
+    Ok(langs.join(","))
+}
+
+/// Longest wait accepted for `--sleep-interval`/`--max-sleep-interval`.
+const MAX_SLEEP_SECS: u32 = 3_600;
+
+/// Parses a yt-dlp sleep interval in whole seconds.
+pub fn parse_sleep_secs(value: &str) -> Result<u32> {
+    match value.trim().parse::<u32>() {
+        Ok(secs) if secs <= MAX_SLEEP_SECS => Ok(secs),
+        _ => bail!("sleep interval must be between 0 and {MAX_SLEEP_SECS} seconds, got {value:?}"),
+    }
+}
+
+/// yt-dlp refuses a maximum sleep without a minimum, or below it; checked
+/// up front so the run fails before the first download.
+pub fn check_sleep_intervals(min: Option<u32>, max: Option<u32>) -> Result<()> {
+    match (min, max) {
+        (None, Some(_)) => bail!("--max-sleep-interval needs --sleep-interval"),
+        (Some(min), Some(max)) if max < min => {
+            bail!("--max-sleep-interval ({max}) must not be below --sleep-interval ({min})")
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Longest `--since` window; 100 years covers any channel.
+const MAX_SINCE_DAYS: u32 = 36_500;
+
+/// Parses the `--since <days>` window shared by `download_channel` and
+/// `routine_update`. `0` means a full scan.
+pub fn parse_since_days(value: &str) -> Result<u32> {
+    match value.trim().parse::<u32>() {
+        Ok(days) if days <= MAX_SINCE_DAYS => Ok(days),
+        _ => {
+            bail!("--since must be a number of days between 0 and {MAX_SINCE_DAYS}, got {value:?}")
+        }
+    }
+}
+
+/// Accepts an `http(s)://` bucket URL such as
+/// `https://s3.example.com/newtube-media`; a trailing slash is dropped so
+/// object keys can be appended directly.
+fn parse_object_store_url(value: &str) -> Result<String> {
+    let rest = value
+        .strip_prefix("https://")
+        .or_else(|| value.strip_prefix("http://"))
+        .ok_or_else(|| anyhow!("object store URL must start with http:// or https://"))?;
+    if rest.is_empty() || rest.starts_with('/') {
+        bail!("object store URL has no host: {value:?}");
+    }
+    if value.contains(['?', '#']) || value.chars().any(|ch| ch.is_whitespace()) {
+        bail!("object store URL must be a plain bucket URL without query or fragment: {value:?}");
+    }
+    Ok(value.trim_end_matches('/').to_owned())
+}
+
+/// A zero timeout would make every request fail immediately.
+fn parse_timeout_secs(value: &str) -> Result<u64> {
+    let secs: u64 = value.parse()?;
+    if secs == 0 {
+        bail!("timeout must be at least 1 second");
+    }
+    Ok(secs)
+}
+
+/// Validates a media/www root coming from the CLI or the env file. Relative
+/// paths would resolve against whatever directory a binary happens to run
+/// from (systemd uses `/`), so they are rejected instead of guessed at.
+/// The returned path has `.`/`..` segments and trailing slashes resolved.
+pub fn absolute_root(label: &str, path: PathBuf) -> Result<PathBuf> {
+    if !path.is_absolute() {
+        bail!(
+            "{label} must be an absolute path, got {}; pass the full path instead (e.g. {})",
+            path.display(),
+            std::path::absolute(&path)
+                .unwrap_or_else(|_| path.clone())
+                .display()
+        );
+    }
+    normalize_path(&path)
+}
+
+/// Rejects layouts where the media root and www root overlap, or where
+/// either one overlaps a `reserved` location (config file, binary dir).
+///
+/// The installer wipes the www root when redeploying the frontend and nginx
+/// serves it verbatim, so nesting the two would either delete downloads or
+/// publish raw media.
+pub fn ensure_roots_disjoint(media_root: &Path, www_root: &Path, reserved: &[&Path]) -> Result<()> {
+    let media = normalize_path(media_root)?;
+    let www = normalize_path(www_root)?;
+
+    if paths_overlap(&media, &www) {
+        bail!(
+            "media root {} and www root {} must not be the same directory or nested inside each other",
+            media_root.display(),
+            www_root.display()
+        );
+    }
+
+    for reserved_path in reserved {
+        let normalized = normalize_path(reserved_path)?;
+        for (label, root, original) in [("media", &media, media_root), ("www", &www, www_root)] {
+            if paths_overlap(root, &normalized) {
+                bail!(
+                    "{label} root {} overlaps {}; pick a dedicated directory",
+                    original.display(),
+                    reserved_path.display()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// True when `a` and `b` are the same path or one contains the other.
+fn paths_overlap(a: &Path, b: &Path) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
+
+/// Makes `path` absolute and resolves `.`/`..` lexically. The directories may
+/// not exist yet, so we cannot rely on `canonicalize`.
+fn normalize_path(path: &Path) -> Result<PathBuf> {
+    let absolute =
+        std::path::absolute(path).with_context(|| format!("resolving {}", path.display()))?;
+    let mut normalized = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    Ok(normalized)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -132,10 +682,340 @@ mod tests {
         assert_eq!(runtime.newtube_host, DEFAULT_NEWTUBE_HOST);
     }
 
+    #[test]
+    fn missing_config_falls_back_to_install_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let runtime = load_runtime_paths_or_defaults(dir.path().join("newtube-env")).unwrap();
+        assert_eq!(runtime.media_root, PathBuf::from(DEFAULT_MEDIA_ROOT));
+        assert_eq!(runtime.www_root, PathBuf::from(DEFAULT_WWW_ROOT));
+        assert_eq!(runtime.newtube_port, DEFAULT_NEWTUBE_PORT);
+        assert_eq!(runtime.newtube_host, DEFAULT_NEWTUBE_HOST);
+
+        // An existing file is read as usual, including its errors.
+        let cfg = make_config("MEDIA_ROOT=\"/m\"\nWWW_ROOT=\"/w\"\nNEWTUBE_HOST=\"0.0.0.0\"\n");
+        let runtime = load_runtime_paths_or_defaults(cfg.path()).unwrap();
+        assert_eq!(runtime.media_root, PathBuf::from("/m"));
+        assert_eq!(runtime.newtube_host, "0.0.0.0");
+        assert!(load_runtime_paths_or_defaults(make_config("WWW_ROOT=\"/w\"\n").path()).is_err());
+    }
+
     #[test]
     fn load_runtime_paths_reads_host() {
         let cfg = make_config("MEDIA_ROOT=\"/m\"\nWWW_ROOT=\"/w\"\nNEWTUBE_HOST=\"0.0.0.0\"\n");
         let runtime = load_runtime_paths_from(cfg.path()).unwrap();
         assert_eq!(runtime.newtube_host, "0.0.0.0");
     }
+
+    #[test]
+    fn load_runtime_paths_reads_admin_token() {
+        let cfg = make_config("MEDIA_ROOT=\"/m\"\nWWW_ROOT=\"/w\"\nADMIN_TOKEN=\"s3cret\"\n");
+        let runtime = load_runtime_paths_from(cfg.path()).unwrap();
+        assert_eq!(runtime.admin_token.as_deref(), Some("s3cret"));
+
+        let cfg = make_config("MEDIA_ROOT=\"/m\"\nWWW_ROOT=\"/w\"\nADMIN_TOKEN=\"\"\n");
+        let runtime = load_runtime_paths_from(cfg.path()).unwrap();
+        assert!(runtime.admin_token.is_none());
+    }
+
+    #[test]
+    fn read_env_config_validates_sqlite_tuning() {
+        let cfg = make_config(
+            "MEDIA_ROOT=\"/m\"\nWWW_ROOT=\"/w\"\nSQLITE_PAGE_SIZE=\"8192\"\nSQLITE_CACHE_KB=\"65536\"\nSQLITE_READ_POOL=\"4\"\n",
+        );
+        let runtime = load_runtime_paths_from(cfg.path()).unwrap();
+        assert_eq!(runtime.sqlite.page_size, Some(8192));
+        assert_eq!(runtime.sqlite.cache_kb, Some(65536));
+        assert_eq!(runtime.sqlite.read_pool, Some(4));
+        for bad in ["65", "-1", "many"] {
+            let cfg = make_config(&format!("SQLITE_READ_POOL=\"{bad}\"\n"));
+            let err = read_env_config(cfg.path()).unwrap_err();
+            assert!(format!("{err:#}").contains("SQLITE_READ_POOL"), "{bad}");
+        }
+
+        for bad in ["1000", "256", "131072", "big"] {
+            let cfg = make_config(&format!("SQLITE_PAGE_SIZE=\"{bad}\"\n"));
+            let err = read_env_config(cfg.path()).unwrap_err();
+            assert!(format!("{err:#}").contains("SQLITE_PAGE_SIZE"), "{bad}");
+        }
+    }
+
+    #[test]
+    fn read_env_config_reads_rotation_limits() {
+        let runtime =
+            load_runtime_paths_from(make_config("MEDIA_ROOT=\"/m\"\nWWW_ROOT=\"/w\"\n").path())
+                .unwrap();
+        assert_eq!(runtime.rotation, RotationPolicy::default());
+
+        let cfg = make_config(
+            "MEDIA_ROOT=\"/m\"\nWWW_ROOT=\"/w\"\nLOG_MAX_BYTES=\"4096\"\nLOG_MAX_AGE_DAYS=\"14\"\nLOG_KEEP=\"3\"\n",
+        );
+        let runtime = load_runtime_paths_from(cfg.path()).unwrap();
+        assert_eq!(runtime.rotation.max_bytes, 4096);
+        assert_eq!(
+            runtime.rotation.max_age,
+            Some(Duration::from_secs(14 * 24 * 60 * 60))
+        );
+        assert_eq!(runtime.rotation.keep, 3);
+
+        let err = read_env_config(make_config("LOG_KEEP=\"-1\"\n").path()).unwrap_err();
+        assert!(format!("{err:#}").contains("LOG_KEEP"));
+    }
+
+    #[test]
+    fn read_env_config_reads_display_timezone() {
+        let cfg =
+            make_config("MEDIA_ROOT=\"/m\"\nWWW_ROOT=\"/w\"\nDISPLAY_TIMEZONE=\"Europe/Paris\"\n");
+        let runtime = load_runtime_paths_from(cfg.path()).unwrap();
+        assert_eq!(runtime.display_timezone, Some(chrono_tz::Europe::Paris));
+
+        let err = read_env_config(make_config("DISPLAY_TIMEZONE=\"CET+1\"\n").path()).unwrap_err();
+        assert!(format!("{err:#}").contains("DISPLAY_TIMEZONE"));
+    }
+
+    #[test]
+    fn read_env_config_reads_stream_rate_limit() {
+        let cfg = make_config("MEDIA_ROOT=\"/m\"\nWWW_ROOT=\"/w\"\nSTREAM_RATE_LIMIT=\"500000\"\n");
+        let runtime = load_runtime_paths_from(cfg.path()).unwrap();
+        assert_eq!(runtime.stream_rate_limit, Some(500_000));
+
+        let cfg = read_env_config(make_config("STREAM_RATE_LIMIT=\"0\"\n").path()).unwrap();
+        assert_eq!(cfg.unwrap().stream_rate_limit, None);
+        let err = read_env_config(make_config("STREAM_RATE_LIMIT=\"1MB\"\n").path()).unwrap_err();
+        assert!(format!("{err:#}").contains("STREAM_RATE_LIMIT"));
+    }
+
+    #[test]
+    fn list_description_chars_defaults_and_accepts_zero() {
+        let cfg = make_config("MEDIA_ROOT=\"/m\"\nWWW_ROOT=\"/w\"\n");
+        let runtime = load_runtime_paths_from(cfg.path()).unwrap();
+        assert_eq!(
+            runtime.list_description_chars,
+            DEFAULT_LIST_DESCRIPTION_CHARS
+        );
+
+        let cfg = make_config("MEDIA_ROOT=\"/m\"\nWWW_ROOT=\"/w\"\nLIST_DESCRIPTION_CHARS=\"0\"\n");
+        let runtime = load_runtime_paths_from(cfg.path()).unwrap();
+        assert_eq!(runtime.list_description_chars, 0);
+    }
+
+    #[test]
+    fn read_env_config_reads_max_height() {
+        let cfg = make_config("MEDIA_ROOT=\"/m\"\nWWW_ROOT=\"/w\"\nMAX_HEIGHT=\"720p\"\n");
+        let runtime = load_runtime_paths_from(cfg.path()).unwrap();
+        assert_eq!(runtime.max_height, Some(720));
+
+        let err = read_env_config(make_config("MAX_HEIGHT=\"0\"\n").path()).unwrap_err();
+        assert!(format!("{err:#}").contains("MAX_HEIGHT"));
+    }
+
+    fn make_toml_config(contents: &str) -> NamedTempFile {
+        let mut file = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn toml_config_loads_like_the_env_file() {
+        let env = make_config(
+            "MEDIA_ROOT=\"/yt\"\nWWW_ROOT=\"/www\"\nNEWTUBE_PORT=\"4242\"\nMAX_HEIGHT=\"720\"\nRATE_LIMIT=\"4M\"\nDISPLAY_TIMEZONE=\"Europe/Paris\"\n",
+        );
+        let toml = make_toml_config(
+            r#"
+# Same settings as the env file above.
+media_root = "/yt"
+www_root = "/www"   # comments may follow a value
+newtube_port = 4242
+max_height = 720
+rate_limit = "4M"
+display_timezone = "Europe/Paris"
+admin_token = ""
+"#,
+        );
+        let from_env = load_runtime_paths_from(env.path()).unwrap();
+        let from_toml = load_runtime_paths_from(toml.path()).unwrap();
+        assert_eq!(format!("{from_env:?}"), format!("{from_toml:?}"));
+        assert_eq!(from_toml.newtube_port, 4242);
+        assert_eq!(from_toml.max_height, Some(720));
+        assert_eq!(from_toml.rate_limit.as_deref(), Some("4M"));
+        assert_eq!(from_toml.admin_token, None);
+    }
+
+    #[test]
+    fn toml_config_rejects_unknown_keys_and_bad_values() {
+        for (contents, expected) in [
+            ("media_rot = \"/yt\"\n", "Unknown key `media_rot`"),
+            ("MEDIA_ROOT = \"/yt\"\n", "Unknown key `MEDIA_ROOT`"),
+            ("max_height = [720]\n", "must be a string or an integer"),
+            ("max_height = 0\n", "MAX_HEIGHT"),
+            ("media_root = \"/yt\n", "Parsing"),
+        ] {
+            let err = read_env_config(make_toml_config(contents).path()).unwrap_err();
+            assert!(
+                format!("{err:#}").contains(expected),
+                "{contents:?}: {err:#}"
+            );
+        }
+    }
+
+    #[test]
+    fn read_env_config_reads_download_throttling() {
+        let cfg = make_config(
+            "MEDIA_ROOT=\"/m\"\nWWW_ROOT=\"/w\"\nRATE_LIMIT=\"4M\"\nSLEEP_INTERVAL=\"5\"\nMAX_SLEEP_INTERVAL=\"30\"\n",
+        );
+        let runtime = load_runtime_paths_from(cfg.path()).unwrap();
+        assert_eq!(runtime.rate_limit.as_deref(), Some("4M"));
+        assert_eq!(runtime.sleep_interval, Some(5));
+        assert_eq!(runtime.max_sleep_interval, Some(30));
+
+        let err = read_env_config(make_config("RATE_LIMIT=\"4 MB\"\n").path()).unwrap_err();
+        assert!(format!("{err:#}").contains("RATE_LIMIT"));
+        let err = read_env_config(make_config("SLEEP_INTERVAL=\"-1\"\n").path()).unwrap_err();
+        assert!(format!("{err:#}").contains("SLEEP_INTERVAL"));
+    }
+
+    #[test]
+    fn read_env_config_reads_proxy() {
+        let cfg =
+            make_config("MEDIA_ROOT=\"/m\"\nWWW_ROOT=\"/w\"\nPROXY=\"socks5://127.0.0.1:1080\"\n");
+        let runtime = load_runtime_paths_from(cfg.path()).unwrap();
+        assert_eq!(runtime.proxy.as_deref(), Some("socks5://127.0.0.1:1080"));
+
+        // An empty key means "no proxy", like the other optional keys.
+        let cfg = make_config("MEDIA_ROOT=\"/m\"\nWWW_ROOT=\"/w\"\nPROXY=\"\"\n");
+        assert_eq!(load_runtime_paths_from(cfg.path()).unwrap().proxy, None);
+
+        let err = read_env_config(make_config("PROXY=\"proxy.lan:3128\"\n").path()).unwrap_err();
+        assert!(format!("{err:#}").contains("PROXY"));
+    }
+
+    #[test]
+    fn sub_langs_are_validated() {
+        assert_eq!(parse_sub_langs("en, es ,fr").unwrap(), "en,es,fr");
+        assert_eq!(parse_sub_langs("all,-live_chat").unwrap(), "all,-live_chat");
+        assert_eq!(parse_sub_langs("en.*,pt-BR").unwrap(), "en.*,pt-BR");
+        for bad in ["", "en,", "en;fr", "en fr", "en,$(rm)"] {
+            assert!(parse_sub_langs(bad).is_err(), "{bad:?}");
+        }
+        let cfg = make_config("MEDIA_ROOT=\"/m\"\nWWW_ROOT=\"/w\"\nSUB_LANGS=\"en,de\"\n");
+        let runtime = load_runtime_paths_from(cfg.path()).unwrap();
+        assert_eq!(runtime.sub_langs.as_deref(), Some("en,de"));
+    }
+
+    #[test]
+    fn rate_limits_and_sleep_intervals_are_validated() {
+        for good in ["500K", "4M", "1.5m", "1G", "2048"] {
+            assert_eq!(parse_rate_limit(good).unwrap(), good);
+        }
+        for bad in ["", "0", "4MM", "M", "4MB", "-1M", ".5M", "4.M", "fast"] {
+            assert!(parse_rate_limit(bad).is_err(), "{bad:?}");
+        }
+        assert!(check_sleep_intervals(Some(5), Some(30)).is_ok());
+        assert!(check_sleep_intervals(Some(5), None).is_ok());
+        assert!(check_sleep_intervals(None, Some(30)).is_err());
+        assert!(check_sleep_intervals(Some(30), Some(5)).is_err());
+        assert!(parse_sleep_secs("3601").is_err());
+    }
+
+    #[test]
+    fn read_env_config_reads_object_store_url() {
+        let cfg = make_config(
+            "MEDIA_ROOT=\"/m\"\nWWW_ROOT=\"/w\"\nOBJECT_STORE_URL=\"https://s3.lan/media/\"\n",
+        );
+        let runtime = load_runtime_paths_from(cfg.path()).unwrap();
+        assert_eq!(
+            runtime.object_store_url.as_deref(),
+            Some("https://s3.lan/media")
+        );
+
+        for bad in [
+            "s3.lan/media",
+            "ftp://s3.lan",
+            "https://",
+            "https://s3.lan/m?x=1",
+        ] {
+            let cfg = make_config(&format!("OBJECT_STORE_URL=\"{bad}\"\n"));
+            let err = read_env_config(cfg.path()).unwrap_err();
+            assert!(format!("{err:#}").contains("OBJECT_STORE_URL"), "{bad}");
+        }
+    }
+
+    #[test]
+    fn read_env_config_reads_github_timeouts() {
+        let cfg = read_env_config(
+            make_config("GITHUB_CONNECT_TIMEOUT_SECS=\"5\"\nGITHUB_READ_TIMEOUT_SECS=\"120\"\n")
+                .path(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(cfg.github_connect_timeout_secs, Some(5));
+        assert_eq!(cfg.github_read_timeout_secs, Some(120));
+
+        let err =
+            read_env_config(make_config("GITHUB_READ_TIMEOUT_SECS=\"0\"\n").path()).unwrap_err();
+        assert!(format!("{err:#}").contains("at least 1 second"));
+    }
+
+    #[test]
+    fn absolute_root_rejects_relative_paths() {
+        let err = absolute_root("media root", PathBuf::from("yt")).unwrap_err();
+        assert!(err.to_string().contains("must be an absolute path"));
+        assert_eq!(
+            absolute_root("media root", PathBuf::from("/srv/./yt/../media/")).unwrap(),
+            PathBuf::from("/srv/media")
+        );
+    }
+
+    #[test]
+    fn ensure_roots_disjoint_rejects_equal_roots() {
+        let err =
+            ensure_roots_disjoint(Path::new("/srv/yt"), Path::new("/srv/yt/"), &[]).unwrap_err();
+        assert!(err.to_string().contains("must not be the same"));
+    }
+
+    #[test]
+    fn ensure_roots_disjoint_rejects_nested_roots() {
+        assert!(
+            ensure_roots_disjoint(Path::new("/srv/yt"), Path::new("/srv/yt/www"), &[]).is_err()
+        );
+        assert!(
+            ensure_roots_disjoint(Path::new("/srv/www/media"), Path::new("/srv/www"), &[]).is_err()
+        );
+        assert!(
+            ensure_roots_disjoint(Path::new("/srv/yt"), Path::new("/srv/other/../yt/www"), &[])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn ensure_roots_disjoint_accepts_siblings() {
+        ensure_roots_disjoint(Path::new("/srv/yt"), Path::new("/srv/yt-www"), &[]).unwrap();
+        ensure_roots_disjoint(
+            Path::new("/yt"),
+            Path::new("/www/newtube.com"),
+            &[
+                Path::new(DEFAULT_CONFIG_PATH),
+                Path::new("/opt/newtube/bin"),
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn ensure_roots_disjoint_rejects_reserved_paths() {
+        let err = ensure_roots_disjoint(
+            Path::new("/yt"),
+            Path::new("/opt/newtube"),
+            &[Path::new("/opt/newtube/bin")],
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("www root"));
+        assert!(
+            ensure_roots_disjoint(
+                Path::new("/etc"),
+                Path::new("/www"),
+                &[Path::new(DEFAULT_CONFIG_PATH)]
+            )
+            .is_err()
+        );
+    }
 }
@@ -0,0 +1,161 @@
+//! Naming of the per-video directories shared by the downloader and backend.
+//!
+//! YouTube ids are case-sensitive, so `abcD` and `abcd` are different videos.
+//! On a case-insensitive filesystem (macOS/Windows defaults, some network
+//! shares) they would land in the same directory and overwrite each other.
+//! When the media root is case-insensitive every directory name carries a
+//! suffix recording which characters are uppercase; the database always keeps
+//! the exact id.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::metadata::VideoSource;
+
+/// Directory inside each media root where the downloader assembles a video
+/// before renaming it into place. Nothing under it is ever served.
+pub const STAGING_DIR: &str = ".staging";
+
+/// Separator between the id and the case suffix. `~` never appears in
+/// YouTube ids, so suffixed names cannot collide with plain ones.
+const CASE_SUFFIX_SEPARATOR: char = '~';
+
+/// Probes `dir` by creating two directories whose names differ only in case.
+pub fn is_case_insensitive(dir: &Path) -> Result<bool> {
+    let probe = tempfile::Builder::new()
+        .prefix(".newtube-case-probe-")
+        .tempdir_in(dir)
+        .with_context(|| format!("creating case probe in {}", dir.display()))?;
+    let lower = probe.path().join("case-probe");
+    fs::create_dir(&lower).with_context(|| format!("creating {}", lower.display()))?;
+    let upper = probe.path().join("CASE-PROBE");
+    match fs::create_dir(&upper) {
+        Ok(()) => Ok(false),
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => Ok(true),
+        Err(err) => Err(err).with_context(|| format!("creating {}", upper.display())),
+    }
+}
+
+/// Directory name used for `videoid`. Unchanged on case-sensitive
+/// filesystems; otherwise `<id>~<mask>`, where each hex digit of the mask
+/// flags the uppercase characters in one group of four.
+pub fn video_dir_name(videoid: &str, case_insensitive: bool) -> String {
+    if !case_insensitive {
+        return videoid.to_owned();
+    }
+
+    let chars: Vec<char> = videoid.chars().collect();
+    let mask: String = chars
+        .chunks(4)
+        .map(|chunk| {
+            let nibble = chunk
+                .iter()
+                .enumerate()
+                .filter(|(_, ch)| ch.is_uppercase())
+                .fold(0u32, |acc, (index, _)| acc | (1 << index));
+            char::from_digit(nibble, 16).unwrap_or('0')
+        })
+        .collect();
+    format!("{videoid}{CASE_SUFFIX_SEPARATOR}{mask}")
+}
+
+/// Resolves the directory for `videoid` under `parent`. Libraries created
+/// before the suffix existed keep their plain directory so nothing already on
+/// disk goes missing. The plain directory must be spelled exactly `videoid`:
+/// the filesystem folds case, so a lookup by path would also match an id
+/// that differs only in case.
+pub fn video_dir(parent: &Path, videoid: &str, case_insensitive: bool) -> PathBuf {
+    let suffixed = parent.join(video_dir_name(videoid, case_insensitive));
+    if case_insensitive && !suffixed.exists() && has_exact_dir(parent, videoid) {
+        return parent.join(videoid);
+    }
+    suffixed
+}
+
+/// Whether `parent` lists a directory named byte-for-byte `name`.
+fn has_exact_dir(parent: &Path, name: &str) -> bool {
+    let Ok(entries) = fs::read_dir(parent) else {
+        return false;
+    };
+    entries
+        .flatten()
+        .any(|entry| entry.file_name() == name && entry.file_type().is_ok_and(|kind| kind.is_dir()))
+}
+
+/// Locates the file backing `source`: the stored absolute path when present,
+/// otherwise the `{videoid}_{format}.{ext}` name the downloader writes inside
+/// the video's directory under `media_dir`.
+pub fn source_path(
+    media_dir: &Path,
+    videoid: &str,
+    source: &VideoSource,
+    case_insensitive: bool,
+) -> PathBuf {
+    match &source.path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            // Rows older than `stream_key` only carry the slug in their URL.
+            let format = source
+                .stream_key
+                .as_deref()
+                .or_else(|| source.url.rsplit('/').next())
+                .unwrap_or(&source.format_id);
+            let ext = source.ext.as_deref().unwrap_or("mp4");
+            video_dir(media_dir, videoid, case_insensitive)
+                .join(format!("{videoid}_{format}.{ext}"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn names_are_unchanged_on_case_sensitive_filesystems() {
+        assert_eq!(video_dir_name("abcD", false), "abcD");
+    }
+
+    #[test]
+    fn ids_differing_only_in_case_get_distinct_names() {
+        let upper = video_dir_name("dQw4w9WgXcQ", true);
+        let lower = video_dir_name("dqw4w9wgxcq", true);
+        assert_eq!(upper, "dQw4w9WgXcQ~245");
+        assert_eq!(lower, "dqw4w9wgxcq~000");
+        assert!(!upper.eq_ignore_ascii_case(&lower));
+    }
+
+    #[test]
+    fn legacy_plain_directories_are_still_resolved() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("abcD")).unwrap();
+        assert_eq!(video_dir(dir.path(), "abcD", true), dir.path().join("abcD"));
+        // Another id folding to the same name must not claim that directory.
+        assert!(!has_exact_dir(dir.path(), "abcd"));
+        assert_eq!(
+            video_dir(dir.path(), "abcd", true),
+            dir.path().join("abcd~0")
+        );
+        assert_eq!(
+            video_dir(dir.path(), "abcd", false),
+            dir.path().join("abcd")
+        );
+
+        fs::create_dir(dir.path().join("abcD~8")).unwrap();
+        assert_eq!(
+            video_dir(dir.path(), "abcD", true),
+            dir.path().join("abcD~8")
+        );
+    }
+
+    #[test]
+    fn probe_cleans_up_after_itself() {
+        let dir = tempdir().unwrap();
+        is_case_insensitive(dir.path()).unwrap();
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
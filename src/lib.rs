@@ -5,6 +5,12 @@
 //! The crate is intentionally small; it mostly exposes the metadata module so
 //! binaries can share struct definitions and database helpers.
 
+pub mod archive;
 pub mod config;
+pub mod layout;
+pub mod logging;
 pub mod metadata;
+pub mod preflight;
+pub mod rotation;
 pub mod security;
+pub mod timestamps;
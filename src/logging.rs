@@ -0,0 +1,108 @@
+//! `tracing` setup shared by the newtube binaries.
+//!
+//! Every binary accepts `--quiet` (warnings and errors only) and `--verbose`
+//! (adds per-step `debug` events). `RUST_LOG` overrides both, e.g.
+//! `RUST_LOG=download_channel=debug,warn`. Warnings and errors go to stderr
+//! and everything else to stdout, so shell redirects keep working as before.
+
+use std::io::IsTerminal;
+
+use anyhow::{Result, bail};
+use tracing::Level;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::writer::MakeWriterExt;
+
+/// How much a binary logs when `RUST_LOG` is unset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Verbosity {
+    /// `--quiet`: warnings and errors only.
+    Quiet,
+    /// Progress at `info`.
+    #[default]
+    Normal,
+    /// `--verbose`: also per-step `debug` events.
+    Verbose,
+}
+
+impl Verbosity {
+    /// Combines the `--quiet`/`--verbose` flags.
+    pub fn from_flags(quiet: bool, verbose: bool) -> Result<Self> {
+        Ok(match (quiet, verbose) {
+            (true, true) => bail!("--quiet and --verbose cannot be combined"),
+            (true, false) => Self::Quiet,
+            (false, true) => Self::Verbose,
+            (false, false) => Self::Normal,
+        })
+    }
+
+    /// The flag that selects this level, for forwarding to child processes.
+    pub fn flag(self) -> Option<&'static str> {
+        match self {
+            Self::Quiet => Some("--quiet"),
+            Self::Normal => None,
+            Self::Verbose => Some("--verbose"),
+        }
+    }
+
+    /// Filter applied without `RUST_LOG`. Dependencies stay at `warn` so
+    /// `--verbose` only adds our own events.
+    fn directives(self, target: &str) -> String {
+        let level = match self {
+            Self::Quiet => return "warn".to_owned(),
+            Self::Normal => "info",
+            Self::Verbose => "debug",
+        };
+        format!("warn,{target}={level},newtube_tools={level}")
+    }
+}
+
+/// Installs the global subscriber for the binary whose crate name is
+/// `target` (`download_channel`, `backend`, ...). On a terminal the output
+/// is compact and colored; otherwise (journald, cron mail, log files) it
+/// carries timestamps and targets, with fields such as `video_id=...` left
+/// plain for grepping. Calling it twice keeps the first subscriber.
+pub fn init(target: &str, verbosity: Verbosity) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(verbosity.directives(target)));
+    let writer = std::io::stderr
+        .with_max_level(Level::WARN)
+        .or_else(std::io::stdout);
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer);
+
+    let _ = if std::io::stdout().is_terminal() {
+        builder.without_time().with_target(false).try_init()
+    } else {
+        builder.with_ansi(false).try_init()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_map_to_filter_directives() {
+        let quiet = Verbosity::from_flags(true, false).unwrap();
+        assert_eq!(quiet, Verbosity::Quiet);
+        assert_eq!(quiet.directives("backend"), "warn");
+        assert_eq!(quiet.flag(), Some("--quiet"));
+
+        let normal = Verbosity::from_flags(false, false).unwrap();
+        assert_eq!(
+            normal.directives("backend"),
+            "warn,backend=info,newtube_tools=info"
+        );
+        assert_eq!(normal.flag(), None);
+
+        let verbose = Verbosity::from_flags(false, true).unwrap();
+        assert_eq!(
+            verbose.directives("download_channel"),
+            "warn,download_channel=debug,newtube_tools=debug"
+        );
+
+        let err = Verbosity::from_flags(true, true).unwrap_err();
+        assert!(err.to_string().contains("cannot be combined"));
+    }
+}
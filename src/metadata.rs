@@ -3,12 +3,31 @@
 //! All structs in this module mirror how metadata is serialized to disk and
 //! exposed to the API.
 
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
-use anyhow::{Context, Result};
-use rusqlite::{Connection, OptionalExtension, Row, params};
+use anyhow::{Context, Result, bail};
+use chrono_tz::Tz;
+use parking_lot::Mutex;
+use rusqlite::{Connection, OpenFlags, OptionalExtension, Row, TransactionBehavior, params};
 use serde::{Deserialize, Serialize};
 
+use crate::timestamps::epoch_in_timezone;
+
+/// How long a writer waits for another process holding the write lock
+/// (e.g. a stats-only refresh racing a full download) before giving up.
+const WRITER_BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// BM25 weights of the title, description and tags search columns: a hit in
+/// the title counts for far more than one buried in a long description.
+const SEARCH_WEIGHTS: &str = "10.0, 1.0, 5.0";
+
+/// Readers rarely wait in WAL mode, but a writer migrating the schema or a
+/// checkpoint can briefly lock them out; retry instead of failing the request.
+const READER_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Description of a single downloadable media source (e.g. 1080p mp4).
 ///
 /// Sources can point to files on disk (`path`) or merely expose a streaming
@@ -34,6 +53,11 @@ pub struct VideoSource {
     pub url: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub path: Option<String>,
+    /// Format slug used by `/api/{videos,shorts}/{id}/streams/{stream_key}`.
+    /// Older rows predate this field, so consumers fall back to the last URL
+    /// segment when it is missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_key: Option<String>,
 }
 
 /// Rows stored in the `videos` and `shorts` tables.
@@ -51,8 +75,16 @@ pub struct VideoRecord {
     pub dislikes: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub views: Option<i64>,
+    /// ISO 8601 UTC (`...Z`), derived from `upload_epoch`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub upload_date: Option<String>,
+    /// Upload time in UTC epoch seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_epoch: Option<i64>,
+    /// `upload_date` in the configured `DISPLAY_TIMEZONE` (derived when
+    /// reading, never stored).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_date_display: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub author: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -65,6 +97,11 @@ pub struct VideoRecord {
     pub channel_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thumbnail_url: Option<String>,
+    /// Whether `thumbnail_url` was picked by an operator through
+    /// [`MetadataStore::set_thumbnail_url`] rather than by the downloader.
+    /// Metadata refreshes only keep the stored URL while this is set.
+    #[serde(default)]
+    pub poster_override: bool,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub tags: Vec<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -73,6 +110,75 @@ pub struct VideoRecord {
     pub extras: serde_json::Value,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub sources: Vec<VideoSource>,
+    /// Audio-only file kept by `download_channel --keep-audio`, served by
+    /// `/api/videos/{id}/audio`. Never part of `sources`, which only lists
+    /// muxed files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_source: Option<VideoSource>,
+    /// Chapters from yt-dlp's `chapters`, in order. Always serialized, as
+    /// `[]` when the video has none.
+    #[serde(default)]
+    pub chapters: Vec<Chapter>,
+    /// SponsorBlock segments. `None` (stored as NULL) when the video was
+    /// never fetched with `--sponsorblock`, so a refresh without the flag
+    /// keeps the segments stored earlier; `Some([])` when it was and none
+    /// exist.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sponsor_segments: Option<Vec<SponsorSegment>>,
+    /// Whether a `subtitles` row exists for this video. Derived when reading,
+    /// never written back by `upsert_video`.
+    #[serde(default)]
+    pub has_subtitles: bool,
+    /// Language codes of the stored subtitle tracks (derived like
+    /// `has_subtitles`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subtitle_languages: Vec<String>,
+    /// Whether the video was marked watched, from `watch_state` (derived).
+    #[serde(default)]
+    pub watched: bool,
+    /// When the row was last written (`YYYY-MM-DDTHH:MM:SS.sssZ`). Set by
+    /// the store on every upsert; `None` for rows untouched since the
+    /// column was added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refreshed_at: Option<String>,
+    /// yt-dlp's `webpage_url`: the canonical watch page. `None` for rows
+    /// written before it was captured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webpage_url: Option<String>,
+    /// yt-dlp's `original_url`: the URL the download was started from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_url: Option<String>,
+    /// Set by list responses when `description` was clipped; the full text
+    /// is only returned by single-video lookups. Never stored.
+    #[serde(default)]
+    pub description_truncated: bool,
+    /// Times the video was played from this archive, from `view_counts`
+    /// (unlike `views`, which is YouTube's count at download time). Only
+    /// single-video lookups fill it in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_views: Option<i64>,
+}
+
+/// One chapter of a video, in seconds from the start. yt-dlp sometimes
+/// omits `end_time` on the last chapter; readers bound it with the next
+/// chapter or the video duration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Chapter {
+    pub start_time: f64,
+    pub end_time: Option<f64>,
+    #[serde(default)]
+    pub title: String,
+}
+
+/// One SponsorBlock segment (sponsor, intro, outro, ...), in seconds from the
+/// start, as yt-dlp records it under `sponsorblock_chapters`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SponsorSegment {
+    pub start_time: f64,
+    pub end_time: f64,
+    pub category: String,
 }
 
 /// Subtitle manifest for a single video.
@@ -104,8 +210,16 @@ pub struct CommentRecord {
     pub text: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub likes: Option<i64>,
+    /// ISO 8601 UTC when yt-dlp reported a timestamp, otherwise its
+    /// relative text (e.g. "2 days ago").
     #[serde(skip_serializing_if = "Option::is_none")]
     pub time_posted: Option<String>,
+    /// Posting time in UTC epoch seconds, when known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_posted_epoch: Option<i64>,
+    /// `time_posted` in the configured `DISPLAY_TIMEZONE` (derived).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_posted_display: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parent_comment_id: Option<String>,
     #[serde(default)]
@@ -114,6 +228,183 @@ pub struct CommentRecord {
     pub reply_count: Option<i64>,
 }
 
+/// A comment with its replies nested under it, as served by
+/// `/comments?threaded=true`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommentThread {
+    pub comment: CommentRecord,
+    pub replies: Vec<CommentThread>,
+}
+
+/// Every video, short, subtitle set and comment in the database, as
+/// written by `routine_update --export`. Rows are sorted by id so two dumps
+/// of the same library diff cleanly. Watch progress, favorites and view
+/// counters are user state and are not included.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryExport {
+    pub version: u32,
+    pub videos: Vec<VideoRecord>,
+    pub shorts: Vec<VideoRecord>,
+    pub subtitles: Vec<SubtitleCollection>,
+    pub comments: Vec<CommentRecord>,
+}
+
+impl LibraryExport {
+    /// Bumped whenever the dump layout changes incompatibly.
+    pub const VERSION: u32 = 1;
+}
+
+/// Last playback position reported by the player for a video or short.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchProgress {
+    pub videoid: String,
+    pub position_seconds: f64,
+    pub updated_at: String,
+}
+
+/// A partially watched video joined with its stored progress, used for the
+/// "continue watching" row.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContinueWatchingEntry {
+    pub video: VideoRecord,
+    pub progress: WatchProgress,
+}
+
+/// Label of the [`ChannelSummary`] that collects uploads without an author.
+pub const UNKNOWN_AUTHOR: &str = "Unknown";
+
+/// One channel of the archive, as listed by `GET /api/channels`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelSummary {
+    /// [`UNKNOWN_AUTHOR`] for uploads without one.
+    pub author: String,
+    /// `None` for the unknown bucket, which may span several channels.
+    pub channel_url: Option<String>,
+    /// Videos and Shorts together.
+    pub video_count: u64,
+    /// Newest `upload_date` of the channel.
+    pub latest_upload: Option<String>,
+}
+
+/// Slice of a newest-first listing: at most `limit` rows after skipping
+/// `offset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageRange {
+    pub limit: usize,
+    pub offset: usize,
+}
+
+/// Column a listing is ordered by, `?sort=` on `/api/videos` and
+/// `/api/shorts`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    /// Upload date.
+    #[default]
+    Date,
+    Views,
+    Likes,
+    Duration,
+}
+
+/// Direction of a [`SortKey`], `?order=asc|desc`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Which rows a listing returns and in what order. The default is every
+/// row, newest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListFilter {
+    /// `Some(true)` keeps only watched rows, `Some(false)` only unwatched
+    /// ones.
+    pub watched: Option<bool>,
+    /// Exact stored `channel_url`.
+    pub channel_url: Option<String>,
+    /// Keeps only rows carrying this tag (compared case-insensitively).
+    pub tag: Option<String>,
+    pub sort: SortKey,
+    pub order: SortOrder,
+}
+
+impl ListFilter {
+    /// `ORDER BY` clause for the listing. Built only from the enums, never
+    /// from request text. Rows without a value for the key come last either
+    /// way, and ties stay newest first.
+    fn order_by(&self) -> String {
+        let dir = match self.order {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        };
+        let column = match self.sort {
+            SortKey::Date => {
+                return format!("v.upload_date IS NULL, v.upload_date {dir}, v.rowid {dir}");
+            }
+            SortKey::Views => "v.views",
+            SortKey::Likes => "v.likes",
+            SortKey::Duration => "v.duration",
+        };
+        format!("{column} IS NULL, {column} {dir}, v.upload_date DESC, v.rowid DESC")
+    }
+}
+
+/// Columns [`row_to_video_record`] reads, selected from a `videos`/`shorts`
+/// table aliased `v` joined with `subtitles s` and `watch_state w`.
+const VIDEO_COLUMNS: &str = r#"
+    v.videoid, v.title, v.description, v.likes, v.dislikes, v.views,
+    v.upload_date, v.author, v.subscriber_count, v.duration,
+    v.duration_text, v.channel_url, v.thumbnail_url, v.tags_json,
+    v.thumbnails_json, v.extras_json, v.sources_json, v.refreshed_at,
+    v.upload_epoch, v.webpage_url, v.original_url, v.chapters_json,
+    v.sponsor_segments_json, v.audio_source_json, v.poster_override,
+    s.languages_json AS subtitles_json,
+    COALESCE(w.watched, 0) AS watched
+"#;
+
+/// `WHERE` clause of the filtered listings and their counts: `?1` watched,
+/// `?2` channel URL, `?3` tag.
+const LIST_FILTER_SQL: &str = r#"
+    WHERE (?1 IS NULL OR COALESCE(w.watched, 0) = ?1)
+      AND (?2 IS NULL OR v.channel_url = ?2)
+      AND (?3 IS NULL OR EXISTS (
+          SELECT 1 FROM json_each(v.tags_json) t WHERE t.value = ?3 COLLATE NOCASE
+      ))
+"#;
+
+/// Optional SQLite knobs read from `SQLITE_PAGE_SIZE`/`SQLITE_CACHE_KB`.
+/// `None` keeps SQLite's defaults.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SqliteTuning {
+    /// Bytes per page. SQLite fixes the page size once the first table is
+    /// written (and WAL mode pins it for good), so this only applies when the
+    /// DB file is created; existing DBs keep their page size.
+    pub page_size: Option<u32>,
+    /// Page cache size per connection, in KiB.
+    pub cache_kb: Option<u32>,
+    /// Idle read-only connections each [`MetadataReader`] keeps for reuse;
+    /// `None` means [`DEFAULT_READ_POOL`] and `0` opens one per query.
+    pub read_pool: Option<u32>,
+}
+
+/// Idle connections a [`MetadataReader`] keeps when `SQLITE_READ_POOL` is
+/// unset; enough for the backend's usual handful of concurrent requests.
+pub const DEFAULT_READ_POOL: u32 = 8;
+
+impl SqliteTuning {
+    fn apply_cache_size(&self, conn: &Connection) -> Result<()> {
+        if let Some(cache_kb) = self.cache_kb {
+            // Negative values are interpreted as KiB rather than pages.
+            conn.pragma_update(None, "cache_size", -i64::from(cache_kb))
+                .context("setting metadata DB cache size")?;
+        }
+        Ok(())
+    }
+}
+
 /// Wrapper around the SQLite connection that performs read/write operations.
 #[derive(Debug)]
 pub struct MetadataStore {
@@ -122,8 +413,16 @@ pub struct MetadataStore {
 
 impl MetadataStore {
     /// Opens (and if necessary creates) the SQLite DB and ensures the expected
-    /// schema exists. WAL mode is enabled to avoid readers blocking writers.
+    /// schema exists. WAL mode is enabled to avoid readers blocking writers,
+    /// and writers wait briefly on each other so two downloader runs can
+    /// share the DB.
     pub fn open(path: &Path) -> Result<Self> {
+        Self::open_tuned(path, SqliteTuning::default())
+    }
+
+    /// Same as [`MetadataStore::open`] but applies `tuning` first. The page
+    /// size is only set when the DB has no pages yet.
+    pub fn open_tuned(path: &Path, tuning: SqliteTuning) -> Result<Self> {
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)
                 .with_context(|| format!("creating metadata directory {}", parent.display()))?;
@@ -131,6 +430,19 @@ impl MetadataStore {
 
         let conn = Connection::open(path)
             .with_context(|| format!("opening metadata DB {}", path.display()))?;
+        // Set before anything else: switching to WAL and creating the schema
+        // both need the write lock, which another process may be holding.
+        conn.busy_timeout(WRITER_BUSY_TIMEOUT)
+            .context("setting metadata DB busy timeout")?;
+
+        if let Some(page_size) = tuning.page_size {
+            let page_count: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+            if page_count == 0 {
+                conn.pragma_update(None, "page_size", page_size)
+                    .context("setting metadata DB page size")?;
+            }
+        }
+        tuning.apply_cache_size(&conn)?;
 
         conn.pragma_update(None, "journal_mode", "WAL")
             .context("enabling WAL mode for metadata DB")?;
@@ -145,7 +457,12 @@ impl MetadataStore {
     /// Runs the SQL required to create the tables if they do not already
     /// exist. Wrapped in a transaction so a failure leaves the DB untouched.
     fn ensure_tables(&mut self) -> Result<()> {
-        let tx = self.conn.transaction()?;
+        // Take the write lock up front: a deferred transaction that upgrades
+        // from read to write fails immediately instead of honoring the busy
+        // timeout when another store opens at the same moment.
+        let tx = self
+            .conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)?;
 
         tx.execute_batch(
             r#"
@@ -207,46 +524,159 @@ impl MetadataStore {
                 FOREIGN KEY (videoid) REFERENCES videos(videoid) ON DELETE CASCADE
             );
 
+            CREATE TABLE IF NOT EXISTS watch_progress (
+                videoid TEXT PRIMARY KEY,
+                position_seconds REAL NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS channels (
+                channel_url TEXT PRIMARY KEY,
+                last_upload_date TEXT,
+                last_run_at TEXT,
+                resume_cursor TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS watch_state (
+                videoid TEXT PRIMARY KEY,
+                watched INTEGER NOT NULL DEFAULT 0,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS favorites (
+                videoid TEXT PRIMARY KEY,
+                added_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS view_counts (
+                videoid TEXT PRIMARY KEY,
+                local_views INTEGER NOT NULL DEFAULT 0
+            );
+
+            CREATE TABLE IF NOT EXISTS deleted_media (
+                videoid TEXT PRIMARY KEY,
+                deleted_at TEXT NOT NULL
+            );
+
             CREATE INDEX IF NOT EXISTS idx_comments_videoid ON comments(videoid);
             CREATE INDEX IF NOT EXISTS idx_comments_parent ON comments(parent_comment_id);
             "#,
         )?;
 
+        // Columns added after a table first shipped. `CREATE TABLE IF NOT
+        // EXISTS` leaves existing tables alone, so older DBs get them here.
+        add_column_if_missing(&tx, "channels", "resume_cursor", "TEXT")?;
+        add_column_if_missing(&tx, "videos", "refreshed_at", "TEXT")?;
+        add_column_if_missing(&tx, "shorts", "refreshed_at", "TEXT")?;
+        for table in ["videos", "shorts"] {
+            add_column_if_missing(&tx, table, "webpage_url", "TEXT")?;
+            add_column_if_missing(&tx, table, "original_url", "TEXT")?;
+        }
+        for table in ["videos", "shorts"] {
+            if add_column_if_missing(&tx, table, "upload_epoch", "INTEGER")? {
+                // Stored dates are UTC ISO strings; NULL when unparseable.
+                tx.execute(
+                    &format!(
+                        "UPDATE {table} SET upload_epoch = CAST(strftime('%s', upload_date) AS INTEGER)"
+                    ),
+                    [],
+                )?;
+            }
+        }
+        for table in ["videos", "shorts"] {
+            if add_column_if_missing(&tx, table, "chapters_json", "TEXT DEFAULT '[]'")? {
+                // Chapters used to live in `extras.chapters`, in the same shape.
+                tx.execute(
+                    &format!(
+                        "UPDATE {table} SET chapters_json = json_extract(extras_json, '$.chapters')
+                         WHERE json_valid(extras_json)
+                           AND json_type(extras_json, '$.chapters') = 'array'"
+                    ),
+                    [],
+                )?;
+            }
+        }
+        for table in ["videos", "shorts"] {
+            add_column_if_missing(&tx, table, "sponsor_segments_json", "TEXT")?;
+            add_column_if_missing(&tx, table, "audio_source_json", "TEXT")?;
+            add_column_if_missing(&tx, table, "poster_override", "INTEGER NOT NULL DEFAULT 0")?;
+        }
+        if add_column_if_missing(&tx, "comments", "time_posted_epoch", "INTEGER")? {
+            tx.execute(
+                "UPDATE comments SET time_posted_epoch = CAST(strftime('%s', time_posted) AS INTEGER)",
+                [],
+            )?;
+        }
+        tx.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_videos_refreshed_at ON videos(refreshed_at);",
+        )?;
+        for table in ["videos", "shorts"] {
+            ensure_search_index(&tx, table)?;
+        }
+
         tx.commit()?;
         Ok(())
     }
 
     /// Inserts or updates a long-form video entry.
     pub fn upsert_video(&self, record: &VideoRecord) -> Result<()> {
-        self.upsert("videos", record)
+        self.upsert_in_transaction("videos", record)
     }
 
     pub fn upsert_short(&self, record: &VideoRecord) -> Result<()> {
-        self.upsert("shorts", record)
+        self.upsert_in_transaction("shorts", record)
+    }
+
+    /// Runs [`Self::upsert`] in its own transaction, so a failure cannot
+    /// leave the row and its search index entry out of step.
+    fn upsert_in_transaction(&self, table: &str, record: &VideoRecord) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        Self::upsert(&tx, table, record)?;
+        tx.commit()?;
+        Ok(())
     }
 
-    /// Shared helper used by both `videos` and `shorts` tables.
-    fn upsert(&self, table: &str, record: &VideoRecord) -> Result<()> {
+    /// Shared helper used by both `videos` and `shorts` tables. Takes the
+    /// connection so [`Self::import_all`] can run it inside its transaction.
+    fn upsert(conn: &Connection, table: &str, record: &VideoRecord) -> Result<()> {
         let tags_json = serde_json::to_string(&record.tags).context("serializing tags")?;
         let thumbnails_json =
             serde_json::to_string(&record.thumbnails).context("serializing thumbnails")?;
         let extras_json =
             serde_json::to_string(&record.extras).context("serializing extra metadata")?;
         let sources_json = serde_json::to_string(&record.sources).context("serializing sources")?;
-
-        self.conn.execute(
+        let chapters_json =
+            serde_json::to_string(&record.chapters).context("serializing chapters")?;
+        let sponsor_segments_json = record
+            .sponsor_segments
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .context("serializing sponsor segments")?;
+        let audio_source_json = record
+            .audio_source
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .context("serializing audio source")?;
+
+        conn.execute(
             &format!(
                 r#"
                 INSERT INTO {table} (
                     videoid, title, description, likes, dislikes, views,
                     upload_date, author, subscriber_count, duration, duration_text,
                     channel_url, thumbnail_url, tags_json, thumbnails_json,
-                    extras_json, sources_json
+                    extras_json, sources_json, refreshed_at, upload_epoch,
+                    webpage_url, original_url, chapters_json, sponsor_segments_json,
+                    audio_source_json, poster_override
                 ) VALUES (
                     :videoid, :title, :description, :likes, :dislikes, :views,
                     :upload_date, :author, :subscriber_count, :duration, :duration_text,
                     :channel_url, :thumbnail_url, :tags_json, :thumbnails_json,
-                    :extras_json, :sources_json
+                    :extras_json, :sources_json, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+                    :upload_epoch, :webpage_url, :original_url, :chapters_json,
+                    :sponsor_segments_json, :audio_source_json, :poster_override
                 )
                 ON CONFLICT(videoid) DO UPDATE SET
                     title = excluded.title,
@@ -260,11 +690,41 @@ impl MetadataStore {
                     duration = excluded.duration,
                     duration_text = excluded.duration_text,
                     channel_url = excluded.channel_url,
-                    thumbnail_url = excluded.thumbnail_url,
+                    -- Keep an operator-chosen poster as long as the file is
+                    -- still listed; a downloader pick follows the refresh.
+                    thumbnail_url = CASE
+                        WHEN NOT excluded.poster_override
+                            AND {table}.poster_override
+                            AND EXISTS (
+                                SELECT 1 FROM json_each(excluded.thumbnails_json)
+                                WHERE value = {table}.thumbnail_url
+                            )
+                        THEN {table}.thumbnail_url
+                        ELSE excluded.thumbnail_url
+                    END,
+                    poster_override = excluded.poster_override OR (
+                        {table}.poster_override
+                        AND EXISTS (
+                            SELECT 1 FROM json_each(excluded.thumbnails_json)
+                            WHERE value = {table}.thumbnail_url
+                        )
+                    ),
                     tags_json = excluded.tags_json,
                     thumbnails_json = excluded.thumbnails_json,
                     extras_json = excluded.extras_json,
-                    sources_json = excluded.sources_json
+                    sources_json = excluded.sources_json,
+                    refreshed_at = excluded.refreshed_at,
+                    upload_epoch = excluded.upload_epoch,
+                    webpage_url = excluded.webpage_url,
+                    original_url = excluded.original_url,
+                    chapters_json = excluded.chapters_json,
+                    -- A refresh without `--sponsorblock` writes NULL; keep
+                    -- the segments fetched earlier.
+                    sponsor_segments_json = COALESCE(
+                        excluded.sponsor_segments_json,
+                        {table}.sponsor_segments_json
+                    ),
+                    audio_source_json = excluded.audio_source_json
                 "#,
             ),
             params![
@@ -285,18 +745,203 @@ impl MetadataStore {
                 thumbnails_json,
                 extras_json,
                 sources_json,
+                record.upload_epoch,
+                record.webpage_url,
+                record.original_url,
+                chapters_json,
+                sponsor_segments_json,
+                audio_source_json,
+                record.poster_override,
             ],
         )?;
 
+        // The upsert keeps the rowid, which also keys the search index.
+        let tags = record.tags.join(" ");
+        conn.execute(
+            &format!(
+                "DELETE FROM {table}_fts WHERE rowid = (SELECT rowid FROM {table} WHERE videoid = ?1)"
+            ),
+            [&record.videoid],
+        )?;
+        conn.execute(
+            &format!(
+                r#"
+                INSERT INTO {table}_fts (rowid, title, description, tags)
+                SELECT rowid, title, description, ?2 FROM {table} WHERE videoid = ?1
+                "#
+            ),
+            params![record.videoid, tags],
+        )?;
+
+        Ok(())
+    }
+
+    /// Overrides the poster of a long-form video and marks it as
+    /// `poster_override`. Returns `false` when the video does not exist.
+    /// Later metadata refreshes keep the choice as long as the thumbnail is
+    /// still listed for the video.
+    pub fn set_thumbnail_url(&self, videoid: &str, thumbnail_url: &str) -> Result<bool> {
+        let updated = self.conn.execute(
+            r#"
+            UPDATE videos
+            SET thumbnail_url = ?2, poster_override = 1,
+                refreshed_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+            WHERE videoid = ?1
+            "#,
+            params![videoid, thumbnail_url],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Records a finished downloader run for `channel_url` (see
+    /// [`canonical_channel_url`]). `last_upload_date` only ever moves forward so
+    /// a partial run cannot make the next incremental scan skip videos.
+    pub fn record_channel_run(
+        &self,
+        channel_url: &str,
+        newest_upload_date: Option<&str>,
+    ) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO channels (channel_url, last_upload_date, last_run_at)
+            VALUES (?1, ?2, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+            ON CONFLICT(channel_url) DO UPDATE SET
+                last_upload_date = CASE
+                    WHEN excluded.last_upload_date IS NULL THEN channels.last_upload_date
+                    WHEN channels.last_upload_date IS NULL
+                        OR excluded.last_upload_date > channels.last_upload_date
+                        THEN excluded.last_upload_date
+                    ELSE channels.last_upload_date
+                END,
+                last_run_at = excluded.last_run_at,
+                resume_cursor = NULL
+            "#,
+            params![channel_url, newest_upload_date],
+        )?;
+        Ok(())
+    }
+
+    /// Newest `upload_date` seen for a channel during previous runs.
+    pub fn channel_last_upload_date(&self, channel_url: &str) -> Result<Option<String>> {
+        let value = self
+            .conn
+            .query_row(
+                "SELECT last_upload_date FROM channels WHERE channel_url = ?1",
+                [channel_url],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()?;
+        Ok(value.flatten())
+    }
+
+    /// Remembers the last entry an in-progress download run handled so an
+    /// interrupted run can resume there. Cleared by `record_channel_run`.
+    pub fn save_resume_cursor(&self, channel_url: &str, cursor: &str) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO channels (channel_url, resume_cursor)
+            VALUES (?1, ?2)
+            ON CONFLICT(channel_url) DO UPDATE SET
+                resume_cursor = excluded.resume_cursor
+            "#,
+            params![channel_url, cursor],
+        )?;
+        Ok(())
+    }
+
+    /// Cursor left by a run that did not finish, if any.
+    pub fn channel_resume_cursor(&self, channel_url: &str) -> Result<Option<String>> {
+        let value = self
+            .conn
+            .query_row(
+                "SELECT resume_cursor FROM channels WHERE channel_url = ?1",
+                [channel_url],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()?;
+        Ok(value.flatten())
+    }
+
+    /// Bookmarks a video. Favoriting twice keeps the original `added_at` so
+    /// the list order does not jump around.
+    pub fn add_favorite(&self, videoid: &str) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO favorites (videoid, added_at)
+            VALUES (?1, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+            ON CONFLICT(videoid) DO NOTHING
+            "#,
+            [videoid],
+        )?;
+        Ok(())
+    }
+
+    /// Removes a bookmark. Returns `false` when the video was not favorited.
+    pub fn remove_favorite(&self, videoid: &str) -> Result<bool> {
+        let removed = self
+            .conn
+            .execute("DELETE FROM favorites WHERE videoid = ?1", [videoid])?;
+        Ok(removed > 0)
+    }
+
+    /// Saves the latest playback position for a video or short, replacing any
+    /// previous value.
+    pub fn record_watch_progress(&self, videoid: &str, position_seconds: f64) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO watch_progress (videoid, position_seconds, updated_at)
+            VALUES (?1, ?2, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+            ON CONFLICT(videoid) DO UPDATE SET
+                position_seconds = excluded.position_seconds,
+                updated_at = excluded.updated_at
+            "#,
+            params![videoid, position_seconds],
+        )?;
+        Ok(())
+    }
+
+    /// Counts one local play of a video or short and returns the new total.
+    /// The first view creates the row.
+    pub fn increment_view(&self, videoid: &str) -> Result<i64> {
+        let views = self.conn.query_row(
+            r#"
+            INSERT INTO view_counts (videoid, local_views)
+            VALUES (?1, 1)
+            ON CONFLICT(videoid) DO UPDATE SET local_views = local_views + 1
+            RETURNING local_views
+            "#,
+            [videoid],
+            |row| row.get(0),
+        )?;
+        Ok(views)
+    }
+
+    /// Marks a video or short as watched (or not). Explicit and automatic
+    /// marks share the same row, so the latest one wins.
+    pub fn set_watched(&self, videoid: &str, watched: bool) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO watch_state (videoid, watched, updated_at)
+            VALUES (?1, ?2, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+            ON CONFLICT(videoid) DO UPDATE SET
+                watched = excluded.watched,
+                updated_at = excluded.updated_at
+            "#,
+            params![videoid, watched],
+        )?;
         Ok(())
     }
 
     /// Stores subtitle metadata in the DB.
     pub fn upsert_subtitles(&self, subtitles: &SubtitleCollection) -> Result<()> {
+        Self::write_subtitles(&self.conn, subtitles)
+    }
+
+    fn write_subtitles(conn: &Connection, subtitles: &SubtitleCollection) -> Result<()> {
         let languages_json =
             serde_json::to_string(&subtitles.languages).context("serializing subtitles")?;
 
-        self.conn.execute(
+        conn.execute(
             r#"
             INSERT INTO subtitles (videoid, languages_json)
             VALUES (:videoid, :languages_json)
@@ -313,16 +958,22 @@ impl MetadataStore {
     /// not mix old and new comment trees.
     pub fn replace_comments(&mut self, videoid: &str, comments: &[CommentRecord]) -> Result<()> {
         let tx = self.conn.transaction()?;
-        tx.execute("DELETE FROM comments WHERE videoid = ?1", params![videoid])?;
+        Self::write_comments(&tx, videoid, comments)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn write_comments(conn: &Connection, videoid: &str, comments: &[CommentRecord]) -> Result<()> {
+        conn.execute("DELETE FROM comments WHERE videoid = ?1", params![videoid])?;
 
         for comment in comments {
-            tx.execute(
+            conn.execute(
                 r#"
                 INSERT INTO comments (
-                    id, videoid, author, text, likes, time_posted,
+                    id, videoid, author, text, likes, time_posted, time_posted_epoch,
                     parent_comment_id, status_likedbycreator, reply_count
                 ) VALUES (
-                    :id, :videoid, :author, :text, :likes, :time_posted,
+                    :id, :videoid, :author, :text, :likes, :time_posted, :time_posted_epoch,
                     :parent_comment_id, :status_likedbycreator, :reply_count
                 )
                 "#,
@@ -333,6 +984,7 @@ impl MetadataStore {
                     comment.text,
                     comment.likes,
                     comment.time_posted,
+                    comment.time_posted_epoch,
                     comment.parent_comment_id,
                     comment.status_likedbycreator as i64,
                     comment.reply_count,
@@ -340,16 +992,220 @@ impl MetadataStore {
             )?;
         }
 
+        Ok(())
+    }
+
+    /// Restores a [`LibraryExport`] through the regular upsert paths, so the
+    /// search index is rebuilt as rows go in. Existing rows with the same id
+    /// are overwritten; everything else is left alone. The import runs in
+    /// one transaction, so a bad row leaves the database as it was.
+    pub fn import_all(&mut self, export: &LibraryExport) -> Result<()> {
+        if export.version != LibraryExport::VERSION {
+            bail!(
+                "Unsupported export version {} (expected {})",
+                export.version,
+                LibraryExport::VERSION
+            );
+        }
+        let tx = self
+            .conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)?;
+        for video in &export.videos {
+            Self::upsert(&tx, "videos", video)
+                .with_context(|| format!("importing video {}", video.videoid))?;
+        }
+        for short in &export.shorts {
+            Self::upsert(&tx, "shorts", short)
+                .with_context(|| format!("importing short {}", short.videoid))?;
+        }
+        for subtitles in &export.subtitles {
+            Self::write_subtitles(&tx, subtitles)
+                .with_context(|| format!("importing subtitles of {}", subtitles.videoid))?;
+        }
+        let mut comments: BTreeMap<&str, Vec<CommentRecord>> = BTreeMap::new();
+        for comment in &export.comments {
+            comments
+                .entry(comment.videoid.as_str())
+                .or_default()
+                .push(comment.clone());
+        }
+        for (videoid, comments) in comments {
+            Self::write_comments(&tx, videoid, &comments)
+                .with_context(|| format!("importing comments of {videoid}"))?;
+        }
         tx.commit()?;
         Ok(())
     }
+
+    /// Removes a long-form video together with its search entry, subtitles,
+    /// comments, progress, watched flag and bookmark. Returns `false` when
+    /// the video does not exist. Media files on disk are left alone.
+    ///
+    /// The id stays in the download archive, so a tombstone in
+    /// `deleted_media` tells the downloader not to index it again (see
+    /// [`Self::is_deleted`]).
+    pub fn delete_video(&mut self, videoid: &str) -> Result<bool> {
+        self.delete("videos", videoid)
+    }
+
+    /// Same as [`Self::delete_video`] for the `shorts` table.
+    pub fn delete_short(&mut self, videoid: &str) -> Result<bool> {
+        self.delete("shorts", videoid)
+    }
+
+    fn delete(&mut self, table: &str, videoid: &str) -> Result<bool> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            &format!(
+                "DELETE FROM {table}_fts WHERE rowid = (SELECT rowid FROM {table} WHERE videoid = ?1)"
+            ),
+            [videoid],
+        )?;
+        let removed = tx.execute(
+            &format!("DELETE FROM {table} WHERE videoid = ?1"),
+            [videoid],
+        )?;
+        if removed == 0 {
+            return Ok(false);
+        }
+        // Only `comments` has a foreign key (to `videos`), so everything
+        // else keyed by the id is removed by hand.
+        for dependent in [
+            "subtitles",
+            "comments",
+            "watch_progress",
+            "watch_state",
+            "favorites",
+            "view_counts",
+        ] {
+            tx.execute(
+                &format!("DELETE FROM {dependent} WHERE videoid = ?1"),
+                [videoid],
+            )?;
+        }
+        tx.execute(
+            r#"
+            INSERT INTO deleted_media (videoid, deleted_at)
+            VALUES (?1, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+            ON CONFLICT(videoid) DO UPDATE SET deleted_at = excluded.deleted_at
+            "#,
+            [videoid],
+        )?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Whether `videoid` was removed with [`Self::delete_video`] or
+    /// [`Self::delete_short`]. The downloader skips such entries instead of
+    /// refreshing them back into the library.
+    pub fn is_deleted(&self, videoid: &str) -> Result<bool> {
+        let found = self
+            .conn
+            .query_row(
+                "SELECT 1 FROM deleted_media WHERE videoid = ?1",
+                [videoid],
+                |_| Ok(()),
+            )
+            .optional()?;
+        Ok(found.is_some())
+    }
+}
+
+/// Creates the FTS5 index over `table`'s titles, descriptions and tags,
+/// filling it from the existing rows the first time. Index rows share the
+/// rowid of the row they describe.
+fn ensure_search_index(conn: &Connection, table: &str) -> Result<()> {
+    let index = format!("{table}_fts");
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+        [&index],
+        |row| row.get(0),
+    )?;
+    if exists {
+        return Ok(());
+    }
+
+    conn.execute_batch(&format!(
+        r#"
+        CREATE VIRTUAL TABLE {index} USING fts5(
+            title, description, tags, tokenize = 'unicode61 remove_diacritics 2'
+        );
+        INSERT INTO {index} (rowid, title, description, tags)
+        SELECT rowid, title, COALESCE(description, ''),
+               COALESCE((SELECT group_concat(value, ' ') FROM json_each(tags_json)), '')
+        FROM {table};
+        "#
+    ))
+    .with_context(|| format!("creating search index {index}"))
+}
+
+/// Turns free text into an FTS5 query: every word must match, as a prefix,
+/// and FTS5 operators in the input are treated as plain text. `None` when
+/// nothing searchable is left.
+fn fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Whether a failed first read means the WAL `-shm` file could not be
+/// created, the one case [`MetadataReader::open_read_only`] retries as
+/// `immutable`.
+fn needs_immutable(err: &rusqlite::Error) -> bool {
+    matches!(
+        err.sqlite_error_code(),
+        Some(rusqlite::ErrorCode::ReadOnly | rusqlite::ErrorCode::CannotOpen)
+    )
+}
+
+/// Escapes the characters that would end or corrupt the path part of an
+/// SQLite `file:` URI.
+fn uri_path(path: &Path) -> String {
+    let mut escaped = String::new();
+    for ch in path.to_string_lossy().chars() {
+        match ch {
+            '%' => escaped.push_str("%25"),
+            '?' => escaped.push_str("%3f"),
+            '#' => escaped.push_str("%23"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Adds `column` to `table` unless it already exists (SQLite has no
+/// `ADD COLUMN IF NOT EXISTS`). Returns whether the column was added, so
+/// callers can backfill it.
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<bool> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2)",
+        [table, column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {table} ADD COLUMN {column} {decl}"),
+            [],
+        )
+        .with_context(|| format!("adding {table}.{column}"))?;
+    }
+    Ok(!exists)
 }
 
-/// Lightweight cloneable reader that opens short‑lived connections for each
-/// query. This avoids keeping a single connection open across threads/tasks.
+/// Lightweight cloneable reader. Each query borrows a read-only connection
+/// from a small pool shared by every clone and returns it afterwards, so the
+/// backend does not reopen the DB and rerun its PRAGMAs on every request.
+/// Queries running at the same time each get their own connection.
 #[derive(Clone)]
 pub struct MetadataReader {
     db_path: PathBuf,
+    tuning: SqliteTuning,
+    display_timezone: Option<Tz>,
+    /// Idle connections, at most `tuning.read_pool` of them.
+    pool: Arc<Mutex<Vec<Connection>>>,
 }
 
 impl MetadataReader {
@@ -357,48 +1213,350 @@ impl MetadataReader {
     pub fn new(path: impl AsRef<Path>) -> Result<Self> {
         Ok(Self {
             db_path: path.as_ref().to_path_buf(),
+            tuning: SqliteTuning::default(),
+            display_timezone: None,
+            pool: Arc::default(),
         })
     }
 
+    /// Applies `tuning`'s cache size and pool size to every connection this
+    /// reader opens. Connections pooled under the old tuning are dropped.
+    pub fn with_tuning(mut self, tuning: SqliteTuning) -> Self {
+        self.tuning = tuning;
+        self.pool = Arc::default();
+        self
+    }
+
+    /// Fills the `*_display` fields of returned records with times in `tz`
+    /// (`DISPLAY_TIMEZONE`). The UTC fields are always present.
+    pub fn with_display_timezone(mut self, tz: Option<Tz>) -> Self {
+        self.display_timezone = tz;
+        self
+    }
+
     fn with_connection<F, T>(&self, f: F) -> Result<T>
     where
         F: FnOnce(&Connection) -> Result<T>,
     {
-        // Open a dedicated connection per invocation so long running queries
-        // do not block unrelated threads.
-        let conn = Connection::open(&self.db_path)
+        // Take the connection out of the pool rather than holding the lock,
+        // so long running queries do not block unrelated threads.
+        let pooled = self.pool.lock().pop();
+        let (conn, reusable) = match pooled {
+            Some(conn) => (conn, true),
+            None => {
+                let (conn, immutable) = self
+                    .open_read_only()
+                    .with_context(|| format!("opening metadata DB {}", self.db_path.display()))?;
+                conn.pragma_update(None, "foreign_keys", "ON")?;
+                self.tuning.apply_cache_size(&conn)?;
+                // An immutable connection would not notice a writer showing
+                // up later, so it is never reused.
+                (conn, !immutable)
+            }
+        };
+        let result = f(&conn);
+        let capacity = self.tuning.read_pool.unwrap_or(DEFAULT_READ_POOL) as usize;
+        let mut idle = self.pool.lock();
+        if reusable && idle.len() < capacity {
+            idle.push(conn);
+        }
+        result
+    }
+
+    /// Opens the DB read-only so the backend can serve from a read-only mount
+    /// or a file owned by the downloader user. Reading a WAL database still
+    /// needs its `-shm` file; when that cannot be created (read-only
+    /// directory after the writer cleaned up) the file is opened as
+    /// `immutable`, which skips locking and is only safe because nothing can
+    /// write to it there either. The flag is `true` for such a connection.
+    /// Any other failure (busy, corrupt, missing) is returned as is.
+    fn open_read_only(&self) -> Result<(Connection, bool)> {
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY
+            | OpenFlags::SQLITE_OPEN_URI
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        let conn = Connection::open_with_flags(&self.db_path, flags)?;
+        // Set before the probe so a writer holding the lock is waited for
+        // rather than mistaken for an unwritable directory.
+        conn.busy_timeout(READER_BUSY_TIMEOUT)?;
+        // Opening is lazy; the first read is what touches the WAL index.
+        match conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(())) {
+            Ok(()) => return Ok((conn, false)),
+            Err(err) if needs_immutable(&err) => drop(conn),
+            Err(err) => return Err(err.into()),
+        }
+
+        let uri = format!("file:{}?immutable=1", uri_path(&self.db_path));
+        let conn = Connection::open_with_flags(uri, flags)?;
+        conn.busy_timeout(READER_BUSY_TIMEOUT)?;
+        conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))?;
+        Ok((conn, true))
+    }
+
+    /// Opens a fresh connection and reads `sqlite_master`, for the backend's
+    /// health check. The pool is bypassed: an idle connection keeps working
+    /// after the file is deleted or its mount goes away.
+    pub fn check(&self) -> Result<()> {
+        self.open_read_only()
             .with_context(|| format!("opening metadata DB {}", self.db_path.display()))?;
-        conn.pragma_update(None, "foreign_keys", "ON")?;
-        f(&conn)
+        Ok(())
     }
 
     pub fn list_videos(&self) -> Result<Vec<VideoRecord>> {
-        self.fetch_videos_from("videos")
+        self.fetch_videos_from("videos", &ListFilter::default(), None)
     }
 
     pub fn list_shorts(&self) -> Result<Vec<VideoRecord>> {
-        self.fetch_videos_from("shorts")
+        self.fetch_videos_from("shorts", &ListFilter::default(), None)
     }
 
-    pub fn get_video(&self, videoid: &str) -> Result<Option<VideoRecord>> {
-        self.fetch_single("videos", videoid)
+    /// Long-form videos whose stored `channel_url` is exactly `channel_url`,
+    /// newest first.
+    pub fn list_videos_for_channel(&self, channel_url: &str) -> Result<Vec<VideoRecord>> {
+        self.fetch_videos_from("videos", &channel_filter(channel_url), None)
     }
 
-    pub fn get_short(&self, videoid: &str) -> Result<Option<VideoRecord>> {
-        self.fetch_single("shorts", videoid)
+    /// Same as [`Self::list_videos_for_channel`] for Shorts.
+    pub fn list_shorts_for_channel(&self, channel_url: &str) -> Result<Vec<VideoRecord>> {
+        self.fetch_videos_from("shorts", &channel_filter(channel_url), None)
     }
 
-    pub fn get_subtitles(&self, videoid: &str) -> Result<Option<SubtitleCollection>> {
+    /// One page of [`Self::list_videos`], optionally keeping only watched
+    /// (`Some(true)`) or unwatched (`Some(false)`) videos.
+    pub fn list_videos_page(
+        &self,
+        range: PageRange,
+        watched: Option<bool>,
+    ) -> Result<Vec<VideoRecord>> {
+        let filter = ListFilter {
+            watched,
+            ..ListFilter::default()
+        };
+        self.fetch_videos_from("videos", &filter, Some(range))
+    }
+
+    /// One page of [`Self::list_shorts`].
+    pub fn list_shorts_page(&self, range: PageRange) -> Result<Vec<VideoRecord>> {
+        self.fetch_videos_from("shorts", &ListFilter::default(), Some(range))
+    }
+
+    /// Long-form videos matching `filter` in its order; `range` selects one
+    /// page of them.
+    pub fn list_videos_matching(
+        &self,
+        filter: &ListFilter,
+        range: Option<PageRange>,
+    ) -> Result<Vec<VideoRecord>> {
+        self.fetch_videos_from("videos", filter, range)
+    }
+
+    /// Same as [`Self::list_videos_matching`] for Shorts.
+    pub fn list_shorts_matching(
+        &self,
+        filter: &ListFilter,
+        range: Option<PageRange>,
+    ) -> Result<Vec<VideoRecord>> {
+        self.fetch_videos_from("shorts", filter, range)
+    }
+
+    /// Long-form videos matching `filter`'s `watched`/`channel_url` that were
+    /// written after the `(refreshed_at, videoid)` position, oldest first in
+    /// that order. The id breaks ties between rows written in the same
+    /// millisecond; pass `""` to start at `refreshed_at` itself. Rows never
+    /// refreshed are left out.
+    pub fn list_videos_refreshed_after(
+        &self,
+        filter: &ListFilter,
+        refreshed_at: &str,
+        videoid: &str,
+    ) -> Result<Vec<VideoRecord>> {
         self.with_connection(|conn| {
-            let mut stmt = conn.prepare(
+            let mut stmt = conn.prepare(&format!(
                 r#"
-                SELECT languages_json
-                FROM subtitles
-                WHERE videoid = ?1
-                "#,
-            )?;
-
-            let json: Option<String> = stmt.query_row([videoid], |row| row.get(0)).optional()?;
+                SELECT {VIDEO_COLUMNS}
+                FROM videos v
+                LEFT JOIN subtitles s ON s.videoid = v.videoid
+                LEFT JOIN watch_state w ON w.videoid = v.videoid
+                {LIST_FILTER_SQL}
+                  AND (v.refreshed_at, v.videoid) > (?4, ?5)
+                ORDER BY v.refreshed_at, v.videoid
+                "#
+            ))?;
+
+            let mut rows = stmt.query(params![
+                filter.watched,
+                filter.channel_url,
+                filter.tag,
+                refreshed_at,
+                videoid
+            ])?;
+            let mut records = Vec::new();
+            while let Some(row) = rows.next()? {
+                records.push(row_to_video_record(row, self.display_timezone)?);
+            }
+            Ok(records)
+        })
+    }
+
+    /// Number of rows [`Self::list_videos_page`] pages through.
+    pub fn count_videos(&self, watched: Option<bool>) -> Result<usize> {
+        let filter = ListFilter {
+            watched,
+            ..ListFilter::default()
+        };
+        self.count_rows("videos", &filter)
+    }
+
+    pub fn count_shorts(&self) -> Result<usize> {
+        self.count_rows("shorts", &ListFilter::default())
+    }
+
+    /// Number of rows [`Self::list_videos_matching`] pages through.
+    pub fn count_videos_matching(&self, filter: &ListFilter) -> Result<usize> {
+        self.count_rows("videos", filter)
+    }
+
+    /// Number of rows [`Self::list_shorts_matching`] pages through.
+    pub fn count_shorts_matching(&self, filter: &ListFilter) -> Result<usize> {
+        self.count_rows("shorts", filter)
+    }
+
+    pub fn get_video(&self, videoid: &str) -> Result<Option<VideoRecord>> {
+        self.fetch_single("videos", videoid)
+    }
+
+    /// Every author with their number of videos and Shorts, most uploads
+    /// first. Missing or blank authors share one [`UNKNOWN_AUTHOR`] entry.
+    pub fn list_channels(&self) -> Result<Vec<ChannelSummary>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                r#"
+                SELECT author, MAX(channel_url) AS channel_url, COUNT(*) AS video_count,
+                       MAX(upload_date) AS latest_upload
+                FROM (
+                    SELECT NULLIF(TRIM(author), '') AS author, channel_url, upload_date
+                    FROM videos
+                    UNION ALL
+                    SELECT NULLIF(TRIM(author), ''), channel_url, upload_date
+                    FROM shorts
+                )
+                GROUP BY author
+                ORDER BY video_count DESC, author IS NULL, author COLLATE NOCASE
+                "#,
+            )?;
+
+            let rows = stmt.query_map([], |row| {
+                let author: Option<String> = row.get("author")?;
+                let channel_url: Option<String> = row.get("channel_url")?;
+                let video_count: i64 = row.get("video_count")?;
+                Ok(ChannelSummary {
+                    channel_url: author.as_ref().and(channel_url),
+                    author: author.unwrap_or_else(|| UNKNOWN_AUTHOR.to_owned()),
+                    video_count: u64::try_from(video_count).unwrap_or(0),
+                    latest_upload: row.get("latest_upload")?,
+                })
+            })?;
+            Ok(rows.collect::<rusqlite::Result<_>>()?)
+        })
+    }
+
+    /// Full-text search over long-form titles, descriptions and tags, best
+    /// match first. Case- and accent-insensitive; each word also matches as
+    /// a prefix.
+    pub fn search_videos(&self, query: &str, limit: usize) -> Result<Vec<VideoRecord>> {
+        self.search("videos", query, limit)
+    }
+
+    /// Same as [`Self::search_videos`] for Shorts.
+    pub fn search_shorts(&self, query: &str, limit: usize) -> Result<Vec<VideoRecord>> {
+        self.search("shorts", query, limit)
+    }
+
+    /// Long-form videos uploaded in `month` (1-12), newest first, narrowed to
+    /// one `day` and/or `year` when given: `month` + `day` is "on this day"
+    /// across every year, `year` + `month` an archive month. Dates are the UTC
+    /// calendar dates of `upload_epoch`; rows without one never match.
+    pub fn list_videos_by_date(
+        &self,
+        year: Option<i32>,
+        month: u32,
+        day: Option<u32>,
+    ) -> Result<Vec<VideoRecord>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                r#"
+                SELECT {VIDEO_COLUMNS}
+                FROM videos v
+                LEFT JOIN subtitles s ON s.videoid = v.videoid
+                LEFT JOIN watch_state w ON w.videoid = v.videoid
+                WHERE v.upload_epoch IS NOT NULL
+                  AND CAST(strftime('%m', v.upload_epoch, 'unixepoch') AS INTEGER) = ?1
+                  AND (?2 IS NULL
+                       OR CAST(strftime('%d', v.upload_epoch, 'unixepoch') AS INTEGER) = ?2)
+                  AND (?3 IS NULL
+                       OR CAST(strftime('%Y', v.upload_epoch, 'unixepoch') AS INTEGER) = ?3)
+                ORDER BY v.upload_epoch DESC, v.rowid DESC
+                "#
+            ))?;
+
+            let mut rows = stmt.query(params![month, day, year])?;
+            let mut records = Vec::new();
+            while let Some(row) = rows.next()? {
+                records.push(row_to_video_record(row, self.display_timezone)?);
+            }
+            Ok(records)
+        })
+    }
+
+    /// The long-form video uploaded right after `videoid` on the same channel
+    /// (matched by `extras.channelId`, else the channel URL), for autoplay.
+    /// `None` when `videoid` is the channel's newest upload or unknown.
+    pub fn next_in_channel(&self, videoid: &str) -> Result<Option<VideoRecord>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                r#"
+                WITH current AS (
+                    SELECT COALESCE(upload_epoch, 0) AS epoch, videoid,
+                           COALESCE(json_extract(extras_json, '$.channelId'),
+                                    lower(rtrim(channel_url, '/'))) AS channel
+                    FROM videos WHERE videoid = ?1
+                )
+                SELECT {VIDEO_COLUMNS}
+                FROM videos v
+                JOIN current c
+                LEFT JOIN subtitles s ON s.videoid = v.videoid
+                LEFT JOIN watch_state w ON w.videoid = v.videoid
+                WHERE COALESCE(json_extract(v.extras_json, '$.channelId'),
+                               lower(rtrim(v.channel_url, '/'))) = c.channel
+                  AND (COALESCE(v.upload_epoch, 0), v.videoid) > (c.epoch, c.videoid)
+                ORDER BY COALESCE(v.upload_epoch, 0), v.videoid
+                LIMIT 1
+                "#
+            ))?;
+
+            let mut rows = stmt.query([videoid])?;
+            if let Some(row) = rows.next()? {
+                Ok(Some(row_to_video_record(row, self.display_timezone)?))
+            } else {
+                Ok(None)
+            }
+        })
+    }
+
+    pub fn get_short(&self, videoid: &str) -> Result<Option<VideoRecord>> {
+        self.fetch_single("shorts", videoid)
+    }
+
+    pub fn get_subtitles(&self, videoid: &str) -> Result<Option<SubtitleCollection>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                r#"
+                SELECT languages_json
+                FROM subtitles
+                WHERE videoid = ?1
+                "#,
+            )?;
+
+            let json: Option<String> = stmt.query_row([videoid], |row| row.get(0)).optional()?;
 
             if let Some(languages_json) = json {
                 let languages: Vec<SubtitleTrack> =
@@ -439,7 +1597,7 @@ impl MetadataReader {
         self.with_connection(|conn| {
             let mut stmt = conn.prepare(
                 r#"
-                SELECT id, videoid, author, text, likes, time_posted,
+                SELECT id, videoid, author, text, likes, time_posted, time_posted_epoch,
                        parent_comment_id, status_likedbycreator, reply_count
                 FROM comments
                 WHERE videoid = ?1
@@ -450,17 +1608,70 @@ impl MetadataReader {
             let mut comments = Vec::new();
             let mut rows = stmt.query([videoid])?;
             while let Some(row) = rows.next()? {
-                comments.push(row_to_comment(row)?);
+                comments.push(row_to_comment(row, self.display_timezone)?);
             }
             Ok(comments)
         })
     }
 
+    /// Same comments as [`Self::get_comments`], nested by
+    /// `parent_comment_id` (see [`build_comment_threads`]).
+    pub fn get_comment_threads(&self, videoid: &str) -> Result<Vec<CommentThread>> {
+        Ok(build_comment_threads(self.get_comments(videoid)?))
+    }
+
+    /// Loads one comment of `videoid` with every reply below it. `None` when
+    /// the comment does not exist or belongs to another video.
+    pub fn get_comment_thread(
+        &self,
+        videoid: &str,
+        comment_id: &str,
+    ) -> Result<Option<CommentThread>> {
+        let mut comments = self.with_connection(|conn| {
+            // `UNION` (not `UNION ALL`) stops on a parent cycle.
+            let mut stmt = conn.prepare(
+                r#"
+                WITH RECURSIVE subtree(id) AS (
+                    SELECT id FROM comments WHERE id = ?2 AND videoid = ?1
+                    UNION
+                    SELECT c.id FROM comments c
+                    JOIN subtree s ON c.parent_comment_id = s.id
+                    WHERE c.videoid = ?1
+                )
+                SELECT id, videoid, author, text, likes, time_posted, time_posted_epoch,
+                       parent_comment_id, status_likedbycreator, reply_count
+                FROM comments
+                WHERE id IN subtree
+                ORDER BY time_posted ASC
+                "#,
+            )?;
+
+            let mut comments = Vec::new();
+            let mut rows = stmt.query([videoid, comment_id])?;
+            while let Some(row) = rows.next()? {
+                comments.push(row_to_comment(row, self.display_timezone)?);
+            }
+            Ok(comments)
+        })?;
+
+        let Some(index) = comments.iter().position(|comment| comment.id == comment_id) else {
+            return Ok(None);
+        };
+        let root = comments.swap_remove(index);
+        let mut children: HashMap<String, Vec<CommentRecord>> = HashMap::new();
+        for comment in comments {
+            if let Some(parent) = comment.parent_comment_id.clone() {
+                children.entry(parent).or_default().push(comment);
+            }
+        }
+        Ok(Some(attach_replies(root, &mut children)))
+    }
+
     pub fn list_all_comments(&self) -> Result<Vec<CommentRecord>> {
         self.with_connection(|conn| {
             let mut stmt = conn.prepare(
                 r#"
-                SELECT id, videoid, author, text, likes, time_posted,
+                SELECT id, videoid, author, text, likes, time_posted, time_posted_epoch,
                        parent_comment_id, status_likedbycreator, reply_count
                 FROM comments
                 ORDER BY time_posted ASC
@@ -470,29 +1681,225 @@ impl MetadataReader {
             let mut rows = stmt.query([])?;
             let mut comments = Vec::new();
             while let Some(row) = rows.next()? {
-                comments.push(row_to_comment(row)?);
+                comments.push(row_to_comment(row, self.display_timezone)?);
             }
             Ok(comments)
         })
     }
 
-    fn fetch_videos_from(&self, table: &str) -> Result<Vec<VideoRecord>> {
+    /// Dumps the whole library for `routine_update --export`, sorted by id
+    /// (comments by video, then comment id) rather than by upload date so
+    /// the output only changes when the data does. Per-user fields derived
+    /// when reading (`watched`, `local_views`) are cleared.
+    pub fn export_all(&self) -> Result<LibraryExport> {
+        let mut videos = self.list_videos()?;
+        videos.sort_by(|a, b| a.videoid.cmp(&b.videoid));
+        let mut shorts = self.list_shorts()?;
+        shorts.sort_by(|a, b| a.videoid.cmp(&b.videoid));
+        for record in videos.iter_mut().chain(shorts.iter_mut()) {
+            record.watched = false;
+            record.local_views = None;
+        }
+        let mut subtitles = self.list_subtitles()?;
+        subtitles.sort_by(|a, b| a.videoid.cmp(&b.videoid));
+        let mut comments = self.list_all_comments()?;
+        comments.sort_by(|a, b| (&a.videoid, &a.id).cmp(&(&b.videoid, &b.id)));
+        Ok(LibraryExport {
+            version: LibraryExport::VERSION,
+            videos,
+            shorts,
+            subtitles,
+            comments,
+        })
+    }
+
+    /// Returns every video/short id known to the database, sorted and
+    /// deduplicated. Used to advertise a node's archive to its peers.
+    pub fn list_archived_ids(&self) -> Result<Vec<String>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                r#"
+                SELECT videoid FROM videos
+                UNION
+                SELECT videoid FROM shorts
+                ORDER BY videoid
+                "#,
+            )?;
+
+            let ids = stmt
+                .query_map([], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(ids)
+        })
+    }
+
+    /// Returns videos and shorts whose recorded position lies between
+    /// `min_ratio` and `max_ratio` of their duration, most recently updated
+    /// first. Items without a known duration are skipped.
+    pub fn list_continue_watching(
+        &self,
+        min_ratio: f64,
+        max_ratio: f64,
+    ) -> Result<Vec<ContinueWatchingEntry>> {
+        self.with_connection(|conn| {
+            let select = |table: &str| {
+                format!(
+                    r#"
+                    SELECT {VIDEO_COLUMNS},
+                           p.position_seconds, p.updated_at AS updated_at
+                    FROM watch_progress p
+                    JOIN {table} v ON v.videoid = p.videoid
+                    LEFT JOIN subtitles s ON s.videoid = v.videoid
+                    LEFT JOIN watch_state w ON w.videoid = v.videoid
+                    WHERE v.duration > 0
+                      AND p.position_seconds >= v.duration * ?1
+                      AND p.position_seconds <= v.duration * ?2
+                    "#
+                )
+            };
+            let mut stmt = conn.prepare(&format!(
+                "{} UNION ALL {} ORDER BY updated_at DESC",
+                select("videos"),
+                select("shorts")
+            ))?;
+
+            let mut rows = stmt.query(params![min_ratio, max_ratio])?;
+            let mut entries = Vec::new();
+            while let Some(row) = rows.next()? {
+                let video = row_to_video_record(row, self.display_timezone)?;
+                let progress = WatchProgress {
+                    videoid: video.videoid.clone(),
+                    position_seconds: row.get("position_seconds")?,
+                    updated_at: row.get("updated_at")?,
+                };
+                entries.push(ContinueWatchingEntry { video, progress });
+            }
+            Ok(entries)
+        })
+    }
+
+    /// Favorited video ids, most recently added first.
+    pub fn list_favorite_ids(&self) -> Result<Vec<String>> {
         self.with_connection(|conn| {
+            let mut stmt =
+                conn.prepare("SELECT videoid FROM favorites ORDER BY added_at DESC, rowid DESC")?;
+            let ids = stmt
+                .query_map([], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(ids)
+        })
+    }
+
+    /// Loads several long-form videos at once, returned in the order of `ids`.
+    /// Unknown ids are skipped.
+    pub fn get_videos_by_ids(&self, ids: &[String]) -> Result<Vec<VideoRecord>> {
+        let ids_json = serde_json::to_string(ids)?;
+        let mut found = self.with_connection(|conn| {
             let mut stmt = conn.prepare(&format!(
                 r#"
-                SELECT videoid, title, description, likes, dislikes, views,
-                       upload_date, author, subscriber_count, duration, duration_text,
-                       channel_url, thumbnail_url, tags_json, thumbnails_json,
-                       extras_json, sources_json
-                FROM {table}
-                ORDER BY upload_date DESC, rowid DESC
+                SELECT {VIDEO_COLUMNS}
+                FROM videos v
+                LEFT JOIN subtitles s ON s.videoid = v.videoid
+                LEFT JOIN watch_state w ON w.videoid = v.videoid
+                WHERE v.videoid IN (SELECT value FROM json_each(?1))
                 "#
             ))?;
 
-            let mut rows = stmt.query([])?;
+            let mut rows = stmt.query([ids_json])?;
+            let mut records = HashMap::new();
+            while let Some(row) = rows.next()? {
+                let record = row_to_video_record(row, self.display_timezone)?;
+                records.insert(record.videoid.clone(), record);
+            }
+            Ok(records)
+        })?;
+
+        Ok(ids.iter().filter_map(|id| found.remove(id)).collect())
+    }
+
+    fn fetch_videos_from(
+        &self,
+        table: &str,
+        filter: &ListFilter,
+        range: Option<PageRange>,
+    ) -> Result<Vec<VideoRecord>> {
+        // SQLite treats a negative LIMIT as "no limit".
+        let (limit, offset) = range.map_or((-1, 0), |range| {
+            (
+                i64::try_from(range.limit).unwrap_or(i64::MAX),
+                i64::try_from(range.offset).unwrap_or(i64::MAX),
+            )
+        });
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                r#"
+                SELECT {VIDEO_COLUMNS}
+                FROM {table} v
+                LEFT JOIN subtitles s ON s.videoid = v.videoid
+                LEFT JOIN watch_state w ON w.videoid = v.videoid
+                {LIST_FILTER_SQL}
+                ORDER BY {order_by}
+                LIMIT ?4 OFFSET ?5
+                "#,
+                order_by = filter.order_by(),
+            ))?;
+
+            let mut rows = stmt.query(params![
+                filter.watched,
+                filter.channel_url,
+                filter.tag,
+                limit,
+                offset
+            ])?;
+            let mut records = Vec::new();
+            while let Some(row) = rows.next()? {
+                records.push(row_to_video_record(row, self.display_timezone)?);
+            }
+            Ok(records)
+        })
+    }
+
+    fn count_rows(&self, table: &str, filter: &ListFilter) -> Result<usize> {
+        self.with_connection(|conn| {
+            let count: i64 = conn.query_row(
+                &format!(
+                    r#"
+                    SELECT COUNT(*)
+                    FROM {table} v
+                    LEFT JOIN watch_state w ON w.videoid = v.videoid
+                    {LIST_FILTER_SQL}
+                    "#
+                ),
+                params![filter.watched, filter.channel_url, filter.tag],
+                |row| row.get(0),
+            )?;
+            Ok(usize::try_from(count).unwrap_or(0))
+        })
+    }
+
+    fn search(&self, table: &str, query: &str, limit: usize) -> Result<Vec<VideoRecord>> {
+        let Some(query) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                r#"
+                SELECT {VIDEO_COLUMNS}
+                FROM {table}_fts
+                JOIN {table} v ON v.rowid = {table}_fts.rowid
+                LEFT JOIN subtitles s ON s.videoid = v.videoid
+                LEFT JOIN watch_state w ON w.videoid = v.videoid
+                WHERE {table}_fts MATCH ?1
+                ORDER BY bm25({table}_fts, {SEARCH_WEIGHTS}), v.upload_date DESC
+                LIMIT ?2
+                "#
+            ))?;
+
+            let mut rows = stmt.query(params![query, limit])?;
             let mut records = Vec::new();
             while let Some(row) = rows.next()? {
-                records.push(row_to_video_record(row)?);
+                records.push(row_to_video_record(row, self.display_timezone)?);
             }
             Ok(records)
         })
@@ -502,18 +1909,21 @@ impl MetadataReader {
         self.with_connection(|conn| {
             let mut stmt = conn.prepare(&format!(
                 r#"
-                SELECT videoid, title, description, likes, dislikes, views,
-                       upload_date, author, subscriber_count, duration, duration_text,
-                       channel_url, thumbnail_url, tags_json, thumbnails_json,
-                       extras_json, sources_json
-                FROM {table}
-                WHERE videoid = ?1
+                SELECT {VIDEO_COLUMNS},
+                       COALESCE(c.local_views, 0) AS local_views
+                FROM {table} v
+                LEFT JOIN subtitles s ON s.videoid = v.videoid
+                LEFT JOIN watch_state w ON w.videoid = v.videoid
+                LEFT JOIN view_counts c ON c.videoid = v.videoid
+                WHERE v.videoid = ?1
                 "#
             ))?;
 
             let mut rows = stmt.query([videoid])?;
             if let Some(row) = rows.next()? {
-                Ok(Some(row_to_video_record(row)?))
+                let mut record = row_to_video_record(row, self.display_timezone)?;
+                record.local_views = Some(row.get("local_views")?);
+                Ok(Some(record))
             } else {
                 Ok(None)
             }
@@ -521,8 +1931,73 @@ impl MetadataReader {
     }
 }
 
+fn channel_filter(channel_url: &str) -> ListFilter {
+    ListFilter {
+        channel_url: Some(channel_url.to_owned()),
+        ..ListFilter::default()
+    }
+}
+
+/// Nests flat comments under their parents. Top-level comments come newest
+/// first and replies oldest first; comments without a timestamp sort last.
+/// Replies whose parent is missing are kept as top-level threads.
+pub fn build_comment_threads(comments: Vec<CommentRecord>) -> Vec<CommentThread> {
+    let ids: HashSet<String> = comments.iter().map(|comment| comment.id.clone()).collect();
+    let mut children: HashMap<String, Vec<CommentRecord>> = HashMap::new();
+    let mut roots = Vec::new();
+    for comment in comments {
+        match &comment.parent_comment_id {
+            Some(parent) if ids.contains(parent) => {
+                children.entry(parent.clone()).or_default().push(comment)
+            }
+            _ => roots.push(comment),
+        }
+    }
+
+    let mut threads: Vec<CommentThread> = roots
+        .into_iter()
+        .map(|comment| attach_replies(comment, &mut children))
+        .collect();
+    // Whatever is left only hangs off a parent cycle; surface it rather than
+    // dropping it.
+    while let Some(parent) = children.keys().next().cloned() {
+        for comment in children.remove(&parent).unwrap_or_default() {
+            threads.push(attach_replies(comment, &mut children));
+        }
+    }
+    threads.sort_by(|a, b| {
+        b.comment
+            .time_posted_epoch
+            .cmp(&a.comment.time_posted_epoch)
+    });
+    threads
+}
+
+fn attach_replies(
+    comment: CommentRecord,
+    children: &mut HashMap<String, Vec<CommentRecord>>,
+) -> CommentThread {
+    let mut replies: Vec<CommentThread> = children
+        .remove(&comment.id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|reply| attach_replies(reply, children))
+        .collect();
+    replies.sort_by_key(|reply| {
+        let posted = reply.comment.time_posted_epoch;
+        (posted.is_none(), posted)
+    });
+    CommentThread { comment, replies }
+}
+
+/// Returns a lowercase, slash-normalized version of a channel URL so the same
+/// channel is keyed identically no matter how the URL was typed.
+pub fn canonical_channel_url(url: &str) -> String {
+    url.trim().trim_end_matches('/').to_ascii_lowercase()
+}
+
 /// Converts a SQL row into a `VideoRecord`, deserializing the Vec/JSON fields.
-fn row_to_video_record(row: &Row<'_>) -> Result<VideoRecord> {
+fn row_to_video_record(row: &Row<'_>, display_timezone: Option<Tz>) -> Result<VideoRecord> {
     let tags_json: String = row.get("tags_json")?;
     let thumbnails_json: String = row.get("thumbnails_json")?;
     let extras_json: String = row.get("extras_json")?;
@@ -535,6 +2010,31 @@ fn row_to_video_record(row: &Row<'_>) -> Result<VideoRecord> {
         serde_json::from_str(&extras_json).context("parsing stored extras JSON")?;
     let sources: Vec<VideoSource> =
         serde_json::from_str(&sources_json).context("parsing stored sources JSON")?;
+    let chapters: Vec<Chapter> = match row.get::<_, Option<String>>("chapters_json")? {
+        Some(json) => serde_json::from_str(&json).context("parsing stored chapters JSON")?,
+        None => Vec::new(),
+    };
+    let sponsor_segments: Option<Vec<SponsorSegment>> = row
+        .get::<_, Option<String>>("sponsor_segments_json")?
+        .map(|json| serde_json::from_str(&json))
+        .transpose()
+        .context("parsing stored sponsor segments JSON")?;
+    let audio_source: Option<VideoSource> = row
+        .get::<_, Option<String>>("audio_source_json")?
+        .map(|json| serde_json::from_str(&json))
+        .transpose()
+        .context("parsing stored audio source JSON")?;
+    // NULL when the LEFT JOIN found no `subtitles` row.
+    let subtitles_json: Option<String> = row.get("subtitles_json")?;
+    let subtitle_languages: Vec<String> = match subtitles_json {
+        Some(json) => serde_json::from_str::<Vec<SubtitleTrack>>(&json)
+            .context("parsing stored subtitles JSON")?
+            .into_iter()
+            .map(|track| track.code)
+            .collect(),
+        None => Vec::new(),
+    };
+    let upload_epoch: Option<i64> = row.get("upload_epoch")?;
 
     Ok(VideoRecord {
         videoid: row.get("videoid")?,
@@ -544,22 +2044,37 @@ fn row_to_video_record(row: &Row<'_>) -> Result<VideoRecord> {
         dislikes: row.get("dislikes")?,
         views: row.get("views")?,
         upload_date: row.get("upload_date")?,
+        upload_epoch,
+        upload_date_display: display_time(upload_epoch, display_timezone),
         author: row.get("author")?,
         subscriber_count: row.get("subscriber_count")?,
         duration: row.get("duration")?,
         duration_text: row.get("duration_text")?,
         channel_url: row.get("channel_url")?,
         thumbnail_url: row.get("thumbnail_url")?,
+        poster_override: row.get("poster_override")?,
         tags,
         thumbnails,
         extras,
         sources,
+        audio_source,
+        chapters,
+        sponsor_segments,
+        has_subtitles: !subtitle_languages.is_empty(),
+        subtitle_languages,
+        watched: row.get("watched")?,
+        refreshed_at: row.get("refreshed_at")?,
+        webpage_url: row.get("webpage_url")?,
+        original_url: row.get("original_url")?,
+        description_truncated: false,
+        local_views: None,
     })
 }
 
 /// Converts a SQL row into a `CommentRecord` while normalizing the boolean flag
 /// stored as an INTEGER in SQLite.
-fn row_to_comment(row: &Row<'_>) -> Result<CommentRecord> {
+fn row_to_comment(row: &Row<'_>, display_timezone: Option<Tz>) -> Result<CommentRecord> {
+    let time_posted_epoch: Option<i64> = row.get("time_posted_epoch")?;
     Ok(CommentRecord {
         id: row.get("id")?,
         videoid: row.get("videoid")?,
@@ -567,6 +2082,8 @@ fn row_to_comment(row: &Row<'_>) -> Result<CommentRecord> {
         text: row.get("text")?,
         likes: row.get("likes")?,
         time_posted: row.get("time_posted")?,
+        time_posted_epoch,
+        time_posted_display: display_time(time_posted_epoch, display_timezone),
         parent_comment_id: row.get("parent_comment_id")?,
         status_likedbycreator: row
             .get::<_, i64>("status_likedbycreator")
@@ -575,6 +2092,10 @@ fn row_to_comment(row: &Row<'_>) -> Result<CommentRecord> {
     })
 }
 
+fn display_time(epoch: Option<i64>, tz: Option<Tz>) -> Option<String> {
+    epoch_in_timezone(epoch?, tz?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -594,12 +2115,15 @@ mod tests {
             dislikes: Some(0),
             views: Some(42),
             upload_date: Some("2024-01-01".into()),
+            upload_epoch: None,
+            upload_date_display: None,
             author: Some("Author".into()),
             subscriber_count: Some(1000),
             duration: Some(120),
             duration_text: Some("2:00".into()),
             channel_url: Some("https://example.com".into()),
             thumbnail_url: Some("thumb.jpg".into()),
+            poster_override: false,
             tags: vec!["tech".into()],
             thumbnails: vec!["thumb.jpg".into()],
             extras: serde_json::json!({"kind": "demo"}),
@@ -614,7 +2138,19 @@ mod tests {
                 file_size: Some(1_000_000),
                 url: "https://cdn.example/video.mp4".into(),
                 path: Some("/videos/video.mp4".into()),
+                stream_key: Some("1080p".into()),
             }],
+            audio_source: None,
+            chapters: Vec::new(),
+            sponsor_segments: None,
+            has_subtitles: false,
+            subtitle_languages: Vec::new(),
+            watched: false,
+            refreshed_at: None,
+            webpage_url: None,
+            original_url: None,
+            description_truncated: false,
+            local_views: None,
         }
     }
 
@@ -628,6 +2164,8 @@ mod tests {
             text: format!("text-{id}"),
             likes: Some(0),
             time_posted: Some("2024-01-01T00:00:00Z".into()),
+            time_posted_epoch: Some(1_704_067_200),
+            time_posted_display: None,
             parent_comment_id: None,
             status_likedbycreator: false,
             reply_count: Some(0),
@@ -645,6 +2183,115 @@ mod tests {
         Ok((dir, store, reader, path))
     }
 
+    /// The cache size applies to every connection, while the page size is
+    /// only set when the database file is first created.
+    #[test]
+    fn open_tuned_sets_page_size_on_fresh_db_only() -> Result<()> {
+        let temp = tempdir()?;
+        let path = temp.path().join("tuned.db");
+        let tuning = SqliteTuning {
+            page_size: Some(16384),
+            cache_kb: Some(2048),
+            read_pool: None,
+        };
+        let store = MetadataStore::open_tuned(&path, tuning)?;
+        let cache_size: i64 = store
+            .conn
+            .query_row("PRAGMA cache_size", [], |row| row.get(0))?;
+        assert_eq!(cache_size, -2048);
+        drop(store);
+
+        let conn = Connection::open(&path)?;
+        let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        assert_eq!(page_size, 16384);
+        drop(conn);
+
+        // Reopening with another size leaves the existing DB alone.
+        let reopened = SqliteTuning {
+            page_size: Some(4096),
+            cache_kb: None,
+            read_pool: None,
+        };
+        MetadataStore::open_tuned(&path, reopened)?;
+        let conn = Connection::open(&path)?;
+        let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        assert_eq!(page_size, 16384);
+        Ok(())
+    }
+
+    /// Clones share one pool, queries reuse its connections, and a pool size
+    /// of zero keeps nothing open between queries.
+    #[test]
+    fn reader_reuses_pooled_connections() -> Result<()> {
+        let (_temp, store, reader, _path) = create_store()?;
+        store.upsert_video(&sample_video("vid"))?;
+
+        let clone = reader.clone();
+        assert_eq!(reader.list_videos()?.len(), 1);
+        assert_eq!(clone.list_videos()?.len(), 1);
+        assert_eq!(reader.pool.lock().len(), 1);
+
+        // Writes made after a connection was pooled are visible through it.
+        store.upsert_video(&sample_video("other"))?;
+        assert_eq!(clone.list_videos()?.len(), 2);
+
+        // Queries running at the same time open extra connections, and no
+        // more than the pool size are kept afterwards.
+        let tuning = SqliteTuning {
+            read_pool: Some(1),
+            ..SqliteTuning::default()
+        };
+        let small = reader.clone().with_tuning(tuning);
+        small.with_connection(|_| small.with_connection(|_| Ok(())))?;
+        assert_eq!(small.pool.lock().len(), 1);
+        assert_eq!(reader.pool.lock().len(), 1);
+
+        let unpooled = reader.clone().with_tuning(SqliteTuning {
+            read_pool: Some(0),
+            ..SqliteTuning::default()
+        });
+        assert_eq!(unpooled.list_videos()?.len(), 2);
+        assert!(unpooled.pool.lock().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn concurrent_store_opens_and_reads_succeed() -> Result<()> {
+        let temp = tempdir()?;
+        let path = temp.path().join("metadata.db");
+        MetadataStore::open(&path)?;
+
+        let writers: Vec<_> = (0..4)
+            .map(|writer| {
+                let path = path.clone();
+                std::thread::spawn(move || -> Result<()> {
+                    for round in 0..10 {
+                        let store = MetadataStore::open(&path)?;
+                        store.upsert_video(&sample_video(&format!("w{writer}-{round}")))?;
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let reader = MetadataReader::new(&path).unwrap();
+                std::thread::spawn(move || -> Result<()> {
+                    for _ in 0..20 {
+                        reader.list_videos()?;
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+
+        for handle in writers.into_iter().chain(readers) {
+            handle.join().expect("thread panicked")?;
+        }
+        assert_eq!(MetadataReader::new(&path)?.list_videos()?.len(), 40);
+        Ok(())
+    }
+
     /// Validates that opening a store creates the DB file, turns on WAL mode and
     /// provisions every expected table/index. This guards against regressions in
     /// the bootstrap SQL.
@@ -662,7 +2309,17 @@ mod tests {
             "synchronous should be NORMAL or stricter but was {synchronous}"
         );
 
-        for table in ["videos", "shorts", "subtitles", "comments"] {
+        for table in [
+            "videos",
+            "shorts",
+            "subtitles",
+            "comments",
+            "watch_progress",
+            "watch_state",
+            "channels",
+            "favorites",
+            "view_counts",
+        ] {
             let exists: Option<String> = conn
                 .query_row(
                     "SELECT name FROM sqlite_master WHERE type='table' AND name=?1",
@@ -780,6 +2437,8 @@ mod tests {
             text: "hello".into(),
             likes: Some(1),
             time_posted: Some("2024-01-01".into()),
+            time_posted_epoch: None,
+            time_posted_display: None,
             parent_comment_id: None,
             status_likedbycreator: true,
             reply_count: Some(0),
@@ -794,6 +2453,8 @@ mod tests {
             text: "world".into(),
             likes: Some(2),
             time_posted: Some("2024-01-02".into()),
+            time_posted_epoch: None,
+            time_posted_display: None,
             parent_comment_id: None,
             status_likedbycreator: false,
             reply_count: Some(1),
@@ -808,6 +2469,120 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn comment_threads_nest_replies_and_keep_orphans() -> Result<()> {
+        let (_temp, mut store, reader, _path) = create_store()?;
+        store.upsert_video(&sample_video("vid"))?;
+        let comment = |id: &str, parent: Option<&str>, epoch: i64| CommentRecord {
+            parent_comment_id: parent.map(str::to_owned),
+            time_posted_epoch: Some(epoch),
+            ..sample_comment(id, "vid")
+        };
+        store.replace_comments(
+            "vid",
+            &[
+                comment("old", None, 100),
+                comment("new", None, 300),
+                comment("late-reply", Some("old"), 250),
+                comment("early-reply", Some("old"), 150),
+                comment("nested", Some("early-reply"), 160),
+                comment("orphan", Some("gone"), 200),
+            ],
+        )?;
+
+        let threads = reader.get_comment_threads("vid")?;
+        let top: Vec<_> = threads.iter().map(|t| t.comment.id.as_str()).collect();
+        assert_eq!(top, ["new", "orphan", "old"]);
+        let old = &threads[2];
+        let replies: Vec<_> = old.replies.iter().map(|t| t.comment.id.as_str()).collect();
+        assert_eq!(replies, ["early-reply", "late-reply"]);
+        assert_eq!(old.replies[0].replies[0].comment.id, "nested");
+        assert!(threads[1].replies.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn comment_thread_returns_one_subtree() -> Result<()> {
+        let (_temp, mut store, reader, _path) = create_store()?;
+        store.upsert_video(&sample_video("vid"))?;
+        store.upsert_video(&sample_video("other"))?;
+        let comment = |id: &str, parent: Option<&str>, epoch: i64| CommentRecord {
+            parent_comment_id: parent.map(str::to_owned),
+            time_posted_epoch: Some(epoch),
+            ..sample_comment(id, "vid")
+        };
+        store.replace_comments(
+            "vid",
+            &[
+                comment("top", None, 100),
+                comment("sibling", None, 110),
+                comment("second", Some("top"), 300),
+                comment("first", Some("top"), 200),
+                comment("deep", Some("first"), 250),
+            ],
+        )?;
+        store.replace_comments("other", &[sample_comment("elsewhere", "other")])?;
+
+        let thread = reader.get_comment_thread("vid", "top")?.expect("thread");
+        let replies: Vec<_> = thread
+            .replies
+            .iter()
+            .map(|t| t.comment.id.as_str())
+            .collect();
+        assert_eq!(replies, ["first", "second"]);
+        assert_eq!(thread.replies[0].replies[0].comment.id, "deep");
+
+        let thread = reader.get_comment_thread("vid", "first")?.expect("reply");
+        assert_eq!(thread.comment.id, "first");
+        assert_eq!(thread.replies.len(), 1);
+        assert_eq!(thread.replies[0].comment.id, "deep");
+
+        assert!(reader.get_comment_thread("vid", "elsewhere")?.is_none());
+        assert!(reader.get_comment_thread("vid", "missing")?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn delete_video_removes_dependent_rows() -> Result<()> {
+        let (_temp, mut store, reader, path) = create_store()?;
+        let mut doomed = sample_video("doomed");
+        doomed.title = "Unique walrus".into();
+        store.upsert_video(&doomed)?;
+        store.upsert_video(&sample_video("kept"))?;
+        store.upsert_subtitles(&SubtitleCollection {
+            videoid: "doomed".into(),
+            languages: Vec::new(),
+        })?;
+        store.replace_comments("doomed", &[sample_comment("c1", "doomed")])?;
+        store.record_watch_progress("doomed", 12.0)?;
+        store.set_watched("doomed", true)?;
+        store.add_favorite("doomed")?;
+
+        assert!(!store.is_deleted("doomed")?);
+        assert!(store.delete_video("doomed")?);
+        assert!(store.is_deleted("doomed")?);
+        assert!(reader.get_video("doomed")?.is_none());
+        assert!(reader.get_subtitles("doomed")?.is_none());
+        assert!(reader.get_comments("doomed")?.is_empty());
+        assert!(reader.search_videos("walrus", 10)?.is_empty());
+        assert!(reader.list_favorite_ids()?.is_empty());
+        let conn = Connection::open(&path)?;
+        for table in ["watch_progress", "watch_state"] {
+            let left: i64 = conn.query_row(
+                &format!("SELECT COUNT(*) FROM {table} WHERE videoid = 'doomed'"),
+                [],
+                |row| row.get(0),
+            )?;
+            assert_eq!(left, 0, "{table}");
+        }
+        assert!(reader.get_video("kept")?.is_some());
+
+        assert!(!store.delete_video("doomed")?);
+        assert!(!store.delete_short("kept")?);
+        assert!(!store.is_deleted("kept")?);
+        Ok(())
+    }
+
     /// Verifies that listing videos applies the desired ordering (newest first)
     /// even when dates differ, which is critical for deterministic feeds.
     #[test]
@@ -829,6 +2604,367 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn list_pages_keep_newest_first_order() -> Result<()> {
+        let (_temp, store, reader, _path) = create_store()?;
+        for day in 1..=5 {
+            let mut video = sample_video(&format!("day{day}"));
+            video.upload_date = Some(format!("2024-01-0{day}"));
+            store.upsert_video(&video)?;
+        }
+        store.set_watched("day2", true)?;
+
+        let ids = |videos: Vec<VideoRecord>| -> Vec<String> {
+            videos.into_iter().map(|video| video.videoid).collect()
+        };
+        let range = PageRange {
+            limit: 2,
+            offset: 2,
+        };
+        assert_eq!(ids(reader.list_videos_page(range, None)?), ["day3", "day2"]);
+        assert_eq!(
+            ids(reader.list_videos_page(range, Some(false))?),
+            ["day3", "day1"]
+        );
+        let past_end = PageRange {
+            limit: 2,
+            offset: 10,
+        };
+        assert!(reader.list_videos_page(past_end, None)?.is_empty());
+
+        assert_eq!(reader.count_videos(None)?, 5);
+        assert_eq!(reader.count_videos(Some(true))?, 1);
+        assert_eq!(reader.count_shorts()?, 0);
+        Ok(())
+    }
+
+    /// Every sort key orders both ways, rows missing the value come last,
+    /// and the tag filter applies to the count as well.
+    #[test]
+    fn filtered_listings_sort_by_each_key() -> Result<()> {
+        let (_temp, store, reader, _path) = create_store()?;
+        for (id, date, views, likes, duration, tags) in [
+            (
+                "a",
+                "2024-01-01",
+                Some(30),
+                Some(1),
+                Some(300),
+                vec!["Rust"],
+            ),
+            (
+                "b",
+                "2024-01-02",
+                Some(10),
+                Some(3),
+                Some(100),
+                vec!["rust", "cli"],
+            ),
+            ("c", "2024-01-03", Some(20), None, Some(200), vec![]),
+            ("d", "2024-01-04", None, Some(2), None, vec!["cli"]),
+        ] {
+            let mut video = sample_video(id);
+            video.upload_date = Some(date.into());
+            video.views = views;
+            video.likes = likes;
+            video.duration = duration;
+            video.tags = tags.into_iter().map(String::from).collect();
+            store.upsert_video(&video)?;
+        }
+
+        let ids = |sort, order| -> Result<Vec<String>> {
+            let filter = ListFilter {
+                sort,
+                order,
+                ..ListFilter::default()
+            };
+            Ok(reader
+                .list_videos_matching(&filter, None)?
+                .into_iter()
+                .map(|video| video.videoid)
+                .collect())
+        };
+        use SortKey::*;
+        use SortOrder::*;
+        assert_eq!(ids(Date, Desc)?, ["d", "c", "b", "a"]);
+        assert_eq!(ids(Date, Asc)?, ["a", "b", "c", "d"]);
+        assert_eq!(ids(Views, Desc)?, ["a", "c", "b", "d"]);
+        assert_eq!(ids(Views, Asc)?, ["b", "c", "a", "d"]);
+        assert_eq!(ids(Likes, Desc)?, ["b", "d", "a", "c"]);
+        assert_eq!(ids(Likes, Asc)?, ["a", "d", "b", "c"]);
+        assert_eq!(ids(Duration, Desc)?, ["a", "c", "b", "d"]);
+        assert_eq!(ids(Duration, Asc)?, ["b", "c", "a", "d"]);
+
+        let tagged = ListFilter {
+            tag: Some("RUST".into()),
+            sort: Views,
+            order: Asc,
+            ..ListFilter::default()
+        };
+        let page = reader.list_videos_matching(
+            &tagged,
+            Some(PageRange {
+                limit: 1,
+                offset: 0,
+            }),
+        )?;
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].videoid, "b");
+        assert_eq!(reader.count_videos_matching(&tagged)?, 2);
+        // A tag is not a substring match, nor an injection vector.
+        for tag in ["ru", "x' OR 1=1 --"] {
+            let filter = ListFilter {
+                tag: Some(tag.into()),
+                ..ListFilter::default()
+            };
+            assert_eq!(reader.count_videos_matching(&filter)?, 0);
+        }
+        Ok(())
+    }
+
+    /// Every upsert stamps `refreshed_at` with the write time, in UTC.
+    #[test]
+    fn upserts_stamp_refreshed_at() -> Result<()> {
+        let (_temp, store, reader, path) = create_store()?;
+        store.upsert_video(&sample_video("vid"))?;
+        let first = reader.get_video("vid")?.unwrap().refreshed_at.unwrap();
+        assert!(first.ends_with('Z') && first.len() == 24, "{first}");
+
+        let conn = Connection::open(&path)?;
+        conn.execute(
+            "UPDATE videos SET refreshed_at = '2000-01-01T00:00:00.000Z'",
+            [],
+        )?;
+        store.upsert_video(&sample_video("vid"))?;
+        let second = reader.get_video("vid")?.unwrap().refreshed_at.unwrap();
+        assert!(second >= first, "{second} < {first}");
+        Ok(())
+    }
+
+    #[test]
+    fn source_urls_round_trip_and_stay_optional_for_older_rows() -> Result<()> {
+        let (_temp, store, reader, path) = create_store()?;
+        let mut video = sample_video("vid");
+        video.webpage_url = Some("https://www.youtube.com/watch?v=vid".into());
+        video.original_url = Some("https://youtu.be/vid".into());
+        store.upsert_video(&video)?;
+        store.upsert_short(&sample_video("short"))?;
+        drop(store);
+
+        let stored = reader.get_video("vid")?.expect("video");
+        assert_eq!(stored.webpage_url, video.webpage_url);
+        assert_eq!(stored.original_url, video.original_url);
+        assert!(
+            reader
+                .get_short("short")?
+                .expect("short")
+                .webpage_url
+                .is_none()
+        );
+
+        // A DB from before the columns existed opens with them unset.
+        let conn = Connection::open(&path)?;
+        conn.execute_batch(
+            "ALTER TABLE videos DROP COLUMN webpage_url;
+             ALTER TABLE videos DROP COLUMN original_url;",
+        )?;
+        drop(conn);
+        MetadataStore::open(&path)?;
+        let stored = reader.get_video("vid")?.expect("video");
+        assert!(stored.webpage_url.is_none() && stored.original_url.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn chapters_round_trip_and_migrate_from_extras() -> Result<()> {
+        let (_temp, store, reader, path) = create_store()?;
+        let mut video = sample_video("vid");
+        video.chapters = vec![
+            Chapter {
+                start_time: 0.0,
+                end_time: Some(61.5),
+                title: "Intro".into(),
+            },
+            Chapter {
+                start_time: 61.5,
+                end_time: None,
+                title: "Main part".into(),
+            },
+        ];
+        store.upsert_video(&video)?;
+        store.upsert_short(&sample_video("short"))?;
+
+        assert_eq!(
+            reader.get_video("vid")?.expect("video").chapters,
+            video.chapters
+        );
+        let short = reader.get_short("short")?.expect("short");
+        assert!(short.chapters.is_empty());
+        assert_eq!(
+            serde_json::to_value(&short)?["chapters"],
+            serde_json::json!([])
+        );
+
+        // Before the column existed, chapters were kept in `extras`.
+        let mut legacy = sample_video("legacy");
+        legacy.extras =
+            serde_json::json!({"chapters": [{"startTime": 5.0, "endTime": 9.0, "title": "Old"}]});
+        store.upsert_video(&legacy)?;
+        drop(store);
+        let conn = Connection::open(&path)?;
+        conn.execute_batch("ALTER TABLE videos DROP COLUMN chapters_json;")?;
+        drop(conn);
+        MetadataStore::open(&path)?;
+        assert_eq!(
+            reader.get_video("legacy")?.expect("legacy").chapters,
+            [Chapter {
+                start_time: 5.0,
+                end_time: Some(9.0),
+                title: "Old".into(),
+            }]
+        );
+        assert!(reader.get_video("vid")?.expect("video").chapters.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn audio_source_round_trips_and_clears() -> Result<()> {
+        let (_temp, store, reader, _) = create_store()?;
+        let mut video = sample_video("vid");
+        let mut audio = video.sources[0].clone();
+        audio.format_id = "audio".into();
+        audio.mime_type = Some("audio/mp4".into());
+        audio.stream_key = Some("audio".into());
+        video.audio_source = Some(audio);
+        store.upsert_video(&video)?;
+
+        let stored = reader.get_video("vid")?.expect("video");
+        let audio = stored.audio_source.expect("audio source");
+        assert_eq!(audio.format_id, "audio");
+        assert_eq!(audio.mime_type.as_deref(), Some("audio/mp4"));
+        assert_eq!(stored.sources.len(), 1);
+
+        // Rebuilt records reflect the files on disk, so a missing audio file
+        // clears the column.
+        store.upsert_video(&sample_video("vid"))?;
+        assert!(
+            reader
+                .get_video("vid")?
+                .expect("video")
+                .audio_source
+                .is_none()
+        );
+        Ok(())
+    }
+
+    /// A failed search index update rolls the row back with it.
+    #[test]
+    fn upsert_writes_row_and_search_index_together() -> Result<()> {
+        let (_temp, store, reader, _path) = create_store()?;
+        let mut video = sample_video("vid");
+        store.upsert_video(&video)?;
+
+        store.conn.execute_batch("DROP TABLE videos_fts;")?;
+        video.title = "Renamed".into();
+        assert!(store.upsert_video(&video).is_err());
+        assert_eq!(reader.get_video("vid")?.expect("video").title, "Video vid");
+        Ok(())
+    }
+
+    #[test]
+    fn sponsor_segments_survive_refreshes_without_them() -> Result<()> {
+        let (_temp, store, reader, _) = create_store()?;
+        let mut video = sample_video("vid");
+        video.sponsor_segments = Some(vec![SponsorSegment {
+            start_time: 10.0,
+            end_time: 42.5,
+            category: "sponsor".into(),
+        }]);
+        store.upsert_video(&video)?;
+        assert_eq!(
+            reader.get_video("vid")?.expect("video").sponsor_segments,
+            video.sponsor_segments
+        );
+
+        let mut refreshed = sample_video("vid");
+        refreshed.title = "Renamed".into();
+        store.upsert_video(&refreshed)?;
+        let stored = reader.get_video("vid")?.expect("video");
+        assert_eq!(stored.title, "Renamed");
+        assert_eq!(stored.sponsor_segments, video.sponsor_segments);
+
+        // A `--sponsorblock` refresh that finds no segments clears them.
+        refreshed.sponsor_segments = Some(Vec::new());
+        store.upsert_video(&refreshed)?;
+        let stored = reader.get_video("vid")?.expect("video");
+        assert_eq!(stored.sponsor_segments, Some(Vec::new()));
+        store.upsert_video(&sample_video("vid"))?;
+        let stored = reader.get_video("vid")?.expect("video");
+        assert_eq!(stored.sponsor_segments, Some(Vec::new()));
+
+        store.upsert_short(&sample_video("short"))?;
+        let short = reader.get_short("short")?.expect("short");
+        assert!(
+            serde_json::to_value(&short)?
+                .get("sponsor_segments")
+                .is_none()
+        );
+        Ok(())
+    }
+
+    /// The list response flags only videos that have a `subtitles` row.
+    #[test]
+    fn list_videos_reports_subtitle_availability() -> Result<()> {
+        let (_temp, store, reader, _path) = create_store()?;
+        store.upsert_video(&sample_video("captioned"))?;
+        store.upsert_video(&sample_video("silent"))?;
+        store.upsert_subtitles(&SubtitleCollection {
+            videoid: "captioned".into(),
+            languages: vec![
+                SubtitleTrack {
+                    code: "en".into(),
+                    name: "English".into(),
+                    url: "/api/videos/captioned/subtitles/en".into(),
+                    path: None,
+                },
+                SubtitleTrack {
+                    code: "fr".into(),
+                    name: "French".into(),
+                    url: "/api/videos/captioned/subtitles/fr".into(),
+                    path: None,
+                },
+            ],
+        })?;
+
+        let videos = reader.list_videos()?;
+        let captioned = videos.iter().find(|v| v.videoid == "captioned").unwrap();
+        assert!(captioned.has_subtitles);
+        assert_eq!(captioned.subtitle_languages, vec!["en", "fr"]);
+        let silent = videos.iter().find(|v| v.videoid == "silent").unwrap();
+        assert!(!silent.has_subtitles);
+        assert!(silent.subtitle_languages.is_empty());
+
+        let json = serde_json::to_value(silent)?;
+        assert_eq!(json["has_subtitles"], false);
+        Ok(())
+    }
+
+    /// `watched` is read back from `watch_state` and can be cleared again.
+    #[test]
+    fn set_watched_toggles_flag() -> Result<()> {
+        let (_temp, store, reader, _path) = create_store()?;
+        store.upsert_video(&sample_video("alpha"))?;
+        assert!(!reader.get_video("alpha")?.unwrap().watched);
+
+        store.set_watched("alpha", true)?;
+        assert!(reader.get_video("alpha")?.unwrap().watched);
+        assert!(reader.list_videos()?[0].watched);
+
+        store.set_watched("alpha", false)?;
+        assert!(!reader.get_video("alpha")?.unwrap().watched);
+        Ok(())
+    }
+
     /// Reader helpers should gracefully return `None` when a record is missing.
     #[test]
     fn reader_returns_none_for_missing_entries() -> Result<()> {
@@ -937,4 +3073,604 @@ mod tests {
         assert_eq!(all[2].id, "3");
         Ok(())
     }
+
+    /// An export is sorted by id and restores into a fresh database with the
+    /// same content; only the `refreshed_at` stamp of the import differs.
+    #[test]
+    fn export_all_round_trips_through_import() -> Result<()> {
+        let (_temp, mut store, reader, _path) = create_store()?;
+        store.upsert_video(&sample_video("bravo"))?;
+        store.upsert_video(&sample_video("alpha"))?;
+        store.upsert_short(&sample_video("short"))?;
+        store.set_watched("alpha", true)?;
+        store.increment_view("alpha")?;
+        store.upsert_subtitles(&SubtitleCollection {
+            videoid: "bravo".into(),
+            languages: vec![SubtitleTrack {
+                code: "en".into(),
+                name: "English".into(),
+                url: "/subs/en.vtt".into(),
+                path: None,
+            }],
+        })?;
+        let mut reply = sample_comment("1", "bravo");
+        reply.parent_comment_id = Some("2".into());
+        store.replace_comments("bravo", &[reply, sample_comment("2", "bravo")])?;
+        store.replace_comments("alpha", &[sample_comment("3", "alpha")])?;
+
+        let export = reader.export_all()?;
+        let ids = |records: &[VideoRecord]| -> Vec<String> {
+            records.iter().map(|r| r.videoid.clone()).collect()
+        };
+        assert_eq!(ids(&export.videos), vec!["alpha", "bravo"]);
+        assert_eq!(ids(&export.shorts), vec!["short"]);
+        let comment_ids: Vec<_> = export.comments.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(comment_ids, vec!["3", "1", "2"]);
+
+        let json = serde_json::to_string(&export)?;
+        assert!(!json.contains("local_views"), "{json}");
+        assert!(!export.videos[0].watched);
+        let (_temp2, mut fresh, fresh_reader, _path2) = create_store()?;
+        fresh.import_all(&serde_json::from_str(&json)?)?;
+
+        let strip = |mut export: LibraryExport| -> Result<String> {
+            for record in export.videos.iter_mut().chain(export.shorts.iter_mut()) {
+                record.refreshed_at = None;
+            }
+            Ok(serde_json::to_string(&export)?)
+        };
+        assert_eq!(strip(fresh_reader.export_all()?)?, strip(export.clone())?);
+
+        let mut future = export;
+        future.version = LibraryExport::VERSION + 1;
+        let err = fresh.import_all(&future).unwrap_err();
+        assert!(err.to_string().contains("Unsupported export version"));
+        Ok(())
+    }
+
+    /// A row that fails halfway through an import rolls back every row
+    /// written before it.
+    #[test]
+    fn import_all_is_all_or_nothing() -> Result<()> {
+        let (_temp, mut store, reader, _path) = create_store()?;
+        let mut export = LibraryExport {
+            version: LibraryExport::VERSION,
+            videos: vec![sample_video("alpha")],
+            shorts: Vec::new(),
+            subtitles: Vec::new(),
+            comments: vec![sample_comment("1", "alpha"), sample_comment("1", "alpha")],
+        };
+        store
+            .import_all(&export)
+            .expect_err("duplicate comment ids must fail");
+        assert!(reader.get_video("alpha")?.is_none());
+
+        export.comments.pop();
+        store.import_all(&export)?;
+        assert!(reader.get_video("alpha")?.is_some());
+        Ok(())
+    }
+
+    /// Archived ids cover both tables and collapse duplicates so peers can
+    /// diff a single sorted list.
+    #[test]
+    fn list_archived_ids_merges_videos_and_shorts() -> Result<()> {
+        let (_temp, store, reader, _path) = create_store()?;
+        store.upsert_video(&sample_video("bravo"))?;
+        store.upsert_video(&sample_video("alpha"))?;
+        store.upsert_short(&sample_video("charlie"))?;
+        store.upsert_short(&sample_video("alpha"))?;
+
+        let ids = reader.list_archived_ids()?;
+        assert_eq!(ids, vec!["alpha", "bravo", "charlie"]);
+        Ok(())
+    }
+
+    /// Rows written before `stream_key` existed must still deserialize.
+    #[test]
+    fn legacy_sources_without_stream_key_load() -> Result<()> {
+        let (_temp, store, reader, path) = create_store()?;
+        store.upsert_video(&sample_video("legacy"))?;
+
+        let conn = Connection::open(&path)?;
+        conn.execute(
+            "UPDATE videos SET sources_json = ?1 WHERE videoid = 'legacy'",
+            [r#"[{"format_id":"22","url":"/api/videos/legacy/streams/22"}]"#],
+        )?;
+
+        let fetched = reader.get_video("legacy")?.expect("video fetched");
+        assert_eq!(fetched.sources.len(), 1);
+        assert!(fetched.sources[0].stream_key.is_none());
+        Ok(())
+    }
+
+    /// Curated posters replace the stored URL and survive a metadata refresh
+    /// (or an export/import) that still lists the same thumbnail; the
+    /// downloader's own pick always follows the refresh.
+    #[test]
+    fn set_thumbnail_url_survives_refresh() -> Result<()> {
+        let (_temp, store, reader, _path) = create_store()?;
+        let mut record = sample_video("poster");
+        record.thumbnails = vec!["a.jpg".into(), "b.jpg".into()];
+        record.thumbnail_url = Some("a.jpg".into());
+        store.upsert_video(&record)?;
+
+        // Without an override, a refresh that ranks the thumbnails
+        // differently moves the poster along.
+        record.thumbnail_url = Some("b.jpg".into());
+        store.upsert_video(&record)?;
+        record.thumbnail_url = Some("a.jpg".into());
+        store.upsert_video(&record)?;
+        let fetched = reader.get_video("poster")?.expect("video fetched");
+        assert_eq!(fetched.thumbnail_url.as_deref(), Some("a.jpg"));
+        assert!(!fetched.poster_override);
+
+        assert!(store.set_thumbnail_url("poster", "b.jpg")?);
+        assert!(!store.set_thumbnail_url("ghost", "b.jpg")?);
+        store.upsert_video(&record)?;
+        let fetched = reader.get_video("poster")?.expect("video fetched");
+        assert_eq!(fetched.thumbnail_url.as_deref(), Some("b.jpg"));
+        assert!(fetched.poster_override);
+
+        // An export carries the override through `--import`.
+        let (_other_temp, mut other, other_reader, _other_path) = create_store()?;
+        other.import_all(&reader.export_all()?)?;
+        other.upsert_video(&record)?;
+        let fetched = other_reader.get_video("poster")?.expect("video fetched");
+        assert_eq!(fetched.thumbnail_url.as_deref(), Some("b.jpg"));
+        assert!(fetched.poster_override);
+
+        // Once the curated file disappears, the refreshed default wins again.
+        record.thumbnails = vec!["a.jpg".into()];
+        store.upsert_video(&record)?;
+        let fetched = reader.get_video("poster")?.expect("video fetched");
+        assert_eq!(fetched.thumbnail_url.as_deref(), Some("a.jpg"));
+        assert!(!fetched.poster_override);
+        Ok(())
+    }
+
+    /// The resume cursor follows the latest saved position and is dropped
+    /// once a run finishes the channel.
+    #[test]
+    fn resume_cursor_is_cleared_by_a_completed_run() -> Result<()> {
+        let (_temp, store, _reader, _path) = create_store()?;
+        let key = canonical_channel_url("https://youtube.com/@chan");
+        assert!(store.channel_resume_cursor(&key)?.is_none());
+
+        store.save_resume_cursor(&key, "videos/alpha")?;
+        store.save_resume_cursor(&key, "videos/beta")?;
+        assert_eq!(
+            store.channel_resume_cursor(&key)?.as_deref(),
+            Some("videos/beta")
+        );
+
+        store.record_channel_run(&key, None)?;
+        assert!(store.channel_resume_cursor(&key)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn open_adds_columns_missing_from_older_dbs() -> Result<()> {
+        let temp = tempdir()?;
+        let path = temp.path().join("old.db");
+        let conn = Connection::open(&path)?;
+        conn.execute_batch(
+            "CREATE TABLE channels (channel_url TEXT PRIMARY KEY, last_upload_date TEXT, last_run_at TEXT);
+             INSERT INTO channels VALUES ('https://youtube.com/@old', '2024-01-01', NULL);",
+        )?;
+        drop(conn);
+
+        let store = MetadataStore::open(&path)?;
+        store.save_resume_cursor("https://youtube.com/@old", "shorts/x")?;
+        assert_eq!(
+            store
+                .channel_last_upload_date("https://youtube.com/@old")?
+                .as_deref(),
+            Some("2024-01-01")
+        );
+        // Reopening must not try to add the column twice.
+        drop(store);
+        MetadataStore::open(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn epochs_are_backfilled_and_rendered_in_the_display_timezone() -> Result<()> {
+        let (_temp, mut store, reader, path) = create_store()?;
+        let mut video = sample_video("vid");
+        video.upload_date = Some("2024-01-02T00:00:00Z".into());
+        store.upsert_video(&video)?;
+        let mut comment = sample_comment("c1", "vid");
+        comment.time_posted_epoch = None;
+        store.replace_comments("vid", &[comment])?;
+        drop(store);
+
+        // Simulate a DB written before the epoch columns existed.
+        let conn = Connection::open(&path)?;
+        conn.execute_batch(
+            "ALTER TABLE videos DROP COLUMN upload_epoch;
+             ALTER TABLE comments DROP COLUMN time_posted_epoch;",
+        )?;
+        drop(conn);
+        MetadataStore::open(&path)?;
+
+        let stored = reader.get_video("vid")?.expect("video");
+        assert_eq!(stored.upload_epoch, Some(1_704_153_600));
+        assert!(stored.upload_date_display.is_none());
+        let comments = reader.get_comments("vid")?;
+        assert_eq!(comments[0].time_posted_epoch, Some(1_704_067_200));
+
+        let reader = reader.with_display_timezone(Some(chrono_tz::America::New_York));
+        let stored = reader.get_video("vid")?.expect("video");
+        assert_eq!(stored.upload_date.as_deref(), Some("2024-01-02T00:00:00Z"));
+        assert_eq!(
+            stored.upload_date_display.as_deref(),
+            Some("2024-01-01T19:00:00-05:00")
+        );
+        assert_eq!(
+            reader.get_comments("vid")?[0]
+                .time_posted_display
+                .as_deref(),
+            Some("2023-12-31T19:00:00-05:00")
+        );
+        Ok(())
+    }
+
+    /// Channel bookkeeping keeps the newest upload date even when a later run
+    /// only saw older videos (or nothing at all).
+    #[test]
+    fn record_channel_run_keeps_newest_upload() -> Result<()> {
+        let (_temp, store, _reader, _path) = create_store()?;
+        let key = canonical_channel_url("https://YouTube.com/@Chan/");
+        assert_eq!(key, "https://youtube.com/@chan");
+        assert!(store.channel_last_upload_date(&key)?.is_none());
+
+        store.record_channel_run(&key, Some("2024-03-01T00:00:00Z"))?;
+        store.record_channel_run(&key, Some("2024-01-01T00:00:00Z"))?;
+        store.record_channel_run(&key, None)?;
+        assert_eq!(
+            store.channel_last_upload_date(&key)?.as_deref(),
+            Some("2024-03-01T00:00:00Z")
+        );
+
+        store.record_channel_run(&key, Some("2024-04-01T00:00:00Z"))?;
+        assert_eq!(
+            store.channel_last_upload_date(&key)?.as_deref(),
+            Some("2024-04-01T00:00:00Z")
+        );
+        Ok(())
+    }
+
+    /// Continue-watching only keeps items between the two thresholds and
+    /// orders them by the most recent progress update, across both tables.
+    #[test]
+    fn list_continue_watching_filters_by_ratio() -> Result<()> {
+        let (_temp, store, reader, path) = create_store()?;
+        // sample_video durations are 120 seconds.
+        for id in ["barely", "halfway", "done", "unknown"] {
+            store.upsert_video(&sample_video(id))?;
+        }
+        store.upsert_short(&sample_video("short-mid"))?;
+        let mut no_duration = sample_video("no-duration");
+        no_duration.duration = None;
+        store.upsert_video(&no_duration)?;
+
+        store.record_watch_progress("barely", 1.0)?;
+        store.record_watch_progress("halfway", 60.0)?;
+        store.record_watch_progress("done", 119.0)?;
+        store.record_watch_progress("short-mid", 30.0)?;
+        store.record_watch_progress("no-duration", 30.0)?;
+
+        let conn = Connection::open(&path)?;
+        conn.execute(
+            "UPDATE watch_progress SET updated_at = '2024-01-01T00:00:00.000Z' WHERE videoid = 'halfway'",
+            [],
+        )?;
+        conn.execute(
+            "UPDATE watch_progress SET updated_at = '2024-01-02T00:00:00.000Z' WHERE videoid = 'short-mid'",
+            [],
+        )?;
+
+        let entries = reader.list_continue_watching(0.02, 0.95)?;
+        let ids: Vec<&str> = entries.iter().map(|e| e.video.videoid.as_str()).collect();
+        assert_eq!(ids, vec!["short-mid", "halfway"]);
+        assert_eq!(entries[1].progress.position_seconds, 60.0);
+
+        // Updating progress replaces the previous row and bumps it to the top.
+        store.record_watch_progress("halfway", 90.0)?;
+        let entries = reader.list_continue_watching(0.02, 0.95)?;
+        assert_eq!(entries[0].video.videoid, "halfway");
+        assert_eq!(entries[0].progress.position_seconds, 90.0);
+        Ok(())
+    }
+
+    #[test]
+    fn favorites_toggle_and_list_in_added_order() -> Result<()> {
+        let (_temp, store, reader, path) = create_store()?;
+        for id in ["first", "second", "third"] {
+            store.upsert_video(&sample_video(id))?;
+            store.add_favorite(id)?;
+        }
+
+        let conn = Connection::open(&path)?;
+        conn.execute(
+            "UPDATE favorites SET added_at = '2024-01-01T00:00:00.000Z' WHERE videoid = 'third'",
+            [],
+        )?;
+        // Re-adding must not bump the timestamp.
+        store.add_favorite("third")?;
+
+        let ids = reader.list_favorite_ids()?;
+        assert_eq!(ids, vec!["second", "first", "third"]);
+        let records = reader.get_videos_by_ids(&ids)?;
+        let hydrated: Vec<&str> = records.iter().map(|r| r.videoid.as_str()).collect();
+        assert_eq!(hydrated, vec!["second", "first", "third"]);
+
+        assert!(store.remove_favorite("first")?);
+        assert!(!store.remove_favorite("first")?);
+        assert_eq!(reader.list_favorite_ids()?, vec!["second", "third"]);
+        Ok(())
+    }
+
+    #[test]
+    fn search_ranks_matches_and_follows_updates() -> Result<()> {
+        let (_temp, store, reader, _path) = create_store()?;
+        let mut title_hit = sample_video("title");
+        title_hit.title = "Rust Café tour".into();
+        let mut description_hit = sample_video("description");
+        description_hit.description = "A long ramble that mentions rust once.".into();
+        let mut tag_hit = sample_video("tag");
+        tag_hit.tags = vec!["rustlang".into()];
+        for record in [&description_hit, &title_hit, &tag_hit] {
+            store.upsert_video(record)?;
+        }
+        store.upsert_short(&sample_video("short"))?;
+
+        let ids = |records: Vec<VideoRecord>| -> Vec<String> {
+            records.into_iter().map(|record| record.videoid).collect()
+        };
+        assert_eq!(
+            ids(reader.search_videos("RUST", 10)?),
+            ["title", "tag", "description"]
+        );
+        assert_eq!(ids(reader.search_videos("rust", 1)?), ["title"]);
+        assert_eq!(ids(reader.search_videos("cafe tour", 10)?), ["title"]);
+        // FTS5 syntax in the input is searched as text, not parsed.
+        assert!(reader.search_videos("rust\" OR NEAR(", 10).is_ok());
+        assert!(reader.search_videos(" - ", 10)?.is_empty());
+        assert_eq!(ids(reader.search_shorts("video", 10)?), ["short"]);
+
+        title_hit.title = "Renamed".into();
+        store.upsert_video(&title_hit)?;
+        assert_eq!(
+            ids(reader.search_videos("rust", 10)?),
+            ["tag", "description"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn search_index_is_backfilled_for_existing_rows() -> Result<()> {
+        let (_temp, store, reader, path) = create_store()?;
+        store.upsert_video(&sample_video("old"))?;
+        drop(store);
+        let conn = Connection::open(&path)?;
+        conn.execute_batch("DROP TABLE videos_fts; DROP TABLE shorts_fts;")?;
+        drop(conn);
+
+        MetadataStore::open(&path)?;
+        let found = reader.search_videos("tech", 10)?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].videoid, "old");
+        Ok(())
+    }
+
+    #[test]
+    fn reader_opens_read_only_databases() -> Result<()> {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        let (temp, store, reader, path) = create_store()?;
+        store.upsert_video(&sample_video("alpha"))?;
+        // Closing the last connection checkpoints and removes `-wal`/`-shm`,
+        // which a read-only directory then cannot recreate.
+        drop(store);
+        let dir = path.parent().unwrap().to_path_buf();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o444))?;
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o555))?;
+
+        let result = (|| -> Result<()> {
+            assert!(reader.get_video("alpha")?.is_some());
+            // Writes are refused even when running as root.
+            let write = reader.with_connection(|conn| {
+                conn.execute("CREATE TABLE scratch (id INTEGER)", [])?;
+                Ok(())
+            });
+            assert!(write.is_err());
+            Ok(())
+        })();
+
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755))?;
+        drop(temp);
+        result
+    }
+
+    #[test]
+    fn check_does_not_trust_pooled_connections() -> Result<()> {
+        let (_temp, store, reader, path) = create_store()?;
+        store.upsert_video(&sample_video("alpha"))?;
+        drop(store);
+        reader.check()?;
+        // Leaves a connection in the pool, which survives the unlink below.
+        assert!(reader.get_video("alpha")?.is_some());
+
+        for suffix in ["", "-wal", "-shm"] {
+            let file = PathBuf::from(format!("{}{suffix}", path.display()));
+            if file.exists() {
+                std::fs::remove_file(file)?;
+            }
+        }
+        assert!(reader.get_video("alpha")?.is_some());
+        assert!(reader.check().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn only_unwritable_wal_files_fall_back_to_immutable() {
+        let failure = |code| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(code), None);
+        assert!(needs_immutable(&failure(rusqlite::ffi::SQLITE_READONLY)));
+        assert!(needs_immutable(&failure(rusqlite::ffi::SQLITE_CANTOPEN)));
+        assert!(!needs_immutable(&failure(rusqlite::ffi::SQLITE_BUSY)));
+        assert!(!needs_immutable(&failure(rusqlite::ffi::SQLITE_CORRUPT)));
+        assert!(!needs_immutable(&rusqlite::Error::QueryReturnedNoRows));
+    }
+
+    #[test]
+    fn uri_paths_escape_reserved_characters() {
+        assert_eq!(
+            uri_path(Path::new("/srv/a?b#c%d.db")),
+            "/srv/a%3fb%23c%25d.db"
+        );
+    }
+
+    #[test]
+    fn videos_by_date_match_on_this_day_and_month() -> Result<()> {
+        let (_temp, store, reader, _) = create_store()?;
+        for (id, date) in [
+            ("y2019", "2019-03-14T08:00:00Z"),
+            ("y2021", "2021-03-14T23:59:59Z"),
+            ("y2021b", "2021-03-02T12:00:00Z"),
+            ("other", "2021-04-14T12:00:00Z"),
+        ] {
+            let mut record = sample_video(id);
+            record.upload_epoch = Some(chrono::DateTime::parse_from_rfc3339(date)?.timestamp());
+            store.upsert_video(&record)?;
+        }
+        let mut undated = sample_video("undated");
+        undated.upload_date = None;
+        store.upsert_video(&undated)?;
+
+        let ids = |records: Vec<VideoRecord>| {
+            records
+                .into_iter()
+                .map(|record| record.videoid)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids(reader.list_videos_by_date(None, 3, Some(14))?),
+            ["y2021", "y2019"]
+        );
+        assert_eq!(
+            ids(reader.list_videos_by_date(Some(2021), 3, None)?),
+            ["y2021", "y2021b"]
+        );
+        assert_eq!(
+            ids(reader.list_videos_by_date(Some(2019), 3, Some(14))?),
+            ["y2019"]
+        );
+        assert!(reader.list_videos_by_date(Some(2020), 3, None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn channels_are_grouped_by_author_with_an_unknown_bucket() -> Result<()> {
+        let (_temp, store, reader, _) = create_store()?;
+        let upload = |id: &str, author: Option<&str>, date: &str| {
+            let mut record = sample_video(id);
+            record.author = author.map(str::to_owned);
+            record.channel_url = author.map(|author| format!("https://youtube.com/@{author}"));
+            record.upload_date = Some(date.to_owned());
+            record
+        };
+        store.upsert_video(&upload("a1", Some("Alpha"), "2024-01-01"))?;
+        store.upsert_video(&upload("b1", Some("Beta"), "2024-02-01"))?;
+        store.upsert_short(&upload("b2", Some("Beta"), "2024-03-05"))?;
+        store.upsert_video(&upload("n1", None, "2023-01-01"))?;
+        store.upsert_short(&upload("n2", Some("  "), "2023-06-01"))?;
+        store.upsert_video(&upload("n3", None, "2022-01-01"))?;
+        store.upsert_video(&upload("c1", Some("Gamma"), "2021-01-01"))?;
+
+        let channels = reader.list_channels()?;
+        let summary: Vec<(&str, u64)> = channels
+            .iter()
+            .map(|channel| (channel.author.as_str(), channel.video_count))
+            .collect();
+        assert_eq!(
+            summary,
+            [(UNKNOWN_AUTHOR, 3), ("Beta", 2), ("Alpha", 1), ("Gamma", 1)]
+        );
+        assert_eq!(channels[0].channel_url, None);
+        assert_eq!(
+            channels[1],
+            ChannelSummary {
+                author: "Beta".into(),
+                channel_url: Some("https://youtube.com/@Beta".into()),
+                video_count: 2,
+                latest_upload: Some("2024-03-05".into()),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn channel_listing_matches_the_stored_url_exactly() -> Result<()> {
+        let (_temp, store, reader, _) = create_store()?;
+        for (id, channel) in [
+            ("a1", "https://youtube.com/@Alpha"),
+            ("a2", "https://youtube.com/@Alpha"),
+            ("b1", "https://youtube.com/@Beta"),
+            ("a3", "https://youtube.com/@Alpha/"),
+        ] {
+            let mut record = sample_video(id);
+            record.channel_url = Some(channel.to_owned());
+            store.upsert_video(&record)?;
+        }
+        let mut short = sample_video("s1");
+        short.channel_url = Some("https://youtube.com/@Alpha".into());
+        store.upsert_short(&short)?;
+
+        let ids = |records: Vec<VideoRecord>| {
+            records
+                .into_iter()
+                .map(|record| record.videoid)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids(reader.list_videos_for_channel("https://youtube.com/@Alpha")?),
+            ["a2", "a1"]
+        );
+        assert_eq!(
+            ids(reader.list_videos_for_channel("https://youtube.com/@Beta")?),
+            ["b1"]
+        );
+        assert_eq!(
+            ids(reader.list_shorts_for_channel("https://youtube.com/@Alpha")?),
+            ["s1"]
+        );
+        assert!(
+            reader
+                .list_videos_for_channel("https://youtube.com/@alpha")?
+                .is_empty()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn view_counts_upsert_and_show_on_single_lookups() -> Result<()> {
+        let (_dir, mut store, reader, _) = create_store()?;
+        store.upsert_video(&sample_video("alpha"))?;
+
+        assert_eq!(reader.get_video("alpha")?.unwrap().local_views, Some(0));
+        assert_eq!(store.increment_view("alpha")?, 1);
+        assert_eq!(store.increment_view("alpha")?, 2);
+        let video = reader.get_video("alpha")?.unwrap();
+        assert_eq!(video.local_views, Some(2));
+        assert_eq!(video.views, sample_video("alpha").views);
+        assert_eq!(reader.list_videos()?[0].local_views, None);
+
+        assert!(store.delete_video("alpha")?);
+        store.upsert_video(&sample_video("alpha"))?;
+        assert_eq!(reader.get_video("alpha")?.unwrap().local_views, Some(0));
+        Ok(())
+    }
 }
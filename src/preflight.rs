@@ -0,0 +1,308 @@
+//! Readiness checks run by `download_channel --preflight` and
+//! `routine_update --preflight`, typically from a systemd `ExecStartPre=` so
+//! a broken environment fails fast instead of half-way through a channel.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{Local, NaiveDate};
+
+use crate::metadata::{MetadataStore, SqliteTuning};
+use crate::security::{CookieStatus, check_cookies, ensure_writable};
+
+/// yt-dlp releases older than this usually fail against current YouTube.
+pub const YT_DLP_MAX_AGE_DAYS: i64 = 90;
+/// Free space the media root needs before a run is allowed to start.
+pub const DEFAULT_MIN_FREE_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Everything the checks need to know about the environment.
+pub struct PreflightInputs {
+    /// Command that runs yt-dlp (without arguments).
+    pub yt_dlp: Command,
+    pub media_root: PathBuf,
+    pub metadata_db: PathBuf,
+    /// yt-dlp's `cookies.txt`; skipped when it does not exist.
+    pub cookies: PathBuf,
+    /// `--strict-cookies`: loose permissions on `cookies` fail the check
+    /// instead of only being reported.
+    pub strict_cookies: bool,
+    pub sqlite: SqliteTuning,
+    pub min_free_bytes: u64,
+}
+
+/// Outcome of one check: a short detail on success, the reason on failure.
+pub struct PreflightCheck {
+    pub name: &'static str,
+    pub outcome: Result<String>,
+}
+
+/// Results of every check, in the order they ran.
+pub struct PreflightReport {
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.outcome.is_ok())
+    }
+
+    /// Prints one `[ OK ]`/`[FAIL]` line per check and turns any failure
+    /// into an error so callers exit non-zero.
+    pub fn print_and_finish(&self) -> Result<()> {
+        for check in &self.checks {
+            match &check.outcome {
+                Ok(detail) => println!("[ OK ] {}: {}", check.name, detail),
+                Err(err) => println!("[FAIL] {}: {:#}", check.name, err),
+            }
+        }
+        let failed = self
+            .checks
+            .iter()
+            .filter(|check| check.outcome.is_err())
+            .count();
+        if failed > 0 {
+            bail!("preflight failed: {failed} check(s) did not pass");
+        }
+        println!("Preflight passed.");
+        Ok(())
+    }
+}
+
+/// Runs every check; a failing check does not stop the others so the report
+/// lists all problems at once.
+pub fn run_preflight(inputs: PreflightInputs) -> PreflightReport {
+    let PreflightInputs {
+        yt_dlp,
+        media_root,
+        metadata_db,
+        cookies,
+        strict_cookies,
+        sqlite,
+        min_free_bytes,
+    } = inputs;
+    let today = Local::now().date_naive();
+
+    PreflightReport {
+        checks: vec![
+            PreflightCheck {
+                name: "yt-dlp",
+                outcome: check_yt_dlp(yt_dlp, today),
+            },
+            PreflightCheck {
+                name: "media root writable",
+                outcome: ensure_writable(&media_root).map(|()| media_root.display().to_string()),
+            },
+            PreflightCheck {
+                name: "metadata DB",
+                outcome: check_metadata_db(&metadata_db, sqlite),
+            },
+            PreflightCheck {
+                name: "free disk space",
+                outcome: check_free_space(&media_root, min_free_bytes),
+            },
+            PreflightCheck {
+                name: "cookies.txt",
+                outcome: cookies_outcome(&cookies, strict_cookies),
+            },
+        ],
+    }
+}
+
+/// Requires yt-dlp to run and to be at most [`YT_DLP_MAX_AGE_DAYS`] old.
+/// Versions that are not `YYYY.MM.DD` dates (custom builds) only need to run.
+fn check_yt_dlp(mut command: Command, today: NaiveDate) -> Result<String> {
+    let output = command
+        .arg("--version")
+        .output()
+        .context("yt-dlp is not installed or not in PATH")?;
+    if !output.status.success() {
+        bail!("`yt-dlp --version` exited with {}", output.status);
+    }
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+
+    let Some(released) = release_date(&version) else {
+        return Ok(format!("{version} (release date unknown)"));
+    };
+    let age = (today - released).num_days();
+    if age > YT_DLP_MAX_AGE_DAYS {
+        bail!(
+            "yt-dlp {version} is {age} days old (limit {YT_DLP_MAX_AGE_DAYS}); run `yt-dlp -U` or upgrade the package"
+        );
+    }
+    Ok(format!("{version} ({age} days old)"))
+}
+
+/// Parses the date out of versions such as `2025.09.26` or the nightly
+/// `2025.09.26.232831`.
+fn release_date(version: &str) -> Option<NaiveDate> {
+    let mut parts = version.split('.');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let day = parts.next()?.parse().ok()?;
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+fn check_metadata_db(path: &Path, sqlite: SqliteTuning) -> Result<String> {
+    MetadataStore::open_tuned(path, sqlite)?;
+    Ok(path.display().to_string())
+}
+
+fn check_free_space(dir: &Path, min_free_bytes: u64) -> Result<String> {
+    let stats = nix::sys::statvfs::statvfs(dir)
+        .map_err(|err| anyhow!("reading filesystem stats for {}: {err}", dir.display()))?;
+    let available = stats.blocks_available() as u64 * stats.fragment_size() as u64;
+    if available < min_free_bytes {
+        bail!(
+            "only {} free on {}, need at least {}",
+            format_bytes(available),
+            dir.display(),
+            format_bytes(min_free_bytes)
+        );
+    }
+    Ok(format!("{} free", format_bytes(available)))
+}
+
+/// Reports [`check_cookies`] as a check: exposed cookies pass with a warning
+/// and only fail under `--strict-cookies`, as in the downloader.
+fn cookies_outcome(path: &Path, strict: bool) -> Result<String> {
+    match check_cookies(path, strict) {
+        CookieStatus::Missing => Ok("not present".to_owned()),
+        CookieStatus::Private => Ok(path.display().to_string()),
+        CookieStatus::Exposed(reason) => Ok(format!("warning: {reason}")),
+        CookieStatus::Refused(reason) => Err(anyhow!(reason)),
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    format!("{:.1} GiB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    /// Runs the script through `sh` rather than executing it directly, which
+    /// avoids `ETXTBSY` when another test thread forks while it is written.
+    fn version_stub(dir: &Path, body: &str) -> Command {
+        let path = dir.join("yt-dlp");
+        fs::write(&path, format!("{body}\n")).unwrap();
+        let mut command = Command::new("sh");
+        command.arg(path);
+        command
+    }
+
+    fn inputs(dir: &Path, yt_dlp: Command) -> PreflightInputs {
+        PreflightInputs {
+            yt_dlp,
+            media_root: dir.to_path_buf(),
+            metadata_db: dir.join("metadata.db"),
+            cookies: dir.join("cookies.txt"),
+            strict_cookies: false,
+            sqlite: SqliteTuning::default(),
+            min_free_bytes: 0,
+        }
+    }
+
+    fn failed(report: &PreflightReport) -> Vec<&'static str> {
+        report
+            .checks
+            .iter()
+            .filter(|check| check.outcome.is_err())
+            .map(|check| check.name)
+            .collect()
+    }
+
+    #[test]
+    fn healthy_environment_passes() {
+        let dir = tempdir().unwrap();
+        let today = Local::now().date_naive().format("%Y.%m.%d").to_string();
+        let stub = version_stub(dir.path(), &format!("echo {today}"));
+
+        let report = run_preflight(inputs(dir.path(), stub));
+        assert!(report.passed(), "{:?}", failed(&report));
+        assert!(report.print_and_finish().is_ok());
+    }
+
+    #[test]
+    fn missing_or_broken_yt_dlp_fails() {
+        let dir = tempdir().unwrap();
+        let report = run_preflight(inputs(dir.path(), Command::new(dir.path().join("missing"))));
+        assert_eq!(failed(&report), ["yt-dlp"]);
+
+        let stub = version_stub(dir.path(), "exit 2");
+        let report = run_preflight(inputs(dir.path(), stub));
+        assert_eq!(failed(&report), ["yt-dlp"]);
+        assert!(report.print_and_finish().is_err());
+    }
+
+    #[test]
+    fn stale_yt_dlp_fails() {
+        let today = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        let dir = tempdir().unwrap();
+        let stub = version_stub(dir.path(), "echo 2025.01.15");
+        let err = check_yt_dlp(stub, today).unwrap_err();
+        assert!(err.to_string().contains("137 days old"), "{err}");
+
+        let stub = version_stub(dir.path(), "echo 2025.05.30.232831");
+        assert!(check_yt_dlp(stub, today).is_ok());
+        let stub = version_stub(dir.path(), "echo custom-build");
+        assert!(check_yt_dlp(stub, today).is_ok());
+    }
+
+    #[test]
+    fn unwritable_media_root_fails() {
+        let dir = tempdir().unwrap();
+        let stub = version_stub(dir.path(), "echo custom-build");
+        let mut inputs = inputs(dir.path(), stub);
+        inputs.media_root = dir.path().join("missing");
+
+        let report = run_preflight(inputs);
+        assert_eq!(failed(&report), ["media root writable", "free disk space"]);
+    }
+
+    #[test]
+    fn unopenable_metadata_db_fails() {
+        let dir = tempdir().unwrap();
+        let stub = version_stub(dir.path(), "echo custom-build");
+        let mut inputs = inputs(dir.path(), stub);
+        // A directory where the DB file should be cannot be opened.
+        inputs.metadata_db = dir.path().to_path_buf();
+
+        let report = run_preflight(inputs);
+        assert_eq!(failed(&report), ["metadata DB"]);
+    }
+
+    #[test]
+    fn shared_cookies_only_fail_when_strict() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let cookies = dir.path().join("cookies.txt");
+        fs::write(&cookies, "# Netscape HTTP Cookie File").unwrap();
+        fs::set_permissions(&cookies, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let stub = version_stub(dir.path(), "echo custom-build");
+        let report = run_preflight(inputs(dir.path(), stub));
+        assert!(report.passed(), "{:?}", failed(&report));
+
+        let stub = version_stub(dir.path(), "echo custom-build");
+        let mut inputs = inputs(dir.path(), stub);
+        inputs.strict_cookies = true;
+        let report = run_preflight(inputs);
+        assert_eq!(failed(&report), ["cookies.txt"]);
+    }
+
+    #[test]
+    fn low_disk_space_fails() {
+        let dir = tempdir().unwrap();
+        let stub = version_stub(dir.path(), "echo custom-build");
+        let mut inputs = inputs(dir.path(), stub);
+        inputs.min_free_bytes = u64::MAX;
+
+        let report = run_preflight(inputs);
+        assert_eq!(failed(&report), ["free disk space"]);
+    }
+}
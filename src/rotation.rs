@@ -0,0 +1,249 @@
+//! Size/age/count based rotation for the status and history files the
+//! binaries write next to the library (`file`, `file.1`, `file.2`, ...).
+//!
+//! Every step is a single `rename`, so a process killed mid-rotation leaves
+//! at worst a gap in the numbering or one copy fewer, never a truncated file;
+//! the next rotation carries on from whatever is on disk.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+
+/// Default size at which append-only files are rotated.
+pub const DEFAULT_ROTATE_MAX_BYTES: u64 = 1024 * 1024;
+/// Default number of rotated copies kept.
+pub const DEFAULT_ROTATE_KEEP: usize = 5;
+
+/// Limits applied when writing rotated files, read from `LOG_MAX_BYTES`,
+/// `LOG_MAX_AGE_DAYS` and `LOG_KEEP`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RotationPolicy {
+    /// Append-only files are rotated once they reach this size.
+    pub max_bytes: u64,
+    /// Rotated copies whose last write is older than this are deleted.
+    pub max_age: Option<Duration>,
+    /// Number of rotated copies to keep; `0` keeps none.
+    pub keep: usize,
+}
+
+impl Default for RotationPolicy {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_ROTATE_MAX_BYTES,
+            max_age: None,
+            keep: DEFAULT_ROTATE_KEEP,
+        }
+    }
+}
+
+impl RotationPolicy {
+    /// Appends `line` (plus a newline) to `path`, rotating it first when it
+    /// has reached `max_bytes`. Meant for `*.jsonl` histories.
+    pub fn append_line(&self, path: &Path, line: &str) -> Result<()> {
+        let size = fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+        if size > 0 && size + line.len() as u64 + 1 > self.max_bytes {
+            self.rotate(path)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("opening {}", path.display()))?;
+        writeln!(file, "{line}").with_context(|| format!("appending to {}", path.display()))?;
+        self.prune(path)
+    }
+
+    /// Replaces `path` with `contents`, keeping the previous version as
+    /// `path.1`. Meant for one-shot status files such as run summaries. The
+    /// new file is written to a temporary sibling and renamed into place.
+    pub fn replace_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let staging = sibling(path, "tmp");
+        fs::write(&staging, contents).with_context(|| format!("writing {}", staging.display()))?;
+        if path.exists() {
+            self.rotate(path)?;
+        }
+        fs::rename(&staging, path).with_context(|| format!("replacing {}", path.display()))?;
+        self.prune(path)
+    }
+
+    /// Shifts `path.N` to `path.N+1` (dropping the oldest) and moves `path`
+    /// to `path.1`.
+    pub fn rotate(&self, path: &Path) -> Result<()> {
+        if self.keep == 0 {
+            return remove_if_exists(path);
+        }
+
+        for index in (1..self.keep).rev() {
+            let from = numbered(path, index);
+            if from.exists() {
+                let to = numbered(path, index + 1);
+                fs::rename(&from, &to)
+                    .with_context(|| format!("rotating {} to {}", from.display(), to.display()))?;
+            }
+        }
+        if path.exists() {
+            let to = numbered(path, 1);
+            fs::rename(path, &to)
+                .with_context(|| format!("rotating {} to {}", path.display(), to.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Deletes rotated copies beyond `keep` or older than `max_age`.
+    pub fn prune(&self, path: &Path) -> Result<()> {
+        for (index, copy) in rotated_copies(path)? {
+            let expired = self.max_age.is_some_and(|max_age| {
+                fs::metadata(&copy)
+                    .and_then(|meta| meta.modified())
+                    .ok()
+                    .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                    .is_some_and(|age| age > max_age)
+            });
+            if index > self.keep || expired {
+                remove_if_exists(&copy)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Every `path.N` sibling currently on disk.
+fn rotated_copies(path: &Path) -> Result<Vec<(usize, PathBuf)>> {
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return Ok(Vec::new());
+    };
+    let parent = if parent.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        parent
+    };
+    let prefix = format!("{}.", name.to_string_lossy());
+
+    let mut copies = Vec::new();
+    let entries = match fs::read_dir(parent) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(copies),
+        Err(err) => return Err(err).with_context(|| format!("reading {}", parent.display())),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let index = file_name
+            .to_string_lossy()
+            .strip_prefix(&prefix)
+            .and_then(|suffix| suffix.parse::<usize>().ok());
+        if let Some(index) = index {
+            copies.push((index, entry.path()));
+        }
+    }
+    Ok(copies)
+}
+
+fn numbered(path: &Path, index: usize) -> PathBuf {
+    sibling(path, &index.to_string())
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err).with_context(|| format!("removing {}", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn append_line_rotates_by_size() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("update-history.jsonl");
+        let policy = RotationPolicy {
+            max_bytes: 16,
+            ..RotationPolicy::default()
+        };
+
+        policy.append_line(&path, "first")?;
+        policy.append_line(&path, "second")?;
+        assert_eq!(read(&path), "first\nsecond\n");
+
+        policy.append_line(&path, "third")?;
+        assert_eq!(read(&path), "third\n");
+        assert_eq!(read(&numbered(&path, 1)), "first\nsecond\n");
+        Ok(())
+    }
+
+    #[test]
+    fn replace_file_keeps_only_the_configured_count() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("run-summary.json");
+        let policy = RotationPolicy {
+            keep: 2,
+            ..RotationPolicy::default()
+        };
+
+        for run in 1..=4 {
+            policy.replace_file(&path, format!("run {run}").as_bytes())?;
+        }
+        assert_eq!(read(&path), "run 4");
+        assert_eq!(read(&numbered(&path, 1)), "run 3");
+        assert_eq!(read(&numbered(&path, 2)), "run 2");
+        assert!(!numbered(&path, 3).exists());
+        assert!(!sibling(&path, "tmp").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn rotation_recovers_from_an_interrupted_run() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("refresh-status.json");
+        let policy = RotationPolicy {
+            keep: 2,
+            ..RotationPolicy::default()
+        };
+        // A previous run died after shifting `.1` but before moving the live
+        // file, and an older run left a copy beyond the limit.
+        fs::write(&path, "live")?;
+        fs::write(numbered(&path, 2), "older")?;
+        fs::write(numbered(&path, 5), "stale")?;
+
+        policy.replace_file(&path, b"new")?;
+        assert_eq!(read(&path), "new");
+        assert_eq!(read(&numbered(&path, 1)), "live");
+        assert!(!numbered(&path, 5).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn prune_drops_copies_older_than_max_age() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("update-history.jsonl");
+        fs::write(numbered(&path, 1), "old")?;
+        let policy = RotationPolicy {
+            max_age: Some(Duration::ZERO),
+            ..RotationPolicy::default()
+        };
+        std::thread::sleep(Duration::from_millis(10));
+
+        policy.prune(&path)?;
+        assert!(!numbered(&path, 1).exists());
+        Ok(())
+    }
+}
@@ -2,7 +2,12 @@
 
 //! Shared security helpers used by the newtube binaries.
 
-use anyhow::{Result, bail};
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
 use nix::unistd::Uid;
 
 /// Fails fast when a binary is started as root. All services are expected to
@@ -15,3 +20,104 @@ pub fn ensure_not_root(process: &str) -> Result<()> {
     }
     Ok(())
 }
+
+/// Fails unless the current account can create and write files in `dir`.
+/// Checked by actually writing a scratch file, because permission bits do not
+/// tell the whole story (read-only mounts, ACLs, systemd `ProtectSystem=`).
+pub fn ensure_writable(dir: &Path) -> Result<()> {
+    let mut probe = tempfile::Builder::new()
+        .prefix(".newtube-write-probe-")
+        .tempfile_in(dir)
+        .with_context(|| format!("{} is not writable", dir.display()))?;
+    probe
+        .write_all(b"ok")
+        .and_then(|()| probe.flush())
+        .with_context(|| format!("{} is not writable", dir.display()))?;
+    Ok(())
+}
+
+/// Fails when accounts other than the owner can read or write `path`. Meant
+/// for credentials such as yt-dlp's `cookies.txt`, whose session cookies are
+/// as good as the account password.
+pub fn check_sensitive_file_perms(path: &Path) -> Result<()> {
+    let mode = fs::metadata(path)
+        .with_context(|| format!("reading permissions of {}", path.display()))?
+        .permissions()
+        .mode();
+    if mode & 0o066 != 0 {
+        bail!(
+            "{} is readable or writable by other accounts (mode {:04o}); run `chmod 600` on it",
+            path.display(),
+            mode & 0o7777
+        );
+    }
+    Ok(())
+}
+
+/// What [`check_cookies`] found at yt-dlp's `cookies.txt`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CookieStatus {
+    /// No file; yt-dlp runs without cookies.
+    Missing,
+    /// Only the owner can read or write it.
+    Private,
+    /// Other accounts can read or write it. Callers warn and carry on.
+    Exposed(String),
+    /// Same as `Exposed` under `--strict-cookies`: callers must not use it.
+    Refused(String),
+}
+
+/// The one rule for a `cookies.txt`, shared by `download_channel` and
+/// `--preflight`: loose permissions (see [`check_sensitive_file_perms`]) are
+/// only a warning unless `strict` is set.
+pub fn check_cookies(path: &Path, strict: bool) -> CookieStatus {
+    if !path.exists() {
+        return CookieStatus::Missing;
+    }
+    match check_sensitive_file_perms(path) {
+        Ok(()) => CookieStatus::Private,
+        Err(err) if strict => CookieStatus::Refused(format!("{err:#}")),
+        Err(err) => CookieStatus::Exposed(format!("{err:#}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sensitive_files_must_be_private() -> Result<()> {
+        let file = tempfile::NamedTempFile::new()?;
+        fs::set_permissions(file.path(), fs::Permissions::from_mode(0o600))?;
+        check_sensitive_file_perms(file.path())?;
+
+        for mode in [0o640, 0o604, 0o620] {
+            fs::set_permissions(file.path(), fs::Permissions::from_mode(mode))?;
+            let err = check_sensitive_file_perms(file.path()).unwrap_err();
+            assert!(err.to_string().contains("chmod 600"), "{mode:o}: {err}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn shared_cookies_are_only_refused_when_strict() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cookies = dir.path().join("cookies.txt");
+        assert_eq!(check_cookies(&cookies, true), CookieStatus::Missing);
+
+        fs::write(&cookies, "# Netscape HTTP Cookie File")?;
+        fs::set_permissions(&cookies, fs::Permissions::from_mode(0o644))?;
+        assert!(matches!(
+            check_cookies(&cookies, false),
+            CookieStatus::Exposed(reason) if reason.contains("chmod 600")
+        ));
+        assert!(matches!(
+            check_cookies(&cookies, true),
+            CookieStatus::Refused(reason) if reason.contains("chmod 600")
+        ));
+
+        fs::set_permissions(&cookies, fs::Permissions::from_mode(0o600))?;
+        assert_eq!(check_cookies(&cookies, true), CookieStatus::Private);
+        Ok(())
+    }
+}
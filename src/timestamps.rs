@@ -0,0 +1,69 @@
+//! Conversions between the date formats yt-dlp emits and what the database
+//! stores: UTC epoch seconds plus an ISO 8601 UTC string (`...Z`) derived
+//! from it, so every timestamp in the API compares and sorts the same way.
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+
+/// Formats epoch seconds as `YYYY-MM-DDTHH:MM:SSZ`.
+pub fn epoch_to_iso(epoch: i64) -> Option<String> {
+    DateTime::<Utc>::from_timestamp(epoch, 0)
+        .map(|datetime| datetime.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+}
+
+/// Converts yt-dlp's `upload_date` (`YYYYMMDD`, a UTC calendar date) to the
+/// epoch of midnight UTC on that day.
+pub fn yt_dlp_date_to_epoch(value: &str) -> Option<i64> {
+    if value.len() != 8 {
+        return None;
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc().timestamp())
+}
+
+/// Parses a `DISPLAY_TIMEZONE` value such as `Europe/Paris` or `UTC`.
+pub fn parse_display_timezone(value: &str) -> Result<Tz> {
+    value.parse::<Tz>().map_err(|_| {
+        anyhow!("unknown time zone {value:?} (expected an IANA name like Europe/Paris)")
+    })
+}
+
+/// Renders epoch seconds as RFC 3339 with `tz`'s offset, for display.
+pub fn epoch_in_timezone(epoch: i64, tz: Tz) -> Option<String> {
+    DateTime::<Utc>::from_timestamp(epoch, 0)
+        .map(|datetime| datetime.with_timezone(&tz).to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn both_yt_dlp_formats_convert_to_the_same_utc_string() {
+        // `upload_date` and `timestamp` for a video uploaded at midnight UTC.
+        let from_date = yt_dlp_date_to_epoch("20240102").unwrap();
+        assert_eq!(from_date, 1_704_153_600);
+        assert_eq!(epoch_to_iso(from_date).unwrap(), "2024-01-02T00:00:00Z");
+        assert_eq!(epoch_to_iso(1_704_153_600).unwrap(), "2024-01-02T00:00:00Z");
+        assert_eq!(epoch_to_iso(0).unwrap(), "1970-01-01T00:00:00Z");
+
+        assert!(yt_dlp_date_to_epoch("2024").is_none());
+        assert!(yt_dlp_date_to_epoch("20241340").is_none());
+    }
+
+    #[test]
+    fn display_timezone_applies_offsets_and_dst() {
+        let paris = parse_display_timezone("Europe/Paris").unwrap();
+        assert_eq!(
+            epoch_in_timezone(1_704_153_600, paris).unwrap(),
+            "2024-01-02T01:00:00+01:00"
+        );
+        // Summer time.
+        assert_eq!(
+            epoch_in_timezone(1_719_792_000, paris).unwrap(),
+            "2024-07-01T02:00:00+02:00"
+        );
+        assert!(parse_display_timezone("Mars/Olympus").is_err());
+    }
+}